@@ -0,0 +1,733 @@
+//! # Analog Comparator
+//!
+//! The AC contains two comparators, each comparing a positive and a negative
+//! input. Inputs are either `AIN` pins ([`PA04`] to [`PA07`], configured as
+//! [`AlternateB`]) or internal sources: [`Gnd`], the [`Bandgap`] reference,
+//! the [`DacOutput`], and a [`VddScaler`] dedicated to each comparator.
+//!
+//! [`Ac::new`] returns the [`Ac`] itself, which controls the features shared
+//! by both comparators, along with a [`ComparatorToken`] for each comparator.
+//! A token is turned into an enabled [`Comparator`] by providing its inputs
+//! and a [`ComparatorConfig`]:
+//!
+//! ```
+//! use atsamd_hal::ac::{Ac, ComparatorConfig, Gnd, InterruptSelection};
+//!
+//! let (mut ac, comp0, _comp1) = Ac::new(&mut mclk, peripherals.AC, clock);
+//! let ain0 = pins.pa04.into_alternate();
+//! let config = ComparatorConfig::new().interrupt(InterruptSelection::TOGGLE);
+//! let comp0 = comp0.enable(ain0, Gnd, config);
+//! let above = comp0.state();
+//! ```
+//!
+//! On SAMD11 and SAMD21, [`Ac::new`] takes both the digital and analog GCLK
+//! tokens instead of a single one.
+//!
+//! ## Single-shot comparisons
+//!
+//! By default, comparators run continuously and their output is available at
+//! any time through [`Comparator::state`]. Single-shot comparators save power
+//! by only running when [`Comparator::compare`] is called, or when their
+//! start event is received.
+//!
+//! ## Window mode
+//!
+//! Both comparators can be combined into a window comparator, usually by
+//! connecting the signal to both positive inputs, and the window limits to
+//! the negative inputs. See [`Ac::enable_window`].
+
+use core::marker::PhantomData;
+
+use bitflags::bitflags;
+
+use crate::gpio::v2::{AlternateB, Pin, PinId, PA04, PA05};
+#[cfg(not(feature = "samd11c"))]
+use crate::gpio::v2::{PA06, PA07};
+use crate::pac::ac::RegisterBlock;
+use crate::pac::generic::Variant;
+use crate::pac::{ac, AC};
+use crate::typelevel::Sealed;
+
+#[cfg(any(feature = "samd11", feature = "samd21"))]
+use crate::{
+    clock::{AcAnaClock, AcDigClock},
+    pac::PM,
+};
+
+#[cfg(feature = "min-samd51g")]
+use crate::{clock::AcClock, pac::MCLK as PM};
+
+/// Length of the majority filter applied to the comparator output
+pub use ac::compctrl::FLEN_A as Filter;
+/// Condition setting the comparator interrupt flag
+pub use ac::compctrl::INTSEL_A as InterruptSelection;
+/// Negative input multiplexer selection
+pub use ac::compctrl::MUXNEG_A as MuxNeg;
+/// Positive input multiplexer selection
+pub use ac::compctrl::MUXPOS_A as MuxPos;
+/// Routing of the comparator output to its `CMP` pin
+pub use ac::compctrl::OUT_A as Output;
+/// Position of the signal relative to the window
+pub use ac::statusa::WSTATE0_A as WindowState;
+/// Condition setting the window interrupt flag
+pub use ac::winctrl::WINTSEL0_A as WindowInterrupt;
+
+/// Hysteresis level
+#[cfg(feature = "min-samd51g")]
+pub use ac::compctrl::HYST_A as Hysteresis;
+
+/// Comparator speed
+#[cfg(any(feature = "samd11", feature = "samd21"))]
+pub use ac::compctrl::SPEED_A as Speed;
+
+/// Wait for register synchronization
+#[inline]
+fn wait_sync(ac: &RegisterBlock) {
+    #[cfg(any(feature = "samd11", feature = "samd21"))]
+    while ac.statusb.read().syncbusy().bit_is_set() {}
+
+    #[cfg(feature = "min-samd51g")]
+    while ac.syncbusy.read().bits() != 0 {}
+}
+
+//==============================================================================
+//  ComparatorId
+//==============================================================================
+
+/// Type-level `enum` representing a comparator
+pub trait ComparatorId: Sealed {
+    /// Comparator number
+    const NUM: usize;
+}
+
+/// Type-level variant of [`ComparatorId`] for comparator 0
+pub enum Comp0 {}
+impl Sealed for Comp0 {}
+impl ComparatorId for Comp0 {
+    const NUM: usize = 0;
+}
+
+/// Type-level variant of [`ComparatorId`] for comparator 1
+pub enum Comp1 {}
+impl Sealed for Comp1 {}
+impl ComparatorId for Comp1 {
+    const NUM: usize = 1;
+}
+
+//==============================================================================
+//  Inputs
+//==============================================================================
+
+/// Type-level function mapping a [`PinId`] to its `AIN` input
+pub trait AinPin: PinId {
+    /// Positive multiplexer selection of the pin
+    const MUXPOS: MuxPos;
+    /// Negative multiplexer selection of the pin
+    const MUXNEG: MuxNeg;
+}
+
+macro_rules! ain_pin {
+    ($Id:ident, $Pin:ident) => {
+        impl AinPin for $Id {
+            const MUXPOS: MuxPos = MuxPos::$Pin;
+            const MUXNEG: MuxNeg = MuxNeg::$Pin;
+        }
+    };
+}
+
+ain_pin!(PA04, PIN0);
+ain_pin!(PA05, PIN1);
+#[cfg(not(feature = "samd11c"))]
+ain_pin!(PA06, PIN2);
+#[cfg(not(feature = "samd11c"))]
+ain_pin!(PA07, PIN3);
+
+/// Input usable as the positive input of a comparator
+pub trait PositiveInput: Sealed {
+    /// Positive multiplexer selection
+    fn muxpos(&self) -> MuxPos;
+    /// Value of the `SCALER` register, if the input is the VDD scaler
+    #[inline]
+    fn scaler(&self) -> Option<u8> {
+        None
+    }
+}
+
+/// Input usable as the negative input of a comparator
+pub trait NegativeInput: Sealed {
+    /// Negative multiplexer selection
+    fn muxneg(&self) -> MuxNeg;
+    /// Value of the `SCALER` register, if the input is the VDD scaler
+    #[inline]
+    fn scaler(&self) -> Option<u8> {
+        None
+    }
+}
+
+impl<I: AinPin> PositiveInput for Pin<I, AlternateB> {
+    #[inline]
+    fn muxpos(&self) -> MuxPos {
+        I::MUXPOS
+    }
+}
+
+impl<I: AinPin> NegativeInput for Pin<I, AlternateB> {
+    #[inline]
+    fn muxneg(&self) -> MuxNeg {
+        I::MUXNEG
+    }
+}
+
+/// Ground, as a negative input
+pub struct Gnd;
+impl Sealed for Gnd {}
+impl NegativeInput for Gnd {
+    #[inline]
+    fn muxneg(&self) -> MuxNeg {
+        MuxNeg::GND
+    }
+}
+
+/// Internal bandgap reference, as a negative input
+pub struct Bandgap;
+impl Sealed for Bandgap {}
+impl NegativeInput for Bandgap {
+    #[inline]
+    fn muxneg(&self) -> MuxNeg {
+        MuxNeg::BANDGAP
+    }
+}
+
+/// DAC output, as a negative input
+///
+/// The internal output of the DAC must be enabled. On SAMD51, channel 0 of
+/// the DAC is used.
+pub struct DacOutput;
+impl Sealed for DacOutput {}
+impl NegativeInput for DacOutput {
+    #[inline]
+    fn muxneg(&self) -> MuxNeg {
+        MuxNeg::DAC
+    }
+}
+
+/// VDD scaler of the comparator, producing `VDD * (value + 1) / 64`
+///
+/// The scaler is a negative input. On SAMD51, it can also be used as the
+/// positive input.
+#[derive(Clone, Copy, Debug)]
+pub struct VddScaler(u8);
+
+impl VddScaler {
+    /// Create a new `VddScaler`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not lower than 64.
+    #[inline]
+    pub fn new(value: u8) -> Self {
+        assert!(value < 64, "VDD scaler value must be lower than 64");
+        Self(value)
+    }
+}
+
+impl Sealed for VddScaler {}
+
+impl NegativeInput for VddScaler {
+    #[inline]
+    fn muxneg(&self) -> MuxNeg {
+        MuxNeg::VSCALE
+    }
+
+    #[inline]
+    fn scaler(&self) -> Option<u8> {
+        Some(self.0)
+    }
+}
+
+#[cfg(feature = "min-samd51g")]
+impl PositiveInput for VddScaler {
+    #[inline]
+    fn muxpos(&self) -> MuxPos {
+        MuxPos::VSCALE
+    }
+
+    #[inline]
+    fn scaler(&self) -> Option<u8> {
+        Some(self.0)
+    }
+}
+
+//==============================================================================
+//  ComparatorConfig
+//==============================================================================
+
+/// Configuration of a comparator
+#[derive(Clone, Copy, Debug)]
+pub struct ComparatorConfig {
+    single_shot: bool,
+    #[cfg(any(feature = "samd11", feature = "samd21"))]
+    speed: Speed,
+    #[cfg(any(feature = "samd11", feature = "samd21"))]
+    hysteresis: bool,
+    #[cfg(feature = "min-samd51g")]
+    hysteresis: Option<Hysteresis>,
+    filter: Filter,
+    interrupt: InterruptSelection,
+    output: Output,
+    run_standby: bool,
+}
+
+impl ComparatorConfig {
+    /// Create a new `ComparatorConfig`. The default configuration is:
+    /// * Continuous comparisons
+    /// * High speed
+    /// * No hysteresis, no filtering
+    /// * Interrupt flag set when the output toggles
+    /// * Output not routed to the `CMP` pin
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            single_shot: false,
+            #[cfg(any(feature = "samd11", feature = "samd21"))]
+            speed: Speed::HIGH,
+            #[cfg(any(feature = "samd11", feature = "samd21"))]
+            hysteresis: false,
+            #[cfg(feature = "min-samd51g")]
+            hysteresis: None,
+            filter: Filter::OFF,
+            interrupt: InterruptSelection::TOGGLE,
+            output: Output::OFF,
+            run_standby: false,
+        }
+    }
+
+    /// Only compare when requested by [`Comparator::compare`] or by the
+    /// start event
+    #[inline]
+    pub fn single_shot(mut self, single_shot: bool) -> Self {
+        self.single_shot = single_shot;
+        self
+    }
+
+    /// Select the comparator speed, trading propagation delay for power
+    /// consumption
+    #[cfg(any(feature = "samd11", feature = "samd21"))]
+    #[inline]
+    pub fn speed(mut self, speed: Speed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Enable hysteresis
+    #[cfg(any(feature = "samd11", feature = "samd21"))]
+    #[inline]
+    pub fn hysteresis(mut self, hysteresis: bool) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    /// Enable hysteresis with the given level, or disable it with `None`
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub fn hysteresis(mut self, hysteresis: Option<Hysteresis>) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    /// Select the majority filter applied to the output
+    ///
+    /// Filtering is only available for continuous comparisons.
+    #[inline]
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Select the condition setting the interrupt flag and generating the
+    /// comparator event
+    #[inline]
+    pub fn interrupt(mut self, interrupt: InterruptSelection) -> Self {
+        self.interrupt = interrupt;
+        self
+    }
+
+    /// Route the output to the `CMP` pin, which must be configured separately
+    #[inline]
+    pub fn output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Keep the comparator running in standby sleep mode
+    ///
+    /// On SAMD11 and SAMD21, this setting is shared by both comparators.
+    #[inline]
+    pub fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+}
+
+impl Default for ComparatorConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//==============================================================================
+//  Interrupts and events
+//==============================================================================
+
+bitflags! {
+    /// Interrupt flags
+    pub struct Interrupts: u8 {
+        /// Comparator 0
+        const COMP0 = 1 << 0;
+        /// Comparator 1
+        const COMP1 = 1 << 1;
+        /// Window 0
+        const WIN0 = 1 << 4;
+    }
+}
+
+bitflags! {
+    /// Event inputs and outputs
+    pub struct Events: u16 {
+        /// Comparator 0 event output
+        const COMP0_OUTPUT = 1 << 0;
+        /// Comparator 1 event output
+        const COMP1_OUTPUT = 1 << 1;
+        /// Window 0 event output
+        const WIN0_OUTPUT = 1 << 4;
+        /// Start a comparison on comparator 0 on its event input
+        const COMP0_START = 1 << 8;
+        /// Start a comparison on comparator 1 on its event input
+        const COMP1_START = 1 << 9;
+        /// Invert the event input of comparator 0
+        #[cfg(feature = "min-samd51g")]
+        const COMP0_INVERT = 1 << 12;
+        /// Invert the event input of comparator 1
+        #[cfg(feature = "min-samd51g")]
+        const COMP1_INVERT = 1 << 13;
+    }
+}
+
+//==============================================================================
+//  Ac
+//==============================================================================
+
+/// The AC peripheral
+///
+/// The event and window configurations can only be changed while the AC is
+/// disabled. Changing them therefore briefly interrupts the comparators.
+pub struct Ac {
+    ac: AC,
+}
+
+impl Ac {
+    /// Reset and enable the AC, and return a token for each comparator
+    #[cfg(any(feature = "samd11", feature = "samd21"))]
+    #[inline]
+    pub fn new(
+        pm: &mut PM,
+        ac: AC,
+        _dig: AcDigClock,
+        _ana: AcAnaClock,
+    ) -> (Self, ComparatorToken<Comp0>, ComparatorToken<Comp1>) {
+        pm.apbcmask.modify(|_, w| w.ac_().set_bit());
+        Self::init(ac)
+    }
+
+    /// Reset and enable the AC, and return a token for each comparator
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub fn new(
+        mclk: &mut PM,
+        ac: AC,
+        _clock: AcClock,
+    ) -> (Self, ComparatorToken<Comp0>, ComparatorToken<Comp1>) {
+        mclk.apbcmask.modify(|_, w| w.ac_().set_bit());
+        Self::init(ac)
+    }
+
+    #[inline]
+    fn init(ac: AC) -> (Self, ComparatorToken<Comp0>, ComparatorToken<Comp1>) {
+        ac.ctrla.write(|w| w.swrst().set_bit());
+        wait_sync(&ac);
+        ac.ctrla.write(|w| w.enable().set_bit());
+        wait_sync(&ac);
+
+        (
+            Self { ac },
+            ComparatorToken { id: PhantomData },
+            ComparatorToken { id: PhantomData },
+        )
+    }
+
+    /// Run `f` with the AC disabled, then enable it again
+    #[inline]
+    fn with_disabled(&mut self, f: impl FnOnce(&AC)) {
+        self.ac.ctrla.modify(|_, w| w.enable().clear_bit());
+        wait_sync(&self.ac);
+        f(&self.ac);
+        wait_sync(&self.ac);
+        self.ac.ctrla.modify(|_, w| w.enable().set_bit());
+        wait_sync(&self.ac);
+    }
+
+    /// Set the event inputs and outputs
+    #[inline]
+    pub fn set_events(&mut self, events: Events) {
+        self.with_disabled(|ac| ac.evctrl.write(|w| unsafe { w.bits(events.bits()) }));
+    }
+
+    /// Get the event inputs and outputs
+    #[inline]
+    pub fn get_events(&self) -> Events {
+        Events::from_bits_truncate(self.ac.evctrl.read().bits())
+    }
+
+    /// Combine both comparators into a window comparator
+    ///
+    /// Requiring both comparators guarantees that they are enabled.
+    #[inline]
+    pub fn enable_window<P0, N0, P1, N1>(
+        &mut self,
+        _comp0: &Comparator<Comp0, P0, N0>,
+        _comp1: &Comparator<Comp1, P1, N1>,
+        interrupt: WindowInterrupt,
+    ) where
+        P0: PositiveInput,
+        N0: NegativeInput,
+        P1: PositiveInput,
+        N1: NegativeInput,
+    {
+        self.with_disabled(|ac| {
+            ac.winctrl.write(|w| {
+                w.wintsel0().variant(interrupt);
+                w.wen0().set_bit()
+            })
+        });
+    }
+
+    /// Disable window mode
+    #[inline]
+    pub fn disable_window(&mut self) {
+        self.with_disabled(|ac| ac.winctrl.reset());
+    }
+
+    /// Position of the signal relative to the window
+    #[inline]
+    pub fn window_state(&self) -> WindowState {
+        match self.ac.statusa.read().wstate0().variant() {
+            Variant::Val(state) => state,
+            Variant::Res(_) => unreachable!("reserved window state"),
+        }
+    }
+
+    /// Enable the given interrupts
+    #[inline]
+    pub fn enable_interrupts(&mut self, interrupts: Interrupts) {
+        self.ac
+            .intenset
+            .write(|w| unsafe { w.bits(interrupts.bits()) });
+    }
+
+    /// Disable the given interrupts
+    #[inline]
+    pub fn disable_interrupts(&mut self, interrupts: Interrupts) {
+        self.ac
+            .intenclr
+            .write(|w| unsafe { w.bits(interrupts.bits()) });
+    }
+
+    /// Read the interrupt flags
+    #[inline]
+    pub fn read_interrupts(&self) -> Interrupts {
+        Interrupts::from_bits_truncate(self.ac.intflag.read().bits())
+    }
+
+    /// Clear the given interrupt flags
+    #[inline]
+    pub fn clear_interrupts(&mut self, interrupts: Interrupts) {
+        self.ac
+            .intflag
+            .write(|w| unsafe { w.bits(interrupts.bits()) });
+    }
+
+    /// Disable the AC and return the underlying peripheral
+    ///
+    /// Both comparators must be disabled first.
+    #[inline]
+    pub fn free(
+        self,
+        pm: &mut PM,
+        _comp0: ComparatorToken<Comp0>,
+        _comp1: ComparatorToken<Comp1>,
+    ) -> AC {
+        self.ac.ctrla.write(|w| w.swrst().set_bit());
+        wait_sync(&self.ac);
+        pm.apbcmask.modify(|_, w| w.ac_().clear_bit());
+        self.ac
+    }
+}
+
+//==============================================================================
+//  Comparator
+//==============================================================================
+
+/// A disabled comparator
+pub struct ComparatorToken<C: ComparatorId> {
+    id: PhantomData<C>,
+}
+
+impl<C: ComparatorId> ComparatorToken<C> {
+    /// Configure and enable the comparator
+    #[inline]
+    pub fn enable<P, N>(
+        self,
+        positive: P,
+        negative: N,
+        config: ComparatorConfig,
+    ) -> Comparator<C, P, N>
+    where
+        P: PositiveInput,
+        N: NegativeInput,
+    {
+        // SAFETY: Each comparator only writes to its own registers
+        let ac = unsafe { &*AC::ptr() };
+
+        if let Some(value) = positive.scaler().or_else(|| negative.scaler()) {
+            ac.scaler[C::NUM].write(|w| unsafe { w.value().bits(value) });
+        }
+
+        #[cfg(any(feature = "samd11", feature = "samd21"))]
+        {
+            ac.ctrla.modify(|_, w| w.runstdby().bit(config.run_standby));
+            wait_sync(ac);
+        }
+
+        ac.compctrl[C::NUM].write(|w| {
+            w.single().bit(config.single_shot);
+            w.intsel().variant(config.interrupt);
+            w.muxpos().variant(positive.muxpos());
+            w.muxneg().variant(negative.muxneg());
+            w.flen().variant(config.filter);
+            w.out().variant(config.output);
+
+            #[cfg(any(feature = "samd11", feature = "samd21"))]
+            {
+                w.speed().variant(config.speed);
+                w.hyst().bit(config.hysteresis);
+            }
+
+            #[cfg(feature = "min-samd51g")]
+            {
+                w.speed().high();
+                w.runstdby().bit(config.run_standby);
+                if let Some(level) = config.hysteresis {
+                    w.hysten().set_bit();
+                    w.hyst().variant(level);
+                }
+            }
+
+            w
+        });
+        wait_sync(ac);
+        ac.compctrl[C::NUM].modify(|_, w| w.enable().set_bit());
+        wait_sync(ac);
+
+        let comparator = Comparator {
+            positive,
+            negative,
+            single_shot: config.single_shot,
+            id: PhantomData,
+        };
+        if !comparator.single_shot {
+            while !comparator.is_ready() {}
+        }
+        comparator
+    }
+}
+
+/// An enabled comparator, owning its inputs
+pub struct Comparator<C, P, N>
+where
+    C: ComparatorId,
+    P: PositiveInput,
+    N: NegativeInput,
+{
+    positive: P,
+    negative: N,
+    single_shot: bool,
+    id: PhantomData<C>,
+}
+
+impl<C, P, N> Comparator<C, P, N>
+where
+    C: ComparatorId,
+    P: PositiveInput,
+    N: NegativeInput,
+{
+    #[inline]
+    fn ac(&self) -> &RegisterBlock {
+        // SAFETY: Each comparator only writes to its own registers, and
+        // interrupt flags are cleared by writing ones
+        unsafe { &*AC::ptr() }
+    }
+
+    /// Returns `true` if the comparator output is valid
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        self.ac().statusb.read().bits() & (1 << C::NUM) != 0
+    }
+
+    /// Current comparator output, `true` if the positive input is above the
+    /// negative input
+    #[inline]
+    pub fn state(&self) -> bool {
+        self.ac().statusa.read().bits() & (1 << C::NUM) != 0
+    }
+
+    /// Run a single-shot comparison and return its result
+    ///
+    /// For a continuous comparator, this is equivalent to
+    /// [`state`](Self::state).
+    #[inline]
+    pub fn compare(&mut self) -> bool {
+        if self.single_shot {
+            let ac = self.ac();
+            ac.ctrlb.write(|w| unsafe { w.bits(1 << C::NUM) });
+            wait_sync(ac);
+            while !self.is_ready() {}
+        }
+        self.state()
+    }
+
+    /// Check and clear the interrupt flag of the comparator
+    #[inline]
+    pub fn check_and_clear_interrupt(&mut self) -> bool {
+        let flag = 1 << C::NUM;
+        let ac = self.ac();
+        let set = ac.intflag.read().bits() & flag != 0;
+        if set {
+            ac.intflag.write(|w| unsafe { w.bits(flag) });
+        }
+        set
+    }
+
+    /// Disable the comparator and return its token and inputs
+    #[inline]
+    pub fn disable(self) -> (ComparatorToken<C>, P, N) {
+        let ac = self.ac();
+        ac.compctrl[C::NUM].write(|w| w.enable().clear_bit());
+        wait_sync(ac);
+        (
+            ComparatorToken { id: PhantomData },
+            self.positive,
+            self.negative,
+        )
+    }
+}
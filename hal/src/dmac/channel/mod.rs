@@ -295,6 +295,26 @@ impl<Id: ChId> Channel<Id, Busy> {
         !self.regs.chctrla.read().enable().bit_is_set()
     }
 
+    /// Returns the number of beats left in the ongoing block transfer.
+    ///
+    /// While the channel is granted access to the bus, the count is read from
+    /// the ACTIVE register. Otherwise, it is read from the channel's
+    /// write-back descriptor, which the DMAC updates when it switches to
+    /// another channel.
+    #[inline]
+    pub(crate) fn remaining_beats(&mut self) -> u16 {
+        let active = self.regs.active.read();
+        if active.abusy().bit_is_set() && active.id().bits() == Id::U8 {
+            active.btcnt().bits()
+        } else {
+            // SAFETY: The write-back descriptor is only written by the DMAC, and
+            // is read volatilely as it may change at any time.
+            unsafe {
+                core::ptr::read_volatile(core::ptr::addr_of!(super::WRITEBACK[Id::USIZE].btcnt))
+            }
+        }
+    }
+
     /// Stop transfer on channel whether or not the transfer has completed
     ///
     /// # Return
@@ -16,7 +16,7 @@ use paste::paste;
 
 use crate::pac::{
     self,
-    dmac::{BUSYCH, INTSTATUS, PENDCH, SWTRIGCTRL},
+    dmac::{ACTIVE, BUSYCH, INTSTATUS, PENDCH, SWTRIGCTRL},
     Peripherals, DMAC,
 };
 
@@ -253,6 +253,18 @@ reg_proxy!(busych, bit, r);
 reg_proxy!(pendch, bit, r);
 reg_proxy!(swtrigctrl, bit, rw);
 
+// The ACTIVE register is shared by all channels, and is not banked by CHID on
+// SAMD11/SAMD21
+reg_proxy!(@new active);
+
+impl<Id: ChId> ActiveProxy<Id, ACTIVE> {
+    /// Read the ACTIVE register
+    #[inline]
+    pub fn read(&self) -> pac::dmac::active::R {
+        self.dmac.active.read()
+    }
+}
+
 /// Acts as a proxy to the PAC DMAC object. Only registers and bits
 /// within registers that should be readable/writable by specific
 /// [`Channel`]s are exposed.
@@ -268,6 +280,7 @@ pub(super) struct RegisterBlock<Id: ChId> {
     pub busych: BusychProxy<Id, BUSYCH>,
     pub pendch: PendchProxy<Id, PENDCH>,
     pub swtrigctrl: SwtrigctrlProxy<Id, SWTRIGCTRL>,
+    pub active: ActiveProxy<Id, ACTIVE>,
     #[cfg(feature = "min-samd51g")]
     pub chprilvl: ChprilvlProxy<Id, CHPRILVL>,
 }
@@ -285,6 +298,7 @@ impl<Id: ChId> RegisterBlock<Id> {
             busych: BusychProxy::new(),
             pendch: PendchProxy::new(),
             swtrigctrl: SwtrigctrlProxy::new(),
+            active: ActiveProxy::new(),
             #[cfg(feature = "min-samd51g")]
             chprilvl: ChprilvlProxy::new(),
         }
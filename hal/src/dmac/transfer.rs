@@ -86,7 +86,7 @@
 use super::{
     channel::{AnyChannel, Busy, CallbackStatus, Channel, ChannelId, InterruptFlags, Ready},
     dma_controller::{ChId, TriggerAction, TriggerSource},
-    BlockTransferControl, DmacDescriptor, Error, Result, DESCRIPTOR_SECTION,
+    BlockTransferControl, DmacDescriptor, Error, Result, DESCRIPTOR_SECTION, WRITEBACK,
 };
 use crate::typelevel::{Is, Sealed};
 use core::{ptr::null_mut, sync::atomic};
@@ -410,6 +410,11 @@ where
         // in the entire library that this section or the array
         // will be written to.
         DESCRIPTOR_SECTION[id] = xfer_descriptor;
+
+        // The DMAC only updates the write-back descriptor once the channel has
+        // been granted access to the bus. Initialize it, so that the remaining
+        // beat count is valid before the first beat.
+        WRITEBACK[id] = xfer_descriptor;
     }
 }
 
@@ -454,8 +459,31 @@ where
             complete: false,
         }
     }
+
+    /// Set the TCMPL flag each time a block completes.
+    ///
+    /// By default, the flag is only set at the end of the transaction, which
+    /// circular transfers never reach. The channel interrupt itself is left
+    /// disabled, so the flag has to be polled with
+    /// [`block_transfer_interrupt`](Transfer::block_transfer_interrupt).
+    #[inline]
+    pub(crate) fn with_block_flag(self) -> Self {
+        let id = <C as AnyChannel>::Id::USIZE;
+
+        // SAFETY: The channel is not running yet, and only the descriptor
+        // belonging to our channel is modified.
+        unsafe {
+            DESCRIPTOR_SECTION[id].btctrl.set_blockact(BLOCKACT_INT);
+            WRITEBACK[id].btctrl.set_blockact(BLOCKACT_INT);
+        }
+        self
+    }
 }
 
+/// BTCTRL.BLOCKACT value raising the block interrupt flag at the end of
+/// each block
+const BLOCKACT_INT: u8 = 0x1;
+
 impl<C, S, D> Transfer<C, BufferPair<S, D>>
 where
     S: Buffer,
@@ -563,12 +591,8 @@ where
     /// Check if the transfer has completed
     #[inline]
     pub fn complete(&mut self) -> bool {
-        if !self.complete {
-            let chan = self.chan.as_mut();
-            let complete = chan.xfer_complete();
-            self.complete = complete;
-        }
-        self.complete
+        let chan = self.chan.as_mut();
+        latch_complete(&mut self.complete, || chan.xfer_complete())
     }
 
     /// Checks and clears the block transfer complete interrupt flag
@@ -580,6 +604,15 @@ where
             .tcmpl()
     }
 
+    /// Returns the number of beats left in the ongoing block transfer
+    ///
+    /// For circular transfers, this tracks the position of the DMAC within
+    /// the buffers, as the block restarts each time it completes.
+    #[inline]
+    pub fn remaining_beats(&mut self) -> usize {
+        self.chan.as_mut().remaining_beats().into()
+    }
+
     /// Modify a completed transfer with new `source` and `destination`, then
     /// restart.
     ///
@@ -590,7 +623,8 @@ where
     pub fn recycle(&mut self, mut source: S, mut destination: D) -> Result<(S, D)> {
         Self::check_buffer_pair(&source, &destination)?;
 
-        if !self.complete() {
+        let chan = self.chan.as_mut();
+        if !take_complete(&mut self.complete, || chan.xfer_complete()) {
             return Err(Error::InvalidState);
         }
 
@@ -620,7 +654,8 @@ where
     pub fn recycle_source(&mut self, mut destination: D) -> Result<D> {
         Self::check_buffer_pair(&self.buffers.source, &destination)?;
 
-        if !self.complete() {
+        let chan = self.chan.as_mut();
+        if !take_complete(&mut self.complete, || chan.xfer_complete()) {
             return Err(Error::InvalidState);
         }
 
@@ -645,7 +680,8 @@ where
     pub fn recycle_destination(&mut self, mut source: S) -> Result<S> {
         Self::check_buffer_pair(&source, &self.buffers.destination)?;
 
-        if !self.complete() {
+        let chan = self.chan.as_mut();
+        if !take_complete(&mut self.complete, || chan.xfer_complete()) {
             return Err(Error::InvalidState);
         }
 
@@ -698,3 +734,46 @@ where
         }
     }
 }
+
+/// Latch the completion of a transfer, so that the channel is only polled
+/// until it completes
+#[inline]
+fn latch_complete(complete: &mut bool, xfer_complete: impl FnOnce() -> bool) -> bool {
+    if !*complete {
+        *complete = xfer_complete();
+    }
+    *complete
+}
+
+/// Check that a transfer completed before it is recycled, and reset the latch
+/// for the restarted transfer
+#[inline]
+fn take_complete(complete: &mut bool, xfer_complete: impl FnOnce() -> bool) -> bool {
+    let done = latch_complete(complete, xfer_complete);
+    *complete = false;
+    done
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{latch_complete, take_complete};
+
+    #[test]
+    fn recycle_twice() {
+        let mut complete = false;
+        assert!(!latch_complete(&mut complete, || false));
+        assert!(latch_complete(&mut complete, || true));
+
+        // First recycle, of the completed transfer
+        assert!(take_complete(&mut complete, || true));
+
+        // The restarted transfer is still running, and must not be seen as
+        // complete because the previous one was
+        assert!(!latch_complete(&mut complete, || false));
+        assert!(!take_complete(&mut complete, || false));
+
+        // Second recycle, once it completes
+        assert!(take_complete(&mut complete, || true));
+        assert!(!latch_complete(&mut complete, || false));
+    }
+}
@@ -0,0 +1,316 @@
+//! # Device Service Unit (DSU)
+//!
+//! The DSU computes the CRC-32 of a memory range in hardware, runs the
+//! built-in self-test (MBIST) of the RAM, and identifies the device.
+//!
+//! The DSU is write-protected by the Peripheral Access Controller at reset.
+//! [`Dsu::new`] removes the protection, and [`Dsu::free`] restores it.
+//!
+//! ```
+//! use atsamd_hal::dsu::Dsu;
+//!
+//! // `peripherals.PM` on SAMD11 and SAMD21
+//! let mut dsu = Dsu::new(&mut peripherals.MCLK, peripherals.DSU);
+//! let crc = dsu.crc32(0x4000, 0x1_0000).unwrap();
+//! let id = dsu.device_id();
+//! ```
+
+use crate::pac::DSU;
+
+#[cfg(feature = "min-samd51g")]
+use crate::pac::{pac::wrctrl::KEY_A, MCLK as PM, PAC};
+
+#[cfg(any(feature = "samd11", feature = "samd21"))]
+use crate::pac::{pac0, PM};
+
+// Bit of the DSU in the write protection registers of PAC1
+#[cfg(any(feature = "samd11", feature = "samd21"))]
+const PAC1_DSU: u32 = 1 << 1;
+
+// PAC1 is missing from the SVD files, but it has the same layout as PAC0
+#[cfg(any(feature = "samd11", feature = "samd21"))]
+const PAC1: *const pac0::RegisterBlock = 0x4100_0000 as *const _;
+
+// Peripheral identifier of the DSU, for the PAC
+#[cfg(feature = "min-samd51g")]
+const DSU_PERID: u16 = 33;
+
+//==============================================================================
+//  Error
+//==============================================================================
+
+/// Errors related to the DSU
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The address or length is not a multiple of 4
+    Unaligned,
+    /// The range is outside of the memory map
+    BusError,
+    /// The operation is not allowed, because the device is protected by the
+    /// security bit or the DSU is write-protected
+    Protected,
+    /// The MBIST found a faulty bit
+    Failure {
+        /// Address of the faulty word
+        address: u32,
+        /// Index of the faulty bit in the word
+        bit: u8,
+    },
+}
+
+//==============================================================================
+//  DeviceId
+//==============================================================================
+
+/// Device series, decoded from the [`DeviceId`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Series {
+    Samd11,
+    Samd21,
+    Samd51,
+    Same51,
+    Same53,
+    Same54,
+    /// A device not supported by this HAL
+    Unknown,
+}
+
+/// Contents of the device identification (`DID`) register
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceId(u32);
+
+impl DeviceId {
+    /// Wrap the raw value of the `DID` register
+    #[inline]
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Raw value of the `DID` register
+    #[inline]
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Processor: 1 for a Cortex-M0+, 6 for a Cortex-M4
+    #[inline]
+    pub const fn processor(&self) -> u8 {
+        (self.0 >> 28) as u8
+    }
+
+    /// Product family
+    #[inline]
+    pub const fn family(&self) -> u8 {
+        (self.0 >> 23) as u8 & 0x1F
+    }
+
+    /// Product series number, within the family
+    #[inline]
+    pub const fn series_number(&self) -> u8 {
+        (self.0 >> 16) as u8 & 0x3F
+    }
+
+    /// Die number
+    #[inline]
+    pub const fn die(&self) -> u8 {
+        (self.0 >> 12) as u8 & 0xF
+    }
+
+    /// Revision of the die, where 0 is revision A
+    #[inline]
+    pub const fn revision(&self) -> u8 {
+        (self.0 >> 8) as u8 & 0xF
+    }
+
+    /// Revision of the die, as a letter
+    #[inline]
+    pub fn revision_letter(&self) -> char {
+        (b'A' + self.revision()) as char
+    }
+
+    /// Device variant within the series, which identifies the pin count and
+    /// the memory sizes; see the datasheet
+    #[inline]
+    pub const fn device_select(&self) -> u8 {
+        self.0 as u8
+    }
+
+    /// Series of the device
+    #[inline]
+    pub fn series(&self) -> Series {
+        match (self.processor(), self.family(), self.series_number()) {
+            (1, 0, 3) => Series::Samd11,
+            (1, 0, 1) => Series::Samd21,
+            (6, 0, 6) => Series::Samd51,
+            (6, 3, 1) => Series::Same51,
+            (6, 3, 3) => Series::Same53,
+            (6, 3, 4) => Series::Same54,
+            _ => Series::Unknown,
+        }
+    }
+}
+
+//==============================================================================
+//  Dsu
+//==============================================================================
+
+/// DSU peripheral
+pub struct Dsu {
+    dsu: DSU,
+}
+
+impl Dsu {
+    /// Enable the DSU and remove its write protection
+    #[inline]
+    pub fn new(pm: &mut PM, dsu: DSU) -> Self {
+        pm.apbbmask.modify(|_, w| w.dsu_().set_bit());
+        Self::set_write_protection(false);
+        Self { dsu }
+    }
+
+    #[inline]
+    fn set_write_protection(protect: bool) {
+        // SAFETY: Writes to the PAC only change the protection of the given
+        // peripheral, and we own the DSU
+        #[cfg(any(feature = "samd11", feature = "samd21"))]
+        unsafe {
+            let pac1 = &*PAC1;
+            if protect {
+                pac1.wpset.write(|w| w.bits(PAC1_DSU));
+            } else {
+                pac1.wpclr.write(|w| w.bits(PAC1_DSU));
+            }
+        }
+        #[cfg(feature = "min-samd51g")]
+        unsafe {
+            let key = if protect { KEY_A::SET } else { KEY_A::CLR };
+            (*PAC::ptr()).wrctrl.write(|w| {
+                w.perid().bits(DSU_PERID);
+                w.key().variant(key)
+            });
+        }
+    }
+
+    /// Compute the CRC-32 of the `length` bytes of memory at `address`
+    ///
+    /// The CRC is the common CRC-32 of IEEE 802.3 and zlib. Both `address`
+    /// and `length` must be multiples of 4. When the device is protected by
+    /// the security bit, the CRC may only be computed over some memory
+    /// ranges; see the datasheet.
+    #[inline]
+    pub fn crc32(&mut self, address: u32, length: u32) -> Result<u32, Error> {
+        self.setup(address, length)?;
+        // SAFETY: Any initial value is valid
+        self.dsu.data.write(|w| unsafe { w.bits(0xFFFF_FFFF) });
+        self.dsu.ctrl.write(|w| w.crc().set_bit());
+        self.wait()?;
+        Ok(!self.dsu.data.read().bits())
+    }
+
+    /// Run the memory built-in self-test (MBIST) over the `length` bytes of
+    /// RAM at `address`
+    ///
+    /// The test stops at the first faulty bit, which is returned as
+    /// [`Error::Failure`]. Both `address` and `length` must be multiples
+    /// of 4.
+    ///
+    /// # Safety
+    ///
+    /// The test overwrites the memory range, which must not hold anything in
+    /// use, including the stack.
+    #[inline]
+    pub unsafe fn memory_test(&mut self, address: u32, length: u32) -> Result<(), Error> {
+        self.setup(address, length)?;
+        self.dsu.ctrl.write(|w| w.mbist().set_bit());
+        self.wait()?;
+        if self.dsu.statusa.read().fail().bit_is_set() {
+            return Err(Error::Failure {
+                address: self.dsu.addr.read().bits() & !0b11,
+                bit: self.dsu.data.read().bits() as u8 & 0x1F,
+            });
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn setup(&mut self, address: u32, length: u32) -> Result<(), Error> {
+        if address & 0b11 != 0 || length & 0b11 != 0 {
+            return Err(Error::Unaligned);
+        }
+        // Clear the status of the previous operation
+        self.dsu.statusa.write(|w| {
+            w.done().set_bit();
+            w.berr().set_bit();
+            w.fail().set_bit();
+            w.perr().set_bit()
+        });
+        // SAFETY: The address and length were checked to be aligned
+        self.dsu.addr.write(|w| unsafe { w.bits(address) });
+        self.dsu.length.write(|w| unsafe { w.bits(length) });
+        Ok(())
+    }
+
+    #[inline]
+    fn wait(&self) -> Result<(), Error> {
+        loop {
+            let status = self.dsu.statusa.read();
+            if status.perr().bit_is_set() {
+                return Err(Error::Protected);
+            }
+            if status.berr().bit_is_set() {
+                return Err(Error::BusError);
+            }
+            if status.done().bit_is_set() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Whether the device is protected by the security bit
+    #[inline]
+    pub fn is_protected(&self) -> bool {
+        self.dsu.statusb.read().prot().bit_is_set()
+    }
+
+    /// Whether a debugger is connected
+    #[inline]
+    pub fn debugger_present(&self) -> bool {
+        self.dsu.statusb.read().dbgpres().bit_is_set()
+    }
+
+    /// Identification of the device
+    #[inline]
+    pub fn device_id(&self) -> DeviceId {
+        DeviceId(self.dsu.did.read().bits())
+    }
+
+    /// Restore the write protection of the DSU and return it
+    #[inline]
+    pub fn free(self) -> DSU {
+        Self::set_write_protection(true);
+        self.dsu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeviceId, Series};
+
+    #[test]
+    fn device_id() {
+        let samd51j19a = DeviceId::from_bits(0x6006_0306);
+        assert_eq!(samd51j19a.series(), Series::Samd51);
+        assert_eq!(samd51j19a.revision_letter(), 'D');
+        assert_eq!(samd51j19a.device_select(), 6);
+
+        let same54p20a = DeviceId::from_bits(0x6184_0000);
+        assert_eq!(same54p20a.processor(), 6);
+        assert_eq!(same54p20a.family(), 3);
+        assert_eq!(same54p20a.series_number(), 4);
+        assert_eq!(same54p20a.series(), Series::Same54);
+
+        assert_eq!(DeviceId::from_bits(0x1001_0305).series(), Series::Samd21);
+        assert_eq!(DeviceId::from_bits(0x1003_0000).series(), Series::Samd11);
+        assert_eq!(DeviceId::from_bits(0x1081_0000).series(), Series::Unknown);
+    }
+}
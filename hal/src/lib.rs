@@ -96,9 +96,13 @@ macro_rules! dbgprint {
     ($($arg:tt)*) => {{}};
 }
 
+#[cfg(feature = "device")]
+pub mod ac;
 #[cfg(feature = "device")]
 pub mod delay;
 #[cfg(feature = "device")]
+pub mod dsu;
+#[cfg(feature = "device")]
 pub mod gpio;
 #[cfg(feature = "device")]
 pub mod prelude;
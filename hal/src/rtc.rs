@@ -134,6 +134,12 @@ impl<Mode: RtcMode> Rtc<Mode> {
         self.sync();
     }
 
+    /// Whether any interrupt of the RTC is enabled
+    #[inline]
+    pub(crate) fn interrupt_enabled(&self) -> bool {
+        self.mode0().intenset.read().bits() != 0
+    }
+
     #[inline]
     fn enable(&mut self, enable: bool) {
         if enable {
@@ -218,6 +224,70 @@ impl<Mode: RtcMode> Rtc<Mode> {
     }
 }
 
+/// Tamper input of the RTC
+///
+/// See the I/O multiplexing table of the datasheet for the pin of each input.
+#[cfg(feature = "min-samd51g")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TamperInput {
+    In0,
+    In1,
+    In2,
+    In3,
+    In4,
+}
+
+#[cfg(feature = "min-samd51g")]
+impl<Mode: RtcMode> Rtc<Mode> {
+    /// Wake the device up, from any sleep mode but off, on an edge of the
+    /// tamper input. The edge is rising if `rising` is set, and falling
+    /// otherwise.
+    ///
+    /// The tamper interrupt is enabled. The RTC is briefly disabled while the
+    /// input is configured.
+    pub fn enable_tamper_wake(&mut self, input: TamperInput, rising: bool, debounce: bool) {
+        let n = input as u32;
+        let mut bits = 1 << (2 * n);
+        if rising {
+            bits |= 1 << (16 + n);
+        }
+        if debounce {
+            bits |= 1 << (24 + n);
+        }
+        self.write_tampctrl(n, bits);
+        self.mode0().intenset.write(|w| w.tamper().set_bit());
+    }
+
+    /// Whether edges of the tamper input wake the device up
+    pub(crate) fn tamper_wake_enabled(&self, input: TamperInput) -> bool {
+        let n = input as u32;
+        let action = self.mode0().tampctrl.read().bits() >> (2 * n) & 0b11;
+        action == 1 && self.mode0().intenset.read().tamper().bit_is_set()
+    }
+
+    /// Stop waking the device up on edges of the tamper input
+    pub fn disable_tamper_wake(&mut self, input: TamperInput) {
+        self.write_tampctrl(input as u32, 0);
+    }
+
+    /// Clear the tamper interrupt flag and the detected tamper inputs
+    pub fn clear_tamper(&mut self) {
+        self.mode0().tampid.write(|w| unsafe { w.bits(0x1F) });
+        self.mode0().intflag.write(|w| w.tamper().set_bit());
+    }
+
+    fn write_tampctrl(&mut self, n: u32, bits: u32) {
+        let mask = 0b11 << (2 * n) | 1 << (16 + n) | 1 << (24 + n);
+        // TAMPCTRL is enable-protected
+        self.sync();
+        self.enable(false);
+        self.mode0()
+            .tampctrl
+            .modify(|r, w| unsafe { w.bits(r.bits() & !mask | bits) });
+        self.enable(true);
+    }
+}
+
 impl Rtc<Count32Mode> {
     /// Configures the RTC in 32-bit counter mode with no prescaler (default
     /// state after reset) and the counter initialized to zero.
@@ -1,8 +1,7 @@
 //! # Version 2 of the SERCOM module
 //!
-//! This module provides a new API for the SERCOM peripherals. So far, only the
-//! [`pad`] and [`spi`] modules have been updated, but it is expected that the
-//! `uart`, and `i2c` modules will eventually receive updates as well.
+//! This module provides a new API for the SERCOM peripherals. So far, the
+//! [`pad`], [`spi`], [`uart`] and [`i2c`] modules have been updated.
 
 use core::ops::Deref;
 
@@ -37,6 +36,7 @@ use crate::typelevel::Sealed;
 pub mod pad;
 pub use pad::*;
 
+pub mod i2c;
 pub mod spi_future;
 pub mod uart;
 
@@ -1,22 +1,62 @@
 //! Use the DMA Controller to perform transfers using the SERCOM peripheral
 //!
-//! See the [`mod@uart`] and [`mod@spi`] modules for the corresponding DMA
-//! transfer implementations.
+//! See the [`mod@uart`], [`mod@spi`] and [`mod@i2c`] modules for the
+//! corresponding DMA transfer implementations.
+
+use core::sync::atomic;
+
+use embedded_hal::timer::CountDown;
 
 use crate::{
     dmac::{
         self,
         channel::{AnyChannel, Busy, CallbackStatus, Channel, InterruptFlags, Ready},
+        dma_controller::ChId,
         transfer::BufferPair,
         Beat, Buffer, Transfer, TriggerAction,
     },
     sercom::v2::{
+        i2c::{self, I2c, I2cBusReady},
         spi::{self, AnySpi, Spi},
         uart::{self, Capability, Receive, Transmit, Uart},
         Sercom,
     },
 };
 
+//=============================================================================
+// DATA register
+//=============================================================================
+
+/// The `DATA` register of a SERCOM, used by transfers which leave the
+/// peripheral struct available to the user
+struct DataRegister<W> {
+    ptr: *mut W,
+}
+
+// SAFETY: The pointer is only handed to the DMAC as a transfer address, and is
+// never dereferenced by the CPU, so it may be moved to another execution
+// context.
+unsafe impl<W: Send> Send for DataRegister<W> {}
+
+unsafe impl<W: Beat> Buffer for DataRegister<W> {
+    type Beat = W;
+
+    #[inline]
+    fn dma_ptr(&mut self) -> *mut Self::Beat {
+        self.ptr
+    }
+
+    #[inline]
+    fn incrementing(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn buffer_len(&self) -> usize {
+        1
+    }
+}
+
 //=============================================================================
 // UART DMA transfers
 //=============================================================================
@@ -77,9 +117,10 @@ where
         // SAFETY: We use new_unchecked to avoid having to pass a 'static self as the
         // destination buffer. This is safe as long as we guarantee the destination
         // buffer is static.
-        unsafe { dmac::Transfer::new_unchecked(channel, self, buf, false) }
+        let transfer = unsafe { dmac::Transfer::new_unchecked(channel, self, buf, false) }
             .with_waker(waker)
-            .begin(C::Sercom::DMA_RX_TRIGGER, trigger_action)
+            .begin(C::Sercom::DMA_RX_TRIGGER, trigger_action);
+        Ok(transfer)
     }
 }
 
@@ -116,9 +157,501 @@ where
         // SAFETY: We use new_unchecked to avoid having to pass a 'static self as the
         // destination buffer. This is safe as long as we guarantee the source buffer is
         // static.
-        unsafe { dmac::Transfer::new_unchecked(channel, buf, self, false) }
+        let transfer = unsafe { dmac::Transfer::new_unchecked(channel, buf, self, false) }
             .with_waker(waker)
-            .begin(C::Sercom::DMA_TX_TRIGGER, trigger_action)
+            .begin(C::Sercom::DMA_TX_TRIGGER, trigger_action);
+        Ok(transfer)
+    }
+}
+
+//=============================================================================
+// UART DMA ring buffer
+//=============================================================================
+
+/// The buffer of an [`RxRing`], shared between the DMAC and the reader
+///
+/// The `&'static mut` slice is converted into a raw pointer as soon as the
+/// ring is created, so that no reference to the buffer is alive while the
+/// DMAC writes to it. It is only turned back into a slice once the transfer
+/// is stopped.
+#[derive(Clone, Copy)]
+struct RingBuffer<W> {
+    ptr: *mut W,
+    len: usize,
+}
+
+// SAFETY: The buffer is 'static, and only accessed by the DMAC and the RxRing
+// owning the RingBuffer. Moving the RxRing to another execution context, such
+// as an interrupt handler, moves the only handle to the buffer along with it.
+unsafe impl<W: Send> Send for RingBuffer<W> {}
+
+unsafe impl<W: Beat> Buffer for RingBuffer<W> {
+    type Beat = W;
+
+    #[inline]
+    fn dma_ptr(&mut self) -> *mut Self::Beat {
+        self.ptr
+    }
+
+    #[inline]
+    fn incrementing(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn buffer_len(&self) -> usize {
+        self.len
+    }
+}
+
+/// Circular transfer filling the buffer of an [`RxRing`]
+type RingTransfer<Id, W> = Transfer<Channel<Id, Busy>, BufferPair<DataRegister<W>, RingBuffer<W>>>;
+
+/// Error returned by an [`RxRing`] when the DMAC caught up with the reader
+///
+/// The unread words were overwritten, or could be at any time. They are
+/// discarded, and the ring resumes from the current position of the DMAC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overrun;
+
+/// Position of the reader and of the DMAC within the buffer of an [`RxRing`]
+#[derive(Clone, Copy)]
+struct RingIndex {
+    /// Length of the buffer
+    len: usize,
+    /// Index of the next word to read
+    read: usize,
+    /// Number of beats completed by the DMAC in its current lap, when last
+    /// observed. Ranges from `0` to `len` included.
+    write: usize,
+    /// Number of words received and not read yet
+    pending: usize,
+    /// Total number of words received, wrapping
+    received: usize,
+    /// Whether the DMAC caught up with the reader
+    overrun: bool,
+    /// Whether a lap was counted before its TCMPL flag was observed
+    unflagged_lap: bool,
+}
+
+impl RingIndex {
+    #[inline]
+    fn new(len: usize) -> Self {
+        Self {
+            len,
+            read: 0,
+            write: 0,
+            pending: 0,
+            received: 0,
+            overrun: false,
+            unflagged_lap: false,
+        }
+    }
+
+    /// Update the position of the DMAC
+    ///
+    /// `position` is the number of beats completed in the current lap, and
+    /// `lap` tells whether a lap was completed since the previous update.
+    #[inline]
+    fn update(&mut self, position: usize, mut lap: bool) {
+        if lap && self.unflagged_lap {
+            // This flag belongs to the lap which was already counted
+            lap = false;
+            self.unflagged_lap = false;
+        }
+        if !lap && position < self.write {
+            // The DMAC restarted the block before the flag was observed
+            lap = true;
+            self.unflagged_lap = true;
+        }
+
+        let advance = if lap {
+            let position = position % self.len;
+            let advance = self.len - self.write + position;
+            self.write = position;
+            advance
+        } else {
+            let advance = position - self.write;
+            self.write = position;
+            advance
+        };
+
+        self.received = self.received.wrapping_add(advance);
+        self.pending += advance;
+        if self.pending >= self.len {
+            self.overrun = true;
+        }
+    }
+
+    /// Report and clear an overrun, discarding the unread words
+    #[inline]
+    fn check_overrun(&mut self) -> Result<(), Overrun> {
+        if self.overrun {
+            self.overrun = false;
+            self.read = self.write % self.len;
+            self.pending = 0;
+            Err(Overrun)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Mark `count` words as read
+    #[inline]
+    fn consume(&mut self, count: usize) {
+        self.read = (self.read + count) % self.len;
+        self.pending -= count;
+    }
+}
+
+/// Continuous, DMA-backed reception into a ring buffer
+///
+/// An [`RxRing`] is created by [`Uart::receive_ring_with_dma`]. A circular DMA
+/// transfer writes each received word into the buffer, and restarts from its
+/// beginning when it reaches the end, so that words keep being received while
+/// the previous ones are processed. The words are consumed with
+/// [`read`](RxRing::read), which follows the position of the DMAC without any
+/// critical section.
+///
+/// # Overruns
+///
+/// The ring holds at most `len - 1` unread words, where `len` is the length of
+/// the buffer. The laps of the DMAC are counted with the TCMPL flag of the
+/// channel. When the DMAC catches up with the reader, [`read`](RxRing::read)
+/// and [`available`](RxRing::available) return an [`Overrun`] error, and the
+/// unread words are discarded. A single flag can only count one lap, so
+/// overruns are only guaranteed to be detected if fewer than `2 * len` words
+/// are received between two calls to these methods.
+///
+/// # Idle line detection
+///
+/// Variable-length messages are delimited by idle periods of the line.
+/// [`poll_idle`](RxRing::poll_idle) detects them with a periodic timer, and
+/// the `RXS` and `RXBRK` flags of the SERCOM. `RXS` is only raised if
+/// [start-of-frame detection] is enabled, and `RXBRK` in auto-baud frame
+/// formats. Both flags are polled, so their interrupts do not need to be
+/// enabled. If neither flag is available, the position of the DMAC is used
+/// alone, and the word being received when the timer expires is missed.
+///
+/// ```
+/// static mut RX_BUFFER: [u8; 1024] = [0; 1024];
+///
+/// // Period of a few words at the configured baud rate
+/// timer.start(50.us());
+/// let mut ring = uart.receive_ring_with_dma(unsafe { &mut RX_BUFFER[..] }, channel);
+///
+/// // In the timer interrupt handler
+/// if ring.poll_idle(&mut timer) {
+///     let mut message = [0; 256];
+///     match ring.read(&mut message) {
+///         Ok(len) => (), // Process &message[..len]
+///         Err(Overrun) => (), // Words were lost
+///     }
+/// }
+/// ```
+///
+/// [start-of-frame detection]: uart::Config::start_of_frame_detection
+pub struct RxRing<C, D, Id>
+where
+    C: uart::ValidConfig,
+    C::Word: Beat,
+    D: Receive,
+    Id: ChId,
+{
+    uart: Uart<C, D>,
+    transfer: RingTransfer<Id, C::Word>,
+    buf: RingBuffer<C::Word>,
+    index: RingIndex,
+    last: usize,
+    active: bool,
+}
+
+impl<C, D> Uart<C, D>
+where
+    C: uart::ValidConfig,
+    C::Word: Beat,
+    D: Receive,
+{
+    /// Transform an [`Uart`] into an [`RxRing`], continuously receiving into
+    /// the provided buffer
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is shorter than 2 words, or longer than 65535
+    /// words.
+    #[inline]
+    pub fn receive_ring_with_dma<Ch>(
+        self,
+        buf: &'static mut [C::Word],
+        channel: Ch,
+    ) -> RxRing<C, D, Ch::Id>
+    where
+        Ch: AnyChannel<Status = Ready>,
+    {
+        let len = buf.len();
+        assert!(len > 1 && len <= usize::from(u16::MAX));
+        let buf = RingBuffer {
+            ptr: buf.as_mut_ptr(),
+            len,
+        };
+        let data = DataRegister {
+            ptr: self.data_ptr(),
+        };
+
+        #[cfg(feature = "min-samd51g")]
+        let trigger_action = TriggerAction::BURST;
+
+        #[cfg(any(feature = "samd11", feature = "samd21"))]
+        let trigger_action = TriggerAction::BEAT;
+
+        // SAFETY: The buffer is 'static, and the DATA register lives as long as
+        // the Uart, which is owned by the RxRing. The transfer is never dropped
+        // while running, since it can only be released by RxRing::stop.
+        let transfer = unsafe { Transfer::new_unchecked(channel, data, buf, true) }
+            .with_block_flag()
+            .begin(C::Sercom::DMA_RX_TRIGGER, trigger_action);
+
+        RxRing {
+            uart: self,
+            transfer,
+            buf,
+            index: RingIndex::new(len),
+            last: 0,
+            active: false,
+        }
+    }
+}
+
+impl<C, D, Id> RxRing<C, D, Id>
+where
+    C: uart::ValidConfig,
+    C::Word: Beat,
+    D: Receive,
+    Id: ChId,
+{
+    /// Update the index with the current position of the DMAC
+    #[inline]
+    fn sync(&mut self) {
+        let mut lap = self.transfer.block_transfer_interrupt();
+        let mut remaining = self.transfer.remaining_beats();
+        if !lap && self.transfer.block_transfer_interrupt() {
+            // A lap completed while reading the position, which may belong to
+            // either lap
+            lap = true;
+            remaining = self.transfer.remaining_beats();
+        }
+        self.index.update(self.index.len - remaining, lap);
+    }
+
+    /// Number of received words, which were not read yet
+    ///
+    /// Returns an [`Overrun`] error if the DMAC caught up with the reader, in
+    /// which case the unread words are discarded.
+    #[inline]
+    pub fn available(&mut self) -> Result<usize, Overrun> {
+        self.sync();
+        self.index.check_overrun()?;
+        Ok(self.index.pending)
+    }
+
+    /// Read the received words into `words`, and return the number of words
+    /// read
+    ///
+    /// Returns `Ok(0)` if no word was received since the last read, and an
+    /// [`Overrun`] error if the DMAC caught up with the reader, in which case
+    /// the unread words are discarded.
+    #[inline]
+    pub fn read(&mut self, words: &mut [C::Word]) -> Result<usize, Overrun> {
+        let count = core::cmp::min(self.available()?, words.len());
+
+        // Read the words only after the position of the DMAC
+        atomic::fence(atomic::Ordering::Acquire);
+
+        let mut read = self.index.read;
+        for word in &mut words[..count] {
+            // SAFETY: The index is within the buffer, which is only written by
+            // the DMAC
+            *word = unsafe { self.buf.ptr.add(read).read_volatile() };
+            read = if read + 1 == self.buf.len {
+                0
+            } else {
+                read + 1
+            };
+        }
+
+        // The DMAC may have overwritten the words while they were copied
+        atomic::fence(atomic::Ordering::Acquire);
+        self.sync();
+        self.index.check_overrun()?;
+
+        self.index.consume(count);
+        Ok(count)
+    }
+
+    /// Check whether the line went idle after receiving words, using only
+    /// the position of the DMAC
+    ///
+    /// Returns `true` if there are words to read, and no word was received
+    /// since the previous call. Prefer [`poll_idle`](RxRing::poll_idle),
+    /// which also accounts for the word currently being received.
+    #[inline]
+    pub fn idle(&mut self) -> bool {
+        self.sync();
+        let received = self.index.received;
+        let idle = received == self.last && (self.index.pending != 0 || self.index.overrun);
+        self.last = received;
+        idle
+    }
+
+    /// Check whether the line went idle after receiving a message
+    ///
+    /// `timer` must be a periodic timer started with the idle period, for
+    /// example a few words at the configured baud rate. If the period did not
+    /// elapse yet, returns `false` right away. Otherwise, the line is idle if
+    /// neither the `RXS` nor the `RXBRK` flag was raised, and no word was
+    /// received, during the last period. The flags are cleared.
+    ///
+    /// Returns `true` once per message, when the line goes idle after words
+    /// were received.
+    #[inline]
+    pub fn poll_idle<T: CountDown>(&mut self, timer: &mut T) -> bool {
+        if timer.wait().is_err() {
+            return false;
+        }
+
+        let starts = self.uart.read_flags() & (uart::Flags::RXS | uart::Flags::RXBRK);
+        self.uart.clear_flags(starts);
+
+        self.sync();
+        let received = self.index.received;
+        let busy = !starts.is_empty() || received != self.last;
+        self.last = received;
+
+        if busy {
+            self.active = true;
+            false
+        } else {
+            core::mem::replace(&mut self.active, false)
+        }
+    }
+
+    /// Obtain a reference to the [`Uart`], to read its flags and errors
+    #[inline]
+    pub fn uart(&self) -> &Uart<C, D> {
+        &self.uart
+    }
+
+    /// Clear interrupt flags
+    ///
+    /// See [`Uart::clear_flags`].
+    #[inline]
+    pub fn clear_flags(&mut self, flags: uart::Flags) {
+        self.uart.clear_flags(flags);
+    }
+
+    /// Enable interrupts for the specified flags
+    ///
+    /// See [`Uart::enable_interrupts`].
+    #[inline]
+    pub fn enable_interrupts(&mut self, flags: uart::Flags) {
+        self.uart.enable_interrupts(flags);
+    }
+
+    /// Disable interrupts for the specified flags
+    ///
+    /// See [`Uart::disable_interrupts`].
+    #[inline]
+    pub fn disable_interrupts(&mut self, flags: uart::Flags) {
+        self.uart.disable_interrupts(flags);
+    }
+
+    /// Clear the status flags
+    ///
+    /// See [`Uart::clear_status`].
+    #[inline]
+    pub fn clear_status(&mut self, status: uart::Status) {
+        self.uart.clear_status(status);
+    }
+
+    /// Stop the transfer, and return the [`Uart`], the channel and the buffer
+    ///
+    /// The words which were not read are lost.
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    pub fn stop(self) -> (Uart<C, D>, Channel<Id, Ready>, &'static mut [C::Word]) {
+        let (channel, _, buf) = self.transfer.stop();
+
+        // SAFETY: The pointer and length were taken from a 'static mut slice,
+        // which is not accessed by the DMAC anymore. The RxRing is consumed,
+        // so this is the only reference to the buffer.
+        let buf = unsafe { core::slice::from_raw_parts_mut(buf.ptr, buf.len) };
+        (self.uart, channel, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Overrun, RingIndex};
+
+    #[test]
+    fn ring_wrap() {
+        let mut index = RingIndex::new(8);
+        index.update(6, false);
+        assert_eq!(index.check_overrun(), Ok(()));
+        assert_eq!(index.pending, 6);
+        index.consume(6);
+
+        // The DMAC restarts the block, and writes 3 more words
+        index.update(1, true);
+        assert_eq!(index.check_overrun(), Ok(()));
+        assert_eq!(index.pending, 3);
+        assert_eq!(index.read, 6);
+        index.consume(3);
+        assert_eq!(index.read, 1);
+
+        // The block restarts before the flag is observed, then the stale
+        // flag must not count another lap
+        index.update(7, false);
+        index.consume(6);
+        index.update(2, false);
+        assert_eq!(index.pending, 3);
+        index.update(2, true);
+        assert_eq!(index.check_overrun(), Ok(()));
+        assert_eq!(index.pending, 3);
+
+        // Completed block, not restarted yet
+        index.consume(3);
+        index.update(8, false);
+        assert_eq!(index.pending, 6);
+        index.update(0, true);
+        assert_eq!(index.pending, 6);
+        assert_eq!(index.check_overrun(), Ok(()));
+    }
+
+    #[test]
+    fn ring_full() {
+        // A full lap between two reads must not look like an empty ring
+        let mut index = RingIndex::new(8);
+        index.update(3, false);
+        index.consume(3);
+        index.update(3, true);
+        assert_eq!(index.check_overrun(), Err(Overrun));
+        assert_eq!(index.pending, 0);
+        assert_eq!(index.read, 3);
+
+        // At most len - 1 words can be held
+        index.update(2, true);
+        assert_eq!(index.check_overrun(), Ok(()));
+        assert_eq!(index.pending, 7);
+        index.update(3, false);
+        assert_eq!(index.check_overrun(), Err(Overrun));
+
+        // More than a lap between two reads
+        let mut index = RingIndex::new(8);
+        index.update(5, false);
+        index.update(7, true);
+        assert_eq!(index.check_overrun(), Err(Overrun));
+        assert_eq!(index.read, 7);
     }
 }
 
@@ -144,7 +677,7 @@ unsafe impl<P, M, L> Buffer for Spi<spi::Config<P, M, L>>
 where
     spi::Config<P, M, L>: spi::ValidConfig,
     P: spi::ValidPads,
-    M: spi::MasterMode,
+    M: spi::OpMode,
     L: CharSize,
     L::Word: Beat,
 {
@@ -259,3 +792,339 @@ where
             .begin(<Self as AnySpi>::Sercom::DMA_RX_TRIGGER, trigger_action)
     }
 }
+
+//=============================================================================
+// SPI slave DMA exchange
+//=============================================================================
+
+/// Transfer receiving the frames of a [`SlaveExchange`]
+type SlaveRxTransfer<R, W, B> = Transfer<Channel<R, Busy>, BufferPair<DataRegister<W>, B>>;
+
+/// Transfer sending the frames of a [`SlaveExchange`]
+type SlaveTxTransfer<T, W, B> = Transfer<Channel<T, Busy>, BufferPair<B, DataRegister<W>>>;
+
+/// DMA-backed, double-buffered data exchange of an SPI slave
+///
+/// A [`SlaveExchange`] is created by [`Spi::exchange_with_dma`]. Two DMA
+/// transfers run at the same time: one receives a frame into the receive
+/// buffer, while the other sends the transmit buffer. At the end of each
+/// frame, [`swap`](SlaveExchange::swap) installs a new pair of buffers, and
+/// returns the previous ones, so that the received frame can be processed and
+/// the next response prepared while the master transfers the following frame.
+///
+/// Each frame must have the length of the buffers. Detect the end of the
+/// frames with [`frame_event`](SlaveExchange::frame_event), from the SERCOM
+/// interrupt handler with the `TXC` interrupt enabled.
+///
+/// ```
+/// // In the SERCOM interrupt handler
+/// if let Some(FrameEvent::End) = exchange.frame_event() {
+///     match exchange.swap(spare_rx, spare_tx) {
+///         Ok((received, sent)) => { /* Process the frame */ }
+///         // The frame was too short
+///         Err((rx, tx)) => { /* Restart the exchange */ }
+///     }
+/// }
+/// ```
+pub struct SlaveExchange<P, L, R, T, B>
+where
+    spi::Config<P, spi::Slave, L>: spi::ValidConfig,
+    P: spi::Tx + spi::Rx,
+    L: CharSize,
+    L::Word: Beat,
+    R: ChId,
+    T: ChId,
+    B: Buffer<Beat = L::Word>,
+{
+    spi: Spi<spi::Config<P, spi::Slave, L>>,
+    rx: SlaveRxTransfer<R, L::Word, B>,
+    tx: SlaveTxTransfer<T, L::Word, B>,
+}
+
+impl<P, L> Spi<spi::Config<P, spi::Slave, L>>
+where
+    Self: Buffer<Beat = L::Word>,
+    spi::Config<P, spi::Slave, L>: spi::ValidConfig,
+    P: spi::Tx + spi::Rx,
+    L: CharSize,
+    L::Word: Beat,
+{
+    /// Transform an [`Spi`] slave into a [`SlaveExchange`], receiving the next
+    /// frame into `rx_buf` while sending `tx_buf`
+    ///
+    /// Enable [`preload`](spi::Config::preload), so that the first word is
+    /// sent from the start of the frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffers do not have the same length.
+    #[inline]
+    pub fn exchange_with_dma<R, T, B>(
+        mut self,
+        rx_buf: B,
+        tx_buf: B,
+        rx_channel: R,
+        tx_channel: T,
+    ) -> SlaveExchange<P, L, R::Id, T::Id, B>
+    where
+        R: AnyChannel<Status = Ready>,
+        T: AnyChannel<Status = Ready>,
+        B: Buffer<Beat = L::Word> + 'static,
+    {
+        assert_eq!(rx_buf.buffer_len(), tx_buf.buffer_len());
+        let ptr = self.dma_ptr();
+        SlaveExchange::start(self, ptr, rx_buf, tx_buf, rx_channel, tx_channel)
+    }
+}
+
+impl<P, L, R, T, B> SlaveExchange<P, L, R, T, B>
+where
+    spi::Config<P, spi::Slave, L>: spi::ValidConfig,
+    P: spi::Tx + spi::Rx,
+    L: CharSize,
+    L::Word: Beat,
+    R: ChId,
+    T: ChId,
+    B: Buffer<Beat = L::Word> + 'static,
+{
+    /// Begin both transfers
+    #[inline]
+    fn start(
+        spi: Spi<spi::Config<P, spi::Slave, L>>,
+        ptr: *mut L::Word,
+        rx_buf: B,
+        tx_buf: B,
+        rx_channel: impl AnyChannel<Id = R, Status = Ready>,
+        tx_channel: impl AnyChannel<Id = T, Status = Ready>,
+    ) -> Self {
+        #[cfg(feature = "min-samd51g")]
+        let trigger_action = TriggerAction::BURST;
+
+        #[cfg(any(feature = "samd11", feature = "samd21"))]
+        let trigger_action = TriggerAction::BEAT;
+
+        // SAFETY: The buffers are 'static, and the DATA register lives as long
+        // as the Spi, which is owned by the SlaveExchange. The transfers are
+        // never dropped while running, since they can only be released by
+        // SlaveExchange::stop.
+        let rx =
+            unsafe { Transfer::new_unchecked(rx_channel, DataRegister { ptr }, rx_buf, false) }
+                .begin(
+                    <Spi<spi::Config<P, spi::Slave, L>> as AnySpi>::Sercom::DMA_RX_TRIGGER,
+                    trigger_action,
+                );
+        let tx =
+            unsafe { Transfer::new_unchecked(tx_channel, tx_buf, DataRegister { ptr }, false) }
+                .begin(
+                    <Spi<spi::Config<P, spi::Slave, L>> as AnySpi>::Sercom::DMA_TX_TRIGGER,
+                    trigger_action,
+                );
+
+        Self { spi, rx, tx }
+    }
+
+    /// Check for the start or the end of a frame
+    ///
+    /// See [`Spi::frame_event`].
+    #[inline]
+    pub fn frame_event(&mut self) -> Option<spi::FrameEvent> {
+        self.spi.frame_event()
+    }
+
+    /// Obtain a reference to the [`Spi`], to read its flags and errors
+    #[inline]
+    pub fn spi(&self) -> &Spi<spi::Config<P, spi::Slave, L>> {
+        &self.spi
+    }
+
+    /// Install a new pair of buffers after a frame, and return the previous
+    /// ones, as `(received, sent)`
+    ///
+    /// This must be called between the end of a frame and the start of the
+    /// next one. If the frame was shorter than the buffers, the transfers are
+    /// still running, and the new buffers are returned as an error. The
+    /// exchange must then be [`restart`](SlaveExchange::restart)ed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffers do not have the same length.
+    #[inline]
+    pub fn swap(&mut self, rx_buf: B, tx_buf: B) -> Result<(B, B), (B, B)> {
+        assert_eq!(rx_buf.buffer_len(), tx_buf.buffer_len());
+        if !self.rx.complete() || !self.tx.complete() {
+            return Err((rx_buf, tx_buf));
+        }
+
+        // Both transfers are complete and the DATA register has a length of
+        // one, so recycling cannot fail
+        let sent = match self.tx.recycle_destination(tx_buf) {
+            Ok(sent) => sent,
+            Err(_) => unreachable!(),
+        };
+        let received = match self.rx.recycle_source(rx_buf) {
+            Ok(received) => received,
+            Err(_) => unreachable!(),
+        };
+        Ok((received, sent))
+    }
+
+    /// Restart both transfers from the start of their buffers, after a frame
+    /// which was shorter than the buffers
+    ///
+    /// The SPI transmit buffer may still hold words of the previous response,
+    /// which will be sent at the start of the next frame.
+    #[inline]
+    pub fn restart(self) -> Self {
+        let (rx_channel, data, rx_buf) = self.rx.stop();
+        let (tx_channel, tx_buf, _) = self.tx.stop();
+        Self::start(self.spi, data.ptr, rx_buf, tx_buf, rx_channel, tx_channel)
+    }
+
+    /// Stop both transfers, and return the [`Spi`], the receive and transmit
+    /// channels, and the receive and transmit buffers
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    pub fn stop(
+        self,
+    ) -> (
+        Spi<spi::Config<P, spi::Slave, L>>,
+        Channel<R, Ready>,
+        Channel<T, Ready>,
+        B,
+        B,
+    ) {
+        let (rx_channel, _, rx_buf) = self.rx.stop();
+        let (tx_channel, tx_buf, _) = self.tx.stop();
+        (self.spi, rx_channel, tx_channel, rx_buf, tx_buf)
+    }
+}
+
+//=============================================================================
+// I2C DMA transfers
+//=============================================================================
+
+unsafe impl<C: i2c::AnyConfig> Buffer for I2c<C> {
+    type Beat = i2c::Word;
+
+    #[inline]
+    fn dma_ptr(&mut self) -> *mut Self::Beat {
+        self.data_ptr()
+    }
+
+    #[inline]
+    fn incrementing(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn buffer_len(&self) -> usize {
+        1
+    }
+}
+
+impl<C: i2c::AnyConfig> I2c<C> {
+    /// Transform an [`I2c`] into a DMA [`Transfer`]) and
+    /// start sending the provided buffer to the slave at `address`.
+    ///
+    /// If the slave does not acknowledge its address, the error is returned
+    /// along with the [`I2c`], the buffer and the channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is empty or longer than 255 bytes.
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    pub fn send_with_dma<Ch, B, W>(
+        mut self,
+        address: u8,
+        _ready_token: I2cBusReady,
+        buf: B,
+        mut channel: Ch,
+        waker: W,
+    ) -> Result<Transfer<Channel<Ch::Id, Busy>, BufferPair<B, Self>, W>, (i2c::Error, Self, B, Ch)>
+    where
+        Ch: AnyChannel<Status = Ready>,
+        B: Buffer<Beat = i2c::Word> + 'static,
+        W: FnOnce(CallbackStatus) + 'static,
+    {
+        let len = buf.buffer_len();
+        assert!(len > 0 && len <= 255);
+
+        // The transfer only begins once the slave has acknowledged its
+        // address, as the DMA would never be triggered otherwise
+        if let Err(error) = self.start_dma_write(address, len as u8) {
+            return Err((error, self, buf, channel));
+        }
+
+        channel
+            .as_mut()
+            .enable_interrupts(InterruptFlags::new().with_tcmpl(true));
+
+        #[cfg(feature = "min-samd51g")]
+        let trigger_action = TriggerAction::BURST;
+
+        #[cfg(any(feature = "samd11", feature = "samd21"))]
+        let trigger_action = TriggerAction::BEAT;
+
+        // SAFETY: We use new_unchecked to avoid having to pass a 'static self as the
+        // destination buffer. This is safe as long as we guarantee the source buffer is
+        // static.
+        let transfer = unsafe { dmac::Transfer::new_unchecked(channel, buf, self, false) }
+            .with_waker(waker)
+            .begin(C::Sercom::DMA_TX_TRIGGER, trigger_action);
+        Ok(transfer)
+    }
+
+    /// Transform an [`I2c`] into a DMA [`Transfer`]) and
+    /// start receiving into the provided buffer from the slave at `address`.
+    ///
+    /// If the slave does not acknowledge its address, the error is returned
+    /// along with the [`I2c`], the buffer and the channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is empty or longer than 255 bytes.
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    pub fn receive_with_dma<Ch, B, W>(
+        mut self,
+        address: u8,
+        _ready_token: I2cBusReady,
+        buf: B,
+        mut channel: Ch,
+        waker: W,
+    ) -> Result<Transfer<Channel<Ch::Id, Busy>, BufferPair<Self, B>, W>, (i2c::Error, Self, B, Ch)>
+    where
+        Ch: AnyChannel<Status = Ready>,
+        B: Buffer<Beat = i2c::Word> + 'static,
+        W: FnOnce(CallbackStatus) + 'static,
+    {
+        let len = buf.buffer_len();
+        assert!(len > 0 && len <= 255);
+
+        // The transfer only begins once the first byte has been received, as
+        // the DMA would never be triggered if the address was not
+        // acknowledged
+        if let Err(error) = self.start_dma_read(address, len as u8) {
+            return Err((error, self, buf, channel));
+        }
+
+        channel
+            .as_mut()
+            .enable_interrupts(InterruptFlags::new().with_tcmpl(true));
+
+        #[cfg(feature = "min-samd51g")]
+        let trigger_action = TriggerAction::BURST;
+
+        #[cfg(any(feature = "samd11", feature = "samd21"))]
+        let trigger_action = TriggerAction::BEAT;
+
+        // SAFETY: We use new_unchecked to avoid having to pass a 'static self as the
+        // destination buffer. This is safe as long as we guarantee the destination
+        // buffer is static.
+        let transfer = unsafe { dmac::Transfer::new_unchecked(channel, self, buf, false) }
+            .with_waker(waker)
+            .begin(C::Sercom::DMA_RX_TRIGGER, trigger_action);
+        Ok(transfer)
+    }
+}
@@ -0,0 +1,406 @@
+//! Use the SERCOM peripheral as an I2C master
+//!
+//! Configuring an I2C peripheral occurs in three steps. First, you must create
+//! a set of [`Pads`] for use by the peripheral. Next, you assemble pieces into
+//! a [`Config`] struct. After configuring the peripheral, you then [`enable`]
+//! it, yielding a functional [`I2c`] struct. Transactions are performed using
+//! the [`i2c`](embedded_hal::blocking::i2c) traits from embedded HAL.
+//!
+//! # [`Pads`]
+//!
+//! The I2C peripheral always uses [`Pad0`] as SDA and [`Pad1`] as SCL, and both
+//! [`Pin`]s must be mapped to the same [`Sercom`] (see the datasheet). On
+//! SAMx5x chips, they must also belong to the same [`IoSet`], which is the
+//! second type parameter of [`Pads`]. This HAL makes it impossible to use
+//! invalid [`Pin`]/[`Pad`] combinations. Aliases defining the pad types can be
+//! provided by the [`bsp_pins!`](crate::bsp_pins) macro.
+//!
+//! A set of [`Pads`] is created from both [`Pin`]s at once. On SAMD21 and
+//! SAMx5x chips, [`Pads::new`] automatically converts each pin to the correct
+//! [`PinMode`]. But for SAMD11 chips, users must manually convert each pin
+//! beforehand, because the same [`PinId`] can correspond to two different
+//! [`PadNum`]s for the *same* `Sercom`.
+//!
+//! ```
+//! use atsamd_hal::pac::Peripherals;
+//! use atsamd_hal::gpio::v2::Pins;
+//! use atsamd_hal::sercom::v2::{i2c, IoSet1, Sercom0};
+//!
+//! let mut peripherals = Peripherals::take().unwrap();
+//! let pins = Pins::new(peripherals.PORT);
+//! // Assuming SAMx5x
+//! let pads = i2c::Pads::<Sercom0, IoSet1, _, _>::new(pins.pa08, pins.pa09);
+//! ```
+//!
+//! Alternatively, the [`PadsFromIds`] alias defines a set of [`Pads`] from
+//! [`PinId`]s.
+//!
+//! # [`Config`]
+//!
+//! Next, create a [`Config`] struct, which represents the I2C peripheral in its
+//! disabled state. Upon creation, the [`Config`] takes ownership of both the
+//! [`Pads`] struct and the PAC [`Sercom`] struct. It takes a reference to the
+//! PM or MCLK, so that it can enable the APB clock, and it takes a frequency
+//! to indicate the GCLK configuration. Users are responsible for correctly
+//! configuring the GCLK.
+//!
+//! The [`Config`] struct can be configured with builder methods, like
+//! [`baud`](Config::baud), or with setters, like
+//! [`set_baud`](Config::set_baud). The peripheral setup ends with a call to
+//! [`enable`], which consumes the [`Config`] and returns an enabled [`I2c`]
+//! peripheral.
+//!
+//! ```
+//! use atsamd_hal::sercom::v2::i2c::{self, InactiveTimeout};
+//! use atsamd_hal::time::U32Ext;
+//!
+//! let i2c = i2c::Config::new(&mclk, sercom, pads, 48.mhz())
+//!     .baud(400.khz())?
+//!     .inactive_timeout(InactiveTimeout::Us205)
+//!     .enable();
+//! ```
+//!
+//! # Speed modes
+//!
+//! The [`I2cMode`] is selected from the SCL frequency given to
+//! [`baud`](Config::baud):
+//!
+//! * Up to 100 kHz, Standard-mode
+//! * Up to 400 kHz, Fast-mode
+//! * Up to 1 MHz, Fast-mode Plus
+//! * Up to 3.4 MHz, high-speed mode
+//!
+//! Fast-mode Plus and high-speed mode require pins which support them; see
+//! the datasheet. In high-speed mode, each transaction starts with the master
+//! code sent at 400 kHz. The slowest SCL frequency is about 1/520 of the GCLK
+//! frequency; [`baud`](Config::baud) fails with [`Error::InvalidBaud`] below
+//! it.
+//!
+//! # Timeouts
+//!
+//! Without a timeout, a slave which holds SCL low blocks the transactions
+//! forever. The SMBus SCL low timeouts, enabled with
+//! [`low_timeout`](Config::low_timeout) and
+//! [`extend_timeout`](Config::extend_timeout), make those transactions fail
+//! with [`Error::Timeout`]. The
+//! [`inactive_timeout`](Config::inactive_timeout) releases a bus left busy by
+//! another master.
+//!
+//! # [`I2c`]
+//!
+//! Only the [`I2c`] struct can actually perform transactions. To do so, use
+//! the embedded HAL [`Write`], [`Read`] and [`WriteRead`] traits. They are
+//! implemented for both 7-bit and 10-bit addresses: a `u8` address is sent
+//! as a 7-bit address, while a `u16` address is sent as a 10-bit address.
+//! Addresses which don't fit in their width fail with
+//! [`Error::InvalidAddress`].
+//!
+//! ```
+//! use embedded_hal::blocking::i2c::{Write, WriteRead};
+//!
+//! let mut buffer = [0; 2];
+//! i2c.write_read(0x48_u8, &[0x00], &mut buffer)?;
+//! i2c.write(0x2A5_u16, &[0x01, 0x02])?;
+//! ```
+//!
+//! Transactions fail with an [`Error`] if the slave does not acknowledge, if
+//! another master wins the arbitration, or on a bus error or a timeout. A
+//! STOP condition is issued after each transaction, unless the bus was lost.
+//!
+//! # I2C slave
+//!
+//! The [`slave`] module uses the same [`Pads`] to respond to the transactions
+//! of another master, with interrupt-driven callbacks.
+//!
+//! # Reading the current configuration
+//!
+//! The `AsRef<Config<P>>` trait is implemented for `I2c<Config<P>>`. This
+//! means you can use the `get_` methods implemented for `Config`, since they
+//! take an `&self` argument.
+//!
+//! ```
+//! let baud = i2c.as_ref().get_baud();
+//! ```
+//!
+//! [`enable`]: Config::enable
+//! [`Pin`]: crate::gpio::v2::pin::Pin
+//! [`PinId`]: crate::gpio::v2::pin::PinId
+//! [`PinMode`]: crate::gpio::v2::pin::PinMode
+//! [`Write`]: embedded_hal::blocking::i2c::Write
+//! [`Read`]: embedded_hal::blocking::i2c::Read
+//! [`WriteRead`]: embedded_hal::blocking::i2c::WriteRead
+#![cfg_attr(
+    feature = "dma",
+    doc = "
+# Using I2C with DMA
+
+This HAL includes support for DMA-enabled I2C transfers. [`I2c`] implements
+the DMAC [`Buffer`] trait. The provided [`send_with_dma`] and
+[`receive_with_dma`] methods address a slave with a 7-bit address, then build
+and begin a [`dmac::Transfer`] of at most 255 bytes. The hardware does not
+acknowledge the last byte received and issues the STOP condition on its own.
+
+Before each transfer, [`init_dma_transfer`] waits for the bus and checks for
+errors, returning a token which proves that the bus is ready. The transfer
+only begins once the slave has acknowledged its address; otherwise, the
+error is returned along with the [`I2c`], the buffer and the channel.
+
+```
+/// Assume channel0 is a configured `dmac::Channel`
+let mut buffer: [u8; 16] = [0; 16];
+
+let token = i2c.init_dma_transfer()?;
+let transfer = match i2c.receive_with_dma(0x48, token, &mut buffer, channel0, ()) {
+    Ok(transfer) => transfer,
+    Err((error, i2c, buffer, channel0)) => return Err(error),
+};
+let (channel0, i2c, buffer) = transfer.wait();
+```
+
+[`Buffer`]: crate::dmac::transfer::Buffer
+[`send_with_dma`]: I2c::send_with_dma
+[`receive_with_dma`]: I2c::receive_with_dma
+[`init_dma_transfer`]: I2c::init_dma_transfer
+[`dmac::Transfer`]: crate::dmac::Transfer
+
+"
+)]
+
+#[cfg(any(feature = "samd11", feature = "samd21"))]
+#[path = "i2c/pads_thumbv6m.rs"]
+mod pads;
+
+#[cfg(feature = "min-samd51g")]
+#[path = "i2c/pads_thumbv7em.rs"]
+mod pads;
+
+pub use pads::*;
+
+mod reg;
+use reg::Registers;
+
+mod flags;
+pub use flags::*;
+
+mod config;
+pub use config::*;
+
+pub mod impl_ehal;
+
+pub mod slave;
+
+use crate::time::Hertz;
+
+/// Word size of an I2C transfer
+pub type Word = u8;
+
+//=============================================================================
+// Speed modes and timeouts
+//=============================================================================
+
+/// I2C transfer speed mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum I2cMode {
+    /// Standard-mode, up to 100 kHz
+    Standard,
+    /// Fast-mode, up to 400 kHz
+    Fast,
+    /// Fast-mode Plus, up to 1 MHz
+    FastPlus,
+    /// High-speed mode, up to 3.4 MHz
+    HighSpeed,
+}
+
+impl I2cMode {
+    /// Slowest mode supporting the SCL frequency `baud`
+    #[inline]
+    pub fn from_baud(baud: Hertz) -> Self {
+        match baud.0 {
+            0..=100_000 => I2cMode::Standard,
+            100_001..=400_000 => I2cMode::Fast,
+            400_001..=1_000_000 => I2cMode::FastPlus,
+            _ => I2cMode::HighSpeed,
+        }
+    }
+
+    /// Maximum SCL frequency of the mode
+    #[inline]
+    pub fn max_baud(&self) -> Hertz {
+        match self {
+            I2cMode::Standard => Hertz(100_000),
+            I2cMode::Fast => Hertz(400_000),
+            I2cMode::FastPlus => Hertz(1_000_000),
+            I2cMode::HighSpeed => Hertz(3_400_000),
+        }
+    }
+}
+
+/// Inactive bus timeout
+///
+/// The timeout is approximate, as it is counted in SCL periods.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InactiveTimeout {
+    /// No timeout
+    Disabled = 0,
+    /// 5-6 SCL periods, about 55 µs in Standard-mode
+    Us55 = 1,
+    /// 10-11 SCL periods, about 105 µs in Standard-mode
+    Us105 = 2,
+    /// 20-21 SCL periods, about 205 µs in Standard-mode
+    Us205 = 3,
+}
+
+/// State of the I2C bus, as seen by the master
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusState {
+    /// The state is unknown, after the peripheral is enabled
+    Unknown = 0,
+    /// The bus is idle
+    Idle = 1,
+    /// This master owns the bus
+    Owned = 2,
+    /// Another master owns the bus
+    Busy = 3,
+}
+
+//=============================================================================
+// I2c
+//=============================================================================
+
+/// Abstraction over an I2C peripheral configured as a master, allowing to
+/// perform I2C transactions
+pub struct I2c<C: AnyConfig> {
+    config: C,
+}
+
+impl<C: AnyConfig> I2c<C> {
+    /// Obtain a pointer to the `DATA` register. Necessary for DMA transfers.
+    #[cfg(feature = "dma")]
+    #[inline]
+    pub(crate) fn data_ptr(&self) -> *mut Word {
+        self.config.as_ref().registers.data_ptr()
+    }
+
+    /// Read the interrupt flags
+    #[inline]
+    pub fn read_flags(&self) -> Flags {
+        self.config.as_ref().registers.read_flags()
+    }
+
+    /// Clear interrupt flags
+    ///
+    /// Clearing the `MB` or `SB` flag while a transaction is performed by the
+    /// embedded HAL traits will make it hang.
+    #[inline]
+    pub fn clear_flags(&mut self, flags: Flags) {
+        self.config.as_mut().registers.clear_flags(flags);
+    }
+
+    /// Enable interrupts for the specified flags
+    #[inline]
+    pub fn enable_interrupts(&mut self, flags: Flags) {
+        self.config.as_mut().registers.enable_interrupts(flags);
+    }
+
+    /// Disable interrupts for the specified flags
+    #[inline]
+    pub fn disable_interrupts(&mut self, flags: Flags) {
+        self.config.as_mut().registers.disable_interrupts(flags);
+    }
+
+    /// Read the status flags
+    #[inline]
+    pub fn read_status(&self) -> Status {
+        self.config.as_ref().registers.read_status()
+    }
+
+    /// Clear the status flags
+    ///
+    /// The `RXNACK` and `CLKHOLD` flags are read-only, and are **SILENTLY
+    /// IGNORED**.
+    #[inline]
+    pub fn clear_status(&mut self, status: Status) {
+        self.config
+            .as_mut()
+            .registers
+            .clear_status(status & Status::ERRORS);
+    }
+
+    /// Read the state of the bus
+    #[inline]
+    pub fn bus_state(&self) -> BusState {
+        self.config.as_ref().registers.bus_state()
+    }
+
+    /// Wait for the bus to be ready, and check that no error occurred since
+    /// the last transaction
+    ///
+    /// A slave which did not acknowledge the last transaction is not an
+    /// error. The returned token is required to start a DMA transfer.
+    #[cfg(feature = "dma")]
+    #[inline]
+    pub fn init_dma_transfer(&mut self) -> Result<I2cBusReady, Error> {
+        let registers = &mut self.config.as_mut().registers;
+        registers.check_errors()?;
+        registers.wait_bus_ready();
+        Ok(I2cBusReady(()))
+    }
+
+    /// Start a write transaction of `length` bytes, for a DMA transfer, and
+    /// wait for the slave to acknowledge its address
+    #[cfg(feature = "dma")]
+    #[inline]
+    pub(crate) fn start_dma_write(&mut self, addr: u8, length: u8) -> Result<(), Error> {
+        self.config.as_mut().registers.start_dma_write(addr, length)
+    }
+
+    /// Start a read transaction of `length` bytes, for a DMA transfer, and
+    /// wait for the first byte
+    #[cfg(feature = "dma")]
+    #[inline]
+    pub(crate) fn start_dma_read(&mut self, addr: u8, length: u8) -> Result<(), Error> {
+        self.config.as_mut().registers.start_dma_read(addr, length)
+    }
+
+    /// Disable the I2C peripheral and return the underlying [`Config`]
+    #[inline]
+    pub fn disable(self) -> C {
+        let mut config = self.config;
+        config.as_mut().registers.disable();
+        config
+    }
+
+    /// Reconfigure the I2C peripheral
+    ///
+    /// Calling this method will temporarily disable the SERCOM peripheral, as
+    /// some registers are enable-protected. This may interrupt any ongoing
+    /// transactions.
+    ///
+    /// ```
+    /// i2c.reconfigure(|c| c.set_baud(1.mhz()).unwrap());
+    /// ```
+    #[inline]
+    pub fn reconfigure<F>(&mut self, update: F)
+    where
+        F: FnOnce(&mut SpecificConfig<C>),
+    {
+        self.config.as_mut().registers.disable();
+        update(self.config.as_mut());
+        self.config.as_mut().registers.enable();
+    }
+}
+
+impl<C: AnyConfig> AsRef<SpecificConfig<C>> for I2c<C> {
+    #[inline]
+    fn as_ref(&self) -> &SpecificConfig<C> {
+        self.config.as_ref()
+    }
+}
+
+/// Token proving that the bus is ready for a DMA transfer
+///
+/// It is returned by [`I2c::init_dma_transfer`].
+#[cfg(feature = "dma")]
+pub struct I2cBusReady(());
@@ -0,0 +1,316 @@
+//! I2C [`Config`] definition and implementation
+
+use core::cmp;
+
+use super::{Error, I2c, I2cMode, InactiveTimeout, PadSet, Registers};
+use crate::{
+    pac,
+    sercom::v2::*,
+    time::Hertz,
+    typelevel::{Is, Sealed},
+};
+
+//=============================================================================
+// Config
+//=============================================================================
+
+/// A configurable, disabled I2C peripheral
+///
+/// This `struct` represents a configurable I2C master in its disabled state.
+/// It is generic over the set of [`Pads`]. Upon creation, the [`Config`]
+/// takes ownership of the [`Sercom`] and resets it, returning it configured
+/// as an I2C master with a default configuration:
+///
+/// * 100 kHz, Standard-mode, or the slowest SCL frequency if the GCLK is
+///   faster than 52 MHz
+/// * Timeouts disabled
+///
+/// [`Config`] uses a builder-pattern API to configure the peripheral,
+/// culminating in a call to [`enable`], which consumes the [`Config`] and
+/// returns an enabled [`I2c`].
+///
+/// [`enable`]: Config::enable
+/// [`Pads`]: super::Pads
+pub struct Config<P>
+where
+    P: PadSet,
+{
+    pub(super) registers: Registers<P::Sercom>,
+    pads: P,
+    freq: Hertz,
+}
+
+/// Clock type needed to create a new [`Config`]. [`PM`](pac::PM) for thumbv6m
+/// targets.
+#[cfg(any(feature = "samd11", feature = "samd21"))]
+pub type Clock = pac::PM;
+
+/// Clock type needed to create a new [`Config`]. [`MCLK`](pac::MCLK) for
+/// thumbv7em targets.
+#[cfg(feature = "min-samd51g")]
+pub type Clock = pac::MCLK;
+
+impl<P: PadSet> Config<P> {
+    /// Create a new [`Config`] in the default configuration
+    ///
+    /// This function will enable the corresponding APB clock, reset the
+    /// [`Sercom`] peripheral, and return a [`Config`] in the default
+    /// configuration, with a 100 kHz SCL frequency.
+    ///
+    /// [`Config`] takes ownership of the [`Sercom`] and [`Pads`](super::Pads).
+    ///
+    /// Users must configure GCLK manually. The `freq` parameter represents the
+    /// GCLK frequency for this [`Sercom`] instance.
+    #[inline]
+    pub fn new(clk: &Clock, mut sercom: P::Sercom, pads: P, freq: impl Into<Hertz>) -> Self {
+        sercom.enable_apb_clock(clk);
+        Self::default(sercom, pads, freq)
+    }
+
+    /// Create a new [`Config`] in the default configuration
+    #[inline]
+    fn default(sercom: P::Sercom, pads: P, freq: impl Into<Hertz>) -> Self {
+        let mut registers = Registers::new(sercom);
+        registers.swrst();
+        registers.configure_mode();
+
+        let mut config = Self {
+            registers,
+            pads,
+            freq: freq.into(),
+        };
+        // The BAUD register can't divide a GCLK faster than 52 MHz down to
+        // 100 kHz
+        let baud = cmp::max(100_000, config.freq.0 / 520 + 1);
+        let _ = config.set_baud(Hertz(baud));
+        config
+    }
+
+    /// Trigger the [`Sercom`]'s SWRST and return a [`Config`] in the
+    /// default configuration.
+    #[inline]
+    pub fn reset(self) -> Config<P> {
+        Config::default(self.registers.free(), self.pads, self.freq)
+    }
+
+    /// Consume the [`Config`], reset the peripheral, and return the [`Sercom`]
+    /// and [`Pads`](super::Pads)
+    #[inline]
+    pub fn free(mut self) -> (P::Sercom, P) {
+        self.registers.swrst();
+        (self.registers.free(), self.pads)
+    }
+
+    /// Set the SCL frequency (builder pattern version)
+    ///
+    /// The [`I2cMode`] is selected from the frequency, and the `BAUD`
+    /// register is calculated from the stored GCLK frequency so that SCL is
+    /// never faster than requested. The rise time of SCL is neglected, so the
+    /// actual frequency is slightly lower.
+    ///
+    /// Fails with [`Error::InvalidBaud`] if the frequency is zero, or too slow
+    /// to be reached from the GCLK frequency.
+    #[inline]
+    pub fn baud<B: Into<Hertz>>(mut self, baud: B) -> Result<Self, Error> {
+        self.set_baud(baud)?;
+        Ok(self)
+    }
+
+    /// Set the SCL frequency (setter version)
+    ///
+    /// The [`I2cMode`] is selected from the frequency, and the `BAUD`
+    /// register is calculated from the stored GCLK frequency so that SCL is
+    /// never faster than requested. The rise time of SCL is neglected, so the
+    /// actual frequency is slightly lower.
+    ///
+    /// Fails with [`Error::InvalidBaud`] if the frequency is zero, or too slow
+    /// to be reached from the GCLK frequency, in which case the frequency is
+    /// unchanged.
+    #[inline]
+    pub fn set_baud<B: Into<Hertz>>(&mut self, baud: B) -> Result<(), Error> {
+        self.registers.set_baud(self.freq, baud.into())
+    }
+
+    /// Get the SCL frequency, as calculated from the `BAUD` register
+    #[inline]
+    pub fn get_baud(&self) -> Hertz {
+        self.registers.get_baud(self.freq)
+    }
+
+    /// Get the [`I2cMode`] matching the current SCL frequency
+    #[inline]
+    pub fn get_mode(&self) -> I2cMode {
+        I2cMode::from_baud(self.get_baud())
+    }
+
+    /// Set the inactive bus timeout (builder pattern version)
+    ///
+    /// When the bus has been inactive for longer than the timeout, the bus
+    /// state is forced to idle. This recovers from a bus left busy by a
+    /// master which did not issue a STOP condition.
+    #[inline]
+    pub fn inactive_timeout(mut self, timeout: InactiveTimeout) -> Self {
+        self.set_inactive_timeout(timeout);
+        self
+    }
+
+    /// Set the inactive bus timeout (setter version)
+    ///
+    /// When the bus has been inactive for longer than the timeout, the bus
+    /// state is forced to idle. This recovers from a bus left busy by a
+    /// master which did not issue a STOP condition.
+    #[inline]
+    pub fn set_inactive_timeout(&mut self, timeout: InactiveTimeout) {
+        self.registers.set_inactive_timeout(timeout);
+    }
+
+    /// Get the current inactive bus timeout
+    #[inline]
+    pub fn get_inactive_timeout(&self) -> InactiveTimeout {
+        self.registers.get_inactive_timeout()
+    }
+
+    /// Enable or disable the SCL low timeout (builder pattern version)
+    ///
+    /// When set, a transaction fails with [`Error::Timeout`](super::Error)
+    /// if SCL is held low for 25 to 35 ms, as in the SMBus specification.
+    /// This requires the GCLK of the slow clock of the [`Sercom`] to run at
+    /// 32 kHz.
+    #[inline]
+    pub fn low_timeout(mut self, enabled: bool) -> Self {
+        self.set_low_timeout(enabled);
+        self
+    }
+
+    /// Enable or disable the SCL low timeout (setter version)
+    ///
+    /// When set, a transaction fails with [`Error::Timeout`](super::Error)
+    /// if SCL is held low for 25 to 35 ms, as in the SMBus specification.
+    /// This requires the GCLK of the slow clock of the [`Sercom`] to run at
+    /// 32 kHz.
+    #[inline]
+    pub fn set_low_timeout(&mut self, enabled: bool) {
+        self.registers.set_low_timeout(enabled);
+    }
+
+    /// Get the current SCL low timeout setting
+    #[inline]
+    pub fn get_low_timeout(&self) -> bool {
+        self.registers.get_low_timeout()
+    }
+
+    /// Enable or disable the SCL low extend timeouts (builder pattern version)
+    ///
+    /// When set, a transaction fails with [`Error::Timeout`](super::Error)
+    /// if the cumulative time SCL is stretched exceeds 10 ms within a byte,
+    /// or 25 ms within a message, as in the SMBus specification. This
+    /// requires the GCLK of the slow clock of the [`Sercom`] to run at
+    /// 32 kHz.
+    #[inline]
+    pub fn extend_timeout(mut self, enabled: bool) -> Self {
+        self.set_extend_timeout(enabled);
+        self
+    }
+
+    /// Enable or disable the SCL low extend timeouts (setter version)
+    ///
+    /// When set, a transaction fails with [`Error::Timeout`](super::Error)
+    /// if the cumulative time SCL is stretched exceeds 10 ms within a byte,
+    /// or 25 ms within a message, as in the SMBus specification. This
+    /// requires the GCLK of the slow clock of the [`Sercom`] to run at
+    /// 32 kHz.
+    #[inline]
+    pub fn set_extend_timeout(&mut self, enabled: bool) {
+        self.registers.set_extend_timeout(enabled);
+    }
+
+    /// Get the current SCL low extend timeout setting
+    #[inline]
+    pub fn get_extend_timeout(&self) -> bool {
+        self.registers.get_extend_timeout()
+    }
+
+    /// Run in standby mode (builder pattern version)
+    ///
+    /// When set, the I2C peripheral will run in standby mode. See the
+    /// datasheet for more details.
+    #[inline]
+    pub fn run_in_standby(mut self, set: bool) -> Self {
+        self.set_run_in_standby(set);
+        self
+    }
+
+    /// Run in standby mode (setter version)
+    ///
+    /// When set, the I2C peripheral will run in standby mode. See the
+    /// datasheet for more details.
+    #[inline]
+    pub fn set_run_in_standby(&mut self, set: bool) {
+        self.registers.set_run_in_standby(set);
+    }
+
+    /// Get the current run in standby mode
+    #[inline]
+    pub fn get_run_in_standby(&self) -> bool {
+        self.registers.get_run_in_standby()
+    }
+
+    /// Enable the I2C peripheral and return an [`I2c`] struct.
+    ///
+    /// I2C transactions are not possible until the peripheral is enabled.
+    #[inline]
+    pub fn enable(mut self) -> I2c<Self> {
+        self.registers.enable();
+        I2c { config: self }
+    }
+}
+
+//=============================================================================
+// AnyConfig
+//=============================================================================
+
+/// Type class for all possible [`Config`] types
+///
+/// This trait uses the [`AnyKind`] trait pattern to create a [type class] for
+/// [`Config`] types. See the `AnyKind` documentation for more details on the
+/// pattern.
+///
+/// In addition to the normal, `AnyKind` associated types, this trait also
+/// copies the [`Sercom`] type, to make it easier to apply bounds to this type
+/// at the next level of abstraction.
+///
+/// [`AnyKind`]: crate::typelevel#anykind-trait-pattern
+/// [type class]: crate::typelevel#type-classes
+pub trait AnyConfig: Sealed + Is<Type = SpecificConfig<Self>> {
+    type Sercom: Sercom;
+    type Pads: PadSet<Sercom = Self::Sercom>;
+}
+
+/// Type alias to recover the specific [`Config`] type from an implementation of
+/// [`AnyConfig`]
+pub type SpecificConfig<C> = Config<<C as AnyConfig>::Pads>;
+
+/// Type alias to recover the specific [`Sercom`] type from an implementation of
+/// [`AnyConfig`]
+pub type ConfigSercom<C> = <C as AnyConfig>::Sercom;
+
+impl<P: PadSet> AsRef<Self> for Config<P> {
+    #[inline]
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl<P: PadSet> AsMut<Self> for Config<P> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut Self {
+        self
+    }
+}
+
+impl<P: PadSet> Sealed for Config<P> {}
+
+impl<P: PadSet> AnyConfig for Config<P> {
+    type Sercom = P::Sercom;
+    type Pads = P;
+}
@@ -0,0 +1,117 @@
+//! Flag definitions
+
+use bitflags::bitflags;
+use core::convert::TryFrom;
+
+//=============================================================================
+// Interrupt flags
+//=============================================================================
+
+bitflags! {
+    /// Interrupt bit flags for I2C master transactions
+    ///
+    /// The available interrupt flags are `MB`, `SB` and `ERROR`. The binary
+    /// format of the underlying bits exactly matches the INTFLAG bits.
+    pub struct Flags: u8 {
+        /// Master on bus: a byte was transmitted, or the address phase failed
+        const MB = 0x01;
+        /// Slave on bus: a byte was received
+        const SB = 0x02;
+        const ERROR = 0x80;
+    }
+}
+
+//=============================================================================
+// Status flags
+//=============================================================================
+
+bitflags! {
+    /// Status flags for I2C master transactions
+    ///
+    /// The available status flags are `BUSERR`, `ARBLOST`, `RXNACK`,
+    /// `LOWTOUT`, `CLKHOLD`, `MEXTTOUT`, `SEXTTOUT` and `LENERR`. The binary
+    /// format of the underlying bits exactly matches the STATUS bits. The
+    /// `BUSSTATE` field is read with [`I2c::bus_state`](super::I2c::bus_state).
+    pub struct Status: u16 {
+        const BUSERR = 0x0001;
+        const ARBLOST = 0x0002;
+        const RXNACK = 0x0004;
+        const LOWTOUT = 0x0040;
+        const CLKHOLD = 0x0080;
+        const MEXTTOUT = 0x0100;
+        const SEXTTOUT = 0x0200;
+        const LENERR = 0x0400;
+    }
+}
+
+impl Status {
+    /// Status flags which signal an error, and are cleared by writing a one
+    pub(super) const ERRORS: Self = Self::from_bits_truncate(
+        Self::BUSERR.bits()
+            | Self::ARBLOST.bits()
+            | Self::LOWTOUT.bits()
+            | Self::MEXTTOUT.bits()
+            | Self::SEXTTOUT.bits()
+            | Self::LENERR.bits(),
+    );
+}
+
+//=============================================================================
+// Error
+//=============================================================================
+
+/// Errors available for I2C transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// A START or STOP condition was detected at an illegal position
+    BusError,
+    /// Another master took over the bus
+    ArbitrationLost,
+    /// The slave did not acknowledge its address or a byte
+    Nack,
+    /// The SCL line was held low for too long
+    Timeout,
+    /// A DMA transfer ended before its length was reached
+    LengthError,
+    /// The SCL frequency is zero, or too slow for the GCLK frequency
+    InvalidBaud,
+    /// The address does not fit in 7 or 10 bits
+    InvalidAddress,
+}
+
+impl TryFrom<Status> for () {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(errors: Status) -> Result<(), Error> {
+        use Error::*;
+        if errors.contains(Status::BUSERR) {
+            Err(BusError)
+        } else if errors.contains(Status::ARBLOST) {
+            Err(ArbitrationLost)
+        } else if errors.contains(Status::RXNACK) {
+            Err(Nack)
+        } else if errors.intersects(Status::LOWTOUT | Status::MEXTTOUT | Status::SEXTTOUT) {
+            Err(Timeout)
+        } else if errors.contains(Status::LENERR) {
+            Err(LengthError)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl From<Error> for Status {
+    #[inline]
+    fn from(err: Error) -> Self {
+        use Error::*;
+        match err {
+            BusError => Status::BUSERR,
+            ArbitrationLost => Status::ARBLOST,
+            Nack => Status::RXNACK,
+            Timeout => Status::LOWTOUT | Status::MEXTTOUT | Status::SEXTTOUT,
+            LengthError => Status::LENERR,
+            InvalidBaud | InvalidAddress => Status::empty(),
+        }
+    }
+}
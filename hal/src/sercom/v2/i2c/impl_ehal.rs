@@ -0,0 +1,56 @@
+//! `embedded-hal` trait implementations for [`I2c`]s
+
+use super::{AnyConfig, Error, I2c};
+use embedded_hal::blocking::i2c::{Read, SevenBitAddress, TenBitAddress, Write, WriteRead};
+
+macro_rules! impl_ehal {
+    ($Address:ident, $ten_bit:literal) => {
+        impl<C: AnyConfig> Write<$Address> for I2c<C> {
+            type Error = Error;
+
+            /// Send the address, then write `bytes`, and issue a STOP
+            #[inline]
+            fn write(&mut self, addr: $Address, bytes: &[u8]) -> Result<(), Error> {
+                self.config
+                    .as_mut()
+                    .registers
+                    .do_write(addr as u16, $ten_bit, bytes)
+            }
+        }
+
+        impl<C: AnyConfig> Read<$Address> for I2c<C> {
+            type Error = Error;
+
+            /// Send the address, then fill `buffer`, and issue a STOP
+            #[inline]
+            fn read(&mut self, addr: $Address, buffer: &mut [u8]) -> Result<(), Error> {
+                self.config
+                    .as_mut()
+                    .registers
+                    .do_read(addr as u16, $ten_bit, buffer)
+            }
+        }
+
+        impl<C: AnyConfig> WriteRead<$Address> for I2c<C> {
+            type Error = Error;
+
+            /// Send the address and write `bytes`, then send the address
+            /// again after a repeated START, fill `buffer`, and issue a STOP
+            #[inline]
+            fn write_read(
+                &mut self,
+                addr: $Address,
+                bytes: &[u8],
+                buffer: &mut [u8],
+            ) -> Result<(), Error> {
+                self.config
+                    .as_mut()
+                    .registers
+                    .do_write_read(addr as u16, $ten_bit, bytes, buffer)
+            }
+        }
+    };
+}
+
+impl_ehal!(SevenBitAddress, false);
+impl_ehal!(TenBitAddress, true);
@@ -0,0 +1,140 @@
+//! I2C pad definitions for thumbv6m targets
+
+use core::marker::PhantomData;
+
+#[cfg(feature = "samd21")]
+use crate::gpio::v2::AnyPin;
+use crate::sercom::v2::*;
+use crate::typelevel::Sealed;
+
+//=============================================================================
+// Pads
+//=============================================================================
+
+/// Container for a set of SERCOM [`Pad`]s
+///
+/// The I2C peripheral always uses [`Pad0`] as SDA and [`Pad1`] as SCL. See
+/// the [module-level](crate::sercom::v2::i2c) documentation for more details
+/// on specifying a `Pads` type and creating instances.
+pub struct Pads<S, SDA, SCL>
+where
+    S: Sercom,
+    SDA: IsPad<Sercom = S, PadNum = Pad0>,
+    SCL: IsPad<Sercom = S, PadNum = Pad1>,
+{
+    sercom: PhantomData<S>,
+    sda: SDA,
+    scl: SCL,
+}
+
+#[cfg(feature = "samd11")]
+impl<S, SDA, SCL> Pads<S, SDA, SCL>
+where
+    S: Sercom,
+    SDA: IsPad<Sercom = S, PadNum = Pad0>,
+    SCL: IsPad<Sercom = S, PadNum = Pad1>,
+{
+    /// Create a set of [`Pads`] from the SDA and SCL [`Pad`]s
+    ///
+    /// The pins must already be converted to the correct [`PinMode`].
+    ///
+    /// [`PinMode`]: crate::gpio::v2::PinMode
+    #[inline]
+    pub fn new(sda: SDA, scl: SCL) -> Self {
+        Self {
+            sercom: PhantomData,
+            sda,
+            scl,
+        }
+    }
+}
+
+#[cfg(feature = "samd21")]
+impl<S, SDA, SCL> Pads<S, Pad<S, SDA>, Pad<S, SCL>>
+where
+    S: Sercom,
+    SDA: GetPad<S, PadNum = Pad0>,
+    SCL: GetPad<S, PadNum = Pad1>,
+    Pad<S, SDA>: IsPad<Sercom = S, PadNum = Pad0>,
+    Pad<S, SCL>: IsPad<Sercom = S, PadNum = Pad1>,
+{
+    /// Create a set of [`Pads`] from the SDA and SCL [`Pin`]s
+    ///
+    /// Each pin is converted to the correct [`PinMode`].
+    ///
+    /// [`Pin`]: crate::gpio::v2::Pin
+    /// [`PinMode`]: crate::gpio::v2::PinMode
+    #[inline]
+    pub fn new(sda: impl AnyPin<Id = SDA>, scl: impl AnyPin<Id = SCL>) -> Self {
+        Self {
+            sercom: PhantomData,
+            sda: sda.into().into_mode(),
+            scl: scl.into().into_mode(),
+        }
+    }
+}
+
+impl<S, SDA, SCL> Pads<S, SDA, SCL>
+where
+    S: Sercom,
+    SDA: IsPad<Sercom = S, PadNum = Pad0>,
+    SCL: IsPad<Sercom = S, PadNum = Pad1>,
+{
+    /// Consume the [`Pads`] and return each individual [`Pad`]
+    #[inline]
+    pub fn free(self) -> (SDA, SCL) {
+        (self.sda, self.scl)
+    }
+}
+
+/// Define a set of [`Pads`] using [`PinId`]s instead of [`Pin`]s
+///
+/// The first type parameter is the [`Sercom`], while the remaining two are
+/// the [`PinId`]s of the SDA and SCL pads.
+///
+/// ```
+/// use atsamd_hal::gpio::v2::{PA08, PA09};
+/// use atsamd_hal::sercom::v2::{i2c, Sercom0};
+///
+/// pub type Pads = i2c::PadsFromIds<Sercom0, PA08, PA09>;
+/// ```
+///
+/// [`Pin`]: crate::gpio::v2::Pin
+/// [`PinId`]: crate::gpio::v2::PinId
+#[cfg(feature = "samd21")]
+pub type PadsFromIds<S, SDA, SCL> = Pads<S, Pad<S, SDA>, Pad<S, SCL>>;
+
+//=============================================================================
+// PadSet
+//=============================================================================
+
+/// Type-level function to recover the [`Sercom`] and [`Pad`] types from a
+/// generic set of [`Pads`]
+///
+/// This trait allows the [`Config`](super::Config) struct to store an
+/// instance of [`Pads`] without itself being generic over all three type
+/// parameters of the [`Pads`] type.
+pub trait PadSet: Sealed {
+    type Sercom: Sercom;
+    type Sda: IsPad<Sercom = Self::Sercom, PadNum = Pad0>;
+    type Scl: IsPad<Sercom = Self::Sercom, PadNum = Pad1>;
+}
+
+impl<S, SDA, SCL> Sealed for Pads<S, SDA, SCL>
+where
+    S: Sercom,
+    SDA: IsPad<Sercom = S, PadNum = Pad0>,
+    SCL: IsPad<Sercom = S, PadNum = Pad1>,
+{
+}
+
+impl<S, SDA, SCL> PadSet for Pads<S, SDA, SCL>
+where
+    S: Sercom,
+    SDA: IsPad<Sercom = S, PadNum = Pad0>,
+    SCL: IsPad<Sercom = S, PadNum = Pad1>,
+{
+    type Sercom = S;
+    type Sda = SDA;
+    type Scl = SCL;
+}
@@ -0,0 +1,125 @@
+//! I2C pad definitions for thumbv7em targets
+
+use core::marker::PhantomData;
+
+use crate::gpio::v2::AnyPin;
+use crate::sercom::v2::*;
+use crate::typelevel::Sealed;
+
+//=============================================================================
+// Pads
+//=============================================================================
+
+/// Container for a set of SERCOM [`Pad`]s
+///
+/// The I2C peripheral always uses [`Pad0`] as SDA and [`Pad1`] as SCL, and
+/// both must belong to the same [`IoSet`]. See the
+/// [module-level](crate::sercom::v2::i2c) documentation for more details on
+/// specifying a `Pads` type and creating instances.
+pub struct Pads<S, I, SDA, SCL>
+where
+    S: Sercom,
+    I: IoSet,
+    SDA: IsPad<Sercom = S, PadNum = Pad0>,
+    SCL: IsPad<Sercom = S, PadNum = Pad1>,
+{
+    sercom: PhantomData<S>,
+    ioset: PhantomData<I>,
+    sda: SDA,
+    scl: SCL,
+}
+
+impl<S, I, SDA, SCL> Pads<S, I, Pad<S, SDA>, Pad<S, SCL>>
+where
+    S: Sercom,
+    I: IoSet,
+    SDA: GetPad<S, PadNum = Pad0>,
+    SCL: GetPad<S, PadNum = Pad1>,
+    Pad<S, SDA>: IsPad<Sercom = S, PadNum = Pad0> + InIoSet<I>,
+    Pad<S, SCL>: IsPad<Sercom = S, PadNum = Pad1> + InIoSet<I>,
+{
+    /// Create a set of [`Pads`] from the SDA and SCL [`Pin`]s
+    ///
+    /// Each pin is converted to the correct [`PinMode`].
+    ///
+    /// [`Pin`]: crate::gpio::v2::Pin
+    /// [`PinMode`]: crate::gpio::v2::PinMode
+    #[inline]
+    pub fn new(sda: impl AnyPin<Id = SDA>, scl: impl AnyPin<Id = SCL>) -> Self {
+        Self {
+            sercom: PhantomData,
+            ioset: PhantomData,
+            sda: sda.into().into_mode(),
+            scl: scl.into().into_mode(),
+        }
+    }
+}
+
+impl<S, I, SDA, SCL> Pads<S, I, SDA, SCL>
+where
+    S: Sercom,
+    I: IoSet,
+    SDA: IsPad<Sercom = S, PadNum = Pad0>,
+    SCL: IsPad<Sercom = S, PadNum = Pad1>,
+{
+    /// Consume the [`Pads`] and return each individual [`Pad`]
+    #[inline]
+    pub fn free(self) -> (SDA, SCL) {
+        (self.sda, self.scl)
+    }
+}
+
+/// Define a set of [`Pads`] using [`PinId`]s instead of [`Pin`]s
+///
+/// The first two type parameters are the [`Sercom`] and [`IoSet`], while the
+/// remaining two are the [`PinId`]s of the SDA and SCL pads.
+///
+/// ```
+/// use atsamd_hal::gpio::v2::{PA08, PA09};
+/// use atsamd_hal::sercom::v2::{i2c, IoSet1, Sercom0};
+///
+/// pub type Pads = i2c::PadsFromIds<Sercom0, IoSet1, PA08, PA09>;
+/// ```
+///
+/// [`Pin`]: crate::gpio::v2::Pin
+/// [`PinId`]: crate::gpio::v2::PinId
+pub type PadsFromIds<S, I, SDA, SCL> = Pads<S, I, Pad<S, SDA>, Pad<S, SCL>>;
+
+//=============================================================================
+// PadSet
+//=============================================================================
+
+/// Type-level function to recover the [`Sercom`] and [`Pad`] types from a
+/// generic set of [`Pads`]
+///
+/// This trait allows the [`Config`](super::Config) struct to store an
+/// instance of [`Pads`] without itself being generic over all four type
+/// parameters of the [`Pads`] type.
+pub trait PadSet: Sealed {
+    type Sercom: Sercom;
+    type IoSet: IoSet;
+    type Sda: IsPad<Sercom = Self::Sercom, PadNum = Pad0>;
+    type Scl: IsPad<Sercom = Self::Sercom, PadNum = Pad1>;
+}
+
+impl<S, I, SDA, SCL> Sealed for Pads<S, I, SDA, SCL>
+where
+    S: Sercom,
+    I: IoSet,
+    SDA: IsPad<Sercom = S, PadNum = Pad0>,
+    SCL: IsPad<Sercom = S, PadNum = Pad1>,
+{
+}
+
+impl<S, I, SDA, SCL> PadSet for Pads<S, I, SDA, SCL>
+where
+    S: Sercom,
+    I: IoSet,
+    SDA: IsPad<Sercom = S, PadNum = Pad0>,
+    SCL: IsPad<Sercom = S, PadNum = Pad1>,
+{
+    type Sercom = S;
+    type IoSet = I;
+    type Sda = SDA;
+    type Scl = SCL;
+}
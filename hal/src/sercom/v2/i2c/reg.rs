@@ -0,0 +1,604 @@
+//! Register-level access to I2C master configuration and transactions
+
+use core::convert::TryInto;
+
+use super::{BusState, Error, Flags, InactiveTimeout, Status};
+
+use crate::pac;
+use crate::sercom::v2::*;
+use crate::time::Hertz;
+
+use pac::sercom0::i2cm::ctrla::MODE_A;
+
+/// Value of the `CTRLA.SPEED` field in high-speed mode
+const SPEED_HIGH: u8 = 2;
+
+/// Value of the `CTRLA.SPEED` field in Fast-mode Plus
+const SPEED_FAST_PLUS: u8 = 1;
+
+/// `CTRLB.CMD` value to read another byte
+const CMD_READ: u8 = 2;
+
+/// `CTRLB.CMD` value to issue a STOP condition
+const CMD_STOP: u8 = 3;
+
+/// Master code frequency used before switching to high-speed mode
+const MASTER_CODE_FREQ: u32 = 400_000;
+
+pub(super) struct Registers<S: Sercom> {
+    sercom: S,
+}
+
+// SAFETY: It is safe to implement Sync for Registers, because it erases the
+// interior mutability of the PAC SERCOM struct.
+unsafe impl<S: Sercom> Sync for Registers<S> {}
+
+impl<S: Sercom> Registers<S> {
+    /// Create a new `Registers` instance
+    #[inline]
+    pub(super) fn new(sercom: S) -> Self {
+        Self { sercom }
+    }
+
+    /// Helper function to access the underlying `I2CM` from the given `SERCOM`
+    #[inline]
+    fn i2cm(&self) -> &pac::sercom0::I2CM {
+        self.sercom.i2cm()
+    }
+
+    #[cfg(feature = "dma")]
+    /// Get a pointer to the `DATA` register
+    pub(super) fn data_ptr<T>(&self) -> *mut T {
+        self.i2cm().data.as_ptr() as *mut _
+    }
+
+    /// Free the `Registers` struct and return the underlying `Sercom` instance
+    #[inline]
+    pub(super) fn free(self) -> S {
+        self.sercom
+    }
+
+    /// Reset the SERCOM peripheral
+    #[inline]
+    pub(super) fn swrst(&mut self) {
+        self.i2cm().ctrla.write(|w| w.swrst().set_bit());
+        while self.i2cm().syncbusy.read().swrst().bit_is_set() {}
+    }
+
+    /// Configure the SERCOM as an I2C master
+    #[inline]
+    pub(super) fn configure_mode(&mut self) {
+        self.i2cm()
+            .ctrla
+            .modify(|_, w| w.mode().variant(MODE_A::I2C_MASTER));
+    }
+
+    /// Set the SCL frequency, and the matching transfer speed
+    ///
+    /// Frequencies up to 400 kHz use Standard-mode or Fast-mode, up to 1 MHz
+    /// use Fast-mode Plus, and above use high-speed mode. The rise time of
+    /// SCL is neglected.
+    #[inline]
+    pub(super) fn set_baud(&mut self, freq: Hertz, baud: Hertz) -> Result<(), Error> {
+        let (speed, baud, hsbaud) = if baud.0 > 1_000_000 {
+            // The master code is sent in Fast-mode, before switching to
+            // high-speed mode
+            (
+                SPEED_HIGH,
+                calculate_baud(freq.0, MASTER_CODE_FREQ),
+                calculate_hs_baud(freq.0, baud.0),
+            )
+        } else if baud.0 > 400_000 {
+            (SPEED_FAST_PLUS, calculate_baud(freq.0, baud.0), Some(0))
+        } else {
+            (0, calculate_baud(freq.0, baud.0), Some(0))
+        };
+        let (baud, hsbaud) = baud.zip(hsbaud).ok_or(Error::InvalidBaud)?;
+
+        self.i2cm().ctrla.modify(|_, w| unsafe {
+            w.speed().bits(speed);
+            // High-speed mode requires clock stretching after the ACK bit
+            w.sclsm().bit(speed == SPEED_HIGH)
+        });
+        self.i2cm().baud.write(|w| unsafe {
+            w.baud().bits(baud);
+            w.hsbaud().bits(hsbaud)
+        });
+        Ok(())
+    }
+
+    /// Get the current SCL frequency
+    #[inline]
+    pub(super) fn get_baud(&self, freq: Hertz) -> Hertz {
+        let baud = self.i2cm().baud.read();
+        if self.high_speed() {
+            Hertz(freq.0 / (2 + 2 * baud.hsbaud().bits() as u32))
+        } else {
+            Hertz(freq.0 / (10 + 2 * baud.baud().bits() as u32))
+        }
+    }
+
+    /// Whether the peripheral is configured for high-speed mode
+    #[inline]
+    fn high_speed(&self) -> bool {
+        self.i2cm().ctrla.read().speed().bits() == SPEED_HIGH
+    }
+
+    /// Set the inactive bus timeout
+    #[inline]
+    pub(super) fn set_inactive_timeout(&mut self, timeout: InactiveTimeout) {
+        #[cfg(feature = "min-samd51g")]
+        self.i2cm()
+            .ctrla
+            .modify(|_, w| w.inactout().bits(timeout as u8));
+        #[cfg(any(feature = "samd11", feature = "samd21"))]
+        self.i2cm()
+            .ctrla
+            .modify(|_, w| unsafe { w.inactout().bits(timeout as u8) });
+    }
+
+    /// Get the current inactive bus timeout
+    #[inline]
+    pub(super) fn get_inactive_timeout(&self) -> InactiveTimeout {
+        match self.i2cm().ctrla.read().inactout().bits() {
+            0 => InactiveTimeout::Disabled,
+            1 => InactiveTimeout::Us55,
+            2 => InactiveTimeout::Us105,
+            _ => InactiveTimeout::Us205,
+        }
+    }
+
+    /// Enable or disable the SCL low timeout
+    #[inline]
+    pub(super) fn set_low_timeout(&mut self, enabled: bool) {
+        self.i2cm().ctrla.modify(|_, w| w.lowtouten().bit(enabled));
+    }
+
+    /// Get the current SCL low timeout setting
+    #[inline]
+    pub(super) fn get_low_timeout(&self) -> bool {
+        self.i2cm().ctrla.read().lowtouten().bit()
+    }
+
+    /// Enable or disable the SCL low extend timeouts
+    #[inline]
+    pub(super) fn set_extend_timeout(&mut self, enabled: bool) {
+        self.i2cm().ctrla.modify(|_, w| {
+            w.mexttoen().bit(enabled);
+            w.sexttoen().bit(enabled)
+        });
+    }
+
+    /// Get the current SCL low extend timeout setting
+    #[inline]
+    pub(super) fn get_extend_timeout(&self) -> bool {
+        self.i2cm().ctrla.read().mexttoen().bit()
+    }
+
+    /// Run in standby mode
+    #[inline]
+    pub(super) fn set_run_in_standby(&mut self, set: bool) {
+        self.i2cm().ctrla.modify(|_, w| w.runstdby().bit(set));
+    }
+
+    /// Get the current run in standby mode
+    #[inline]
+    pub(super) fn get_run_in_standby(&self) -> bool {
+        self.i2cm().ctrla.read().runstdby().bit()
+    }
+
+    /// Clear specified interrupt flags
+    #[inline]
+    pub(super) fn clear_flags(&mut self, flags: Flags) {
+        self.i2cm()
+            .intflag
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Read interrupt flags
+    #[inline]
+    pub(super) fn read_flags(&self) -> Flags {
+        Flags::from_bits_truncate(self.i2cm().intflag.read().bits())
+    }
+
+    /// Enable specified interrupts
+    #[inline]
+    pub(super) fn enable_interrupts(&mut self, flags: Flags) {
+        self.i2cm()
+            .intenset
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Disable specified interrupts
+    #[inline]
+    pub(super) fn disable_interrupts(&mut self, flags: Flags) {
+        self.i2cm()
+            .intenclr
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Clear specified status flags
+    #[inline]
+    pub(super) fn clear_status(&mut self, status: Status) {
+        // Writing zero to the BUSSTATE field has no effect
+        self.i2cm()
+            .status
+            .write(|w| unsafe { w.bits(status.bits()) });
+    }
+
+    /// Read status flags
+    #[inline]
+    pub(super) fn read_status(&self) -> Status {
+        Status::from_bits_truncate(self.i2cm().status.read().bits())
+    }
+
+    /// Convert the error status flags into a [`Result`]
+    #[inline]
+    pub(super) fn check_status(&self) -> Result<(), Error> {
+        self.read_status().try_into()
+    }
+
+    /// Convert the error status flags into a [`Result`], ignoring `RXNACK`
+    ///
+    /// `RXNACK` is read-only, and keeps the acknowledge of the last byte sent
+    /// until the next transaction.
+    #[cfg(feature = "dma")]
+    #[inline]
+    pub(super) fn check_errors(&self) -> Result<(), Error> {
+        (self.read_status() & Status::ERRORS).try_into()
+    }
+
+    /// Read the state of the bus
+    #[inline]
+    pub(super) fn bus_state(&self) -> BusState {
+        match self.i2cm().status.read().busstate().bits() {
+            0 => BusState::Unknown,
+            1 => BusState::Idle,
+            2 => BusState::Owned,
+            _ => BusState::Busy,
+        }
+    }
+
+    /// Enable the I2C peripheral
+    ///
+    /// The bus state is forced to idle, so that transactions can start.
+    #[inline]
+    pub(super) fn enable(&mut self) {
+        self.enable_peripheral(true);
+        self.i2cm()
+            .status
+            .write(|w| unsafe { w.busstate().bits(BusState::Idle as u8) });
+        self.sync_sysop();
+    }
+
+    /// Disable the I2C peripheral
+    #[inline]
+    pub(super) fn disable(&mut self) {
+        self.enable_peripheral(false);
+    }
+
+    /// Enable or disable the SERCOM peripheral, and wait for the ENABLE bit to
+    /// synchronize.
+    #[inline]
+    pub(super) fn enable_peripheral(&mut self, enable: bool) {
+        self.i2cm().ctrla.modify(|_, w| w.enable().bit(enable));
+        while self.i2cm().syncbusy.read().enable().bit_is_set() {}
+    }
+
+    #[inline]
+    fn sync_sysop(&self) {
+        while self.i2cm().syncbusy.read().sysop().bit_is_set() {}
+    }
+
+    //=========================================================================
+    // Transactions
+    //=========================================================================
+
+    /// Wait until the bus is idle or owned by this master, and clear the
+    /// errors of the previous transaction
+    #[inline]
+    pub(super) fn wait_bus_ready(&mut self) {
+        while let BusState::Unknown | BusState::Busy = self.bus_state() {}
+        self.clear_status(Status::ERRORS);
+    }
+
+    /// Write the `ADDR` register, which issues a START or a repeated START
+    /// condition and sends the address
+    #[inline]
+    fn write_addr(&mut self, addr: u16, ten_bit: bool, read: bool, length: Option<u8>) {
+        let high_speed = self.high_speed();
+        self.i2cm().addr.write(|w| unsafe {
+            w.addr().bits(addr << 1 | read as u16);
+            w.tenbiten().bit(ten_bit);
+            w.hs().bit(high_speed);
+            if let Some(length) = length {
+                w.lenen().set_bit();
+                w.len().bits(length);
+            }
+            w
+        });
+        self.sync_sysop();
+    }
+
+    /// Wait for the `MB` or `ERROR` flag, and convert the status into a
+    /// [`Result`]
+    #[inline]
+    fn wait_master_on_bus(&self) -> Result<(), Error> {
+        while !self.read_flags().intersects(Flags::MB | Flags::ERROR) {}
+        self.check_status()
+    }
+
+    /// Wait for the `SB` flag, signaling that a byte was received
+    #[inline]
+    fn wait_slave_on_bus(&self) -> Result<(), Error> {
+        loop {
+            let flags = self.read_flags();
+            if flags.contains(Flags::SB) {
+                return Ok(());
+            }
+            // The address was not acknowledged, or arbitration was lost
+            if flags.intersects(Flags::MB | Flags::ERROR) {
+                self.check_status()?;
+                return Err(Error::ArbitrationLost);
+            }
+        }
+    }
+
+    /// Enable or disable smart mode, in which reading `DATA` sends the
+    /// acknowledge action and starts reading the next byte
+    ///
+    /// Smart mode is only used by DMA transfers, which can't issue commands.
+    #[inline]
+    fn set_smart_mode(&mut self, enabled: bool) {
+        self.i2cm().ctrlb.modify(|_, w| {
+            w.ackact().clear_bit();
+            w.smen().bit(enabled)
+        });
+    }
+
+    /// Address a slave for writing
+    #[inline]
+    fn start_write(&mut self, addr: u16, ten_bit: bool) -> Result<(), Error> {
+        self.wait_bus_ready();
+        self.set_smart_mode(false);
+        self.write_addr(addr, ten_bit, false, None);
+        self.wait_master_on_bus()
+    }
+
+    /// Address a slave for reading
+    ///
+    /// A 10-bit address is first sent for writing, then followed by a
+    /// repeated START with only the two most significant bits of the address.
+    #[inline]
+    fn start_read(&mut self, addr: u16, ten_bit: bool) -> Result<(), Error> {
+        if ten_bit {
+            self.start_write(addr, true)?;
+            self.write_addr(ten_bit_header(addr), false, true, None);
+        } else {
+            self.wait_bus_ready();
+            self.set_smart_mode(false);
+            self.write_addr(addr, false, true, None);
+        }
+        self.wait_slave_on_bus()
+    }
+
+    #[inline]
+    fn cmd(&mut self, cmd: u8) {
+        self.i2cm()
+            .ctrlb
+            .modify(|_, w| unsafe { w.cmd().bits(cmd) });
+        self.sync_sysop();
+    }
+
+    /// Acknowledge the last byte and read another one
+    #[inline]
+    fn cmd_read(&mut self) {
+        self.i2cm().ctrlb.modify(|_, w| unsafe {
+            w.ackact().clear_bit();
+            w.cmd().bits(CMD_READ)
+        });
+        self.sync_sysop();
+    }
+
+    /// Issue a STOP condition, not acknowledging the last byte of a read
+    #[inline]
+    fn cmd_stop(&mut self) {
+        self.i2cm().ctrlb.modify(|_, w| w.ackact().set_bit());
+        self.cmd(CMD_STOP);
+    }
+
+    #[inline]
+    fn send_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        for b in bytes {
+            self.i2cm().data.write(|w| unsafe { w.bits(*b) });
+            self.wait_master_on_bus()?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn read_one(&mut self) -> Result<u8, Error> {
+        self.wait_slave_on_bus()?;
+        Ok(self.i2cm().data.read().bits())
+    }
+
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        for (i, dest) in buffer.iter_mut().enumerate() {
+            // Acknowledge the previous byte to receive another one
+            if i > 0 {
+                self.cmd_read();
+            }
+            *dest = self.read_one()?;
+        }
+        Ok(())
+    }
+
+    /// Issue a STOP condition after a transaction, unless the bus was lost
+    #[inline]
+    fn finish(&mut self, result: Result<(), Error>) -> Result<(), Error> {
+        match result {
+            Err(Error::ArbitrationLost) | Err(Error::BusError) => (),
+            _ => self.cmd_stop(),
+        }
+        result
+    }
+
+    /// Write `bytes` to the slave at `addr`
+    #[inline]
+    pub(super) fn do_write(&mut self, addr: u16, ten_bit: bool, bytes: &[u8]) -> Result<(), Error> {
+        check_address(addr, ten_bit)?;
+        let result = self
+            .start_write(addr, ten_bit)
+            .and_then(|_| self.send_bytes(bytes));
+        self.finish(result)
+    }
+
+    /// Read `buffer.len()` bytes from the slave at `addr`
+    #[inline]
+    pub(super) fn do_read(
+        &mut self,
+        addr: u16,
+        ten_bit: bool,
+        buffer: &mut [u8],
+    ) -> Result<(), Error> {
+        check_address(addr, ten_bit)?;
+        let result = self
+            .start_read(addr, ten_bit)
+            .and_then(|_| self.fill_buffer(buffer));
+        self.finish(result)
+    }
+
+    /// Write `bytes` to the slave at `addr`, then read `buffer.len()` bytes
+    /// after a repeated START
+    #[inline]
+    pub(super) fn do_write_read(
+        &mut self,
+        addr: u16,
+        ten_bit: bool,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Error> {
+        check_address(addr, ten_bit)?;
+        let result = self
+            .start_write(addr, ten_bit)
+            .and_then(|_| self.send_bytes(bytes))
+            .and_then(|_| self.start_read(addr, ten_bit))
+            .and_then(|_| self.fill_buffer(buffer));
+        self.finish(result)
+    }
+
+    /// Start a write transaction of `length` bytes, which are transferred by
+    /// the DMA, and wait for the slave to acknowledge its address
+    ///
+    /// The STOP condition is issued by the hardware, or by this method if the
+    /// address is not acknowledged.
+    #[cfg(feature = "dma")]
+    #[inline]
+    pub(super) fn start_dma_write(&mut self, addr: u8, length: u8) -> Result<(), Error> {
+        check_address(addr as u16, false)?;
+        self.clear_flags(Flags::all());
+        self.set_smart_mode(true);
+        self.write_addr(addr as u16, false, false, Some(length));
+        self.wait_master_on_bus()
+            .or_else(|error| self.finish(Err(error)))
+    }
+
+    /// Start a read transaction of `length` bytes, which are transferred by
+    /// the DMA, and wait for the first byte
+    ///
+    /// The last byte is not acknowledged, and the STOP condition is issued by
+    /// the hardware, or by this method if the address is not acknowledged.
+    #[cfg(feature = "dma")]
+    #[inline]
+    pub(super) fn start_dma_read(&mut self, addr: u8, length: u8) -> Result<(), Error> {
+        check_address(addr as u16, false)?;
+        self.clear_flags(Flags::all());
+        self.set_smart_mode(true);
+        self.write_addr(addr as u16, false, true, Some(length));
+        self.wait_slave_on_bus()
+            .or_else(|error| self.finish(Err(error)))
+    }
+}
+
+/// Check that a 7-bit or 10-bit address fits in its width
+#[inline]
+fn check_address(addr: u16, ten_bit: bool) -> Result<(), Error> {
+    let max = if ten_bit { 0x3FF } else { 0x7F };
+    if addr <= max {
+        Ok(())
+    } else {
+        Err(Error::InvalidAddress)
+    }
+}
+
+/// Address sent in the repeated START of a 10-bit read: the reserved
+/// `0b11110` prefix followed by the two most significant bits of the address
+#[inline]
+fn ten_bit_header(addr: u16) -> u16 {
+    0b111_1000 | (addr >> 8 & 0b11)
+}
+
+/// Calculate the `BAUD` value for Standard-mode, Fast-mode and Fast-mode Plus,
+/// with equal SCL high and low times, rounding the frequency down
+///
+/// Returns `None` if `baud` is zero, or too slow to be reached.
+#[inline]
+fn calculate_baud(clk_freq: u32, baud: u32) -> Option<u8> {
+    let div = div_ceil(clk_freq, baud)?;
+    (div.saturating_sub(9) / 2).try_into().ok()
+}
+
+/// Calculate the `HSBAUD` value for high-speed mode, with equal SCL high and
+/// low times, rounding the frequency down
+///
+/// Returns `None` if `baud` is zero, or too slow to be reached.
+#[inline]
+fn calculate_hs_baud(clk_freq: u32, baud: u32) -> Option<u8> {
+    let div = div_ceil(clk_freq, baud)?;
+    (div.saturating_sub(1) / 2).try_into().ok()
+}
+
+#[inline]
+fn div_ceil(clk_freq: u32, baud: u32) -> Option<u32> {
+    let div = clk_freq.checked_div(baud)?;
+    Some(div + (clk_freq % baud != 0) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{calculate_baud, calculate_hs_baud, check_address, ten_bit_header, Error};
+
+    #[test]
+    fn baud() {
+        // 48 MHz / (10 + 2 * BAUD)
+        assert_eq!(calculate_baud(48_000_000, 100_000), Some(235));
+        assert_eq!(calculate_baud(48_000_000, 400_000), Some(55));
+        assert_eq!(calculate_baud(48_000_000, 1_000_000), Some(19));
+        // Never faster than requested
+        assert_eq!(calculate_baud(48_000_000, 390_000), Some(57));
+        // 52 MHz / (10 + 2 * 255) = 100 kHz is the slowest frequency
+        assert_eq!(calculate_baud(52_000_000, 100_000), Some(255));
+        assert_eq!(calculate_baud(52_000_001, 100_000), None);
+        assert_eq!(calculate_baud(120_000_000, 10_000), None);
+        assert_eq!(calculate_baud(48_000_000, 0), None);
+        // 100 MHz / (2 + 2 * HSBAUD) = 3.33 MHz
+        assert_eq!(calculate_hs_baud(100_000_000, 3_400_000), Some(14));
+        assert_eq!(calculate_hs_baud(100_000_000, 100_000), None);
+        assert_eq!(calculate_hs_baud(100_000_000, 0), None);
+    }
+
+    #[test]
+    fn address() {
+        assert_eq!(check_address(0x7F, false), Ok(()));
+        assert_eq!(check_address(0x80, false), Err(Error::InvalidAddress));
+        assert_eq!(check_address(0x3FF, true), Ok(()));
+        assert_eq!(check_address(0x400, true), Err(Error::InvalidAddress));
+    }
+
+    #[test]
+    fn ten_bit() {
+        assert_eq!(ten_bit_header(0x3FF), 0b111_1011);
+        assert_eq!(ten_bit_header(0x150), 0b111_1001);
+    }
+}
@@ -0,0 +1,327 @@
+//! Use the SERCOM peripheral as an I2C slave
+//!
+//! The I2C slave uses the same [`Pads`] as the I2C master. The peripheral is
+//! configured with a [`Config`], then [`enable`]d, yielding a functional
+//! [`I2cSlave`] struct. Transactions are driven by the SERCOM interrupt,
+//! which calls the methods of a [`Handler`].
+//!
+//! # [`Config`]
+//!
+//! Create a [`Config`] struct, which represents the I2C slave in its disabled
+//! state. Upon creation, the [`Config`] takes ownership of both the [`Pads`]
+//! struct and the PAC [`Sercom`] struct. It takes a reference to the PM or
+//! MCLK, so that it can enable the APB clock. Users are responsible for
+//! correctly configuring the GCLK.
+//!
+//! The slave responds to the [`Address`] set with
+//! [`address`](Config::address), which can be:
+//!
+//! * A single 7-bit address
+//! * A 7-bit address and a mask of the bits to ignore
+//! * Two 7-bit addresses
+//! * A range of 7-bit addresses
+//! * A single 10-bit address
+//!
+//! The slave can also respond to the general call address, if enabled with
+//! [`general_call`](Config::general_call).
+//!
+//! ```
+//! use atsamd_hal::sercom::v2::i2c::slave::{self, Address};
+//!
+//! let i2c = slave::Config::new(&mclk, sercom, pads)
+//!     .address(Address::Two(0x2A, 0x2B))?
+//!     .general_call(true)
+//!     .enable();
+//! ```
+//!
+//! # Clock stretching and smart mode
+//!
+//! The slave stretches SCL until each interrupt is handled. By default, it
+//! stretches SCL before the ACK bit, so that the [`Handler`] decides whether
+//! each address or byte is acknowledged. With [`ClockStretch::AfterAck`],
+//! required for high-speed mode, or in [smart mode](Config::smart_mode), the
+//! ACK bit is sent before the [`Handler`] sees the byte, and its answer
+//! applies to the next byte.
+//!
+//! # [`Handler`]
+//!
+//! Enable the interrupts, then call [`handle_interrupt`] from the SERCOM
+//! interrupt handler. It calls the [`Handler`] methods when the slave is
+//! addressed, when the master writes or reads a byte, and at the STOP
+//! condition. Each method receives the [`Transaction`] state, which includes
+//! the direction, the number of bytes transferred, and the running SMBus
+//! [`Pec`].
+//!
+//! The [`RegisterFile`] handler presents a slice of bytes as a register map,
+//! with optional PEC checking of the writes.
+//!
+//! ```
+//! use atsamd_hal::sercom::v2::i2c::slave::{Flags, RegisterFile};
+//!
+//! i2c.enable_interrupts(Flags::all());
+//! let mut file = RegisterFile::new(&mut registers).pec(true);
+//!
+//! // In the SERCOM interrupt handler
+//! i2c.handle_interrupt(&mut file)?;
+//! ```
+//!
+//! [`enable`]: Config::enable
+//! [`handle_interrupt`]: I2cSlave::handle_interrupt
+//! [`Pads`]: super::Pads
+//! [`Sercom`]: crate::sercom::v2::Sercom
+
+mod reg;
+use reg::Registers;
+
+mod flags;
+pub use flags::*;
+
+mod config;
+pub use config::*;
+
+mod handler;
+pub use handler::*;
+
+//=============================================================================
+// Address
+//=============================================================================
+
+/// Addresses the slave responds to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Address {
+    /// A single 7-bit address
+    Single(u8),
+    /// A 7-bit address, ignoring the bits set in `mask`
+    Mask { address: u8, mask: u8 },
+    /// Two 7-bit addresses
+    Two(u8, u8),
+    /// All 7-bit addresses from `low` to `high`, inclusive
+    Range { low: u8, high: u8 },
+    /// A single 10-bit address
+    TenBit(u16),
+}
+
+impl Address {
+    /// Whether the addresses and mask fit in their width
+    #[inline]
+    fn is_valid(&self) -> bool {
+        match *self {
+            Address::Single(addr) => addr <= 0x7F,
+            Address::Mask { address, mask } => address <= 0x7F && mask <= 0x7F,
+            Address::Two(first, second) => first <= 0x7F && second <= 0x7F,
+            Address::Range { low, high } => low <= 0x7F && high <= 0x7F,
+            Address::TenBit(addr) => addr <= 0x3FF,
+        }
+    }
+}
+
+//=============================================================================
+// ClockStretch
+//=============================================================================
+
+/// Position of the SCL stretching in each byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockStretch {
+    /// Stretch SCL before the ACK bit, so that the [`Handler`] decides
+    /// whether each address or byte is acknowledged
+    BeforeAck,
+    /// Stretch SCL after the ACK bit, as required in high-speed mode
+    AfterAck,
+}
+
+//=============================================================================
+// I2cSlave
+//=============================================================================
+
+/// Abstraction over an I2C peripheral configured as a slave, responding to
+/// the transactions of a master
+pub struct I2cSlave<C: AnyConfig> {
+    config: C,
+    transaction: Option<Transaction>,
+}
+
+impl<C: AnyConfig> I2cSlave<C> {
+    /// Read the interrupt flags
+    #[inline]
+    pub fn read_flags(&self) -> Flags {
+        self.config.as_ref().registers.read_flags()
+    }
+
+    /// Clear interrupt flags
+    ///
+    /// Clearing the `AMATCH` or `DRDY` flag without calling
+    /// [`handle_interrupt`](I2cSlave::handle_interrupt) will leave SCL
+    /// stretched.
+    #[inline]
+    pub fn clear_flags(&mut self, flags: Flags) {
+        self.config.as_mut().registers.clear_flags(flags);
+    }
+
+    /// Enable interrupts for the specified flags
+    #[inline]
+    pub fn enable_interrupts(&mut self, flags: Flags) {
+        self.config.as_mut().registers.enable_interrupts(flags);
+    }
+
+    /// Disable interrupts for the specified flags
+    #[inline]
+    pub fn disable_interrupts(&mut self, flags: Flags) {
+        self.config.as_mut().registers.disable_interrupts(flags);
+    }
+
+    /// Read the status flags
+    #[inline]
+    pub fn read_status(&self) -> Status {
+        self.config.as_ref().registers.read_status()
+    }
+
+    /// Clear the status flags
+    ///
+    /// Only the error flags can be cleared. The other flags are **SILENTLY
+    /// IGNORED**.
+    #[inline]
+    pub fn clear_status(&mut self, status: Status) {
+        self.config
+            .as_mut()
+            .registers
+            .clear_status(status & Status::ERRORS);
+    }
+
+    /// Respond to the pending interrupt flags, calling the methods of the
+    /// [`Handler`]
+    ///
+    /// This method should be called from the SERCOM interrupt handler, with
+    /// all the [`Flags`] enabled. On an error, the ongoing transaction is
+    /// dropped without calling [`Handler::stop`], and the error is returned.
+    #[inline]
+    pub fn handle_interrupt<H: Handler>(&mut self, handler: &mut H) -> Result<(), Error> {
+        let registers = &mut self.config.as_mut().registers;
+        let flags = registers.read_flags();
+
+        if flags.contains(Flags::ERROR) {
+            let result = registers.check_status();
+            registers.clear_status(Status::ERRORS);
+            registers.clear_flags(Flags::ERROR);
+            self.transaction = None;
+            result?;
+        }
+
+        if flags.contains(Flags::PREC) {
+            registers.clear_flags(Flags::PREC);
+            if let Some(transaction) = self.transaction.take() {
+                handler.stop(&transaction);
+            }
+        }
+
+        if flags.contains(Flags::AMATCH) {
+            let status = registers.read_status();
+            let direction = if status.contains(Status::DIR) {
+                Direction::Read
+            } else {
+                Direction::Write
+            };
+            let address = registers.received_address();
+            match &mut self.transaction {
+                Some(transaction) if status.contains(Status::SR) => {
+                    transaction.restart(address, direction)
+                }
+                slot => *slot = Some(Transaction::new(address, direction)),
+            }
+
+            let ack = match &self.transaction {
+                Some(transaction) => handler.address(transaction),
+                None => false,
+            };
+            registers.set_ack(ack);
+            if ack {
+                registers.cmd_continue();
+            } else {
+                registers.cmd_wait_start();
+                self.transaction = None;
+            }
+        }
+
+        if flags.contains(Flags::DRDY) {
+            let transaction = match &mut self.transaction {
+                Some(transaction) => transaction,
+                None => {
+                    // Not addressed through an address match interrupt
+                    registers.set_ack(false);
+                    registers.cmd_wait_start();
+                    return Ok(());
+                }
+            };
+            let smart = registers.get_smart_mode();
+
+            match transaction.direction() {
+                Direction::Write => {
+                    // In smart mode, reading DATA executes the acknowledge
+                    // action
+                    let byte = registers.read_data();
+                    transaction.add_byte(byte);
+                    let ack = handler.write(transaction, byte);
+                    registers.set_ack(ack);
+                    if smart {
+                        // The acknowledge action applies to the next byte
+                    } else if ack {
+                        registers.cmd_continue();
+                    } else {
+                        registers.cmd_wait_start();
+                    }
+                }
+                Direction::Read => {
+                    let nack = registers.read_status().contains(Status::RXNACK);
+                    if transaction.count() > 0 && nack {
+                        // The master does not read any more bytes
+                        registers.cmd_wait_start();
+                    } else {
+                        let byte = handler.read(transaction);
+                        transaction.add_byte(byte);
+                        // In smart mode, writing DATA resumes the transfer
+                        registers.write_data(byte);
+                        if !smart {
+                            registers.cmd_continue();
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Disable the I2C peripheral and return the underlying [`Config`]
+    #[inline]
+    pub fn disable(self) -> C {
+        let mut config = self.config;
+        config.as_mut().registers.disable();
+        config
+    }
+
+    /// Reconfigure the I2C peripheral
+    ///
+    /// Calling this method will temporarily disable the SERCOM peripheral, as
+    /// some registers are enable-protected. This will abort any ongoing
+    /// transaction.
+    ///
+    /// ```
+    /// i2c.reconfigure(|c| c.set_address(Address::Single(0x2C)).unwrap());
+    /// ```
+    #[inline]
+    pub fn reconfigure<F>(&mut self, update: F)
+    where
+        F: FnOnce(&mut SpecificConfig<C>),
+    {
+        self.config.as_mut().registers.disable();
+        self.transaction = None;
+        update(self.config.as_mut());
+        self.config.as_mut().registers.enable();
+    }
+}
+
+impl<C: AnyConfig> AsRef<SpecificConfig<C>> for I2cSlave<C> {
+    #[inline]
+    fn as_ref(&self) -> &SpecificConfig<C> {
+        self.config.as_ref()
+    }
+}
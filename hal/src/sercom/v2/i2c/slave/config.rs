@@ -0,0 +1,329 @@
+//! I2C slave [`Config`] definition and implementation
+
+use super::{Address, ClockStretch, Error, I2cSlave, Registers};
+use crate::{
+    sercom::v2::{
+        i2c::{Clock, PadSet},
+        *,
+    },
+    typelevel::{Is, Sealed},
+};
+
+//=============================================================================
+// Config
+//=============================================================================
+
+/// A configurable, disabled I2C slave peripheral
+///
+/// This `struct` represents a configurable I2C slave in its disabled state.
+/// It is generic over the set of [`Pads`]. Upon creation, the [`Config`]
+/// takes ownership of the [`Sercom`] and resets it, returning it configured
+/// as an I2C slave with a default configuration:
+///
+/// * Address `0x08`, without general call
+/// * Clock stretching before the ACK bit, smart mode disabled
+/// * Timeouts disabled
+///
+/// [`Config`] uses a builder-pattern API to configure the peripheral,
+/// culminating in a call to [`enable`], which consumes the [`Config`] and
+/// returns an enabled [`I2cSlave`].
+///
+/// [`enable`]: Config::enable
+/// [`Pads`]: crate::sercom::v2::i2c::Pads
+pub struct Config<P>
+where
+    P: PadSet,
+{
+    pub(super) registers: Registers<P::Sercom>,
+    pads: P,
+}
+
+impl<P: PadSet> Config<P> {
+    /// Create a new [`Config`] in the default configuration
+    ///
+    /// This function will enable the corresponding APB clock, reset the
+    /// [`Sercom`] peripheral, and return a [`Config`] in the default
+    /// configuration.
+    ///
+    /// [`Config`] takes ownership of the [`Sercom`] and
+    /// [`Pads`](crate::sercom::v2::i2c::Pads).
+    ///
+    /// Users must configure GCLK manually. The slave does not generate SCL,
+    /// but the GCLK must run for the peripheral to operate.
+    #[inline]
+    pub fn new(clk: &Clock, mut sercom: P::Sercom, pads: P) -> Self {
+        sercom.enable_apb_clock(clk);
+        Self::default(sercom, pads)
+    }
+
+    /// Create a new [`Config`] in the default configuration
+    #[inline]
+    fn default(sercom: P::Sercom, pads: P) -> Self {
+        let mut registers = Registers::new(sercom);
+        registers.swrst();
+        registers.configure_mode();
+        registers.set_address(Address::Single(0x08));
+        Self { registers, pads }
+    }
+
+    /// Trigger the [`Sercom`]'s SWRST and return a [`Config`] in the
+    /// default configuration.
+    #[inline]
+    pub fn reset(self) -> Config<P> {
+        Config::default(self.registers.free(), self.pads)
+    }
+
+    /// Consume the [`Config`], reset the peripheral, and return the [`Sercom`]
+    /// and [`Pads`](crate::sercom::v2::i2c::Pads)
+    #[inline]
+    pub fn free(mut self) -> (P::Sercom, P) {
+        self.registers.swrst();
+        (self.registers.free(), self.pads)
+    }
+
+    /// Set the addresses the slave responds to (builder pattern version)
+    ///
+    /// Fails with [`Error::InvalidAddress`] if an address or the mask does
+    /// not fit in 7 or 10 bits.
+    #[inline]
+    pub fn address(mut self, address: Address) -> Result<Self, Error> {
+        self.set_address(address)?;
+        Ok(self)
+    }
+
+    /// Set the addresses the slave responds to (setter version)
+    ///
+    /// Fails with [`Error::InvalidAddress`] if an address or the mask does
+    /// not fit in 7 or 10 bits, in which case the addresses are unchanged.
+    #[inline]
+    pub fn set_address(&mut self, address: Address) -> Result<(), Error> {
+        if !address.is_valid() {
+            return Err(Error::InvalidAddress);
+        }
+        self.registers.set_address(address);
+        Ok(())
+    }
+
+    /// Get the addresses the slave responds to
+    #[inline]
+    pub fn get_address(&self) -> Address {
+        self.registers.get_address()
+    }
+
+    /// Respond to the general call address (builder pattern version)
+    #[inline]
+    pub fn general_call(mut self, enabled: bool) -> Self {
+        self.set_general_call(enabled);
+        self
+    }
+
+    /// Respond to the general call address (setter version)
+    #[inline]
+    pub fn set_general_call(&mut self, enabled: bool) {
+        self.registers.set_general_call(enabled);
+    }
+
+    /// Get the current general call setting
+    #[inline]
+    pub fn get_general_call(&self) -> bool {
+        self.registers.get_general_call()
+    }
+
+    /// Enable or disable smart mode (builder pattern version)
+    ///
+    /// In smart mode, reading or writing the `DATA` register acknowledges
+    /// the byte and resumes the transfer, which saves a register write per
+    /// byte. Received bytes are acknowledged before the [`Handler`] sees
+    /// them.
+    ///
+    /// [`Handler`]: super::Handler
+    #[inline]
+    pub fn smart_mode(mut self, enabled: bool) -> Self {
+        self.set_smart_mode(enabled);
+        self
+    }
+
+    /// Enable or disable smart mode (setter version)
+    ///
+    /// In smart mode, reading or writing the `DATA` register acknowledges
+    /// the byte and resumes the transfer, which saves a register write per
+    /// byte. Received bytes are acknowledged before the [`Handler`] sees
+    /// them.
+    ///
+    /// [`Handler`]: super::Handler
+    #[inline]
+    pub fn set_smart_mode(&mut self, enabled: bool) {
+        self.registers.set_smart_mode(enabled);
+    }
+
+    /// Get the current smart mode setting
+    #[inline]
+    pub fn get_smart_mode(&self) -> bool {
+        self.registers.get_smart_mode()
+    }
+
+    /// Set the [`ClockStretch`] mode (builder pattern version)
+    #[inline]
+    pub fn clock_stretch(mut self, stretch: ClockStretch) -> Self {
+        self.set_clock_stretch(stretch);
+        self
+    }
+
+    /// Set the [`ClockStretch`] mode (setter version)
+    #[inline]
+    pub fn set_clock_stretch(&mut self, stretch: ClockStretch) {
+        self.registers.set_clock_stretch(stretch);
+    }
+
+    /// Get the current [`ClockStretch`] mode
+    #[inline]
+    pub fn get_clock_stretch(&self) -> ClockStretch {
+        self.registers.get_clock_stretch()
+    }
+
+    /// Enable or disable the SCL low timeout (builder pattern version)
+    ///
+    /// When set, the slave releases the bus and signals
+    /// [`Error::Timeout`](super::Error) if SCL is held low for 25 to 35 ms,
+    /// as in the SMBus specification. This requires the GCLK of the slow
+    /// clock of the [`Sercom`] to run at 32 kHz.
+    #[inline]
+    pub fn low_timeout(mut self, enabled: bool) -> Self {
+        self.set_low_timeout(enabled);
+        self
+    }
+
+    /// Enable or disable the SCL low timeout (setter version)
+    ///
+    /// When set, the slave releases the bus and signals
+    /// [`Error::Timeout`](super::Error) if SCL is held low for 25 to 35 ms,
+    /// as in the SMBus specification. This requires the GCLK of the slow
+    /// clock of the [`Sercom`] to run at 32 kHz.
+    #[inline]
+    pub fn set_low_timeout(&mut self, enabled: bool) {
+        self.registers.set_low_timeout(enabled);
+    }
+
+    /// Get the current SCL low timeout setting
+    #[inline]
+    pub fn get_low_timeout(&self) -> bool {
+        self.registers.get_low_timeout()
+    }
+
+    /// Enable or disable the SCL low extend timeout (builder pattern version)
+    ///
+    /// When set, the slave signals [`Error::Timeout`](super::Error) if it
+    /// stretches SCL for more than 25 ms within a message, as in the SMBus
+    /// specification. This requires the GCLK of the slow clock of the
+    /// [`Sercom`] to run at 32 kHz.
+    #[inline]
+    pub fn extend_timeout(mut self, enabled: bool) -> Self {
+        self.set_extend_timeout(enabled);
+        self
+    }
+
+    /// Enable or disable the SCL low extend timeout (setter version)
+    ///
+    /// When set, the slave signals [`Error::Timeout`](super::Error) if it
+    /// stretches SCL for more than 25 ms within a message, as in the SMBus
+    /// specification. This requires the GCLK of the slow clock of the
+    /// [`Sercom`] to run at 32 kHz.
+    #[inline]
+    pub fn set_extend_timeout(&mut self, enabled: bool) {
+        self.registers.set_extend_timeout(enabled);
+    }
+
+    /// Get the current SCL low extend timeout setting
+    #[inline]
+    pub fn get_extend_timeout(&self) -> bool {
+        self.registers.get_extend_timeout()
+    }
+
+    /// Run in standby mode (builder pattern version)
+    ///
+    /// When set, the I2C peripheral will run in standby mode, and an address
+    /// match can wake up the CPU. See the datasheet for more details.
+    #[inline]
+    pub fn run_in_standby(mut self, set: bool) -> Self {
+        self.set_run_in_standby(set);
+        self
+    }
+
+    /// Run in standby mode (setter version)
+    ///
+    /// When set, the I2C peripheral will run in standby mode, and an address
+    /// match can wake up the CPU. See the datasheet for more details.
+    #[inline]
+    pub fn set_run_in_standby(&mut self, set: bool) {
+        self.registers.set_run_in_standby(set);
+    }
+
+    /// Get the current run in standby mode
+    #[inline]
+    pub fn get_run_in_standby(&self) -> bool {
+        self.registers.get_run_in_standby()
+    }
+
+    /// Enable the I2C peripheral and return an [`I2cSlave`] struct.
+    ///
+    /// The slave does not respond to the master until the peripheral is
+    /// enabled.
+    #[inline]
+    pub fn enable(mut self) -> I2cSlave<Self> {
+        self.registers.enable();
+        I2cSlave {
+            config: self,
+            transaction: None,
+        }
+    }
+}
+
+//=============================================================================
+// AnyConfig
+//=============================================================================
+
+/// Type class for all possible [`Config`] types
+///
+/// This trait uses the [`AnyKind`] trait pattern to create a [type class] for
+/// [`Config`] types. See the `AnyKind` documentation for more details on the
+/// pattern.
+///
+/// In addition to the normal, `AnyKind` associated types, this trait also
+/// copies the [`Sercom`] type, to make it easier to apply bounds to this type
+/// at the next level of abstraction.
+///
+/// [`AnyKind`]: crate::typelevel#anykind-trait-pattern
+/// [type class]: crate::typelevel#type-classes
+pub trait AnyConfig: Sealed + Is<Type = SpecificConfig<Self>> {
+    type Sercom: Sercom;
+    type Pads: PadSet<Sercom = Self::Sercom>;
+}
+
+/// Type alias to recover the specific [`Config`] type from an implementation of
+/// [`AnyConfig`]
+pub type SpecificConfig<C> = Config<<C as AnyConfig>::Pads>;
+
+/// Type alias to recover the specific [`Sercom`] type from an implementation of
+/// [`AnyConfig`]
+pub type ConfigSercom<C> = <C as AnyConfig>::Sercom;
+
+impl<P: PadSet> AsRef<Self> for Config<P> {
+    #[inline]
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl<P: PadSet> AsMut<Self> for Config<P> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut Self {
+        self
+    }
+}
+
+impl<P: PadSet> Sealed for Config<P> {}
+
+impl<P: PadSet> AnyConfig for Config<P> {
+    type Sercom = P::Sercom;
+    type Pads = P;
+}
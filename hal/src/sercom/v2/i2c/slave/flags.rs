@@ -0,0 +1,114 @@
+//! Flag definitions
+
+use bitflags::bitflags;
+use core::convert::TryFrom;
+
+//=============================================================================
+// Interrupt flags
+//=============================================================================
+
+bitflags! {
+    /// Interrupt bit flags for I2C slave transactions
+    ///
+    /// The available interrupt flags are `PREC`, `AMATCH`, `DRDY` and
+    /// `ERROR`. The binary format of the underlying bits exactly matches the
+    /// INTFLAG bits.
+    pub struct Flags: u8 {
+        /// Stop received: a STOP condition ended a transaction addressed to
+        /// this slave
+        const PREC = 0x01;
+        /// Address match: the slave was addressed
+        const AMATCH = 0x02;
+        /// Data ready: a byte was received, or the next byte must be sent
+        const DRDY = 0x04;
+        const ERROR = 0x80;
+    }
+}
+
+//=============================================================================
+// Status flags
+//=============================================================================
+
+bitflags! {
+    /// Status flags for I2C slave transactions
+    ///
+    /// The available status flags are `BUSERR`, `COLL`, `RXNACK`, `DIR`,
+    /// `SR`, `LOWTOUT`, `CLKHOLD`, `SEXTTOUT`, `HS` and `LENERR`. The binary
+    /// format of the underlying bits exactly matches the STATUS bits.
+    pub struct Status: u16 {
+        const BUSERR = 0x0001;
+        const COLL = 0x0002;
+        const RXNACK = 0x0004;
+        const DIR = 0x0008;
+        const SR = 0x0010;
+        const LOWTOUT = 0x0040;
+        const CLKHOLD = 0x0080;
+        const SEXTTOUT = 0x0200;
+        const HS = 0x0400;
+        const LENERR = 0x0800;
+    }
+}
+
+impl Status {
+    /// Status flags which signal an error, and are cleared by writing a one
+    pub(super) const ERRORS: Self = Self::from_bits_truncate(
+        Self::BUSERR.bits()
+            | Self::COLL.bits()
+            | Self::LOWTOUT.bits()
+            | Self::SEXTTOUT.bits()
+            | Self::LENERR.bits(),
+    );
+}
+
+//=============================================================================
+// Error
+//=============================================================================
+
+/// Errors available for I2C slave transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// A START or STOP condition was detected at an illegal position
+    BusError,
+    /// The slave could not drive SDA high while sending data
+    Collision,
+    /// The SCL line was held low for too long
+    Timeout,
+    /// A DMA transfer ended before its length was reached
+    LengthError,
+    /// An address or mask does not fit in 7 or 10 bits
+    InvalidAddress,
+}
+
+impl TryFrom<Status> for () {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(errors: Status) -> Result<(), Error> {
+        use Error::*;
+        if errors.contains(Status::BUSERR) {
+            Err(BusError)
+        } else if errors.contains(Status::COLL) {
+            Err(Collision)
+        } else if errors.intersects(Status::LOWTOUT | Status::SEXTTOUT) {
+            Err(Timeout)
+        } else if errors.contains(Status::LENERR) {
+            Err(LengthError)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl From<Error> for Status {
+    #[inline]
+    fn from(err: Error) -> Self {
+        use Error::*;
+        match err {
+            BusError => Status::BUSERR,
+            Collision => Status::COLL,
+            Timeout => Status::LOWTOUT | Status::SEXTTOUT,
+            LengthError => Status::LENERR,
+            InvalidAddress => Status::empty(),
+        }
+    }
+}
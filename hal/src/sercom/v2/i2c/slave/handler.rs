@@ -0,0 +1,453 @@
+//! Interrupt-driven [`Handler`] trait, and the [`RegisterFile`] handler
+
+//=============================================================================
+// Direction
+//=============================================================================
+
+/// Direction of a transfer, from the point of view of the master
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The master writes bytes to the slave
+    Write,
+    /// The master reads bytes from the slave
+    Read,
+}
+
+//=============================================================================
+// Pec
+//=============================================================================
+
+/// SMBus Packet Error Code calculator
+///
+/// The PEC is a CRC-8 with the polynomial `x^8 + x^2 + x + 1`, calculated over
+/// every byte of a message, including the address bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pec(u8);
+
+impl Pec {
+    /// Create a new [`Pec`], with no bytes
+    #[inline]
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Add a byte to the PEC
+    #[inline]
+    pub fn update(&mut self, byte: u8) {
+        let mut crc = self.0 ^ byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                crc << 1 ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        self.0 = crc;
+    }
+
+    /// Get the PEC of the bytes added so far
+    #[inline]
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+//=============================================================================
+// Transaction
+//=============================================================================
+
+/// State of the ongoing transaction, passed to the [`Handler`] methods
+///
+/// A transaction starts with a START condition and ends with a STOP
+/// condition. The master may change the [`Direction`] with a repeated START
+/// condition, and the running [`Pec`] spans the whole transaction, as in the
+/// SMBus specification.
+#[derive(Debug, Clone, Copy)]
+pub struct Transaction {
+    address: u16,
+    direction: Direction,
+    repeated: bool,
+    count: usize,
+    pec: Pec,
+}
+
+impl Transaction {
+    /// Start a transaction, after a START condition
+    #[inline]
+    pub(super) fn new(address: u16, direction: Direction) -> Self {
+        let mut transaction = Self {
+            address,
+            direction,
+            repeated: false,
+            count: 0,
+            pec: Pec::new(),
+        };
+        transaction.add_address();
+        transaction
+    }
+
+    /// Continue the transaction, after a repeated START condition
+    #[inline]
+    pub(super) fn restart(&mut self, address: u16, direction: Direction) {
+        self.address = address;
+        self.direction = direction;
+        self.repeated = true;
+        self.count = 0;
+        self.add_address();
+    }
+
+    /// Add the 7-bit address byte to the PEC
+    #[inline]
+    fn add_address(&mut self) {
+        let read = self.direction == Direction::Read;
+        self.pec.update((self.address as u8) << 1 | read as u8);
+    }
+
+    /// Record a byte transferred in the current direction
+    #[inline]
+    pub(super) fn add_byte(&mut self, byte: u8) {
+        self.count += 1;
+        self.pec.update(byte);
+    }
+
+    /// Address which matched. It is `0` for the general call address.
+    #[inline]
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// Whether the master addressed all slaves with the general call address
+    #[inline]
+    pub fn is_general_call(&self) -> bool {
+        self.address == 0
+    }
+
+    /// Direction of the transfer since the last (repeated) START condition
+    #[inline]
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Whether the last START condition was a repeated START condition
+    #[inline]
+    pub fn is_repeated_start(&self) -> bool {
+        self.repeated
+    }
+
+    /// Number of bytes transferred since the last (repeated) START condition
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// PEC of the bytes transferred so far, including the address bytes
+    ///
+    /// When the master reads a PEC, this is the value to send.
+    #[inline]
+    pub fn pec(&self) -> u8 {
+        self.pec.value()
+    }
+
+    /// Whether the last byte written by the master is a valid PEC
+    ///
+    /// The PEC of a message followed by its own PEC is always zero.
+    #[inline]
+    pub fn pec_valid(&self) -> bool {
+        self.direction == Direction::Write && self.count > 0 && self.pec.value() == 0
+    }
+}
+
+//=============================================================================
+// Handler
+//=============================================================================
+
+/// Callbacks for I2C slave transactions
+///
+/// The methods are called from [`I2cSlave::handle_interrupt`], while the
+/// slave stretches SCL, so they should return quickly.
+///
+/// [`I2cSlave::handle_interrupt`]: super::I2cSlave::handle_interrupt
+pub trait Handler {
+    /// The slave was addressed by a START or a repeated START condition
+    ///
+    /// Return `false` to not acknowledge the address. By default, the address
+    /// is always acknowledged.
+    #[inline]
+    fn address(&mut self, _transaction: &Transaction) -> bool {
+        true
+    }
+
+    /// The master wrote a byte
+    ///
+    /// Return `false` to not acknowledge the byte. The byte is already added
+    /// to the PEC of the [`Transaction`].
+    ///
+    /// In smart mode, the byte is acknowledged when it is read, so the value
+    /// returned applies to the next byte instead.
+    fn write(&mut self, transaction: &Transaction, byte: u8) -> bool;
+
+    /// The master reads a byte
+    ///
+    /// Return the byte to send.
+    fn read(&mut self, transaction: &Transaction) -> u8;
+
+    /// The transaction ended with a STOP condition
+    #[inline]
+    fn stop(&mut self, _transaction: &Transaction) {}
+}
+
+//=============================================================================
+// RegisterFile
+//=============================================================================
+
+/// Maximum number of bytes in a write to a [`RegisterFile`] with PEC,
+/// including the register pointer and the PEC
+const STAGING_LEN: usize = 34;
+
+/// A [`Handler`] presenting a register map to the master
+///
+/// The first byte written by the master sets the register pointer. Following
+/// bytes are written to the registers, and read bytes are read from the
+/// registers, incrementing the pointer after each byte. Out-of-range writes
+/// are not acknowledged, and out-of-range reads return `0xFF`.
+///
+/// ```
+/// use atsamd_hal::sercom::v2::i2c::slave::RegisterFile;
+///
+/// let mut registers = [0; 16];
+/// let mut file = RegisterFile::new(&mut registers);
+///
+/// // In the SERCOM interrupt handler
+/// i2c.handle_interrupt(&mut file)?;
+/// ```
+///
+/// # SMBus PEC
+///
+/// With [`pec`](RegisterFile::pec) enabled, written bytes are staged until the
+/// STOP condition, and only written to the registers if the last byte is a
+/// valid PEC. Otherwise, the write is dropped and counted by
+/// [`pec_errors`](RegisterFile::pec_errors). A write can hold up to 32 data
+/// bytes. The register pointer of a write followed by a repeated START
+/// condition is used without a PEC, but data bytes before the repeated START
+/// can't be checked, so they are dropped and counted as a PEC error.
+///
+/// The slave does not know how many bytes the master reads, so it does not
+/// append a PEC to reads. Use a custom [`Handler`] and
+/// [`Transaction::pec`] for protocols with a fixed read length.
+pub struct RegisterFile<'a> {
+    registers: &'a mut [u8],
+    pointer: usize,
+    pec: bool,
+    staged: [u8; STAGING_LEN],
+    staged_len: usize,
+    pec_errors: usize,
+}
+
+impl<'a> RegisterFile<'a> {
+    /// Create a new [`RegisterFile`] over `registers`, without PEC
+    #[inline]
+    pub fn new(registers: &'a mut [u8]) -> Self {
+        Self {
+            registers,
+            pointer: 0,
+            pec: false,
+            staged: [0; STAGING_LEN],
+            staged_len: 0,
+            pec_errors: 0,
+        }
+    }
+
+    /// Require a valid PEC for writes (builder pattern version)
+    #[inline]
+    pub fn pec(mut self, enabled: bool) -> Self {
+        self.set_pec(enabled);
+        self
+    }
+
+    /// Require a valid PEC for writes (setter version)
+    #[inline]
+    pub fn set_pec(&mut self, enabled: bool) {
+        self.pec = enabled;
+        self.staged_len = 0;
+    }
+
+    /// Get the current PEC setting
+    #[inline]
+    pub fn get_pec(&self) -> bool {
+        self.pec
+    }
+
+    /// Number of writes dropped because of an invalid PEC
+    #[inline]
+    pub fn pec_errors(&self) -> usize {
+        self.pec_errors
+    }
+
+    /// Current register pointer
+    #[inline]
+    pub fn pointer(&self) -> usize {
+        self.pointer
+    }
+
+    /// Access the registers
+    #[inline]
+    pub fn registers(&self) -> &[u8] {
+        self.registers
+    }
+
+    /// Mutably access the registers
+    #[inline]
+    pub fn registers_mut(&mut self) -> &mut [u8] {
+        self.registers
+    }
+
+    /// Write a byte received from the master. The first byte of a write sets
+    /// the register pointer.
+    #[inline]
+    fn store(&mut self, first: bool, byte: u8) -> bool {
+        if first {
+            self.pointer = byte as usize;
+            return true;
+        }
+        match self.registers.get_mut(self.pointer) {
+            Some(register) => {
+                *register = byte;
+                self.pointer += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Write the staged bytes, except the last `skip` ones
+    #[inline]
+    fn commit(&mut self, skip: usize) {
+        let len = self.staged_len.saturating_sub(skip);
+        self.staged_len = 0;
+        for i in 0..len {
+            let byte = self.staged[i];
+            self.store(i == 0, byte);
+        }
+    }
+}
+
+impl Handler for RegisterFile<'_> {
+    #[inline]
+    fn address(&mut self, transaction: &Transaction) -> bool {
+        if transaction.is_repeated_start() && self.staged_len > 0 {
+            // Keep the register pointer of a write followed by a read, and drop
+            // the unchecked data bytes
+            self.pointer = self.staged[0] as usize;
+            if self.staged_len > 1 {
+                self.pec_errors += 1;
+            }
+            self.staged_len = 0;
+        }
+        true
+    }
+
+    #[inline]
+    fn write(&mut self, transaction: &Transaction, byte: u8) -> bool {
+        if !self.pec {
+            return self.store(transaction.count() == 1, byte);
+        }
+        match self.staged.get_mut(self.staged_len) {
+            Some(staged) => {
+                *staged = byte;
+                self.staged_len += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[inline]
+    fn read(&mut self, _transaction: &Transaction) -> u8 {
+        match self.registers.get(self.pointer) {
+            Some(&register) => {
+                self.pointer += 1;
+                register
+            }
+            None => 0xFF,
+        }
+    }
+
+    #[inline]
+    fn stop(&mut self, transaction: &Transaction) {
+        if self.staged_len == 0 {
+            return;
+        }
+        if transaction.pec_valid() {
+            self.commit(1);
+        } else {
+            self.staged_len = 0;
+            self.pec_errors += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pec() {
+        let mut pec = Pec::new();
+        for &byte in b"123456789" {
+            pec.update(byte);
+        }
+        assert_eq!(pec.value(), 0xF4);
+    }
+
+    #[test]
+    fn register_file_pec() {
+        let mut registers = [0; 4];
+        let mut file = RegisterFile::new(&mut registers).pec(true);
+
+        // Write 0xAB to register 2, then its PEC
+        let mut transaction = Transaction::new(0x2A, Direction::Write);
+        for &byte in &[0x02, 0xAB] {
+            transaction.add_byte(byte);
+            assert!(file.write(&transaction, byte));
+        }
+        let pec = transaction.pec();
+        transaction.add_byte(pec);
+        assert!(file.write(&transaction, pec));
+        file.stop(&transaction);
+        assert_eq!(file.pec_errors(), 0);
+        assert_eq!(file.registers(), &[0, 0, 0xAB, 0]);
+
+        // A corrupted PEC drops the write
+        let mut transaction = Transaction::new(0x2A, Direction::Write);
+        for &byte in &[0x01, 0xCD, 0x00] {
+            transaction.add_byte(byte);
+            assert!(file.write(&transaction, byte));
+        }
+        file.stop(&transaction);
+        assert_eq!(file.pec_errors(), 1);
+        assert_eq!(file.registers(), &[0, 0, 0xAB, 0]);
+
+        // A register pointer followed by a repeated START sets the pointer
+        let mut transaction = Transaction::new(0x2A, Direction::Write);
+        transaction.add_byte(0x02);
+        assert!(file.write(&transaction, 0x02));
+        transaction.restart(0x2A, Direction::Read);
+        assert!(file.address(&transaction));
+        assert_eq!(file.pointer(), 2);
+        assert_eq!(file.pec_errors(), 1);
+
+        // Data bytes followed by a repeated START are dropped
+        let mut transaction = Transaction::new(0x2A, Direction::Write);
+        for &byte in &[0x01, 0xCD] {
+            transaction.add_byte(byte);
+            assert!(file.write(&transaction, byte));
+        }
+        transaction.restart(0x2A, Direction::Read);
+        assert!(file.address(&transaction));
+        assert_eq!(file.pointer(), 1);
+        assert_eq!(file.pec_errors(), 2);
+        assert_eq!(file.read(&transaction), 0);
+        file.stop(&transaction);
+        assert_eq!(file.registers(), &[0, 0, 0xAB, 0]);
+    }
+}
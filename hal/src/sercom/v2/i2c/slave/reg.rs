@@ -0,0 +1,338 @@
+//! Register-level access to I2C slave configuration and transactions
+
+use core::convert::TryInto;
+
+use super::{Address, ClockStretch, Error, Flags, Status};
+
+use crate::pac;
+use crate::sercom::v2::*;
+
+use pac::sercom0::i2cs::ctrla::MODE_A;
+
+/// Value of the `CTRLB.AMODE` field to match addresses with a mask
+const AMODE_MASK: u8 = 0;
+
+/// Value of the `CTRLB.AMODE` field to match two addresses
+const AMODE_TWO_ADDRESSES: u8 = 1;
+
+/// Value of the `CTRLB.AMODE` field to match a range of addresses
+const AMODE_RANGE: u8 = 2;
+
+/// `CTRLB.CMD` value to execute the acknowledge action, then wait for a START
+/// condition
+const CMD_WAIT_START: u8 = 2;
+
+/// `CTRLB.CMD` value to execute the acknowledge action, then transfer the
+/// next byte
+const CMD_CONTINUE: u8 = 3;
+
+pub(super) struct Registers<S: Sercom> {
+    sercom: S,
+}
+
+// SAFETY: It is safe to implement Sync for Registers, because it erases the
+// interior mutability of the PAC SERCOM struct.
+unsafe impl<S: Sercom> Sync for Registers<S> {}
+
+impl<S: Sercom> Registers<S> {
+    /// Create a new `Registers` instance
+    #[inline]
+    pub(super) fn new(sercom: S) -> Self {
+        Self { sercom }
+    }
+
+    /// Helper function to access the underlying `I2CS` from the given `SERCOM`
+    #[inline]
+    fn i2cs(&self) -> &pac::sercom0::I2CS {
+        self.sercom.i2cs()
+    }
+
+    /// Free the `Registers` struct and return the underlying `Sercom` instance
+    #[inline]
+    pub(super) fn free(self) -> S {
+        self.sercom
+    }
+
+    /// Reset the SERCOM peripheral
+    #[inline]
+    pub(super) fn swrst(&mut self) {
+        self.i2cs().ctrla.write(|w| w.swrst().set_bit());
+        while self.i2cs().syncbusy.read().swrst().bit_is_set() {}
+    }
+
+    /// Configure the SERCOM as an I2C slave
+    #[inline]
+    pub(super) fn configure_mode(&mut self) {
+        self.i2cs()
+            .ctrla
+            .modify(|_, w| w.mode().variant(MODE_A::I2C_SLAVE));
+    }
+
+    /// Set the addresses the slave responds to
+    #[inline]
+    pub(super) fn set_address(&mut self, address: Address) {
+        let (amode, addr, mask, ten_bit) = match address {
+            Address::Single(addr) => (AMODE_MASK, addr as u16, 0, false),
+            Address::Mask { address, mask } => (AMODE_MASK, address as u16, mask as u16, false),
+            Address::Two(first, second) => {
+                (AMODE_TWO_ADDRESSES, first as u16, second as u16, false)
+            }
+            Address::Range { low, high } => (AMODE_RANGE, high as u16, low as u16, false),
+            Address::TenBit(addr) => (AMODE_MASK, addr, 0, true),
+        };
+
+        self.i2cs()
+            .ctrlb
+            .modify(|_, w| unsafe { w.amode().bits(amode) });
+        self.i2cs().addr.modify(|_, w| unsafe {
+            w.addr().bits(addr);
+            w.addrmask().bits(mask);
+            w.tenbiten().bit(ten_bit)
+        });
+    }
+
+    /// Get the addresses the slave responds to
+    #[inline]
+    pub(super) fn get_address(&self) -> Address {
+        let reg = self.i2cs().addr.read();
+        let addr = reg.addr().bits();
+        let mask = reg.addrmask().bits() as u8;
+        if reg.tenbiten().bit() {
+            return Address::TenBit(addr);
+        }
+
+        let addr = addr as u8;
+        match self.i2cs().ctrlb.read().amode().bits() {
+            AMODE_MASK if mask == 0 => Address::Single(addr),
+            AMODE_MASK => Address::Mask {
+                address: addr,
+                mask,
+            },
+            AMODE_TWO_ADDRESSES => Address::Two(addr, mask),
+            _ => Address::Range {
+                low: mask,
+                high: addr,
+            },
+        }
+    }
+
+    /// Respond to the general call address
+    #[inline]
+    pub(super) fn set_general_call(&mut self, enabled: bool) {
+        self.i2cs().addr.modify(|_, w| w.gencen().bit(enabled));
+    }
+
+    /// Get the current general call setting
+    #[inline]
+    pub(super) fn get_general_call(&self) -> bool {
+        self.i2cs().addr.read().gencen().bit()
+    }
+
+    /// Enable or disable smart mode
+    #[inline]
+    pub(super) fn set_smart_mode(&mut self, enabled: bool) {
+        self.i2cs().ctrlb.modify(|_, w| w.smen().bit(enabled));
+    }
+
+    /// Get the current smart mode setting
+    #[inline]
+    pub(super) fn get_smart_mode(&self) -> bool {
+        self.i2cs().ctrlb.read().smen().bit()
+    }
+
+    /// Set the clock stretch mode
+    #[inline]
+    pub(super) fn set_clock_stretch(&mut self, stretch: ClockStretch) {
+        self.i2cs()
+            .ctrla
+            .modify(|_, w| w.sclsm().bit(stretch == ClockStretch::AfterAck));
+    }
+
+    /// Get the current clock stretch mode
+    #[inline]
+    pub(super) fn get_clock_stretch(&self) -> ClockStretch {
+        if self.i2cs().ctrla.read().sclsm().bit() {
+            ClockStretch::AfterAck
+        } else {
+            ClockStretch::BeforeAck
+        }
+    }
+
+    /// Enable or disable the SCL low timeout
+    #[inline]
+    pub(super) fn set_low_timeout(&mut self, enabled: bool) {
+        self.i2cs().ctrla.modify(|_, w| w.lowtouten().bit(enabled));
+    }
+
+    /// Get the current SCL low timeout setting
+    #[inline]
+    pub(super) fn get_low_timeout(&self) -> bool {
+        self.i2cs().ctrla.read().lowtouten().bit()
+    }
+
+    /// Enable or disable the SCL low extend timeout
+    #[inline]
+    pub(super) fn set_extend_timeout(&mut self, enabled: bool) {
+        self.i2cs().ctrla.modify(|_, w| w.sexttoen().bit(enabled));
+    }
+
+    /// Get the current SCL low extend timeout setting
+    #[inline]
+    pub(super) fn get_extend_timeout(&self) -> bool {
+        self.i2cs().ctrla.read().sexttoen().bit()
+    }
+
+    /// Run in standby mode
+    #[inline]
+    pub(super) fn set_run_in_standby(&mut self, set: bool) {
+        self.i2cs().ctrla.modify(|_, w| w.runstdby().bit(set));
+    }
+
+    /// Get the current run in standby mode
+    #[inline]
+    pub(super) fn get_run_in_standby(&self) -> bool {
+        self.i2cs().ctrla.read().runstdby().bit()
+    }
+
+    /// Clear specified interrupt flags
+    #[inline]
+    pub(super) fn clear_flags(&mut self, flags: Flags) {
+        self.i2cs()
+            .intflag
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Read interrupt flags
+    #[inline]
+    pub(super) fn read_flags(&self) -> Flags {
+        Flags::from_bits_truncate(self.i2cs().intflag.read().bits())
+    }
+
+    /// Enable specified interrupts
+    #[inline]
+    pub(super) fn enable_interrupts(&mut self, flags: Flags) {
+        self.i2cs()
+            .intenset
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Disable specified interrupts
+    #[inline]
+    pub(super) fn disable_interrupts(&mut self, flags: Flags) {
+        self.i2cs()
+            .intenclr
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Clear specified status flags
+    #[inline]
+    pub(super) fn clear_status(&mut self, status: Status) {
+        self.i2cs()
+            .status
+            .write(|w| unsafe { w.bits(status.bits()) });
+    }
+
+    /// Read status flags
+    #[inline]
+    pub(super) fn read_status(&self) -> Status {
+        Status::from_bits_truncate(self.i2cs().status.read().bits())
+    }
+
+    /// Convert the error status flags into a [`Result`]
+    #[inline]
+    pub(super) fn check_status(&self) -> Result<(), Error> {
+        self.read_status().try_into()
+    }
+
+    /// Enable the I2C peripheral
+    #[inline]
+    pub(super) fn enable(&mut self) {
+        self.enable_peripheral(true);
+    }
+
+    /// Disable the I2C peripheral
+    #[inline]
+    pub(super) fn disable(&mut self) {
+        self.enable_peripheral(false);
+    }
+
+    /// Enable or disable the SERCOM peripheral, and wait for the ENABLE bit to
+    /// synchronize.
+    #[inline]
+    pub(super) fn enable_peripheral(&mut self, enable: bool) {
+        self.i2cs().ctrla.modify(|_, w| w.enable().bit(enable));
+        while self.i2cs().syncbusy.read().enable().bit_is_set() {}
+    }
+
+    //=========================================================================
+    // Transactions
+    //=========================================================================
+
+    /// Read a byte from the `DATA` register
+    #[cfg(any(feature = "samd11", feature = "samd21"))]
+    #[inline]
+    pub(super) fn read_data(&mut self) -> u8 {
+        self.i2cs().data.read().bits()
+    }
+
+    /// Read a byte from the `DATA` register
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub(super) fn read_data(&mut self) -> u8 {
+        self.i2cs().data.read().bits() as u8
+    }
+
+    /// Write a byte to the `DATA` register
+    #[cfg(any(feature = "samd11", feature = "samd21"))]
+    #[inline]
+    pub(super) fn write_data(&mut self, byte: u8) {
+        self.i2cs().data.write(|w| unsafe { w.bits(byte) });
+    }
+
+    /// Write a byte to the `DATA` register
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub(super) fn write_data(&mut self, byte: u8) {
+        self.i2cs().data.write(|w| unsafe { w.bits(byte as u32) });
+    }
+
+    /// Get the address which matched, after an `AMATCH` interrupt
+    ///
+    /// For 7-bit addresses, the address byte is read from the `DATA` register.
+    /// A 10-bit address can only match the configured address.
+    #[inline]
+    pub(super) fn received_address(&mut self) -> u16 {
+        let addr = self.i2cs().addr.read();
+        if addr.tenbiten().bit() {
+            addr.addr().bits()
+        } else {
+            (self.read_data() >> 1).into()
+        }
+    }
+
+    /// Set the acknowledge action, executed by the next command or, in smart
+    /// mode, by reading the `DATA` register
+    #[inline]
+    pub(super) fn set_ack(&mut self, ack: bool) {
+        self.i2cs().ctrlb.modify(|_, w| w.ackact().bit(!ack));
+    }
+
+    /// Execute the acknowledge action, then transfer the next byte
+    #[inline]
+    pub(super) fn cmd_continue(&mut self) {
+        self.cmd(CMD_CONTINUE);
+    }
+
+    /// Execute the acknowledge action, then wait for a START condition
+    #[inline]
+    pub(super) fn cmd_wait_start(&mut self) {
+        self.cmd(CMD_WAIT_START);
+    }
+
+    #[inline]
+    fn cmd(&mut self, cmd: u8) {
+        self.i2cs()
+            .ctrlb
+            .modify(|_, w| unsafe { w.cmd().bits(cmd) });
+    }
+}
@@ -233,6 +233,66 @@ type Pads = uart::PadsFromIds<Sercom0, PA09, PA09>;
 //! conditions. [This application note](https://www.silabs.com/documents/public/application-notes/an0059.0-uart-flow-control.pdf)
 //! provides more information about UART hardware flow control.
 //!
+#![cfg_attr(
+    feature = "min-samd51g",
+    doc = "
+# RS-485 (SAMx5x)
+
+On SAMx5x chips, the [`Pads`] can specify a transmit-enable `TE` pad, which
+is always `Pad2`, instead of the `RTS` and `CTS` pads. The `TE` pad drives
+the line driver of an RS-485 transceiver: it is high while the UART
+transmits, and for a configurable
+[guard time](Config::rs485_guard_time) afterwards. Pads with a `TE` pad are
+released with [`Pads::free`] as an `(RX, TX, TE)` tuple. RS-485 is not
+available on SAMD11 and SAMD21 chips.
+
+```
+let pads = uart::Pads::<Sercom0, IoSet1>::default()
+    .rx(pins.pa05)
+    .tx(pins.pa04)
+    .te(pins.pa06);
+let uart = uart::Config::new(&mclk, sercom, pads, freq)
+    .rs485_guard_time(2)
+    .collision_detection(true)
+    .enable();
+```
+
+"
+)]
+//!
+//! # LIN and bus arbitration
+//!
+//! The [`FrameFormat`] selects the LIN modes. A LIN slave uses the
+//! [`FrameFormat::AutoBaud`] format, which detects the break of each header,
+//! sets the `RXBRK` interrupt flag, and adjusts the baud rate to the sync
+//! field. [`read_lin_header`](Uart::read_lin_header) then returns the
+//! protected identifier of the header. On SAMx5x chips, a LIN master uses the
+//! `LinMaster` format, and sends headers with `send_lin_header`. SAMD11 and
+//! SAMD21 chips can only act as LIN slaves. The [`lin`] module computes the
+//! protected identifiers and checksums.
+//!
+//! On a shared bus, [collision detection](Config::collision_detection) aborts
+//! the transmission of a node which loses the arbitration.
+//! [`check_collision`](Uart::check_collision) reports the collision and
+//! enables the transmitter again.
+//!
+//! ```
+//! use atsamd_hal::sercom::v2::uart::{lin, FrameFormat};
+//!
+//! let mut uart = uart::Config::new(&pm, sercom, pads, freq)
+//!     .baud(19200.hz(), BaudMode::Fractional(Oversampling::Bits16))
+//!     .frame_format(FrameFormat::AutoBaud)
+//!     .enable();
+//! uart.enable_interrupts(Flags::RXBRK);
+//!
+//! // In the SERCOM interrupt handler
+//! if let Ok(pid) = uart.read_lin_header() {
+//!     if lin::frame_id(pid) == Some(0x10) {
+//!         // Send the response
+//!     }
+//! }
+//! ```
+//!
 //! # Splitting
 //!
 //! A `Uart<C, Duplex>` can be split into its [`RxDuplex`] and [`TxDuplex`]
@@ -324,7 +384,6 @@ type Pads = uart::PadsFromIds<Sercom0, PA09, PA09>;
 //! # Non-supported advanced features
 //!
 //! * Synchronous mode (USART) is not supported
-//! * LIN mode is not supported (SAMx5x)
 //! * 32-bit extension mode is not supported (SAMx5x). If you need to transfer
 //!   slices, consider using the DMA methods instead. The `dma` Cargo feature
 //!   must be enabled.
@@ -375,9 +434,16 @@ let (chan0, tx_buffer, tx) = tx_dma.wait();
 let (chan1, rx, rx_buffer) = rx_dma.wait();
 ```
 
+For variable-length messages, [`receive_ring_with_dma`] starts a circular
+transfer, which receives continuously into a ring buffer. The received words
+are consumed from the returned [`RxRing`], which reports overruns, and can also
+detect when the line goes idle at the end of a message.
+
 [`Buffer`]: crate::dmac::transfer::Buffer
 [`send_with_dma`]: Uart::send_with_dma
 [`receive_with_dma`]: Uart::receive_with_dma
+[`receive_ring_with_dma`]: Uart::receive_ring_with_dma
+[`RxRing`]: crate::sercom::v2::dma::RxRing
 [`dmac::Transfer`]: crate::dmac::Transfer
 [`Channel`]: crate::dmac::channel::Channel
 [`dmac`]: crate::dmac
@@ -409,6 +475,8 @@ pub use config::*;
 
 pub mod impl_ehal;
 
+pub mod lin;
+
 use crate::{sercom::v2::*, typelevel::Sealed};
 use core::{convert::TryInto, marker::PhantomData};
 use num_traits::AsPrimitive;
@@ -479,6 +547,62 @@ pub enum BaudMode {
     Fractional(Oversampling),
 }
 
+//=============================================================================
+// Frame format, LIN
+//=============================================================================
+
+/// Frame format of the UART
+///
+/// The LIN formats are combined with the [`lin`] helpers to implement the LIN
+/// protocol.
+///
+/// SAMD11 and SAMD21 chips have no `CTRLC` register, which holds the break
+/// length and header delay of LIN master headers, so only the
+/// [`FrameFormat::AutoBaud`] format of LIN slaves is available on them. The
+/// `LinMaster` format, like RS-485, is only available on SAMx5x chips.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// Regular UART frames
+    Usart,
+    /// LIN master frames. The UART sends the break and sync fields of a LIN
+    /// header before its identifier. Parity is not available.
+    #[cfg(feature = "min-samd51g")]
+    LinMaster,
+    /// Auto-baud frames, as received by a LIN slave. The UART detects breaks,
+    /// then adjusts its baud rate to the sync field following each break.
+    AutoBaud,
+}
+
+/// Length of the break field sent by a LIN master
+#[cfg(feature = "min-samd51g")]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakLength {
+    /// 13 bit times
+    Bits13,
+    /// 17 bit times
+    Bits17,
+    /// 21 bit times
+    Bits21,
+    /// 26 bit times
+    Bits26,
+}
+
+/// Delay between the break and sync fields sent by a LIN master
+#[cfg(feature = "min-samd51g")]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderDelay {
+    /// 1 bit time
+    Bits1,
+    /// 4 bit times
+    Bits4,
+    /// 8 bit times
+    Bits8,
+    /// 14 bit times
+    Bits14,
+}
+
 //=============================================================================
 // Capability
 //=============================================================================
@@ -946,6 +1070,39 @@ where
             Status::BUFOVF | Status::FERR | Status::PERR | Status::ISF | Status::COLL,
         );
     }
+
+    /// Wait for a LIN header, then read its protected identifier
+    ///
+    /// Requires the [`FrameFormat::AutoBaud`] format. The UART detects the
+    /// break, which sets the `RXBRK` flag, and adjusts its baud rate to the
+    /// sync field. The next word is the protected identifier, which can be
+    /// checked with [`lin::frame_id`]. Words received outside of a header,
+    /// such as the responses of other nodes, are discarded.
+    ///
+    /// On an error, such as an [`Error::InconsistentSyncField`], the header is
+    /// dropped. The error must be cleared with
+    /// [`clear_status`](Uart::clear_status).
+    #[inline]
+    pub fn read_lin_header(&mut self) -> nb::Result<u8, Error> {
+        let flags = match self.read_flags_errors() {
+            Ok(flags) => flags,
+            Err(err) => {
+                self.clear_flags(Flags::RXBRK);
+                return Err(nb::Error::Other(err));
+            }
+        };
+
+        if !flags.contains(Flags::RXC) {
+            return Err(nb::Error::WouldBlock);
+        }
+        let word = unsafe { self.read_data() };
+        if flags.contains(Flags::RXBRK) {
+            self.clear_flags(Flags::RXBRK);
+            Ok(word as u8)
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
 }
 
 impl<C, D> Uart<C, D>
@@ -964,4 +1121,42 @@ where
     pub unsafe fn write_data(&mut self, data: DataReg) {
         self.config.as_mut().registers.write_data(data);
     }
+
+    /// Check whether a collision aborted the transmission
+    ///
+    /// With [collision detection](Config::collision_detection), the UART
+    /// compares each bit it sends with the line. When another node drives the
+    /// line, as when it wins the arbitration of a shared bus, the UART aborts
+    /// the transmission, flushes the transmit buffer and disables the
+    /// transmitter. This method then clears the `COLL` status flag, enables
+    /// the transmitter again, and returns an [`Error::CollisionDetected`], so
+    /// that the transmission can be retried.
+    #[inline]
+    pub fn check_collision(&mut self) -> Result<(), Error> {
+        let registers = &mut self.config.as_mut().registers;
+        if !registers.read_status().contains(Status::COLL) {
+            return Ok(());
+        }
+        registers.clear_status(Status::COLL);
+        registers.enable_transmitter();
+        Err(Error::CollisionDetected)
+    }
+
+    /// Send a LIN header with the frame identifier `id`
+    ///
+    /// Requires the [`FrameFormat::LinMaster`] format. The UART sends a break,
+    /// a sync field and the [protected identifier](lin::protected_id) of
+    /// `id`. The response is then sent or received as regular words.
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub fn send_lin_header(&mut self, id: u8) -> nb::Result<(), core::convert::Infallible> {
+        if !self.read_flags().contains(Flags::DRE) {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.config
+            .as_mut()
+            .registers
+            .send_lin_header(lin::protected_id(id));
+        Ok(())
+    }
 }
@@ -2,8 +2,11 @@
 
 use super::{
     BaudMode, BitOrder, Capability, CharSize, CharSizeEnum, DataReg, DynCharSize, EightBit,
-    FixedCharSize, Parity, Registers, StopBits, Uart, ValidConfig, ValidPads,
+    FixedCharSize, FrameFormat, Parity, Registers, StopBits, Uart, ValidConfig, ValidPads,
 };
+
+#[cfg(feature = "min-samd51g")]
+use super::{BreakLength, HeaderDelay, PadSet};
 use crate::{
     pac,
     sercom::v2::*,
@@ -190,6 +193,31 @@ where
         self.registers.get_parity()
     }
 
+    /// Change the [`FrameFormat`] (builder pattern version)
+    ///
+    /// The parity setting is kept, except for LIN master frames, which have no
+    /// parity.
+    #[inline]
+    pub fn frame_format(mut self, format: FrameFormat) -> Self {
+        self.set_frame_format(format);
+        self
+    }
+
+    /// Change the [`FrameFormat`] (setter version)
+    ///
+    /// The parity setting is kept, except for LIN master frames, which have no
+    /// parity.
+    #[inline]
+    pub fn set_frame_format(&mut self, format: FrameFormat) {
+        self.registers.set_frame_format(format);
+    }
+
+    /// Get the current [`FrameFormat`]
+    #[inline]
+    pub fn get_frame_format(&self) -> FrameFormat {
+        self.registers.get_frame_format()
+    }
+
     /// Change the stop bit setting (builder pattern version)
     #[inline]
     pub fn stop_bits(mut self, stop_bits: StopBits) -> Self {
@@ -237,7 +265,9 @@ where
     /// Enable or disable the collision detector (builder pattern version)
     ///
     /// When set, the UART will detect collisions and update the
-    /// corresponding flag in the STATUS register.
+    /// corresponding flag in the STATUS register. A collision disables the
+    /// transmitter, which is enabled again by
+    /// [`Uart::check_collision`].
     #[inline]
     pub fn collision_detection(mut self, enabled: bool) -> Self {
         self.set_collision_detection(enabled);
@@ -247,7 +277,9 @@ where
     /// Enable or disable the collision detector (setter version)
     ///
     /// When set, the UART will detect collisions and update the
-    /// corresponding flag in the STATUS register.
+    /// corresponding flag in the STATUS register. A collision disables the
+    /// transmitter, which is enabled again by
+    /// [`Uart::check_collision`].
     #[inline]
     pub fn set_collision_detection(&mut self, enabled: bool) {
         self.registers.set_collision_detection(enabled);
@@ -376,6 +408,104 @@ where
     }
 }
 
+#[cfg(feature = "min-samd51g")]
+impl<P, C> Config<P, C>
+where
+    P: ValidPads,
+    C: CharSize,
+{
+    /// Set the length of the break field of LIN headers (builder pattern
+    /// version)
+    ///
+    /// Only applies to the [`FrameFormat::LinMaster`] format.
+    #[inline]
+    pub fn lin_break_length(mut self, length: BreakLength) -> Self {
+        self.set_lin_break_length(length);
+        self
+    }
+
+    /// Set the length of the break field of LIN headers (setter version)
+    ///
+    /// Only applies to the [`FrameFormat::LinMaster`] format.
+    #[inline]
+    pub fn set_lin_break_length(&mut self, length: BreakLength) {
+        self.registers.set_break_length(length);
+    }
+
+    /// Get the current length of the break field of LIN headers
+    #[inline]
+    pub fn get_lin_break_length(&self) -> BreakLength {
+        self.registers.get_break_length()
+    }
+
+    /// Set the delay between the break and sync fields of LIN headers
+    /// (builder pattern version)
+    ///
+    /// Only applies to the [`FrameFormat::LinMaster`] format.
+    #[inline]
+    pub fn lin_header_delay(mut self, delay: HeaderDelay) -> Self {
+        self.set_lin_header_delay(delay);
+        self
+    }
+
+    /// Set the delay between the break and sync fields of LIN headers (setter
+    /// version)
+    ///
+    /// Only applies to the [`FrameFormat::LinMaster`] format.
+    #[inline]
+    pub fn set_lin_header_delay(&mut self, delay: HeaderDelay) {
+        self.registers.set_header_delay(delay);
+    }
+
+    /// Get the current delay between the break and sync fields of LIN headers
+    #[inline]
+    pub fn get_lin_header_delay(&self) -> HeaderDelay {
+        self.registers.get_header_delay()
+    }
+}
+
+#[cfg(feature = "min-samd51g")]
+impl<P, C> Config<P, C>
+where
+    P: ValidPads,
+    <P as PadSet>::Te: SomePad,
+    C: CharSize,
+{
+    /// Set the RS-485 guard time (builder pattern version)
+    ///
+    /// The `TE` pad stays high for `bits` bit times after the end of each
+    /// transmission, from 0 to 7, before the line driver is disabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is greater than 7.
+    #[inline]
+    pub fn rs485_guard_time(mut self, bits: u8) -> Self {
+        self.set_rs485_guard_time(bits);
+        self
+    }
+
+    /// Set the RS-485 guard time (setter version)
+    ///
+    /// The `TE` pad stays high for `bits` bit times after the end of each
+    /// transmission, from 0 to 7, before the line driver is disabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is greater than 7.
+    #[inline]
+    pub fn set_rs485_guard_time(&mut self, bits: u8) {
+        assert!(bits <= 7);
+        self.registers.set_guard_time(bits);
+    }
+
+    /// Get the current RS-485 guard time, in bit times
+    #[inline]
+    pub fn get_rs485_guard_time(&self) -> u8 {
+        self.registers.get_guard_time()
+    }
+}
+
 impl<P: ValidPads> Config<P, DynCharSize> {
     /// Dynamically change the character size
     #[inline]
@@ -0,0 +1,66 @@
+//! LIN protocol helpers
+//!
+//! A LIN master sends the header of each frame with `Uart::send_lin_header`
+//! (SAMx5x only), and a LIN slave receives it with
+//! [`read_lin_header`](super::Uart::read_lin_header). These functions compute
+//! the protected identifier of a header and the checksum of a response.
+
+/// Add the parity bits to a 6-bit frame identifier
+#[inline]
+pub fn protected_id(id: u8) -> u8 {
+    let bit = |n: u8| (id >> n) & 1;
+    let p0 = bit(0) ^ bit(1) ^ bit(2) ^ bit(4);
+    let p1 = !(bit(1) ^ bit(3) ^ bit(4) ^ bit(5)) & 1;
+    (id & 0x3F) | p0 << 6 | p1 << 7
+}
+
+/// Recover the frame identifier of a protected identifier
+///
+/// Returns `None` if the parity bits are invalid.
+#[inline]
+pub fn frame_id(pid: u8) -> Option<u8> {
+    let id = pid & 0x3F;
+    if protected_id(id) == pid {
+        Some(id)
+    } else {
+        None
+    }
+}
+
+/// Compute the checksum of a response
+///
+/// Pass the protected identifier of the header for the enhanced checksum of
+/// LIN 2.x, or `None` for the classic checksum of LIN 1.x and diagnostic
+/// frames.
+#[inline]
+pub fn checksum(pid: Option<u8>, data: &[u8]) -> u8 {
+    let mut sum = pid.map_or(0, u16::from);
+    for &byte in data {
+        sum += u16::from(byte);
+        if sum > 0xFF {
+            sum -= 0xFF;
+        }
+    }
+    !(sum as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pid() {
+        assert_eq!(protected_id(0x00), 0x80);
+        assert_eq!(protected_id(0x01), 0xC1);
+        assert_eq!(protected_id(0x3C), 0x3C);
+        assert_eq!(protected_id(0x3D), 0x7D);
+        assert_eq!(frame_id(0x7D), Some(0x3D));
+        assert_eq!(frame_id(0x3D), None);
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(checksum(Some(0x4A), &[0x55, 0x93, 0xE5]), 0xE6);
+        assert_eq!(checksum(None, &[0x55, 0x93, 0xE5]), 0x31);
+    }
+}
@@ -40,7 +40,7 @@ impl Rxpo for NoneT {
 
 /// Lift the implementations of [`Rxpo`] from [`OptionalPadNum`]s to the
 /// corresponding [`Pads`] types.
-impl<S, I, RX, TX, RTS, CTS> Rxpo for Pads<S, I, RX, TX, RTS, CTS>
+impl<S, I, RX, TX, RTS, CTS, TE> Rxpo for Pads<S, I, RX, TX, RTS, CTS, TE>
 where
     S: Sercom,
     I: IoSet,
@@ -48,6 +48,7 @@ where
     TX: OptionalPad,
     RTS: OptionalPad,
     CTS: OptionalPad,
+    TE: OptionalPad,
     RX::PadNum: Rxpo,
 {
     const RXPO: RXPO_A = RX::PadNum::RXPO;
@@ -73,8 +74,8 @@ impl Txpo for NoneT {
 }
 
 /// Lift the implementations of [`Txpo`] from [`OptionalPadNum`]s to the
-/// corresponding [`Pads`] types.
-impl<S, I, RX, TX, RTS, CTS> Txpo for Pads<S, I, RX, TX, RTS, CTS>
+/// corresponding [`Pads`] types without a `TE` [`Pad`].
+impl<S, I, RX, TX, RTS, CTS> Txpo for Pads<S, I, RX, TX, RTS, CTS, NoneT>
 where
     S: Sercom,
     I: IoSet,
@@ -87,6 +88,20 @@ where
     const TXPO: TXPO_A = TX::PadNum::TXPO;
 }
 
+/// [`Pads`] with a `TE` [`Pad`] use the RS-485 pinout, which shares [`Pad2`]
+/// with `RTS` and has no `CTS`.
+impl<S, I, RX, TX, TE> Txpo for Pads<S, I, RX, TX, NoneT, NoneT, TE>
+where
+    S: Sercom,
+    I: IoSet,
+    RX: OptionalPad,
+    TX: OptionalPad,
+    TE: SomePad,
+    TX::PadNum: Txpo,
+{
+    const TXPO: TXPO_A = TXPO_A::TXPO_3;
+}
+
 //=============================================================================
 // Pads
 //=============================================================================
@@ -95,7 +110,7 @@ where
 ///
 /// See the [module-level](crate::sercom::v2::uart) documentation for more
 /// details on specifying a `Pads` type and creating instances.
-pub struct Pads<S, I, RX = NoneT, TX = NoneT, RTS = NoneT, CTS = NoneT>
+pub struct Pads<S, I, RX = NoneT, TX = NoneT, RTS = NoneT, CTS = NoneT, TE = NoneT>
 where
     S: Sercom,
     I: IoSet,
@@ -103,6 +118,7 @@ where
     TX: OptionalPad,
     RTS: OptionalPad,
     CTS: OptionalPad,
+    TE: OptionalPad,
 {
     sercom: PhantomData<S>,
     ioset: PhantomData<I>,
@@ -110,6 +126,7 @@ where
     transmit: TX,
     ready_to_send: RTS,
     clear_to_send: CTS,
+    transmit_enable: TE,
 }
 
 impl<S: Sercom, I: IoSet> Default for Pads<S, I> {
@@ -121,11 +138,12 @@ impl<S: Sercom, I: IoSet> Default for Pads<S, I> {
             transmit: NoneT,
             ready_to_send: NoneT,
             clear_to_send: NoneT,
+            transmit_enable: NoneT,
         }
     }
 }
 
-impl<S, I, RX, TX, RTS, CTS> Pads<S, I, RX, TX, RTS, CTS>
+impl<S, I, RX, TX, RTS, CTS, TE> Pads<S, I, RX, TX, RTS, CTS, TE>
 where
     S: Sercom,
     I: IoSet,
@@ -133,10 +151,11 @@ where
     TX: OptionalPad,
     RTS: OptionalPad,
     CTS: OptionalPad,
+    TE: OptionalPad,
 {
     /// Set the `RX` [`Pad`]
     #[inline]
-    pub fn rx<Id>(self, pin: impl AnyPin<Id = Id>) -> Pads<S, I, Pad<S, Id>, TX, RTS, CTS>
+    pub fn rx<Id>(self, pin: impl AnyPin<Id = Id>) -> Pads<S, I, Pad<S, Id>, TX, RTS, CTS, TE>
     where
         Id: GetPad<S>,
         Id::PadNum: Rxpo,
@@ -149,12 +168,13 @@ where
             transmit: self.transmit,
             ready_to_send: self.ready_to_send,
             clear_to_send: self.clear_to_send,
+            transmit_enable: self.transmit_enable,
         }
     }
 
     /// Set the `TX` [`Pad`]
     #[inline]
-    pub fn tx<Id>(self, pin: impl AnyPin<Id = Id>) -> Pads<S, I, RX, Pad<S, Id>, RTS, CTS>
+    pub fn tx<Id>(self, pin: impl AnyPin<Id = Id>) -> Pads<S, I, RX, Pad<S, Id>, RTS, CTS, TE>
     where
         Id: GetPad<S>,
         Id::PadNum: Txpo,
@@ -167,12 +187,13 @@ where
             transmit: pin.into().into_mode(),
             ready_to_send: self.ready_to_send,
             clear_to_send: self.clear_to_send,
+            transmit_enable: self.transmit_enable,
         }
     }
 
     /// Set the `RTS` [`Pad`], which is always [`Pad2`]
     #[inline]
-    pub fn rts<Id>(self, pin: impl AnyPin<Id = Id>) -> Pads<S, I, RX, TX, Pad<S, Id>, CTS>
+    pub fn rts<Id>(self, pin: impl AnyPin<Id = Id>) -> Pads<S, I, RX, TX, Pad<S, Id>, CTS, TE>
     where
         Id: GetPad<S>,
         Pad<S, Id>: InIoSet<I>,
@@ -184,12 +205,13 @@ where
             transmit: self.transmit,
             ready_to_send: pin.into().into_mode(),
             clear_to_send: self.clear_to_send,
+            transmit_enable: self.transmit_enable,
         }
     }
 
     /// Set the `CTS` [`Pad`], which is always [`Pad3`]
     #[inline]
-    pub fn cts<Id>(self, pin: impl AnyPin<Id = Id>) -> Pads<S, I, RX, TX, RTS, Pad<S, Id>>
+    pub fn cts<Id>(self, pin: impl AnyPin<Id = Id>) -> Pads<S, I, RX, TX, RTS, Pad<S, Id>, TE>
     where
         Id: GetPad<S>,
         Pad<S, Id>: InIoSet<I>,
@@ -201,9 +223,43 @@ where
             transmit: self.transmit,
             ready_to_send: self.ready_to_send,
             clear_to_send: pin.into().into_mode(),
+            transmit_enable: self.transmit_enable,
         }
     }
 
+    /// Set the RS-485 `TE` [`Pad`], which is always [`Pad2`]
+    ///
+    /// The `TE` pad is driven high while the UART transmits, and for the
+    /// [guard time](super::Config::rs485_guard_time) after each transmission,
+    /// to enable the line driver of an RS-485 transceiver. It can't be used
+    /// with the `RTS` and `CTS` pads.
+    #[inline]
+    pub fn te<Id>(self, pin: impl AnyPin<Id = Id>) -> Pads<S, I, RX, TX, RTS, CTS, Pad<S, Id>>
+    where
+        Id: GetPad<S, PadNum = Pad2>,
+        Pad<S, Id>: InIoSet<I>,
+    {
+        Pads {
+            sercom: self.sercom,
+            ioset: self.ioset,
+            receive: self.receive,
+            transmit: self.transmit,
+            ready_to_send: self.ready_to_send,
+            clear_to_send: self.clear_to_send,
+            transmit_enable: pin.into().into_mode(),
+        }
+    }
+}
+
+impl<S, I, RX, TX, RTS, CTS> Pads<S, I, RX, TX, RTS, CTS, NoneT>
+where
+    S: Sercom,
+    I: IoSet,
+    RX: OptionalPad,
+    TX: OptionalPad,
+    RTS: OptionalPad,
+    CTS: OptionalPad,
+{
     /// Consume the [`Pads`] and return each individual [`Pad`]
     #[inline]
     pub fn free(self) -> (RX, TX, RTS, CTS) {
@@ -216,14 +272,30 @@ where
     }
 }
 
+impl<S, I, RX, TX, TE> Pads<S, I, RX, TX, NoneT, NoneT, TE>
+where
+    S: Sercom,
+    I: IoSet,
+    RX: OptionalPad,
+    TX: OptionalPad,
+    TE: SomePad,
+{
+    /// Consume the [`Pads`] and return the `RX`, `TX` and `TE` [`Pad`]s
+    #[inline]
+    pub fn free(self) -> (RX, TX, TE) {
+        (self.receive, self.transmit, self.transmit_enable)
+    }
+}
+
 /// Define a set of [`Pads`] using [`PinId`]s instead of [`Pin`]s
 ///
 /// In some cases, it is more convenient to specify a set of `Pads` using
 /// `PinId`s rather than `Pin`s. This alias makes it easier to do so.
 ///
 /// The first two type parameters are the [`Sercom`] and [`IoSet`], while the
-/// remaining four are effectively [`OptionalPinId`]s representing the
-/// corresponding type parameters of [`Pads`], i.e. `RX`, `TX`, `RTS` & `CTS`.
+/// remaining five are effectively [`OptionalPinId`]s representing the
+/// corresponding type parameters of [`Pads`], i.e. `RX`, `TX`, `RTS`, `CTS` &
+/// `TE`.
 /// Each of the remaining type parameters defaults to [`NoneT`].
 ///
 /// ```
@@ -245,13 +317,14 @@ where
 /// [`Pin`]: crate::gpio::v2::Pin
 /// [`PinId`]: crate::gpio::v2::PinId
 /// [`OptionalPinId`]: crate::gpio::v2::OptionalPinId
-pub type PadsFromIds<S, I, RX = NoneT, TX = NoneT, RTS = NoneT, CTS = NoneT> = Pads<
+pub type PadsFromIds<S, I, RX = NoneT, TX = NoneT, RTS = NoneT, CTS = NoneT, TE = NoneT> = Pads<
     S,
     I,
     <RX as GetOptionalPad<S>>::Pad,
     <TX as GetOptionalPad<S>>::Pad,
     <RTS as GetOptionalPad<S>>::Pad,
     <CTS as GetOptionalPad<S>>::Pad,
+    <TE as GetOptionalPad<S>>::Pad,
 >;
 
 //=============================================================================
@@ -268,7 +341,7 @@ pub type PadsFromIds<S, I, RX = NoneT, TX = NoneT, RTS = NoneT, CTS = NoneT> = P
 /// struct. The [`Config`] struct doesn't need access to the [`Pad`]s directly.
 /// Rather, it only needs to apply the [`SomePad`] trait bound when a `Pin` is
 /// required. The [`PadSet`] trait allows each [`Config`] struct to store an
-/// instance of [`Pads`] without itself being generic over all six type
+/// instance of [`Pads`] without itself being generic over all seven type
 /// parameters of the [`Pads`] type.
 ///
 /// [`Pin`]: crate::gpio::v2::Pin
@@ -281,9 +354,10 @@ pub trait PadSet: Sealed {
     type Tx: OptionalPad;
     type Rts: OptionalPad;
     type Cts: OptionalPad;
+    type Te: OptionalPad;
 }
 
-impl<S, I, RX, TX, RTS, CTS> Sealed for Pads<S, I, RX, TX, RTS, CTS>
+impl<S, I, RX, TX, RTS, CTS, TE> Sealed for Pads<S, I, RX, TX, RTS, CTS, TE>
 where
     S: Sercom,
     I: IoSet,
@@ -291,10 +365,11 @@ where
     TX: OptionalPad,
     RTS: OptionalPad,
     CTS: OptionalPad,
+    TE: OptionalPad,
 {
 }
 
-impl<S, I, RX, TX, RTS, CTS> PadSet for Pads<S, I, RX, TX, RTS, CTS>
+impl<S, I, RX, TX, RTS, CTS, TE> PadSet for Pads<S, I, RX, TX, RTS, CTS, TE>
 where
     S: Sercom,
     I: IoSet,
@@ -302,6 +377,7 @@ where
     TX: OptionalPad,
     RTS: OptionalPad,
     CTS: OptionalPad,
+    TE: OptionalPad,
 {
     type Sercom = S;
     type IoSet = I;
@@ -309,6 +385,7 @@ where
     type Tx = TX;
     type Rts = RTS;
     type Cts = CTS;
+    type Te = TE;
 }
 
 //=============================================================================
@@ -326,7 +403,7 @@ pub trait ValidPads: PadSet + Rxpo + Txpo {
     type Capability: Capability;
 }
 
-impl<S, I, RX, RTS> ValidPads for Pads<S, I, RX, NoneT, RTS, NoneT>
+impl<S, I, RX, RTS> ValidPads for Pads<S, I, RX, NoneT, RTS, NoneT, NoneT>
 where
     S: Sercom,
     I: IoSet,
@@ -337,18 +414,19 @@ where
     type Capability = Rx;
 }
 
-impl<S, I, TX, CTS> ValidPads for Pads<S, I, NoneT, TX, NoneT, CTS>
+impl<S, I, TX, CTS, TE> ValidPads for Pads<S, I, NoneT, TX, NoneT, CTS, TE>
 where
     S: Sercom,
     I: IoSet,
     TX: SomePad,
     CTS: OptionalPad,
+    TE: OptionalPad,
     Self: PadSet + Rxpo + Txpo,
 {
     type Capability = Tx;
 }
 
-impl<S, I, RX, TX, RTS, CTS> ValidPads for Pads<S, I, RX, TX, RTS, CTS>
+impl<S, I, RX, TX, RTS, CTS, TE> ValidPads for Pads<S, I, RX, TX, RTS, CTS, TE>
 where
     S: Sercom,
     I: IoSet,
@@ -356,6 +434,7 @@ where
     TX: SomePad,
     RTS: OptionalPad,
     CTS: OptionalPad,
+    TE: OptionalPad,
     Self: PadSet + Rxpo + Txpo,
 {
     type Capability = Duplex;
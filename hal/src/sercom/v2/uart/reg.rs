@@ -1,6 +1,11 @@
 //! Register-level access to UART configuration
 
-use super::{BaudMode, BitOrder, CharSizeEnum, Flags, Oversampling, Parity, Status, StopBits};
+use super::{
+    BaudMode, BitOrder, CharSizeEnum, Flags, FrameFormat, Oversampling, Parity, Status, StopBits,
+};
+
+#[cfg(feature = "min-samd51g")]
+use super::{BreakLength, HeaderDelay};
 
 use crate::pac;
 use crate::sercom::v2::*;
@@ -13,6 +18,20 @@ use pac::sercom0::usart_int::ctrla::MODE_A;
 
 use crate::time::Hertz;
 
+/// `CTRLA.FORM` bit enabling the parity
+const FORM_PARITY: u8 = 0x1;
+
+/// `CTRLA.FORM` value for LIN master frames, which have no parity
+const FORM_LIN_MASTER: u8 = 0x2;
+
+/// `CTRLA.FORM` value for auto-baud frames, without the parity bit
+const FORM_AUTO_BAUD: u8 = 0x4;
+
+/// `CTRLB.LINCMD` value to send the break, sync and identifier of a LIN
+/// header when `DATA` is written
+#[cfg(feature = "min-samd51g")]
+const LINCMD_HEADER: u8 = 0x2;
+
 pub(super) struct Registers<S: Sercom> {
     sercom: S,
 }
@@ -126,8 +145,8 @@ impl<S: Sercom> Registers<S> {
     /// Change the parity setting
     #[inline]
     pub(super) fn set_parity(&mut self, parity: Parity) {
-        // Use only the first two available settings in the FORM field.
-        // Ignore auto-baud options.
+        // Only change the parity bit of the FORM field, to keep the frame
+        // format
         let enabled = match parity {
             Parity::None => false,
             Parity::Odd => {
@@ -140,16 +159,25 @@ impl<S: Sercom> Registers<S> {
             }
         };
 
+        let form = self.usart().ctrla.read().form().bits();
+        let format = form & !FORM_PARITY;
+        // LIN master frames have no parity
+        let form = if format == FORM_LIN_MASTER {
+            form
+        } else {
+            format | enabled as u8
+        };
+
         self.usart()
             .ctrla
-            .modify(|_, w| unsafe { w.form().bits(enabled as u8) });
+            .modify(|_, w| unsafe { w.form().bits(form) });
     }
 
     /// Get the current parity setting
     #[inline]
     pub(super) fn get_parity(&self) -> Parity {
         let form = self.usart().ctrla.read().form().bits();
-        let enabled = form == 0x1 || form == 0x5;
+        let enabled = form & FORM_PARITY != 0;
 
         if !enabled {
             return Parity::None;
@@ -163,6 +191,107 @@ impl<S: Sercom> Registers<S> {
         }
     }
 
+    /// Change the frame format
+    #[inline]
+    pub(super) fn set_frame_format(&mut self, format: FrameFormat) {
+        let parity = self.usart().ctrla.read().form().bits() & FORM_PARITY;
+        let form = match format {
+            FrameFormat::Usart => parity,
+            #[cfg(feature = "min-samd51g")]
+            FrameFormat::LinMaster => FORM_LIN_MASTER,
+            FrameFormat::AutoBaud => FORM_AUTO_BAUD | parity,
+        };
+
+        self.usart()
+            .ctrla
+            .modify(|_, w| unsafe { w.form().bits(form) });
+    }
+
+    /// Get the current frame format
+    #[inline]
+    pub(super) fn get_frame_format(&self) -> FrameFormat {
+        let form = self.usart().ctrla.read().form().bits();
+        match form & !FORM_PARITY {
+            #[cfg(feature = "min-samd51g")]
+            FORM_LIN_MASTER => FrameFormat::LinMaster,
+            FORM_AUTO_BAUD => FrameFormat::AutoBaud,
+            _ => FrameFormat::Usart,
+        }
+    }
+
+    /// Set the RS-485 guard time, in bit times
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub(super) fn set_guard_time(&mut self, bits: u8) {
+        self.usart()
+            .ctrlc
+            .modify(|_, w| unsafe { w.gtime().bits(bits) });
+    }
+
+    /// Get the current RS-485 guard time, in bit times
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub(super) fn get_guard_time(&self) -> u8 {
+        self.usart().ctrlc.read().gtime().bits()
+    }
+
+    /// Set the length of the break field sent by a LIN master
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub(super) fn set_break_length(&mut self, length: BreakLength) {
+        self.usart()
+            .ctrlc
+            .modify(|_, w| unsafe { w.brklen().bits(length as u8) });
+    }
+
+    /// Get the current length of the break field sent by a LIN master
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub(super) fn get_break_length(&self) -> BreakLength {
+        match self.usart().ctrlc.read().brklen().bits() {
+            0x0 => BreakLength::Bits13,
+            0x1 => BreakLength::Bits17,
+            0x2 => BreakLength::Bits21,
+            0x3 => BreakLength::Bits26,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Set the delay between the break and sync fields sent by a LIN master
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub(super) fn set_header_delay(&mut self, delay: HeaderDelay) {
+        self.usart()
+            .ctrlc
+            .modify(|_, w| unsafe { w.hdrdly().bits(delay as u8) });
+    }
+
+    /// Get the current delay between the break and sync fields sent by a LIN
+    /// master
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub(super) fn get_header_delay(&self) -> HeaderDelay {
+        match self.usart().ctrlc.read().hdrdly().bits() {
+            0x0 => HeaderDelay::Bits1,
+            0x1 => HeaderDelay::Bits4,
+            0x2 => HeaderDelay::Bits8,
+            0x3 => HeaderDelay::Bits14,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Send a LIN header with the protected identifier `pid`
+    #[cfg(feature = "min-samd51g")]
+    #[inline]
+    pub(super) fn send_lin_header(&mut self, pid: u8) {
+        let usart = self.usart();
+        usart
+            .ctrlb
+            .modify(|_, w| unsafe { w.lincmd().bits(LINCMD_HEADER) });
+        while usart.syncbusy.read().ctrlb().bit_is_set() {}
+        usart.data.write(|w| unsafe { w.data().bits(pid.into()) });
+    }
+
     /// Change the stop bit setting
     #[inline]
     pub(super) fn set_stop_bits(&mut self, stop_bits: StopBits) {
@@ -422,6 +551,15 @@ impl<S: Sercom> Registers<S> {
         self.enable_peripheral(true);
     }
 
+    /// Enable the transmitter again, after a collision disabled it
+    #[inline]
+    pub(super) fn enable_transmitter(&mut self) {
+        let usart = self.usart();
+        while usart.syncbusy.read().ctrlb().bit_is_set() {}
+        usart.ctrlb.modify(|_, w| w.txen().set_bit());
+        while usart.syncbusy.read().ctrlb().bit_is_set() {}
+    }
+
     #[inline]
     pub(super) fn disable(&mut self) {
         let usart = self.usart();
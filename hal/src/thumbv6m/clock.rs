@@ -335,6 +335,48 @@ impl GenericClockController {
     }
 }
 
+/// Returns whether the generic clock is enabled, and keeps running in standby
+/// sleep mode
+///
+/// Only the 32kHz oscillators are accepted as the source of the generator, as
+/// the other sources are not configured to run in standby by this module.
+pub(crate) fn runs_in_standby(clock: ClockId) -> bool {
+    // SAFETY: The registers are only read, once selected with an 8-bit write
+    // to their ID field (ref 15.6.4.1 Indirect Access)
+    let gclk = unsafe { &*GCLK::ptr() };
+    let sysctrl = unsafe { &*SYSCTRL::ptr() };
+
+    unsafe {
+        let clkctrl_ptr_u8: *mut u8 = gclk.clkctrl.as_ptr() as *mut u8;
+        clkctrl_ptr_u8.write_volatile(u8::from(clock));
+    }
+    let clkctrl = gclk.clkctrl.read();
+    if clkctrl.clken().bit_is_clear() {
+        return false;
+    }
+
+    unsafe {
+        let genctrl_ptr_u8: *mut u8 = gclk.genctrl.as_ptr() as *mut u8;
+        genctrl_ptr_u8.write_volatile(clkctrl.gen().bits());
+    }
+    while gclk.status.read().syncbusy().bit_is_set() {}
+    let genctrl = gclk.genctrl.read();
+    if genctrl.genen().bit_is_clear() || genctrl.runstdby().bit_is_clear() {
+        return false;
+    }
+
+    let src = genctrl.src().bits();
+    if src == u8::from(OSCULP32K) {
+        true
+    } else if src == u8::from(OSC32K) {
+        sysctrl.osc32k.read().runstdby().bit_is_set()
+    } else if src == u8::from(XOSC32K) {
+        sysctrl.xosc32k.read().runstdby().bit_is_set()
+    } else {
+        false
+    }
+}
+
 macro_rules! clock_generator {
     ($(($id:ident, $Type:ident, $clock:ident),)+) => {
 
@@ -0,0 +1,355 @@
+//! # Digital-to-Analog Converter
+//!
+//! The DAC contains a single 10-bit channel, driving `VOUT` ([`PA02`]).
+//!
+//! A [`Dac`] is created from the peripheral and its GCLK token. Its channel is
+//! then enabled, which yields a [`Channel`] owning the output pin:
+//!
+//! ```
+//! use atsamd_hal::dac::{ChannelConfig, Dac, Reference};
+//!
+//! let clock = clocks.dac(&gclk0).unwrap();
+//! let mut dac = Dac::new(&mut peripherals.PM, peripherals.DAC, clock, Reference::AVCC);
+//! let mut vout = dac.enable_channel(pins.pa02, ChannelConfig::new());
+//! vout.write(512);
+//! ```
+//!
+//! ## Event-triggered conversions
+//!
+//! When [`ChannelConfig::start_event`] is set, [`Channel::write`] stores the
+//! value in the data buffer instead, and the conversion only starts with the
+//! next `START` event from the event system. Setting
+//! [`ChannelConfig::empty_event_output`] generates an `EMPTY` event whenever
+//! the data buffer can accept a new value.
+//!
+//! ## Sample playback
+//!
+//! With the `dma` feature, the [`Channel`] can be fed by the DMAC, one sample
+//! per DMA trigger. Using a TC overflow as the trigger source plays a
+//! circular buffer back at the TC rate:
+//!
+//! ```
+//! use atsamd_hal::dmac::TriggerSource;
+//!
+//! static mut SAMPLES: [u16; 64] = [0; 64];
+//!
+//! let transfer = vout.send_with_dma(
+//!     unsafe { &mut SAMPLES },
+//!     dma_channel,
+//!     TriggerSource::TC3_OVF,
+//!     true,
+//!     |_| {},
+//! );
+//! ```
+//!
+//! When the channel is paced by `START` events instead, the DMAC should be
+//! triggered by [`TriggerSource::DAC_EMPTY`].
+//!
+//! [`TriggerSource::DAC_EMPTY`]: crate::dmac::TriggerSource::DAC_EMPTY
+
+use crate::gpio::v2::{AlternateB, AnyPin, Pin, SpecificPin, PA02};
+use crate::pac::generic::Variant;
+use crate::pac::{dac, DAC, PM};
+
+#[cfg(feature = "dma")]
+use crate::dmac::{
+    self,
+    channel::{AnyChannel, Busy, CallbackStatus, Channel as DmaChannel, InterruptFlags, Ready},
+    transfer::BufferPair,
+    Buffer, Transfer, TriggerAction, TriggerSource,
+};
+
+pub use crate::clock::DacClock;
+
+/// Reference voltage (or its source)
+pub use dac::ctrlb::REFSEL_A as Reference;
+
+//==============================================================================
+//  ChannelConfig
+//==============================================================================
+
+/// Configuration of the DAC channel
+#[derive(Clone, Copy, Debug)]
+pub struct ChannelConfig {
+    external_output: bool,
+    internal_output: bool,
+    left_adjusted: bool,
+    run_standby: bool,
+    start_event: bool,
+    empty_event_output: bool,
+}
+
+impl ChannelConfig {
+    /// Create a new `ChannelConfig`. The default configuration is:
+    /// * Output driven to `VOUT` only
+    /// * Right-adjusted data
+    /// * Conversions started by [`Channel::write`]
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            external_output: true,
+            internal_output: false,
+            left_adjusted: false,
+            run_standby: false,
+            start_event: false,
+            empty_event_output: false,
+        }
+    }
+
+    /// Drive the output to the `VOUT` pin
+    #[inline]
+    pub fn external_output(mut self, enabled: bool) -> Self {
+        self.external_output = enabled;
+        self
+    }
+
+    /// Drive the output to the analog comparator and ADC inputs
+    #[inline]
+    pub fn internal_output(mut self, enabled: bool) -> Self {
+        self.internal_output = enabled;
+        self
+    }
+
+    /// Left-adjust the data, so that the 10-bit value occupies the upper bits
+    /// of the 16-bit data register
+    #[inline]
+    pub fn left_adjusted(mut self, left_adjusted: bool) -> Self {
+        self.left_adjusted = left_adjusted;
+        self
+    }
+
+    /// Keep the DAC running in standby sleep mode
+    #[inline]
+    pub fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Start conversions on the `START` event input
+    #[inline]
+    pub fn start_event(mut self, enabled: bool) -> Self {
+        self.start_event = enabled;
+        self
+    }
+
+    /// Generate an `EMPTY` event when the data buffer is empty
+    #[inline]
+    pub fn empty_event_output(mut self, enabled: bool) -> Self {
+        self.empty_event_output = enabled;
+        self
+    }
+}
+
+impl Default for ChannelConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//==============================================================================
+//  Dac
+//==============================================================================
+
+/// The DAC peripheral
+pub struct Dac {
+    dac: DAC,
+}
+
+impl Dac {
+    /// Reset the DAC and select its voltage reference
+    #[inline]
+    pub fn new(pm: &mut PM, dac: DAC, _clock: DacClock, reference: Reference) -> Self {
+        pm.apbcmask.modify(|_, w| w.dac_().set_bit());
+
+        dac.ctrla.write(|w| w.swrst().set_bit());
+        while dac.ctrla.read().swrst().bit_is_set() {}
+        while dac.status.read().syncbusy().bit_is_set() {}
+        dac.ctrlb.write(|w| w.refsel().variant(reference));
+
+        Self { dac }
+    }
+
+    /// Select the voltage reference
+    #[inline]
+    pub fn set_reference(&mut self, reference: Reference) {
+        self.dac.ctrlb.modify(|_, w| w.refsel().variant(reference));
+    }
+
+    /// Get the voltage reference
+    #[inline]
+    pub fn get_reference(&self) -> Option<Reference> {
+        match self.dac.ctrlb.read().refsel().variant() {
+            Variant::Val(reference) => Some(reference),
+            Variant::Res(_) => None,
+        }
+    }
+
+    /// Configure and enable the channel
+    #[inline]
+    pub fn enable_channel<P>(&mut self, pin: P, config: ChannelConfig) -> Channel
+    where
+        P: AnyPin<Id = PA02>,
+    {
+        let pin: SpecificPin<P> = pin.into();
+
+        self.dac.ctrlb.modify(|_, w| {
+            w.eoen().bit(config.external_output);
+            w.ioen().bit(config.internal_output);
+            w.leftadj().bit(config.left_adjusted)
+        });
+        self.dac.evctrl.write(|w| {
+            w.startei().bit(config.start_event);
+            w.emptyeo().bit(config.empty_event_output)
+        });
+        self.dac.ctrla.write(|w| {
+            w.runstdby().bit(config.run_standby);
+            w.enable().set_bit()
+        });
+        while self.dac.status.read().syncbusy().bit_is_set() {}
+
+        Channel {
+            pin: pin.into_mode(),
+            buffered: config.start_event,
+        }
+    }
+
+    /// Disable the channel and return its pin
+    #[inline]
+    pub fn disable_channel(&mut self, channel: Channel) -> Pin<PA02, AlternateB> {
+        self.dac.ctrla.modify(|_, w| w.enable().clear_bit());
+        while self.dac.status.read().syncbusy().bit_is_set() {}
+        self.dac.evctrl.reset();
+        channel.pin
+    }
+
+    /// Disable the DAC and return the underlying peripheral
+    ///
+    /// The [`Channel`] should be disabled first.
+    #[inline]
+    pub fn free(self, pm: &mut PM) -> DAC {
+        self.dac.ctrla.write(|w| w.swrst().set_bit());
+        while self.dac.ctrla.read().swrst().bit_is_set() {}
+        pm.apbcmask.modify(|_, w| w.dac_().clear_bit());
+        self.dac
+    }
+}
+
+//==============================================================================
+//  Channel
+//==============================================================================
+
+/// The enabled DAC channel
+pub struct Channel {
+    pin: Pin<PA02, AlternateB>,
+    buffered: bool,
+}
+
+impl Channel {
+    #[inline]
+    fn dac(&self) -> &dac::RegisterBlock {
+        // SAFETY: The channel only writes to the data registers, and
+        // interrupt flags are cleared by writing ones
+        unsafe { &*DAC::ptr() }
+    }
+
+    /// Returns `true` if the data buffer can accept a new value
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.dac().intflag.read().empty().bit_is_set()
+    }
+
+    /// Check and clear the underrun flag
+    ///
+    /// An underrun occurs when a `START` event is received while the data
+    /// buffer is empty.
+    #[inline]
+    pub fn underrun(&mut self) -> bool {
+        let underrun = self.dac().intflag.read().underrun().bit_is_set();
+        if underrun {
+            self.dac().intflag.write(|w| w.underrun().set_bit());
+        }
+        underrun
+    }
+
+    /// Output a new value
+    ///
+    /// If the channel is started by events, the value is stored in the data
+    /// buffer, and converted on the next `START` event. This method blocks
+    /// until the data buffer is empty. Otherwise, the conversion starts
+    /// immediately.
+    #[inline]
+    pub fn write(&mut self, value: u16) {
+        let dac = self.dac();
+        if self.buffered {
+            while !self.is_empty() {}
+            dac.databuf.write(|w| unsafe { w.databuf().bits(value) });
+        } else {
+            dac.data.write(|w| unsafe { w.data().bits(value) });
+        }
+        while dac.status.read().syncbusy().bit_is_set() {}
+    }
+}
+
+//==============================================================================
+//  DMA
+//==============================================================================
+
+#[cfg(feature = "dma")]
+unsafe impl Buffer for Channel {
+    type Beat = u16;
+
+    #[inline]
+    fn dma_ptr(&mut self) -> *mut Self::Beat {
+        let dac = self.dac();
+        if self.buffered {
+            dac.databuf.as_ptr()
+        } else {
+            dac.data.as_ptr()
+        }
+    }
+
+    #[inline]
+    fn incrementing(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn buffer_len(&self) -> usize {
+        1
+    }
+}
+
+#[cfg(feature = "dma")]
+impl Channel {
+    /// Transform a [`Channel`] into a DMA [`Transfer`] and start sending
+    /// the provided samples, one per `trigger`
+    ///
+    /// If `circular` is `true`, the buffer is played back repeatedly until
+    /// the transfer is stopped.
+    #[inline]
+    pub fn send_with_dma<Ch, B, W>(
+        self,
+        buf: B,
+        mut channel: Ch,
+        trigger: TriggerSource,
+        circular: bool,
+        waker: W,
+    ) -> Transfer<DmaChannel<Ch::Id, Busy>, BufferPair<B, Self>, W>
+    where
+        Ch: AnyChannel<Status = Ready>,
+        B: Buffer<Beat = u16> + 'static,
+        W: FnOnce(CallbackStatus) + 'static,
+    {
+        channel
+            .as_mut()
+            .enable_interrupts(InterruptFlags::new().with_tcmpl(true));
+
+        // SAFETY: We use new_unchecked to avoid having to pass a 'static self as the
+        // destination buffer. This is safe as long as we guarantee the source buffer is
+        // static.
+        unsafe { dmac::Transfer::new_unchecked(channel, buf, self, circular) }
+            .with_waker(waker)
+            .begin(trigger, TriggerAction::BEAT)
+    }
+}
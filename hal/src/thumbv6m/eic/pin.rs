@@ -96,6 +96,31 @@ crate::paste::item! {
             });
         }
 
+        /// Return a wake source for the standby sleep mode
+        ///
+        /// Returns `None` unless the interrupt and its wakeup are enabled, with
+        /// [`enable_interrupt`](Self::enable_interrupt) and
+        /// [`enable_interrupt_wake`](Self::enable_interrupt_wake). Edges are
+        /// only detected if the EIC is clocked in standby, whereas levels are
+        /// detected asynchronously.
+        pub fn wake_source(&self) -> Option<crate::power::ExtIntWake<'_>> {
+            let eic = unsafe { &(*pac::EIC::ptr()) };
+            let mask: u32 = 1 << $num;
+            let enabled = eic.intenset.read().bits() as u32 & mask != 0
+                && eic.wakeup.read().bits() as u32 & mask != 0;
+
+            // Which of the two config blocks this eic config is in
+            let offset = ($num >> 3) & 0b0001;
+            let sense = eic.config[offset].read().bits() >> (4 * ($num & 0b111)) & 0b111;
+            let level = sense == Sense::HIGH as u32 || sense == Sense::LOW as u32;
+
+            if enabled && (level || crate::clock::runs_in_standby(crate::clock::ClockId::EIC)) {
+                Some(crate::power::ExtIntWake::new())
+            } else {
+                None
+            }
+        }
+
         pub fn is_interrupt(&mut self) -> bool {
             unsafe { &(*pac::EIC::ptr()) }.intflag.read().[<extint $num>]().bit_is_set()
         }
@@ -0,0 +1,1189 @@
+//! # Inter-IC Sound
+//!
+//! The I2S controller contains two clock units, generating or receiving the
+//! serial clock (`SCK`), frame sync (`FS`) and master clock (`MCK`), and two
+//! serializers, each driving or sampling an `SD` pin. Each serializer is
+//! clocked by either clock unit, and can be used as a transmitter ([`Tx`]),
+//! a receiver ([`Rx`]), or a PDM receiver.
+//!
+//! The [`I2s`] is created from the peripheral, which also returns a token for
+//! each clock unit and serializer. A clock unit is enabled either as a master,
+//! from its GCLK, or as a slave, from its `SCK` and `FS` pins:
+//!
+//! ```
+//! use atsamd_hal::i2s::{ClockUnitConfig, DataSize, I2s, SerializerConfig};
+//!
+//! // 48 kHz stereo, with 32-bit slots: 12.288 MHz / 4 = 3.072 MHz
+//! let clock = clocks.i2s0(&gclk_12m288).unwrap();
+//! let (mut i2s, tokens) = I2s::new(&mut peripherals.PM, peripherals.I2S);
+//! let clk0 = i2s.enable_master(tokens.clk0, clock, 4, ClockUnitConfig::new());
+//! let _sck = clk0.sck_pin(pins.pa10);
+//! let _fs = clk0.fs_pin(pins.pa11);
+//!
+//! let config = SerializerConfig::new().data_size(DataSize::_16);
+//! let mut tx = i2s.enable_tx(tokens.ser0, &clk0, config);
+//! let _sd = tx.sd_pin(pins.pa07);
+//! tx.write(0x1234);
+//! ```
+//!
+//! ## PDM microphones
+//!
+//! [`I2s::enable_pdm`] configures a serializer to sample a pair of PDM
+//! microphones sharing the `SD` pin, with the clock unit generating the PDM
+//! clock on `SCK`. Each received word holds 16 samples of each microphone,
+//! interleaved, which must be decimated in software.
+//!
+//! ## Data format
+//!
+//! Each data word holds one sample, right-aligned, unless
+//! [`DataSize::_16C`] or [`DataSize::_8C`] is selected. In these compact
+//! formats, a word packs the samples of two (or four) consecutive slots, the
+//! first slot in the least significant bits.
+//!
+//! ## DMA streaming
+//!
+//! With the `dma` feature, the serializers can be fed by the DMAC, either
+//! with a single buffer ([`Tx::send_with_dma`], [`Rx::receive_with_dma`]), or
+//! as a double-buffered stream ([`Tx::stream_with_dma`],
+//! [`Rx::stream_with_dma`]). A stream owns the buffer being transferred, and
+//! swaps it with the next one once complete:
+//!
+//! ```
+//! static mut PING: [u32; 256] = [0; 256];
+//! static mut PONG: [u32; 256] = [0; 256];
+//!
+//! let mut stream = rx.stream_with_dma(unsafe { &mut PING }, dma_channel);
+//! let mut spare = unsafe { &mut PONG };
+//!
+//! // In the DMAC interrupt handler
+//! if stream.block_transfer_interrupt() {
+//!     let full = stream.swap(spare).ok().unwrap();
+//!     process(full);
+//!     spare = full;
+//! }
+//! ```
+//!
+//! The DMAC stops between the two buffers, so the next buffer must be queued
+//! within one frame of the completion. Otherwise the transmitter underruns,
+//! or the receiver overruns.
+
+use core::marker::PhantomData;
+
+use crate::gpio::v2::{AlternateG, AnyPin, Pin, PinId, SpecificPin};
+use crate::pac::{i2s, I2S, PM};
+use crate::time::Hertz;
+use crate::typelevel::Sealed;
+
+#[cfg(feature = "dma")]
+use crate::dmac::{
+    self,
+    channel::{AnyChannel, Busy, CallbackStatus, Channel as DmaChannel, InterruptFlags, Ready},
+    transfer::BufferPair,
+    Buffer, ChId, Transfer, TriggerAction, TriggerSource,
+};
+
+pub use crate::clock::{I2S0Clock, I2S1Clock};
+
+/// Number of bits of each slot
+pub use i2s::clkctrl::SLOTSIZE_A as SlotSize;
+
+/// Width of the frame sync pulse
+pub use i2s::clkctrl::FSWIDTH_A as FsWidth;
+
+/// Delay between the frame sync edge and the first data bit
+pub use i2s::clkctrl::BITDELAY_A as BitDelay;
+
+/// Number of bits of each data word
+pub use i2s::serctrl::DATASIZE_A as DataSize;
+
+/// Alignment of the data word in its slot
+pub use i2s::serctrl::SLOTADJ_A as SlotAdjust;
+
+/// Alignment of the data in its word
+pub use i2s::serctrl::WORDADJ_A as WordAdjust;
+
+/// Value of the bits extending the data to the slot size
+pub use i2s::serctrl::EXTEND_A as Extend;
+
+/// Level of the `SD` pin during disabled slots
+pub use i2s::serctrl::TXDEFAULT_A as TxDefault;
+
+/// Offset of the slot disable bits in the serializer control registers
+const SLOTDIS_OFFSET: u32 = 16;
+
+/// Offsets of the serializer interrupt flags, each followed by the flag of
+/// serializer 1
+const RXRDY: u32 = 0;
+const RXOR: u32 = 4;
+const TXRDY: u32 = 8;
+const TXUR: u32 = 12;
+
+/// Offset of the data synchronization busy bits, followed by serializer 1
+const DATA_SYNC: u32 = 8;
+
+//==============================================================================
+//  Clock units and serializers
+//==============================================================================
+
+/// Type-level enum for the I2S clock units
+pub trait ClockUnitId: Sealed {
+    /// Index of the clock unit
+    const NUM: usize;
+    /// GCLK token of the clock unit
+    type Clock: Into<Hertz>;
+}
+
+/// Clock unit 0
+pub enum Clk0 {}
+
+impl Sealed for Clk0 {}
+
+impl ClockUnitId for Clk0 {
+    const NUM: usize = 0;
+    type Clock = I2S0Clock;
+}
+
+/// Clock unit 1
+pub enum Clk1 {}
+
+impl Sealed for Clk1 {}
+
+impl ClockUnitId for Clk1 {
+    const NUM: usize = 1;
+    type Clock = I2S1Clock;
+}
+
+/// Type-level enum for the I2S serializers
+pub trait SerializerId: Sealed {
+    /// Index of the serializer
+    const NUM: usize;
+}
+
+/// Serializer 0
+pub enum Ser0 {}
+
+impl Sealed for Ser0 {}
+
+impl SerializerId for Ser0 {
+    const NUM: usize = 0;
+}
+
+/// Serializer 1
+pub enum Ser1 {}
+
+impl Sealed for Ser1 {}
+
+impl SerializerId for Ser1 {
+    const NUM: usize = 1;
+}
+
+//==============================================================================
+//  Pins
+//==============================================================================
+
+/// [`PinId`]s that can be used as the `SCK` pin of a clock unit
+pub trait SckPin: PinId {
+    /// Clock unit using this pin
+    type ClockUnit: ClockUnitId;
+}
+
+/// [`PinId`]s that can be used as the `FS` pin of a clock unit
+pub trait FsPin: PinId {
+    /// Clock unit using this pin
+    type ClockUnit: ClockUnitId;
+}
+
+/// [`PinId`]s that can be used as the `MCK` pin of a clock unit
+pub trait MckPin: PinId {
+    /// Clock unit using this pin
+    type ClockUnit: ClockUnitId;
+}
+
+/// [`PinId`]s that can be used as the `SD` pin of a serializer
+pub trait SdPin: PinId {
+    /// Serializer using this pin
+    type Serializer: SerializerId;
+}
+
+macro_rules! clock_pin {
+    ($Trait:ident: $($(#[$cfg:meta])* $Id:ident: $Clk:ident;)+) => {
+        $(
+            $(#[$cfg])*
+            impl $Trait for crate::gpio::v2::$Id {
+                type ClockUnit = $Clk;
+            }
+        )+
+    };
+}
+
+macro_rules! sd_pin {
+    ($($(#[$cfg:meta])* $Id:ident: $Ser:ident;)+) => {
+        $(
+            $(#[$cfg])*
+            impl SdPin for crate::gpio::v2::$Id {
+                type Serializer = $Ser;
+            }
+        )+
+    };
+}
+
+clock_pin! { SckPin:
+    PA10: Clk0;
+    #[cfg(feature = "min-samd21g")]
+    PA20: Clk0;
+    #[cfg(feature = "min-samd21g")]
+    PB11: Clk1;
+}
+
+clock_pin! { FsPin:
+    PA11: Clk0;
+    #[cfg(feature = "min-samd21g")]
+    PA21: Clk0;
+    #[cfg(feature = "min-samd21j")]
+    PB12: Clk1;
+}
+
+clock_pin! { MckPin:
+    PA09: Clk0;
+    #[cfg(feature = "min-samd21j")]
+    PB17: Clk0;
+    #[cfg(feature = "min-samd21g")]
+    PB10: Clk1;
+}
+
+sd_pin! {
+    PA07: Ser0;
+    PA08: Ser1;
+    PA19: Ser0;
+    #[cfg(feature = "min-samd21j")]
+    PB16: Ser1;
+}
+
+//==============================================================================
+//  ClockUnitConfig
+//==============================================================================
+
+/// Frame format of a clock unit
+#[derive(Clone, Copy, Debug)]
+pub struct ClockUnitConfig {
+    slot_size: SlotSize,
+    slots: u8,
+    fs_width: FsWidth,
+    bit_delay: BitDelay,
+    fs_invert: bool,
+    sck_invert: bool,
+    mck_divider: Option<u8>,
+}
+
+impl ClockUnitConfig {
+    /// Create a new `ClockUnitConfig`. The default configuration is the
+    /// standard I2S format:
+    /// * Two 32-bit slots per frame
+    /// * Frame sync pulse as wide as a slot
+    /// * First data bit one `SCK` cycle after the frame sync edge
+    /// * No `MCK` output
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            slot_size: SlotSize::_32,
+            slots: 2,
+            fs_width: FsWidth::SLOT,
+            bit_delay: BitDelay::I2S,
+            fs_invert: false,
+            sck_invert: false,
+            mck_divider: None,
+        }
+    }
+
+    /// Select the number of bits of each slot
+    #[inline]
+    pub fn slot_size(mut self, slot_size: SlotSize) -> Self {
+        self.slot_size = slot_size;
+        self
+    }
+
+    /// Select the number of slots of each frame
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slots` is not between 1 and 8.
+    #[inline]
+    pub fn slots(mut self, slots: u8) -> Self {
+        assert!((1..=8).contains(&slots), "invalid slot count");
+        self.slots = slots;
+        self
+    }
+
+    /// Select the width of the frame sync pulse
+    #[inline]
+    pub fn fs_width(mut self, fs_width: FsWidth) -> Self {
+        self.fs_width = fs_width;
+        self
+    }
+
+    /// Select the delay between the frame sync edge and the first data bit
+    ///
+    /// [`BitDelay::LJ`] selects the left-justified and TDM formats.
+    #[inline]
+    pub fn bit_delay(mut self, bit_delay: BitDelay) -> Self {
+        self.bit_delay = bit_delay;
+        self
+    }
+
+    /// Invert the frame sync
+    #[inline]
+    pub fn fs_invert(mut self, inverted: bool) -> Self {
+        self.fs_invert = inverted;
+        self
+    }
+
+    /// Invert the serial clock output, in master mode
+    #[inline]
+    pub fn sck_invert(mut self, inverted: bool) -> Self {
+        self.sck_invert = inverted;
+        self
+    }
+
+    /// Output the GCLK divided by `divider` on the `MCK` pin, in master mode
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divider` is not between 1 and 32.
+    #[inline]
+    pub fn mck_output(mut self, divider: u8) -> Self {
+        assert!((1..=32).contains(&divider), "invalid MCK divider");
+        self.mck_divider = Some(divider);
+        self
+    }
+
+    /// Number of serial clock cycles of each frame
+    #[inline]
+    pub fn frame_len(&self) -> u32 {
+        let slot_bits = match self.slot_size {
+            SlotSize::_8 => 8,
+            SlotSize::_16 => 16,
+            SlotSize::_24 => 24,
+            SlotSize::_32 => 32,
+        };
+        slot_bits * self.slots as u32
+    }
+}
+
+impl Default for ClockUnitConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//==============================================================================
+//  SerializerConfig
+//==============================================================================
+
+/// Data format of a serializer
+#[derive(Clone, Copy, Debug)]
+pub struct SerializerConfig {
+    data_size: DataSize,
+    slot_adjust: SlotAdjust,
+    word_adjust: WordAdjust,
+    extend: Extend,
+    lsb_first: bool,
+    mono: bool,
+    disabled_slots: u8,
+    tx_default: TxDefault,
+}
+
+impl SerializerConfig {
+    /// Create a new `SerializerConfig`. The default configuration is:
+    /// * 32-bit data words, left-adjusted in their slot
+    /// * Most significant bit first
+    /// * Stereo, with every slot enabled
+    /// * `SD` driven low during disabled slots
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            data_size: DataSize::_32,
+            slot_adjust: SlotAdjust::LEFT,
+            word_adjust: WordAdjust::RIGHT,
+            extend: Extend::ZERO,
+            lsb_first: false,
+            mono: false,
+            disabled_slots: 0,
+            tx_default: TxDefault::ZERO,
+        }
+    }
+
+    /// Select the number of bits of each data word
+    #[inline]
+    pub fn data_size(mut self, data_size: DataSize) -> Self {
+        self.data_size = data_size;
+        self
+    }
+
+    /// Align the data word in slots larger than the word
+    #[inline]
+    pub fn slot_adjust(mut self, slot_adjust: SlotAdjust) -> Self {
+        self.slot_adjust = slot_adjust;
+        self
+    }
+
+    /// Align the data in the 32-bit data word, when it is smaller than a word
+    #[inline]
+    pub fn word_adjust(mut self, word_adjust: WordAdjust) -> Self {
+        self.word_adjust = word_adjust;
+        self
+    }
+
+    /// Select the value of the bits extending the data to the slot size
+    #[inline]
+    pub fn extend(mut self, extend: Extend) -> Self {
+        self.extend = extend;
+        self
+    }
+
+    /// Transfer the least significant bit first
+    #[inline]
+    pub fn lsb_first(mut self, lsb_first: bool) -> Self {
+        self.lsb_first = lsb_first;
+        self
+    }
+
+    /// Transmit the left channel data on both channels, or only receive the
+    /// left channel
+    #[inline]
+    pub fn mono(mut self, mono: bool) -> Self {
+        self.mono = mono;
+        self
+    }
+
+    /// Disable the slots whose bits are set in `mask`
+    #[inline]
+    pub fn disabled_slots(mut self, mask: u8) -> Self {
+        self.disabled_slots = mask;
+        self
+    }
+
+    /// Select the level of the `SD` pin during disabled slots
+    ///
+    /// This setting is ignored by receivers.
+    #[inline]
+    pub fn tx_default(mut self, tx_default: TxDefault) -> Self {
+        self.tx_default = tx_default;
+        self
+    }
+}
+
+impl Default for SerializerConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//==============================================================================
+//  I2s
+//==============================================================================
+
+/// Token representing a disabled clock unit
+pub struct ClockUnitToken<C: ClockUnitId> {
+    _unit: PhantomData<C>,
+}
+
+impl<C: ClockUnitId> ClockUnitToken<C> {
+    #[inline]
+    fn new() -> Self {
+        Self { _unit: PhantomData }
+    }
+}
+
+/// Token representing a disabled serializer
+pub struct SerializerToken<S: SerializerId> {
+    _ser: PhantomData<S>,
+}
+
+impl<S: SerializerId> SerializerToken<S> {
+    #[inline]
+    fn new() -> Self {
+        Self { _ser: PhantomData }
+    }
+}
+
+/// Tokens for each clock unit and serializer, returned by [`I2s::new`]
+pub struct Tokens {
+    pub clk0: ClockUnitToken<Clk0>,
+    pub clk1: ClockUnitToken<Clk1>,
+    pub ser0: SerializerToken<Ser0>,
+    pub ser1: SerializerToken<Ser1>,
+}
+
+/// The I2S peripheral
+pub struct I2s {
+    i2s: I2S,
+}
+
+impl I2s {
+    /// Reset and enable the I2S
+    #[inline]
+    pub fn new(pm: &mut PM, i2s: I2S) -> (Self, Tokens) {
+        pm.apbcmask.modify(|_, w| w.i2s_().set_bit());
+
+        i2s.ctrla.write(|w| w.swrst().set_bit());
+        while i2s.syncbusy.read().swrst().bit_is_set() {}
+        i2s.ctrla.write(|w| w.enable().set_bit());
+        while i2s.syncbusy.read().enable().bit_is_set() {}
+
+        let tokens = Tokens {
+            clk0: ClockUnitToken::new(),
+            clk1: ClockUnitToken::new(),
+            ser0: SerializerToken::new(),
+            ser1: SerializerToken::new(),
+        };
+        (Self { i2s }, tokens)
+    }
+
+    /// Run a closure with the I2S disabled
+    ///
+    /// The clock unit and serializer configurations are enable-protected.
+    /// Every running clock unit and serializer is briefly stopped while they
+    /// are modified.
+    #[inline]
+    fn with_disabled<T>(&mut self, f: impl FnOnce(&I2S) -> T) -> T {
+        self.i2s.ctrla.modify(|_, w| w.enable().clear_bit());
+        while self.i2s.syncbusy.read().enable().bit_is_set() {}
+        let result = f(&self.i2s);
+        self.i2s.ctrla.modify(|_, w| w.enable().set_bit());
+        while self.i2s.syncbusy.read().enable().bit_is_set() {}
+        result
+    }
+
+    #[inline]
+    fn enable_clock_unit<C: ClockUnitId>(&mut self, config: ClockUnitConfig, master: Option<u8>) {
+        self.with_disabled(|i2s| {
+            i2s.clkctrl[C::NUM].write(|w| {
+                w.slotsize().variant(config.slot_size);
+                // SAFETY: The slot count was checked to fit in 3 bits
+                unsafe { w.nbslots().bits(config.slots - 1) };
+                w.fswidth().variant(config.fs_width);
+                w.bitdelay().variant(config.bit_delay);
+                w.fsinv().bit(config.fs_invert);
+                match master {
+                    Some(divider) => {
+                        w.fssel().sckdiv();
+                        w.scksel().mckdiv();
+                        w.mcksel().gclk();
+                        w.sckoutinv().bit(config.sck_invert);
+                        // SAFETY: The dividers were checked to fit in 5 bits
+                        unsafe { w.mckdiv().bits(divider - 1) };
+                        if let Some(divider) = config.mck_divider {
+                            w.mcken().set_bit();
+                            unsafe { w.mckoutdiv().bits(divider - 1) };
+                        }
+                    }
+                    None => {
+                        w.fssel().fspin();
+                        w.scksel().sckpin();
+                    }
+                }
+                w
+            })
+        });
+        self.i2s.ctrla.modify(|_, w| match C::NUM {
+            0 => w.cken0().set_bit(),
+            _ => w.cken1().set_bit(),
+        });
+        while self.i2s.syncbusy.read().bits() != 0 {}
+    }
+
+    /// Enable a clock unit in master mode
+    ///
+    /// The serial clock is the GCLK divided by `sck_divider`, and drives the
+    /// `SCK` and `FS` pins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sck_divider` is not between 1 and 32.
+    #[inline]
+    pub fn enable_master<C: ClockUnitId>(
+        &mut self,
+        _token: ClockUnitToken<C>,
+        clock: C::Clock,
+        sck_divider: u8,
+        config: ClockUnitConfig,
+    ) -> ClockUnit<C> {
+        assert!((1..=32).contains(&sck_divider), "invalid SCK divider");
+        self.enable_clock_unit::<C>(config, Some(sck_divider));
+        let freq: Hertz = clock.into();
+        ClockUnit {
+            sample_rate: Some(Hertz(freq.0 / sck_divider as u32 / config.frame_len())),
+            _unit: PhantomData,
+        }
+    }
+
+    /// Enable a clock unit in slave mode
+    ///
+    /// The serial clock and frame sync are driven by an external master on
+    /// the `SCK` and `FS` pins. The `MCK` output is not available.
+    #[inline]
+    pub fn enable_slave<C: ClockUnitId>(
+        &mut self,
+        _token: ClockUnitToken<C>,
+        config: ClockUnitConfig,
+    ) -> ClockUnit<C> {
+        self.enable_clock_unit::<C>(config, None);
+        ClockUnit {
+            sample_rate: None,
+            _unit: PhantomData,
+        }
+    }
+
+    /// Disable a clock unit
+    #[inline]
+    pub fn disable_clock_unit<C: ClockUnitId>(&mut self, _unit: ClockUnit<C>) -> ClockUnitToken<C> {
+        self.i2s.ctrla.modify(|_, w| match C::NUM {
+            0 => w.cken0().clear_bit(),
+            _ => w.cken1().clear_bit(),
+        });
+        while self.i2s.syncbusy.read().bits() != 0 {}
+        self.with_disabled(|i2s| i2s.clkctrl[C::NUM].reset());
+        ClockUnitToken::new()
+    }
+
+    #[inline]
+    fn enable_serializer<S, C>(&mut self, config: SerializerConfig, mode: SerializerMode)
+    where
+        S: SerializerId,
+        C: ClockUnitId,
+    {
+        self.with_disabled(|i2s| {
+            let serctrl = &i2s.serctrl[S::NUM];
+            serctrl.write(|w| {
+                match mode {
+                    SerializerMode::Tx => w.sermode().tx(),
+                    SerializerMode::Rx => w.sermode().rx(),
+                    SerializerMode::Pdm => w.sermode().pdm2(),
+                };
+                w.txdefault().variant(config.tx_default);
+                w.clksel().bit(C::NUM != 0);
+                w.slotadj().variant(config.slot_adjust);
+                w.datasize().variant(config.data_size);
+                w.wordadj().variant(config.word_adjust);
+                w.extend().variant(config.extend);
+                w.bitrev().bit(config.lsb_first);
+                w.mono().bit(config.mono)
+            });
+            // SAFETY: Every slot mask is valid
+            serctrl.modify(|r, w| unsafe {
+                w.bits(r.bits() | (config.disabled_slots as u32) << SLOTDIS_OFFSET)
+            });
+        });
+        self.i2s.ctrla.modify(|_, w| match S::NUM {
+            0 => w.seren0().set_bit(),
+            _ => w.seren1().set_bit(),
+        });
+        while self.i2s.syncbusy.read().bits() != 0 {}
+    }
+
+    #[inline]
+    fn disable_serializer<S: SerializerId>(&mut self) {
+        self.i2s.ctrla.modify(|_, w| match S::NUM {
+            0 => w.seren0().clear_bit(),
+            _ => w.seren1().clear_bit(),
+        });
+        while self.i2s.syncbusy.read().bits() != 0 {}
+    }
+
+    /// Configure and enable a serializer as a transmitter, clocked by `unit`
+    #[inline]
+    pub fn enable_tx<S, C>(
+        &mut self,
+        _token: SerializerToken<S>,
+        _unit: &ClockUnit<C>,
+        config: SerializerConfig,
+    ) -> Tx<S>
+    where
+        S: SerializerId,
+        C: ClockUnitId,
+    {
+        self.enable_serializer::<S, C>(config, SerializerMode::Tx);
+        Tx { _ser: PhantomData }
+    }
+
+    /// Configure and enable a serializer as a receiver, clocked by `unit`
+    #[inline]
+    pub fn enable_rx<S, C>(
+        &mut self,
+        _token: SerializerToken<S>,
+        _unit: &ClockUnit<C>,
+        config: SerializerConfig,
+    ) -> Rx<S>
+    where
+        S: SerializerId,
+        C: ClockUnitId,
+    {
+        self.enable_serializer::<S, C>(config, SerializerMode::Rx);
+        Rx { _ser: PhantomData }
+    }
+
+    /// Configure and enable a serializer to receive from a pair of PDM
+    /// microphones, clocked by `unit`
+    ///
+    /// The data of the left microphone is sampled on the rising edges of
+    /// `SCK`, and the data of the right microphone on the falling edges.
+    #[inline]
+    pub fn enable_pdm<S, C>(
+        &mut self,
+        _token: SerializerToken<S>,
+        _unit: &ClockUnit<C>,
+        config: SerializerConfig,
+    ) -> Rx<S>
+    where
+        S: SerializerId,
+        C: ClockUnitId,
+    {
+        self.enable_serializer::<S, C>(config, SerializerMode::Pdm);
+        Rx { _ser: PhantomData }
+    }
+
+    /// Disable a transmitter
+    #[inline]
+    pub fn disable_tx<S: SerializerId>(&mut self, _tx: Tx<S>) -> SerializerToken<S> {
+        self.disable_serializer::<S>();
+        SerializerToken::new()
+    }
+
+    /// Disable a receiver
+    #[inline]
+    pub fn disable_rx<S: SerializerId>(&mut self, _rx: Rx<S>) -> SerializerToken<S> {
+        self.disable_serializer::<S>();
+        SerializerToken::new()
+    }
+
+    /// Reset the I2S, disable it and return the underlying peripheral
+    #[inline]
+    pub fn free(self, _tokens: Tokens, pm: &mut PM) -> I2S {
+        self.i2s.ctrla.write(|w| w.swrst().set_bit());
+        while self.i2s.syncbusy.read().swrst().bit_is_set() {}
+        pm.apbcmask.modify(|_, w| w.i2s_().clear_bit());
+        self.i2s
+    }
+}
+
+/// Serializer operating mode
+#[derive(Clone, Copy)]
+enum SerializerMode {
+    Tx,
+    Rx,
+    Pdm,
+}
+
+//==============================================================================
+//  ClockUnit
+//==============================================================================
+
+/// An enabled clock unit
+pub struct ClockUnit<C: ClockUnitId> {
+    sample_rate: Option<Hertz>,
+    _unit: PhantomData<C>,
+}
+
+impl<C: ClockUnitId> ClockUnit<C> {
+    /// Frame rate generated by the clock unit, in master mode
+    #[inline]
+    pub fn sample_rate(&self) -> Option<Hertz> {
+        self.sample_rate
+    }
+
+    /// Convert a pin to carry the serial clock
+    #[inline]
+    pub fn sck_pin<P>(&self, pin: P) -> Pin<P::Id, AlternateG>
+    where
+        P: AnyPin,
+        P::Id: SckPin<ClockUnit = C>,
+    {
+        let pin: SpecificPin<P> = pin.into();
+        pin.into_mode()
+    }
+
+    /// Convert a pin to carry the frame sync
+    #[inline]
+    pub fn fs_pin<P>(&self, pin: P) -> Pin<P::Id, AlternateG>
+    where
+        P: AnyPin,
+        P::Id: FsPin<ClockUnit = C>,
+    {
+        let pin: SpecificPin<P> = pin.into();
+        pin.into_mode()
+    }
+
+    /// Convert a pin to output the master clock
+    #[inline]
+    pub fn mck_pin<P>(&self, pin: P) -> Pin<P::Id, AlternateG>
+    where
+        P: AnyPin,
+        P::Id: MckPin<ClockUnit = C>,
+    {
+        let pin: SpecificPin<P> = pin.into();
+        pin.into_mode()
+    }
+}
+
+//==============================================================================
+//  Serializers
+//==============================================================================
+
+#[inline]
+fn regs() -> &'static i2s::RegisterBlock {
+    // SAFETY: The serializers only access their own data register and
+    // interrupt flags, which are cleared by writing ones
+    unsafe { &*I2S::ptr() }
+}
+
+/// Check and clear a serializer interrupt flag
+#[inline]
+fn check_and_clear<S: SerializerId>(offset: u32) -> bool {
+    let mask = 1 << (offset + S::NUM as u32);
+    let set = regs().intflag.read().bits() & mask != 0;
+    if set {
+        // SAFETY: Writing ones only clears the corresponding flags
+        regs().intflag.write(|w| unsafe { w.bits(mask) });
+    }
+    set
+}
+
+/// A serializer enabled as a transmitter
+pub struct Tx<S: SerializerId> {
+    _ser: PhantomData<S>,
+}
+
+impl<S: SerializerId> Tx<S> {
+    /// Convert a pin to carry the transmitted data
+    #[inline]
+    pub fn sd_pin<P>(&self, pin: P) -> Pin<P::Id, AlternateG>
+    where
+        P: AnyPin,
+        P::Id: SdPin<Serializer = S>,
+    {
+        let pin: SpecificPin<P> = pin.into();
+        pin.into_mode()
+    }
+
+    /// Returns `true` if the transmitter can accept a new data word
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        regs().intflag.read().bits() & 1 << (TXRDY + S::NUM as u32) != 0
+    }
+
+    /// Check and clear the underrun flag, set when a slot was transmitted
+    /// before its data word was written
+    #[inline]
+    pub fn underrun(&mut self) -> bool {
+        check_and_clear::<S>(TXUR)
+    }
+
+    /// Wait for the transmitter to be ready, and write a data word
+    #[inline]
+    pub fn write(&mut self, word: u32) {
+        while !self.is_ready() {}
+        regs().data[S::NUM].write(|w| unsafe { w.data().bits(word) });
+        while regs().syncbusy.read().bits() & 1 << (DATA_SYNC + S::NUM as u32) != 0 {}
+    }
+}
+
+/// A serializer enabled as a receiver
+pub struct Rx<S: SerializerId> {
+    _ser: PhantomData<S>,
+}
+
+impl<S: SerializerId> Rx<S> {
+    /// Convert a pin to carry the received data
+    #[inline]
+    pub fn sd_pin<P>(&self, pin: P) -> Pin<P::Id, AlternateG>
+    where
+        P: AnyPin,
+        P::Id: SdPin<Serializer = S>,
+    {
+        let pin: SpecificPin<P> = pin.into();
+        pin.into_mode()
+    }
+
+    /// Returns `true` if a data word was received
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        regs().intflag.read().bits() & 1 << (RXRDY + S::NUM as u32) != 0
+    }
+
+    /// Check and clear the overrun flag, set when a data word was received
+    /// before the previous one was read
+    #[inline]
+    pub fn overrun(&mut self) -> bool {
+        check_and_clear::<S>(RXOR)
+    }
+
+    /// Wait for a data word, and read it
+    #[inline]
+    pub fn read(&mut self) -> u32 {
+        while !self.is_ready() {}
+        while regs().syncbusy.read().bits() & 1 << (DATA_SYNC + S::NUM as u32) != 0 {}
+        regs().data[S::NUM].read().data().bits()
+    }
+}
+
+//==============================================================================
+//  DMA
+//==============================================================================
+
+#[cfg(feature = "dma")]
+unsafe impl<S: SerializerId> Buffer for Tx<S> {
+    type Beat = u32;
+
+    #[inline]
+    fn dma_ptr(&mut self) -> *mut Self::Beat {
+        regs().data[S::NUM].as_ptr()
+    }
+
+    #[inline]
+    fn incrementing(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn buffer_len(&self) -> usize {
+        1
+    }
+}
+
+#[cfg(feature = "dma")]
+unsafe impl<S: SerializerId> Buffer for Rx<S> {
+    type Beat = u32;
+
+    #[inline]
+    fn dma_ptr(&mut self) -> *mut Self::Beat {
+        regs().data[S::NUM].as_ptr()
+    }
+
+    #[inline]
+    fn incrementing(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn buffer_len(&self) -> usize {
+        1
+    }
+}
+
+#[cfg(feature = "dma")]
+impl<S: SerializerId> Tx<S> {
+    #[inline]
+    fn trigger() -> TriggerSource {
+        match S::NUM {
+            0 => TriggerSource::I2S_TX_0,
+            _ => TriggerSource::I2S_TX_1,
+        }
+    }
+
+    /// Transform the [`Tx`] into a DMA [`Transfer`] and start sending the
+    /// provided data words
+    ///
+    /// If `circular` is `true`, the buffer is sent repeatedly until the
+    /// transfer is stopped.
+    #[inline]
+    pub fn send_with_dma<Ch, B, W>(
+        self,
+        buf: B,
+        mut channel: Ch,
+        circular: bool,
+        waker: W,
+    ) -> Transfer<DmaChannel<Ch::Id, Busy>, BufferPair<B, Self>, W>
+    where
+        Ch: AnyChannel<Status = Ready>,
+        B: Buffer<Beat = u32> + 'static,
+        W: FnOnce(CallbackStatus) + 'static,
+    {
+        channel
+            .as_mut()
+            .enable_interrupts(InterruptFlags::new().with_tcmpl(true));
+
+        // SAFETY: We use new_unchecked to avoid having to pass a 'static self as the
+        // destination buffer. This is safe as long as we guarantee the source buffer is
+        // static.
+        unsafe { dmac::Transfer::new_unchecked(channel, buf, self, circular) }
+            .with_waker(waker)
+            .begin(Self::trigger(), TriggerAction::BEAT)
+    }
+
+    /// Transform the [`Tx`] into a double-buffered [`TxStream`], and start
+    /// sending the first buffer
+    #[inline]
+    pub fn stream_with_dma<Ch, B>(self, first: B, mut channel: Ch) -> TxStream<Ch::Id, S, B>
+    where
+        Ch: AnyChannel<Status = Ready>,
+        B: Buffer<Beat = u32> + 'static,
+    {
+        channel
+            .as_mut()
+            .enable_interrupts(InterruptFlags::new().with_tcmpl(true));
+
+        // SAFETY: See Tx::send_with_dma
+        let transfer = unsafe { dmac::Transfer::new_unchecked(channel, first, self, false) }
+            .begin(Self::trigger(), TriggerAction::BEAT);
+        TxStream { transfer }
+    }
+}
+
+#[cfg(feature = "dma")]
+impl<S: SerializerId> Rx<S> {
+    #[inline]
+    fn trigger() -> TriggerSource {
+        match S::NUM {
+            0 => TriggerSource::I2S_RX_0,
+            _ => TriggerSource::I2S_RX_1,
+        }
+    }
+
+    /// Transform the [`Rx`] into a DMA [`Transfer`] and start filling the
+    /// provided buffer with the received data words
+    ///
+    /// If `circular` is `true`, the buffer is filled repeatedly until the
+    /// transfer is stopped.
+    #[inline]
+    pub fn receive_with_dma<Ch, B, W>(
+        self,
+        buf: B,
+        mut channel: Ch,
+        circular: bool,
+        waker: W,
+    ) -> Transfer<DmaChannel<Ch::Id, Busy>, BufferPair<Self, B>, W>
+    where
+        Ch: AnyChannel<Status = Ready>,
+        B: Buffer<Beat = u32> + 'static,
+        W: FnOnce(CallbackStatus) + 'static,
+    {
+        channel
+            .as_mut()
+            .enable_interrupts(InterruptFlags::new().with_tcmpl(true));
+
+        // SAFETY: We use new_unchecked to avoid having to pass a 'static self as the
+        // source buffer. This is safe as long as we guarantee the destination buffer
+        // is static.
+        unsafe { dmac::Transfer::new_unchecked(channel, self, buf, circular) }
+            .with_waker(waker)
+            .begin(Self::trigger(), TriggerAction::BEAT)
+    }
+
+    /// Transform the [`Rx`] into a double-buffered [`RxStream`], and start
+    /// filling the first buffer
+    #[inline]
+    pub fn stream_with_dma<Ch, B>(self, first: B, mut channel: Ch) -> RxStream<Ch::Id, S, B>
+    where
+        Ch: AnyChannel<Status = Ready>,
+        B: Buffer<Beat = u32> + 'static,
+    {
+        channel
+            .as_mut()
+            .enable_interrupts(InterruptFlags::new().with_tcmpl(true));
+
+        // SAFETY: See Rx::receive_with_dma
+        let transfer = unsafe { dmac::Transfer::new_unchecked(channel, self, first, false) }
+            .begin(Self::trigger(), TriggerAction::BEAT);
+        RxStream { transfer }
+    }
+}
+
+/// A double-buffered DMA stream to a transmitter
+#[cfg(feature = "dma")]
+pub struct TxStream<Id: ChId, S: SerializerId, B: Buffer<Beat = u32>> {
+    transfer: Transfer<DmaChannel<Id, Busy>, BufferPair<B, Tx<S>>>,
+}
+
+#[cfg(feature = "dma")]
+impl<Id, S, B> TxStream<Id, S, B>
+where
+    Id: ChId,
+    S: SerializerId,
+    B: Buffer<Beat = u32> + 'static,
+{
+    /// Returns `true` if the current buffer was sent
+    #[inline]
+    pub fn is_complete(&mut self) -> bool {
+        self.transfer.complete()
+    }
+
+    /// Check and clear the block transfer complete interrupt flag
+    #[inline]
+    pub fn block_transfer_interrupt(&mut self) -> bool {
+        self.transfer.block_transfer_interrupt()
+    }
+
+    /// Start sending `next` once the current buffer was sent, and return the
+    /// sent buffer
+    ///
+    /// Returns `Err(next)` if the current buffer is still being sent.
+    #[inline]
+    pub fn swap(&mut self, next: B) -> Result<B, B> {
+        if !self.transfer.complete() {
+            return Err(next);
+        }
+        // The data register is a single beat, so the buffer lengths always match
+        match self.transfer.recycle_destination(next) {
+            Ok(sent) => Ok(sent),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Stop the stream, and return the DMA channel, the current buffer and
+    /// the transmitter
+    #[inline]
+    pub fn stop(self) -> (DmaChannel<Id, Ready>, B, Tx<S>) {
+        self.transfer.stop()
+    }
+}
+
+/// A double-buffered DMA stream from a receiver
+#[cfg(feature = "dma")]
+pub struct RxStream<Id: ChId, S: SerializerId, B: Buffer<Beat = u32>> {
+    transfer: Transfer<DmaChannel<Id, Busy>, BufferPair<Rx<S>, B>>,
+}
+
+#[cfg(feature = "dma")]
+impl<Id, S, B> RxStream<Id, S, B>
+where
+    Id: ChId,
+    S: SerializerId,
+    B: Buffer<Beat = u32> + 'static,
+{
+    /// Returns `true` if the current buffer was filled
+    #[inline]
+    pub fn is_complete(&mut self) -> bool {
+        self.transfer.complete()
+    }
+
+    /// Check and clear the block transfer complete interrupt flag
+    #[inline]
+    pub fn block_transfer_interrupt(&mut self) -> bool {
+        self.transfer.block_transfer_interrupt()
+    }
+
+    /// Start filling `next` once the current buffer was filled, and return
+    /// the filled buffer
+    ///
+    /// Returns `Err(next)` if the current buffer is still being filled.
+    #[inline]
+    pub fn swap(&mut self, next: B) -> Result<B, B> {
+        if !self.transfer.complete() {
+            return Err(next);
+        }
+        // The data register is a single beat, so the buffer lengths always match
+        match self.transfer.recycle_source(next) {
+            Ok(filled) => Ok(filled),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Stop the stream, and return the DMA channel, the receiver and the
+    /// current buffer
+    #[inline]
+    pub fn stop(self) -> (DmaChannel<Id, Ready>, Rx<S>, B) {
+        self.transfer.stop()
+    }
+}
@@ -8,6 +8,14 @@ pub use serial_number::*;
 
 pub mod calibration;
 pub mod clock;
+pub mod dac;
+
+#[cfg(feature = "samd21")]
+pub mod i2s;
+
+pub mod nvm;
+pub mod power;
+pub mod supc;
 pub mod timer;
 
 #[cfg(feature = "unproven")]
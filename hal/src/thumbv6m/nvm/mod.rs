@@ -0,0 +1,355 @@
+//! # Non-Volatile Memory Controller
+//!
+//! The [`Nvm`] gives access to the internal flash. The flash is erased by rows
+//! of [`ROW_SIZE`] bytes, and written by pages of [`PAGE_SIZE`] bytes through
+//! the page buffer.
+//!
+//! Erasing or writing flash that holds the running program, or data in use,
+//! is undefined behavior, which is why [`Nvm::erase`] and [`Nvm::write`] are
+//! `unsafe`. A [`FlashStorage`] restricts the accesses to a region reserved
+//! for storage, and implements the [`embedded-storage`] [`NorFlash`] traits:
+//!
+//! ```
+//! use atsamd_hal::nvm::{FlashStorage, Nvm};
+//! use embedded_storage::nor_flash::NorFlash;
+//!
+//! let nvm = Nvm::new(peripherals.NVMCTRL);
+//! // The last 4 kB of flash are reserved for storage by the linker script
+//! let mut storage = unsafe { FlashStorage::new(nvm, 0x3_F000, 0x1000) }.unwrap();
+//! storage.erase(0, 256).unwrap();
+//! storage.write(0, &[0x12, 0x34, 0x56, 0x78]).unwrap();
+//! ```
+//!
+//! ## Region locks
+//!
+//! The flash is divided in 16 regions, which can be locked against erases and
+//! writes. [`Nvm::lock_region`] and [`Nvm::unlock_region`] only last until
+//! the next reset. Permanent locks are configured in the user row, see
+//! [`user_row`].
+//!
+//! [`embedded-storage`]: embedded_storage
+//! [`NorFlash`]: embedded_storage::nor_flash::NorFlash
+
+use core::ptr;
+
+use embedded_storage::nor_flash::{
+    check_erase, check_read, check_write, ErrorType, NorFlash, NorFlashError, NorFlashErrorKind,
+    ReadNorFlash,
+};
+
+use crate::pac::nvmctrl::ctrla::CMD_A;
+use crate::pac::NVMCTRL;
+
+pub mod user_row;
+
+/// Size of a flash page, in bytes
+pub const PAGE_SIZE: usize = 64;
+
+/// Size of a flash row, the smallest erasable unit, in bytes
+pub const ROW_SIZE: usize = 4 * PAGE_SIZE;
+
+/// Alignment of flash writes, in bytes
+///
+/// The page buffer can only be loaded with 16- or 32-bit accesses.
+pub const WRITE_SIZE: usize = 4;
+
+/// Number of lock regions
+const NUM_REGIONS: usize = 16;
+
+//==============================================================================
+//  Error
+//==============================================================================
+
+/// NVM errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The address or length is not properly aligned
+    Alignment,
+    /// The address range is outside of the flash
+    OutOfBounds,
+    /// An invalid command was issued (`PROGE`)
+    Programming,
+    /// The address is in a locked region (`LOCKE`)
+    Lock,
+    /// The NVM controller reported an error (`NVME`)
+    Nvm,
+}
+
+impl NorFlashError for Error {
+    #[inline]
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            Error::Alignment => NorFlashErrorKind::NotAligned,
+            Error::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+            _ => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+impl From<NorFlashErrorKind> for Error {
+    #[inline]
+    fn from(kind: NorFlashErrorKind) -> Self {
+        match kind {
+            NorFlashErrorKind::NotAligned => Error::Alignment,
+            NorFlashErrorKind::OutOfBounds => Error::OutOfBounds,
+            _ => Error::Nvm,
+        }
+    }
+}
+
+//==============================================================================
+//  Nvm
+//==============================================================================
+
+/// The NVMCTRL peripheral
+pub struct Nvm {
+    nvm: NVMCTRL,
+}
+
+impl Nvm {
+    /// Take control of the NVMCTRL
+    ///
+    /// Automatic page writes are disabled, so that the page buffer is only
+    /// written to flash by [`Nvm::write`].
+    #[inline]
+    pub fn new(nvm: NVMCTRL) -> Self {
+        nvm.ctrlb.modify(|_, w| w.manw().set_bit());
+        Self { nvm }
+    }
+
+    /// Size of the flash, in bytes
+    #[inline]
+    pub fn flash_size(&self) -> usize {
+        self.nvm.param.read().nvmp().bits() as usize * PAGE_SIZE
+    }
+
+    /// Size of a lock region, in bytes
+    #[inline]
+    pub fn region_size(&self) -> usize {
+        self.flash_size() / NUM_REGIONS
+    }
+
+    #[inline]
+    fn wait_ready(&self) {
+        while self.nvm.intflag.read().ready().bit_is_clear() {}
+    }
+
+    /// Execute a command and check for errors
+    #[inline]
+    fn command(&mut self, command: CMD_A, addr: Option<u32>) -> Result<(), Error> {
+        self.wait_ready();
+        self.nvm.status.write(|w| {
+            w.proge().set_bit();
+            w.locke().set_bit();
+            w.nvme().set_bit()
+        });
+        if let Some(addr) = addr {
+            // The address register holds 16-bit word addresses
+            self.nvm.addr.write(|w| unsafe { w.addr().bits(addr >> 1) });
+        }
+        self.nvm.ctrla.write(|w| {
+            w.cmd().variant(command);
+            w.cmdex().key()
+        });
+        self.wait_ready();
+
+        let status = self.nvm.status.read();
+        if status.proge().bit_is_set() {
+            Err(Error::Programming)
+        } else if status.locke().bit_is_set() {
+            Err(Error::Lock)
+        } else if status.nvme().bit_is_set() {
+            Err(Error::Nvm)
+        } else {
+            Ok(())
+        }
+    }
+
+    // All alignments are powers of two
+    #[inline]
+    fn check_range(&self, addr: u32, len: usize, align: usize) -> Result<(), Error> {
+        if (addr as usize | len) & (align - 1) != 0 {
+            return Err(Error::Alignment);
+        }
+        match (addr as usize).checked_add(len) {
+            Some(end) if end <= self.flash_size() => Ok(()),
+            _ => Err(Error::OutOfBounds),
+        }
+    }
+
+    /// Read flash contents
+    #[inline]
+    pub fn read(&self, addr: u32, buf: &mut [u8]) -> Result<(), Error> {
+        self.check_range(addr, buf.len(), 1)?;
+        for (i, byte) in buf.iter_mut().enumerate() {
+            // SAFETY: The range was checked to be within the flash. The flash
+            // starts at address 0, which can't be dereferenced as a regular
+            // pointer.
+            *byte = unsafe { ptr::read_volatile((addr as usize + i) as *const u8) };
+        }
+        Ok(())
+    }
+
+    /// Erase the rows in `addr..addr + len`
+    ///
+    /// Both `addr` and `len` must be multiples of [`ROW_SIZE`].
+    ///
+    /// # Safety
+    ///
+    /// The range must not hold the running program, nor any data referenced
+    /// by it.
+    #[inline]
+    pub unsafe fn erase(&mut self, addr: u32, len: usize) -> Result<(), Error> {
+        self.check_range(addr, len, ROW_SIZE)?;
+        for row in (addr..addr + len as u32).step_by(ROW_SIZE) {
+            self.command(CMD_A::ER, Some(row))?;
+        }
+        self.invalidate_cache()
+    }
+
+    /// Write `data` to flash at `addr`
+    ///
+    /// Both `addr` and the length of `data` must be multiples of
+    /// [`WRITE_SIZE`]. The flash must have been erased beforehand, as writes
+    /// can only clear bits.
+    ///
+    /// # Safety
+    ///
+    /// The range must not hold the running program, nor any data referenced
+    /// by it.
+    #[inline]
+    pub unsafe fn write(&mut self, addr: u32, data: &[u8]) -> Result<(), Error> {
+        self.check_range(addr, data.len(), WRITE_SIZE)?;
+
+        let mut addr = addr;
+        let mut data = data;
+        while !data.is_empty() {
+            // Write up to the end of the current page
+            let len = data.len().min(PAGE_SIZE - addr as usize % PAGE_SIZE);
+            let (page, rest) = data.split_at(len);
+
+            // Unwritten bytes of the cleared page buffer leave flash unchanged
+            self.command(CMD_A::PBC, None)?;
+            for (i, word) in page.chunks_exact(WRITE_SIZE).enumerate() {
+                let word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+                ptr::write_volatile((addr as *mut u32).add(i), word);
+            }
+            self.command(CMD_A::WP, Some(addr))?;
+
+            addr += len as u32;
+            data = rest;
+        }
+        self.invalidate_cache()
+    }
+
+    /// Temporarily lock the region containing `addr`
+    #[inline]
+    pub fn lock_region(&mut self, addr: u32) -> Result<(), Error> {
+        self.check_range(addr, 1, 1)?;
+        self.command(CMD_A::LR, Some(addr))
+    }
+
+    /// Temporarily unlock the region containing `addr`
+    #[inline]
+    pub fn unlock_region(&mut self, addr: u32) -> Result<(), Error> {
+        self.check_range(addr, 1, 1)?;
+        self.command(CMD_A::UR, Some(addr))
+    }
+
+    /// Returns `true` if the region containing `addr` is locked
+    #[inline]
+    pub fn is_region_locked(&self, addr: u32) -> Result<bool, Error> {
+        self.check_range(addr, 1, 1)?;
+        let region = addr as usize / self.region_size();
+        Ok(self.nvm.lock.read().lock().bits() & (1 << region) == 0)
+    }
+
+    /// Invalidate the NVM cache
+    ///
+    /// This is done after every erase and write through the [`Nvm`].
+    #[inline]
+    pub fn invalidate_cache(&mut self) -> Result<(), Error> {
+        self.command(CMD_A::INVALL, None)
+    }
+
+    /// Return the underlying peripheral
+    #[inline]
+    pub fn free(self) -> NVMCTRL {
+        self.nvm
+    }
+}
+
+//==============================================================================
+//  FlashStorage
+//==============================================================================
+
+/// A flash region reserved for storage
+///
+/// Offsets used with the [`NorFlash`] traits are relative to the start of the
+/// region.
+pub struct FlashStorage {
+    nvm: Nvm,
+    start: u32,
+    len: usize,
+}
+
+impl FlashStorage {
+    /// Reserve the region `start..start + len` for storage
+    ///
+    /// Both `start` and `len` must be multiples of [`ROW_SIZE`].
+    ///
+    /// # Safety
+    ///
+    /// The region must not hold the program, nor any data referenced by it,
+    /// for as long as the [`FlashStorage`] exists. It is typically reserved
+    /// in the linker script.
+    #[inline]
+    pub unsafe fn new(nvm: Nvm, start: u32, len: usize) -> Result<Self, Error> {
+        nvm.check_range(start, len, ROW_SIZE)?;
+        Ok(Self { nvm, start, len })
+    }
+
+    /// Return the [`Nvm`]
+    #[inline]
+    pub fn free(self) -> Nvm {
+        self.nvm
+    }
+}
+
+impl ErrorType for FlashStorage {
+    type Error = Error;
+}
+
+impl ReadNorFlash for FlashStorage {
+    const READ_SIZE: usize = 1;
+
+    #[inline]
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Error> {
+        check_read(self, offset, bytes.len())?;
+        self.nvm.read(self.start + offset, bytes)
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.len
+    }
+}
+
+impl NorFlash for FlashStorage {
+    const WRITE_SIZE: usize = WRITE_SIZE;
+    const ERASE_SIZE: usize = ROW_SIZE;
+
+    #[inline]
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Error> {
+        check_erase(self, from, to)?;
+        // SAFETY: The region is reserved for storage
+        unsafe { self.nvm.erase(self.start + from, (to - from) as usize) }
+    }
+
+    #[inline]
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Error> {
+        check_write(self, offset, bytes.len())?;
+        // SAFETY: The region is reserved for storage
+        unsafe { self.nvm.write(self.start + offset, bytes) }
+    }
+}
@@ -0,0 +1,303 @@
+//! # User row
+//!
+//! The first 64 bits of the NVM user row hold the fuses loaded at reset: BOD33
+//! and watchdog defaults, bootloader and EEPROM sizes, and NVM region locks. A
+//! [`UserRow`] holds these fuses:
+//!
+//! ```
+//! use atsamd_hal::nvm::{user_row::UserRow, Nvm};
+//!
+//! let mut nvm = Nvm::new(peripherals.NVMCTRL);
+//! let mut row = UserRow::read();
+//! // Protect an 8 kB bootloader
+//! row.set_bootprot(2);
+//! row.set_bod33_level(39);
+//! nvm.write_user_row(&row).unwrap();
+//! ```
+//!
+//! The new values only take effect after a reset. Factory-reserved bits,
+//! such as the BOD12 calibration, cannot be modified, and the rest of the
+//! user row is preserved.
+
+use core::ptr;
+
+use crate::pac::nvmctrl::ctrla::CMD_A;
+
+use super::{Nvm, NUM_REGIONS, PAGE_SIZE, ROW_SIZE};
+
+/// Address of the user row
+const USER_ROW_ADDR: u32 = 0x0080_4000;
+
+/// Factory-reserved bits, which must never be changed
+///
+/// These are the BOD12 calibration (bits 17 to 24) and hysteresis (bit 41),
+/// and bits 3, 7 and 42 to 47.
+const RESERVED: u64 = 0x0000_FE00_01FE_0088;
+
+//==============================================================================
+//  Error
+//==============================================================================
+
+/// User row errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Factory-reserved bits were modified
+    ReservedBits,
+    /// A field holds a reserved value
+    InvalidValue,
+    /// The lock region does not exist
+    InvalidRegion,
+    /// An NVM command failed
+    Nvm(super::Error),
+}
+
+impl From<super::Error> for Error {
+    #[inline]
+    fn from(error: super::Error) -> Self {
+        Error::Nvm(error)
+    }
+}
+
+//==============================================================================
+//  UserRow
+//==============================================================================
+
+/// Fuses of the NVM user row
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UserRow(u64);
+
+/// Types of the [`UserRow`] fields
+trait Field {
+    fn from_bits(bits: u64) -> Self;
+    fn into_bits(self) -> u64;
+}
+
+impl Field for bool {
+    #[inline]
+    fn from_bits(bits: u64) -> Self {
+        bits != 0
+    }
+
+    #[inline]
+    fn into_bits(self) -> u64 {
+        self as u64
+    }
+}
+
+impl Field for u8 {
+    #[inline]
+    fn from_bits(bits: u64) -> Self {
+        bits as u8
+    }
+
+    #[inline]
+    fn into_bits(self) -> u64 {
+        self as u64
+    }
+}
+
+impl Field for u16 {
+    #[inline]
+    fn from_bits(bits: u64) -> Self {
+        bits as u16
+    }
+
+    #[inline]
+    fn into_bits(self) -> u64 {
+        self as u64
+    }
+}
+
+macro_rules! fields {
+    ($(
+        $(#[$attr:meta])*
+        $get:ident, $set:ident: $T:ty = $offset:literal, $width:literal;
+    )+) => {
+        impl UserRow {
+            $(
+                $(#[$attr])*
+                #[inline]
+                pub fn $get(&self) -> $T {
+                    <$T as Field>::from_bits(self.get($offset, $width))
+                }
+
+                #[doc = concat!("Set the value returned by [`UserRow::", stringify!($get), "`]")]
+                ///
+                /// # Panics
+                ///
+                #[doc = concat!("Panics if the value does not fit in ", stringify!($width), " bits.")]
+                #[inline]
+                pub fn $set(&mut self, value: $T) {
+                    self.set($offset, $width, value.into_bits());
+                }
+            )+
+        }
+    };
+}
+
+fields! {
+    /// Bootloader protection size, see [`UserRow::bootloader_size`]
+    bootprot, set_bootprot: u8 = 0, 3;
+    /// Emulated EEPROM size, see [`UserRow::eeprom_size`]
+    eeprom, set_eeprom: u8 = 4, 3;
+    /// BOD33 threshold level at power-on
+    bod33_level, set_bod33_level: u8 = 8, 6;
+    /// BOD33 enabled at power-on
+    bod33_enable, set_bod33_enable: bool = 14, 1;
+    /// BOD33 action at power-on
+    ///
+    /// `0` does nothing, `1` resets the device and `2` generates an
+    /// interrupt.
+    bod33_action, set_bod33_action: u8 = 15, 2;
+    /// Watchdog enabled at power-on
+    wdt_enable, set_wdt_enable: bool = 25, 1;
+    /// Watchdog always-on at power-on
+    wdt_always_on, set_wdt_always_on: bool = 26, 1;
+    /// Watchdog timeout period at power-on
+    wdt_period, set_wdt_period: u8 = 27, 4;
+    /// Watchdog window mode time-out period at power-on
+    wdt_window, set_wdt_window: u8 = 31, 4;
+    /// Watchdog early warning interrupt time offset at power-on
+    wdt_ewoffset, set_wdt_ewoffset: u8 = 35, 4;
+    /// Watchdog window mode enabled at power-on
+    wdt_window_enable, set_wdt_window_enable: bool = 39, 1;
+    /// BOD33 hysteresis enabled at power-on
+    bod33_hysteresis, set_bod33_hysteresis: bool = 40, 1;
+    /// NVM region lock bits, where a cleared bit locks the region
+    region_locks, set_region_locks: u16 = 48, 16;
+}
+
+impl UserRow {
+    /// Read the user row
+    #[inline]
+    pub fn read() -> Self {
+        // SAFETY: The user row is always readable
+        let bytes = unsafe { ptr::read_volatile(USER_ROW_ADDR as *const [u8; 8]) };
+        Self(u64::from_le_bytes(bytes))
+    }
+
+    /// Create a user row from its raw value
+    #[inline]
+    pub const fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// Get the raw value of the user row
+    #[inline]
+    pub const fn bits(&self) -> u64 {
+        self.0
+    }
+
+    #[inline]
+    fn get(&self, offset: u32, width: u32) -> u64 {
+        (self.0 >> offset) & ((1 << width) - 1)
+    }
+
+    #[inline]
+    fn set(&mut self, offset: u32, width: u32, value: u64) {
+        let mask = (1 << width) - 1;
+        assert!(value <= mask, "value does not fit in the field");
+        self.0 = (self.0 & !(mask << offset)) | value << offset;
+    }
+
+    /// Size of the protected bootloader section, in bytes
+    #[inline]
+    pub fn bootloader_size(&self) -> usize {
+        match self.bootprot() {
+            7 => 0,
+            n => 512 << (6 - n),
+        }
+    }
+
+    /// Size of the emulated EEPROM section at the end of the flash, in bytes
+    #[inline]
+    pub fn eeprom_size(&self) -> usize {
+        match self.eeprom() {
+            7 => 0,
+            n => 256 << (6 - n),
+        }
+    }
+
+    /// Number of NVM lock regions
+    #[inline]
+    pub fn regions(&self) -> u8 {
+        NUM_REGIONS as u8
+    }
+
+    /// Returns `true` if the NVM region is locked at power-on
+    #[inline]
+    pub fn is_region_locked(&self, region: u8) -> Result<bool, Error> {
+        let mask = self.region_mask(region)?;
+        Ok(self.region_locks() & mask == 0)
+    }
+
+    /// Lock or unlock the NVM region at power-on
+    #[inline]
+    pub fn set_region_locked(&mut self, region: u8, locked: bool) -> Result<(), Error> {
+        let mask = self.region_mask(region)?;
+        let locks = self.region_locks();
+        self.set_region_locks(if locked { locks & !mask } else { locks | mask });
+        Ok(())
+    }
+
+    #[inline]
+    fn region_mask(&self, region: u8) -> Result<u16, Error> {
+        if region < self.regions() {
+            Ok(1 << region)
+        } else {
+            Err(Error::InvalidRegion)
+        }
+    }
+
+    /// Check that the reserved bits match `current`, and that every field
+    /// holds a valid value
+    #[inline]
+    fn validate(&self, current: &UserRow) -> Result<(), Error> {
+        if (self.0 ^ current.0) & RESERVED != 0 {
+            return Err(Error::ReservedBits);
+        }
+        let valid = self.bod33_action() != 3
+            && self.wdt_period() <= 11
+            && self.wdt_window() <= 11
+            && self.wdt_ewoffset() <= 11;
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidValue)
+        }
+    }
+}
+
+impl Nvm {
+    /// Write the user row
+    ///
+    /// The row is validated before the user row is erased. The rest of the
+    /// user row is preserved.
+    #[inline]
+    pub fn write_user_row(&mut self, row: &UserRow) -> Result<(), Error> {
+        row.validate(&UserRow::read())?;
+
+        let mut data = [0; ROW_SIZE];
+        // SAFETY: The user row is always readable
+        unsafe {
+            ptr::copy_nonoverlapping(USER_ROW_ADDR as *const u8, data.as_mut_ptr(), data.len())
+        };
+        data[..8].copy_from_slice(&row.0.to_le_bytes());
+
+        self.command(CMD_A::EAR, Some(USER_ROW_ADDR))?;
+        for (i, page) in data.chunks_exact(PAGE_SIZE).enumerate() {
+            if page.iter().all(|&b| b == 0xFF) {
+                continue;
+            }
+            let addr = USER_ROW_ADDR + (i * PAGE_SIZE) as u32;
+            self.command(CMD_A::PBC, None)?;
+            for (j, word) in page.chunks_exact(4).enumerate() {
+                let word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+                // SAFETY: Writes to the user row only load the page buffer
+                unsafe { ptr::write_volatile((addr as *mut u32).add(j), word) };
+            }
+            self.command(CMD_A::WAP, Some(addr))?;
+        }
+        Ok(self.invalidate_cache()?)
+    }
+}
@@ -0,0 +1,148 @@
+//! # Sleep modes
+//!
+//! SAMD11 and SAMD21 have two sleep modes, entered by the `WFI` instruction:
+//!
+//! - In idle sleep mode, the CPU and, depending on the [`IdleLevel`], the
+//!   AHB and APB clocks are stopped. Any interrupt wakes the CPU up.
+//! - In standby sleep mode, all clocks are stopped, except those of the
+//!   generic clock generators and peripherals which run in standby. The RAM
+//!   is retained.
+//!
+//! Standby sleep mode is only entered with a [`WakeSource`], which proves
+//! that a peripheral keeps running in it, and has its interrupt enabled. This
+//! makes sure the device does not sleep forever because the only peripheral
+//! able to wake it up was stopped. Wake sources are obtained with
+//! [`RtcWake::new`] and the `wake_source` method of the EIC pins, which check
+//! the configuration of the peripheral.
+//!
+//! ```
+//! use atsamd_hal::power::{Power, RtcWake};
+//!
+//! let mut power = Power::new(core.SCB);
+//!
+//! // The RTC must be clocked by a generator which runs in standby
+//! clocks.configure_standby(ClockGenId::GCLK1, true);
+//!
+//! // Sleep until the RTC compare interrupt fires
+//! rtc.enable_interrupt();
+//! let wake = RtcWake::new(&rtc).unwrap();
+//! power.standby(&wake);
+//! ```
+
+use core::marker::PhantomData;
+
+use cortex_m::asm;
+use cortex_m::peripheral::SCB;
+
+use crate::clock::{self, ClockId};
+use crate::pac::{pm, PM};
+use crate::rtc::{Rtc, RtcMode};
+use crate::typelevel::Sealed;
+
+/// Clocks stopped in idle sleep mode, in addition to the CPU clock
+pub use pm::sleep::IDLE_A as IdleLevel;
+
+//==============================================================================
+//  Sleep modes
+//==============================================================================
+
+/// Type-level marker of a sleep mode
+pub trait SleepMode: Sealed {}
+
+/// Type-level marker of the standby sleep mode
+pub enum Standby {}
+impl Sealed for Standby {}
+impl SleepMode for Standby {}
+
+/// Peripheral which keeps running in the sleep mode `M`, and can wake the
+/// device up from it
+///
+/// This trait is sealed. It is only implemented by types whose constructors
+/// check that the peripheral is configured to wake the device up.
+pub trait WakeSource<M: SleepMode>: Sealed {}
+
+/// RTC able to wake the device up from standby sleep mode
+pub struct RtcWake<'a, R: RtcMode> {
+    _rtc: &'a Rtc<R>,
+}
+
+impl<'a, R: RtcMode> RtcWake<'a, R> {
+    /// Check that the RTC can wake the device up from standby sleep mode
+    ///
+    /// Returns `None` unless an interrupt of the RTC is enabled, and its
+    /// generic clock generator runs in standby from a 32kHz oscillator
+    /// running in standby.
+    #[inline]
+    pub fn new(rtc: &'a Rtc<R>) -> Option<Self> {
+        if rtc.interrupt_enabled() && clock::runs_in_standby(ClockId::RTC) {
+            Some(Self { _rtc: rtc })
+        } else {
+            None
+        }
+    }
+}
+
+impl<R: RtcMode> Sealed for RtcWake<'_, R> {}
+impl<R: RtcMode> WakeSource<Standby> for RtcWake<'_, R> {}
+
+/// External interrupt able to wake the device up from standby sleep mode
+///
+/// Returned by the `wake_source` method of the EIC pins.
+pub struct ExtIntWake<'a> {
+    _pin: PhantomData<&'a ()>,
+}
+
+impl ExtIntWake<'_> {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self { _pin: PhantomData }
+    }
+}
+
+impl Sealed for ExtIntWake<'_> {}
+impl WakeSource<Standby> for ExtIntWake<'_> {}
+
+//==============================================================================
+//  Power
+//==============================================================================
+
+/// Sleep mode control
+///
+/// The sleep mode is selected by the `SLEEPDEEP` bit of the SCB, which is
+/// owned by [`Power`].
+pub struct Power {
+    scb: SCB,
+}
+
+impl Power {
+    /// Take the SCB
+    #[inline]
+    pub fn new(scb: SCB) -> Self {
+        Self { scb }
+    }
+
+    /// Enter idle sleep mode until an interrupt fires
+    #[inline]
+    pub fn idle(&mut self, pm: &mut PM, level: IdleLevel) {
+        pm.sleep.write(|w| w.idle().variant(level));
+        self.scb.clear_sleepdeep();
+        asm::dsb();
+        asm::wfi();
+    }
+
+    /// Enter standby sleep mode until the wake source, or another peripheral
+    /// running in standby, fires an interrupt
+    #[inline]
+    pub fn standby<W: WakeSource<Standby>>(&mut self, _source: &W) {
+        self.scb.set_sleepdeep();
+        asm::dsb();
+        asm::wfi();
+        self.scb.clear_sleepdeep();
+    }
+
+    /// Return the underlying SCB
+    #[inline]
+    pub fn free(self) -> SCB {
+        self.scb
+    }
+}
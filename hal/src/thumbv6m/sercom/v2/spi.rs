@@ -159,6 +159,32 @@ type Pads = spi::PadsFromIds<Sercom0, PA08, NoneT, PA09>;
 //! let rcvd: u16 = block!(spi.read());
 //! ```
 //!
+//! # [`Slave`] mode
+//!
+//! In [`Slave`] mode, frames are delimited by the `SS` line. Enable the `SSL`
+//! and `TXC` interrupts, and call [`Spi::frame_event`] to detect the start
+//! and the end of each frame. With [`preload`](Config::preload), a word
+//! written while `SS` is high is sent from the start of the next frame. With
+//! [`address`](Config::address), the slave only responds to the frames
+//! starting with a matching [`Address`].
+//!
+//! ```
+//! use atsamd_hal::sercom::v2::spi::{Flags, FrameEvent, Slave};
+//!
+//! let mut spi = spi::Config::new(&mclk, sercom, pads, freq)
+//!     .op_mode::<Slave>()
+//!     .preload(true)
+//!     .ss_detect(true)
+//!     .enable();
+//! spi.enable_interrupts(Flags::SSL | Flags::TXC);
+//!
+//! // In the SERCOM interrupt handler
+//! match spi.frame_event() {
+//!     Some(FrameEvent::End) => { /* Process the frame */ }
+//!     _ => (),
+//! }
+//! ```
+//!
 //! [`enable`]: Config::enable
 //! [`bsp_pins`]: crate::bsp_pins
 //! [`Pin`]: crate::gpio::v2::pin::Pin
@@ -192,7 +218,13 @@ let dma_transfer = spi.send_with_dma(&mut buffer, channel, ());
 let (chan0, _, spi, _) = dma_transfer.wait();
 ```
 
+In [`Slave`] mode, [`exchange_with_dma`] creates a [`SlaveExchange`], which
+receives and sends whole frames with two DMA channels, and swaps the buffers
+at the end of each frame.
+
 [`Buffer`]: crate::dmac::transfer::Buffer
+[`exchange_with_dma`]: Spi::exchange_with_dma
+[`SlaveExchange`]: crate::sercom::v2::dma::SlaveExchange
 [`send_with_dma`]: Spi::send_with_dma
 [`receive_with_dma`]: Spi::receive_with_dma
 [`dmac::Transfer`]: crate::dmac::Transfer
@@ -817,6 +849,51 @@ pub trait MasterMode: OpMode {}
 impl MasterMode for Master {}
 impl MasterMode for MasterHWSS {}
 
+//=============================================================================
+// Slave mode
+//=============================================================================
+
+/// Value of the `CTRLA.FORM` field for SPI frames with an address
+const FORM_WITH_ADDRESS: u8 = 2;
+
+/// Value of the `CTRLB.AMODE` field to match addresses with a mask
+const AMODE_MASK: u8 = 0;
+
+/// Value of the `CTRLB.AMODE` field to match two addresses
+const AMODE_TWO_ADDRESSES: u8 = 1;
+
+/// Value of the `CTRLB.AMODE` field to match a range of addresses
+const AMODE_RANGE: u8 = 2;
+
+/// Addresses a [`Slave`] responds to, when address matching is enabled
+///
+/// See [`Config::address`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Address {
+    /// A single address
+    Single(u8),
+    /// An address, ignoring the bits set in `mask`
+    Mask { address: u8, mask: u8 },
+    /// Two addresses
+    Two(u8, u8),
+    /// All addresses from `low` to `high`, inclusive
+    Range { low: u8, high: u8 },
+}
+
+/// Boundary of a frame, delimited by the `SS` line, in [`Slave`] mode
+///
+/// See [`Spi::frame_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameEvent {
+    /// `SS` was pulled low: a frame started
+    Start,
+    /// `SS` was pulled high: the frame ended
+    End,
+    /// `SS` was pulled low, then high: a whole frame was transferred since the
+    /// last check
+    Complete,
+}
+
 //=============================================================================
 // Character size
 //=============================================================================
@@ -1198,6 +1275,63 @@ where
     }
 }
 
+impl<P, C> Config<P, Slave, C>
+where
+    P: ValidPads,
+    C: CharSize,
+{
+    /// Enable or disable data preload
+    ///
+    /// When set, a word written while `SS` is high is loaded into the shift
+    /// register when `SS` is pulled low, so that it is sent from the first
+    /// `SCK` edge of the next frame.
+    #[inline]
+    pub fn preload(self, set: bool) -> Self {
+        self.sercom.spi().ctrlb.modify(|_, w| w.ploaden().bit(set));
+        while self.sercom.spi().syncbusy.read().ctrlb().bit_is_set() {}
+        self
+    }
+
+    /// Enable or disable the `SS` low detection
+    ///
+    /// When set, the `SSL` flag is set at the start of each frame, when `SS`
+    /// is pulled low. It can wake up the CPU from sleep modes.
+    #[inline]
+    pub fn ss_detect(self, set: bool) -> Self {
+        self.sercom.spi().ctrlb.modify(|_, w| w.ssde().bit(set));
+        while self.sercom.spi().syncbusy.read().ctrlb().bit_is_set() {}
+        self
+    }
+
+    /// Enable address matching, or disable it with `None`
+    ///
+    /// When set, the first word of each frame is an address. If it matches,
+    /// the address is received like a data word, and the frame is processed.
+    /// Otherwise, the whole frame is ignored and `MISO` is not driven, so
+    /// several slaves can share the same `SS` line.
+    #[inline]
+    pub fn address(self, address: Option<Address>) -> Self {
+        let (form, amode, addr, mask) = match address {
+            None => (0, AMODE_MASK, 0, 0),
+            Some(Address::Single(addr)) => (FORM_WITH_ADDRESS, AMODE_MASK, addr, 0),
+            Some(Address::Mask { address, mask }) => (FORM_WITH_ADDRESS, AMODE_MASK, address, mask),
+            Some(Address::Two(first, second)) => {
+                (FORM_WITH_ADDRESS, AMODE_TWO_ADDRESSES, first, second)
+            }
+            Some(Address::Range { low, high }) => (FORM_WITH_ADDRESS, AMODE_RANGE, high, low),
+        };
+        let spi = self.sercom.spi();
+        spi.ctrla.modify(|_, w| unsafe { w.form().bits(form) });
+        spi.ctrlb.modify(|_, w| unsafe { w.amode().bits(amode) });
+        while spi.syncbusy.read().ctrlb().bit_is_set() {}
+        spi.addr.write(|w| unsafe {
+            w.addr().bits(addr);
+            w.addrmask().bits(mask)
+        });
+        self
+    }
+}
+
 //=============================================================================
 // AnyConfig
 //=============================================================================
@@ -1473,6 +1607,41 @@ where
     }
 }
 
+impl<P, C> Spi<Config<P, Slave, C>>
+where
+    P: ValidPads,
+    C: CharSize,
+    Config<P, Slave, C>: ValidConfig,
+{
+    /// Check for the start or the end of a frame
+    ///
+    /// Read and clear the `SSL` and `TXC` flags. In [`Slave`] mode, `SSL` is
+    /// set when `SS` is pulled low, if enabled with
+    /// [`ss_detect`](Config::ss_detect), and `TXC` is set when `SS` is pulled
+    /// high. With address matching, `TXC` is only set for frames which
+    /// matched.
+    ///
+    /// Enable the `SSL` and `TXC` interrupts to call this method from the
+    /// SERCOM interrupt handler.
+    ///
+    /// **Warning:** The implementation of [`Write::flush`] waits on the `TXC`
+    /// flag, so it will hang if this method clears the flag first.
+    #[inline]
+    pub fn frame_event(&mut self) -> Option<FrameEvent> {
+        let flags = self.read_flags() & (Flags::SSL | Flags::TXC);
+        self.clear_flags(flags);
+        if flags.contains(Flags::SSL | Flags::TXC) {
+            Some(FrameEvent::Complete)
+        } else if flags.contains(Flags::SSL) {
+            Some(FrameEvent::Start)
+        } else if flags.contains(Flags::TXC) {
+            Some(FrameEvent::End)
+        } else {
+            None
+        }
+    }
+}
+
 //=============================================================================
 // AnySpi
 //=============================================================================
@@ -0,0 +1,218 @@
+//! # Supply control
+//!
+//! On SAMD11 and SAMD21, the 3.3V brown-out detector (BOD33) and the bandgap
+//! voltage reference are controlled by the `SYSCTRL` peripheral, which also
+//! holds the oscillators. [`Supc`] takes the `SYSCTRL` after the clocks are
+//! set up by the [`GenericClockController`](crate::clock::GenericClockController).
+//!
+//! The BOD33 is configured from the NVM user row at reset; see
+//! [`UserRow`](crate::nvm::UserRow). [`Supc::enable_bod33`] reconfigures it at
+//! runtime, for instance to raise an interrupt instead of resetting the
+//! device when the supply voltage drops, which leaves time to save state
+//! before power is lost. The 1.2V brown-out detector (BOD12) is calibrated in
+//! the factory and cannot be configured.
+//!
+//! ```
+//! use atsamd_hal::supc::{Bod33Action, Bod33Config, Flags, Supc};
+//!
+//! let mut supc = Supc::new(peripherals.SYSCTRL);
+//! supc.enable_bod33(Bod33Config {
+//!     level: 48,
+//!     action: Bod33Action::INTERRUPT,
+//!     ..Default::default()
+//! });
+//! supc.enable_interrupts(Flags::BOD33_DETECTION);
+//! ```
+
+use bitflags::bitflags;
+
+use crate::pac::{sysctrl, SYSCTRL};
+
+/// Action of the BOD33 when the supply voltage drops below the threshold
+pub use sysctrl::bod33::ACTION_A as Bod33Action;
+/// Division of the BOD33 sampling clock, `CLK_ULP1K`
+pub use sysctrl::bod33::PSEL_A as Bod33Prescaler;
+
+//==============================================================================
+//  BOD33
+//==============================================================================
+
+/// BOD33 configuration
+///
+/// The default configuration matches the factory settings of the NVM user
+/// row.
+#[derive(Clone, Copy, Debug)]
+pub struct Bod33Config {
+    /// Threshold level, from 0 to 63; see the electrical characteristics of
+    /// the datasheet for the voltage of each level
+    pub level: u8,
+    /// Enable the hysteresis
+    pub hysteresis: bool,
+    /// Action when the supply voltage drops below the threshold
+    pub action: Bod33Action,
+    /// Division of the sampling clock
+    pub prescaler: Bod33Prescaler,
+    /// Sample the supply voltage periodically, rather than monitor it
+    /// continuously
+    pub sampling: bool,
+    /// Keep the BOD33 enabled in standby sleep mode
+    pub run_in_standby: bool,
+}
+
+impl Default for Bod33Config {
+    fn default() -> Self {
+        Self {
+            level: 7,
+            hysteresis: false,
+            action: Bod33Action::RESET,
+            prescaler: Bod33Prescaler::DIV2,
+            sampling: false,
+            run_in_standby: false,
+        }
+    }
+}
+
+//==============================================================================
+//  Flags
+//==============================================================================
+
+bitflags! {
+    /// BOD33 interrupt flags
+    ///
+    /// The binary format of the flags matches the `INTENSET`, `INTENCLR`,
+    /// `INTFLAG` and `PCLKSR` registers of `SYSCTRL`. The flags of the
+    /// oscillators are left out.
+    pub struct Flags: u32 {
+        /// The BOD33 is ready
+        const BOD33_READY = 1 << 9;
+        /// The supply voltage is below the BOD33 threshold
+        const BOD33_DETECTION = 1 << 10;
+        /// The BOD33 configuration is synchronized
+        const BOD33_SYNC_READY = 1 << 11;
+    }
+}
+
+//==============================================================================
+//  Supc
+//==============================================================================
+
+/// Supply control of the `SYSCTRL` peripheral
+pub struct Supc {
+    sysctrl: SYSCTRL,
+}
+
+impl Supc {
+    /// Take the `SYSCTRL`
+    ///
+    /// The current configuration, loaded from the NVM user row at reset, is
+    /// left untouched.
+    #[inline]
+    pub fn new(sysctrl: SYSCTRL) -> Self {
+        Self { sysctrl }
+    }
+
+    /// Configure and enable the BOD33
+    ///
+    /// The BOD33 is disabled while it is reconfigured. This function blocks
+    /// until the BOD33 is ready.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the level is greater than 63.
+    #[inline]
+    pub fn enable_bod33(&mut self, config: Bod33Config) {
+        assert!(config.level < 64);
+        self.disable_bod33();
+        // SAFETY: The level was checked above
+        self.sysctrl.bod33.write(|w| unsafe {
+            w.level().bits(config.level);
+            w.hyst().bit(config.hysteresis);
+            w.action().variant(config.action);
+            w.psel().variant(config.prescaler);
+            w.mode().bit(config.sampling);
+            w.runstdby().bit(config.run_in_standby)
+        });
+        // The prescaler may only be changed while the sampling clock is
+        // disabled
+        if config.sampling {
+            self.sysctrl.bod33.modify(|_, w| w.cen().set_bit());
+            self.sync_bod33();
+        }
+        self.sysctrl.bod33.modify(|_, w| w.enable().set_bit());
+        self.sync_bod33();
+        while self.sysctrl.pclksr.read().bod33rdy().bit_is_clear() {}
+    }
+
+    /// Disable the BOD33
+    #[inline]
+    pub fn disable_bod33(&mut self) {
+        self.sysctrl
+            .bod33
+            .modify(|_, w| w.enable().clear_bit().cen().clear_bit());
+        self.sync_bod33();
+    }
+
+    /// Whether the supply voltage is currently below the BOD33 threshold
+    #[inline]
+    pub fn bod33_detected(&self) -> bool {
+        self.sysctrl.pclksr.read().bod33det().bit_is_set()
+    }
+
+    #[inline]
+    fn sync_bod33(&self) {
+        while self.sysctrl.pclksr.read().b33srdy().bit_is_clear() {}
+    }
+
+    /// Route the 1.0V bandgap reference to an ADC input channel
+    #[inline]
+    pub fn set_bandgap_output(&mut self, enabled: bool) {
+        self.sysctrl.vref.modify(|_, w| w.bgouten().bit(enabled));
+    }
+
+    /// Read the status flags
+    #[inline]
+    pub fn status(&self) -> Flags {
+        Flags::from_bits_truncate(self.sysctrl.pclksr.read().bits())
+    }
+
+    /// Read the interrupt flags
+    #[inline]
+    pub fn read_flags(&self) -> Flags {
+        Flags::from_bits_truncate(self.sysctrl.intflag.read().bits())
+    }
+
+    /// Clear interrupt flags
+    #[inline]
+    pub fn clear_flags(&mut self, flags: Flags) {
+        // SAFETY: Flags only holds valid bits of the register
+        self.sysctrl
+            .intflag
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Enable interrupts for the given flags
+    #[inline]
+    pub fn enable_interrupts(&mut self, flags: Flags) {
+        // SAFETY: Flags only holds valid bits of the register
+        self.sysctrl
+            .intenset
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Disable interrupts for the given flags
+    #[inline]
+    pub fn disable_interrupts(&mut self, flags: Flags) {
+        // SAFETY: Flags only holds valid bits of the register
+        self.sysctrl
+            .intenclr
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Return the underlying `SYSCTRL`
+    ///
+    /// The configuration is left untouched.
+    #[inline]
+    pub fn free(self) -> SYSCTRL {
+        self.sysctrl
+    }
+}
@@ -0,0 +1,1008 @@
+//! # Advanced Encryption Standard (AES)
+//!
+//! The AES peripheral encrypts and decrypts 128-bit blocks with 128, 192 or
+//! 256-bit keys, in the ECB, CBC, OFB, CFB and CTR modes of operation. It
+//! also accelerates the GHASH function of the Galois/Counter Mode (GCM).
+//!
+//! ```
+//! use atsamd_hal::aes::{Aes, Mode};
+//!
+//! let mut aes = Aes::new(&mut peripherals.MCLK, peripherals.AES, &[0x2B; 16]);
+//!
+//! let iv = [0; 16];
+//! let mut data = *b"sixteen byte msg";
+//! aes.encrypt(Mode::Cbc, &iv, &mut data).unwrap();
+//! aes.decrypt(Mode::Cbc, &iv, &mut data).unwrap();
+//!
+//! let tag = aes.gcm_encrypt(&[0; 12], b"header", &mut data).unwrap();
+//! aes.gcm_decrypt(&[0; 12], b"header", &mut data, &tag).unwrap();
+//! ```
+//!
+//! ## RustCrypto traits
+//!
+//! [`Aes`] implements the `BlockEncryptMut` and `BlockDecryptMut` traits of
+//! the `cipher` crate, with the raw block cipher, and the `AeadMutInPlace`
+//! trait of the `aead` crate, with AES-GCM and 96-bit nonces. [`Aes::ctr`]
+//! and [`Aes::ofb`] return a [`Keystream`] implementing `StreamCipher`.
+//!
+//! ## Countermeasures
+//!
+//! The peripheral can randomize its power consumption, to resist
+//! side-channel attacks. [`Aes::set_countermeasures`] enables each type of
+//! [`Countermeasures`], which are seeded by [`Aes::set_random_seed`].
+//!
+//! ## DMA
+//!
+//! With the `dma` feature, [`Aes::encrypt_with_dma`] and
+//! [`Aes::decrypt_with_dma`] process a whole buffer using two DMA channels,
+//! one feeding the input data and the other collecting the output data.
+
+use aead::consts::{U0, U1, U12, U16};
+use aead::{AeadCore, AeadMutInPlace, Nonce, Tag};
+use bitflags::bitflags;
+use cipher::errors::LoopError;
+use cipher::{Block, BlockCipher, BlockDecryptMut, BlockEncryptMut, StreamCipher};
+
+use crate::pac::{aes, AES, MCLK};
+use crate::typelevel::Sealed;
+
+#[cfg(feature = "dma")]
+use crate::dmac::{
+    self,
+    channel::{AnyChannel, Busy, Channel, Ready},
+    transfer::BufferPair,
+    Buffer, BurstLength, ChId, Transfer, TriggerAction, TriggerSource,
+};
+
+/// Size of the feedback of the CFB mode
+pub use aes::ctrla::CFBS_A as CfbSize;
+
+use aes::ctrla::{AESMODE_A as HwMode, KEYSIZE_A as KeySize};
+
+/// Size of a block, in bytes
+pub const BLOCK_SIZE: usize = 16;
+
+//==============================================================================
+//  Configuration
+//==============================================================================
+
+/// Mode of operation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// Electronic codebook
+    Ecb,
+    /// Cipher block chaining
+    Cbc,
+    /// Output feedback
+    Ofb,
+    /// Cipher feedback, with the given feedback size
+    Cfb(CfbSize),
+    /// Counter, incrementing the whole counter block as a big-endian
+    /// integer
+    Ctr,
+}
+
+impl Mode {
+    #[inline]
+    fn hw_mode(self) -> HwMode {
+        match self {
+            Mode::Ecb => HwMode::ECB,
+            Mode::Cbc => HwMode::CBC,
+            Mode::Ofb => HwMode::OFB,
+            Mode::Cfb(_) => HwMode::CFB,
+            Mode::Ctr => HwMode::COUNTER,
+        }
+    }
+
+    /// Number of bytes processed at once
+    #[inline]
+    fn segment_size(self) -> usize {
+        match self {
+            Mode::Cfb(CfbSize::_64BIT) => 8,
+            Mode::Cfb(CfbSize::_32BIT) => 4,
+            Mode::Cfb(CfbSize::_16BIT) => 2,
+            Mode::Cfb(CfbSize::_8BIT) => 1,
+            _ => BLOCK_SIZE,
+        }
+    }
+
+    /// Whether messages must be a multiple of the segment size
+    #[inline]
+    fn needs_padding(self) -> bool {
+        matches!(self, Mode::Ecb | Mode::Cbc | Mode::Cfb(_))
+    }
+}
+
+/// Direction of the operation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Encryption
+    Encrypt,
+    /// Decryption
+    Decrypt,
+}
+
+bitflags! {
+    /// Countermeasures against side-channel attacks
+    ///
+    /// The binary format of the underlying bits exactly matches the `CTYPE`
+    /// field of the `CTRLA` register.
+    pub struct Countermeasures: u8 {
+        /// Randomly add one cycle to data processing
+        const TYPE1 = 1 << 0;
+        /// Randomly add one cycle to data processing, other version
+        const TYPE2 = 1 << 1;
+        /// Add a random number of clock cycles to data processing
+        const TYPE3 = 1 << 2;
+        /// Add random spurious power consumption during data processing
+        const TYPE4 = 1 << 3;
+    }
+}
+
+/// AES key of 128, 192 or 256 bits
+///
+/// Implemented for `[u8; 16]`, `[u8; 24]` and `[u8; 32]`.
+pub trait Key: Sealed {
+    #[doc(hidden)]
+    const KEY_SIZE: KeySize;
+
+    #[doc(hidden)]
+    fn as_bytes(&self) -> &[u8];
+}
+
+macro_rules! key {
+    ($len:literal, $KeySize:ident) => {
+        impl Sealed for [u8; $len] {}
+
+        impl Key for [u8; $len] {
+            const KEY_SIZE: KeySize = KeySize::$KeySize;
+
+            #[inline]
+            fn as_bytes(&self) -> &[u8] {
+                self
+            }
+        }
+    };
+}
+
+key!(16, _128BIT);
+key!(24, _192BIT);
+key!(32, _256BIT);
+
+/// Errors reported by the AES driver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The data is not a multiple of the block or CFB feedback size
+    InvalidLength,
+    /// The GCM authentication tag does not match the data
+    TagMismatch,
+}
+
+/// Hardware configuration of the peripheral
+#[derive(Clone, Copy, PartialEq)]
+struct Config {
+    mode: HwMode,
+    cfb_size: CfbSize,
+    direction: Direction,
+    auto_start: bool,
+}
+
+//==============================================================================
+//  Aes
+//==============================================================================
+
+/// AES peripheral
+pub struct Aes {
+    aes: AES,
+    key: [u32; 8],
+    key_size: KeySize,
+    countermeasures: Countermeasures,
+    config: Option<Config>,
+}
+
+impl Aes {
+    /// Enable the AES peripheral, with a key of 16, 24 or 32 bytes
+    #[inline]
+    pub fn new<K: Key>(mclk: &mut MCLK, aes: AES, key: &K) -> Self {
+        mclk.apbcmask.modify(|_, w| w.aes_().set_bit());
+        aes.ctrla.write(|w| w.swrst().set_bit());
+        while aes.ctrla.read().swrst().bit_is_set() {}
+        let mut aes = Self {
+            aes,
+            key: [0; 8],
+            key_size: K::KEY_SIZE,
+            countermeasures: Countermeasures::empty(),
+            config: None,
+        };
+        aes.set_key(key);
+        aes
+    }
+
+    /// Change the key, of 16, 24 or 32 bytes
+    #[inline]
+    pub fn set_key<K: Key>(&mut self, key: &K) {
+        self.key = [0; 8];
+        for (word, bytes) in self.key.iter_mut().zip(key.as_bytes().chunks(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        self.key_size = K::KEY_SIZE;
+        self.config = None;
+    }
+
+    /// Enable the given countermeasures against side-channel attacks
+    #[inline]
+    pub fn set_countermeasures(&mut self, countermeasures: Countermeasures) {
+        self.countermeasures = countermeasures;
+        self.config = None;
+    }
+
+    /// Seed the random number generator of the countermeasures
+    #[inline]
+    pub fn set_random_seed(&mut self, seed: u32) {
+        // SAFETY: Any seed is valid
+        self.aes.randseed.write(|w| unsafe { w.bits(seed) });
+    }
+
+    /// Encrypt `data` in place
+    ///
+    /// `iv` is ignored in the ECB mode. In the ECB, CBC and CFB modes, the
+    /// length of `data` must be a multiple of the block or feedback size.
+    #[inline]
+    pub fn encrypt(&mut self, mode: Mode, iv: &[u8; 16], data: &mut [u8]) -> Result<(), Error> {
+        self.crypt(mode, Direction::Encrypt, iv, BLOCK_SIZE, data)
+    }
+
+    /// Decrypt `data` in place
+    ///
+    /// `iv` is ignored in the ECB mode. In the ECB, CBC and CFB modes, the
+    /// length of `data` must be a multiple of the block or feedback size.
+    #[inline]
+    pub fn decrypt(&mut self, mode: Mode, iv: &[u8; 16], data: &mut [u8]) -> Result<(), Error> {
+        self.crypt(mode, Direction::Decrypt, iv, BLOCK_SIZE, data)
+    }
+
+    /// Encrypt `data` in place with AES-GCM, and return the authentication
+    /// tag
+    ///
+    /// `aad` is authenticated, but not encrypted.
+    #[inline]
+    pub fn gcm_encrypt(
+        &mut self,
+        nonce: &[u8; 12],
+        aad: &[u8],
+        data: &mut [u8],
+    ) -> Result<[u8; 16], Error> {
+        let gcm = self.gcm_start(nonce);
+        self.crypt(Mode::Ctr, Direction::Encrypt, &gcm.counter, 4, data)?;
+        Ok(self.gcm_tag(&gcm, aad, data))
+    }
+
+    /// Authenticate `data` and `aad` against `tag`, then decrypt `data` in
+    /// place with AES-GCM
+    ///
+    /// `data` is left untouched if the authentication fails.
+    #[inline]
+    pub fn gcm_decrypt(
+        &mut self,
+        nonce: &[u8; 12],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8; 16],
+    ) -> Result<(), Error> {
+        let gcm = self.gcm_start(nonce);
+        let expected = self.gcm_tag(&gcm, aad, data);
+        let diff = expected
+            .iter()
+            .zip(tag.iter())
+            .fold(0, |acc, (a, b)| acc | (a ^ b));
+        if diff != 0 {
+            return Err(Error::TagMismatch);
+        }
+        self.crypt(Mode::Ctr, Direction::Decrypt, &gcm.counter, 4, data)
+    }
+
+    /// Return a CTR mode [`Keystream`], starting with the counter block `iv`
+    #[inline]
+    pub fn ctr(&mut self, iv: &[u8; 16]) -> Keystream<'_> {
+        Keystream::new(self, Mode::Ctr, iv)
+    }
+
+    /// Return an OFB mode [`Keystream`], starting with the initialization
+    /// vector `iv`
+    #[inline]
+    pub fn ofb(&mut self, iv: &[u8; 16]) -> Keystream<'_> {
+        Keystream::new(self, Mode::Ofb, iv)
+    }
+
+    /// Disable the AES peripheral and return it
+    #[inline]
+    pub fn free(self, mclk: &mut MCLK) -> AES {
+        self.aes.ctrla.write(|w| w.swrst().set_bit());
+        while self.aes.ctrla.read().swrst().bit_is_set() {}
+        mclk.apbcmask.modify(|_, w| w.aes_().clear_bit());
+        self.aes
+    }
+
+    //==========================================================================
+    //  Internals
+    //==========================================================================
+
+    /// Configure the peripheral, unless it already is
+    fn configure(&mut self, config: Config) {
+        let key_size = self.key_size;
+        if self.config == Some(config) {
+            return;
+        }
+
+        let countermeasures = self.countermeasures.bits();
+        self.aes.ctrla.modify(|_, w| w.enable().clear_bit());
+        self.aes.ctrla.write(|w| {
+            w.aesmode().variant(config.mode);
+            w.cfbs().variant(config.cfb_size);
+            w.keysize().variant(key_size);
+            w.cipher().bit(config.direction == Direction::Encrypt);
+            w.startmode().bit(config.auto_start);
+            // SAFETY: Countermeasures is a 4-bit value
+            unsafe { w.ctype().bits(countermeasures) }
+        });
+        self.aes.ctrla.modify(|_, w| w.enable().set_bit());
+
+        let words = match key_size {
+            KeySize::_128BIT => 4,
+            KeySize::_192BIT => 6,
+            KeySize::_256BIT => 8,
+        };
+        for (reg, &word) in self.aes.keyword.iter().zip(self.key[..words].iter()) {
+            // SAFETY: Any key word is valid
+            reg.write(|w| unsafe { w.bits(word) });
+        }
+        self.config = Some(config);
+    }
+
+    /// Configure the peripheral for a mode of operation
+    #[inline]
+    fn configure_mode(&mut self, mode: Mode, direction: Direction, auto_start: bool) {
+        // The OFB and CTR modes encrypt the IV to produce the keystream in
+        // both directions
+        let direction = match mode {
+            Mode::Ofb | Mode::Ctr => Direction::Encrypt,
+            _ => direction,
+        };
+        let cfb_size = match mode {
+            Mode::Cfb(size) => size,
+            _ => CfbSize::_128BIT,
+        };
+        self.configure(Config {
+            mode: mode.hw_mode(),
+            cfb_size,
+            direction,
+            auto_start,
+        });
+    }
+
+    #[inline]
+    fn write_iv(&mut self, iv: &[u8; 16]) {
+        for (reg, word) in self.aes.intvectv.iter().zip(words(iv).iter()) {
+            // SAFETY: Any IV is valid
+            reg.write(|w| unsafe { w.bits(*word) });
+        }
+    }
+
+    /// Write `count` words of `block` to the input buffer
+    #[inline]
+    fn write_input(&mut self, block: &[u8; 16], count: usize) {
+        // SAFETY: 0 is a valid word index
+        self.aes
+            .databufptr
+            .write(|w| unsafe { w.indataptr().bits(0) });
+        for &word in words(block)[..count].iter() {
+            // SAFETY: Any data is valid
+            self.aes.indata.write(|w| unsafe { w.bits(word) });
+        }
+    }
+
+    /// Encrypt or decrypt a single block in place, in the ECB mode
+    #[inline]
+    fn crypt_block(&mut self, direction: Direction, block: &mut Block<Self>) {
+        let mut data = [0; 16];
+        data.copy_from_slice(block);
+        self.configure_mode(Mode::Ecb, direction, false);
+        self.process(&mut data, 4, false);
+        block.copy_from_slice(&data);
+    }
+
+    /// Process the first `count` words of `block`, in place
+    fn process(&mut self, block: &mut [u8; 16], count: usize, new_message: bool) {
+        self.write_input(block, count);
+        self.aes
+            .ctrlb
+            .write(|w| w.start().set_bit().newmsg().bit(new_message));
+        while self.aes.intflag.read().enccmp().bit_is_clear() {}
+
+        // SAFETY: 0 is a valid word index
+        self.aes
+            .databufptr
+            .write(|w| unsafe { w.indataptr().bits(0) });
+        let mut output = [0; 4];
+        for word in output[..count].iter_mut() {
+            *word = self.aes.indata.read().bits();
+        }
+        self.aes.intflag.write(|w| w.enccmp().set_bit());
+        *block = bytes(&output);
+    }
+
+    /// Process `data` in place
+    ///
+    /// In the CTR mode, the rightmost `counter_width` bytes of the counter
+    /// block are incremented.
+    fn crypt(
+        &mut self,
+        mode: Mode,
+        direction: Direction,
+        iv: &[u8; 16],
+        counter_width: usize,
+        data: &mut [u8],
+    ) -> Result<(), Error> {
+        let segment = mode.segment_size();
+        if mode.needs_padding() && data.len() & (segment - 1) != 0 {
+            return Err(Error::InvalidLength);
+        }
+        self.configure_mode(mode, direction, false);
+        if mode != Mode::Ecb {
+            self.write_iv(iv);
+        }
+
+        let count = (segment - 1) / 4 + 1;
+        let mut counter = Counter::new(mode, iv, counter_width);
+        let mut new_message = mode != Mode::Ecb;
+        for chunk in data.chunks_mut(segment) {
+            let mut block = [0; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.process(&mut block, count, new_message);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+            new_message = self.advance(&mut counter);
+        }
+        Ok(())
+    }
+
+    /// Advance the CTR mode counter to the next block
+    ///
+    /// The hardware only increments the low 16 bits of the counter block, so
+    /// the counter block is written to the IV registers again when they wrap
+    /// around. Returns whether the next block must start a new message.
+    #[inline]
+    fn advance(&mut self, counter: &mut Option<Counter>) -> bool {
+        match counter.as_mut().and_then(Counter::next) {
+            Some(iv) => {
+                self.write_iv(&iv);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //==========================================================================
+    //  GCM
+    //==========================================================================
+
+    /// Compute the values derived from the key and nonce of a GCM message
+    fn gcm_start(&mut self, nonce: &[u8; 12]) -> GcmState {
+        let mut hash_key = [0; 16];
+        self.configure_mode(Mode::Ecb, Direction::Encrypt, false);
+        self.process(&mut hash_key, 4, false);
+
+        let mut j0 = [0; 16];
+        j0[..12].copy_from_slice(nonce);
+        j0[15] = 1;
+        let mut tag_mask = j0;
+        self.process(&mut tag_mask, 4, false);
+
+        GcmState {
+            hash_key,
+            tag_mask,
+            counter: inc32(j0),
+        }
+    }
+
+    /// Compute the GCM tag of a message
+    fn gcm_tag(&mut self, gcm: &GcmState, aad: &[u8], ciphertext: &[u8]) -> [u8; 16] {
+        self.configure(Config {
+            mode: HwMode::GCM,
+            cfb_size: CfbSize::_128BIT,
+            direction: Direction::Encrypt,
+            auto_start: false,
+        });
+        for (reg, word) in self.aes.hashkey.iter().zip(words(&gcm.hash_key).iter()) {
+            // SAFETY: Any hash key is valid
+            reg.write(|w| unsafe { w.bits(*word) });
+        }
+        for reg in self.aes.ghash.iter() {
+            // SAFETY: The GHASH starts from zero
+            reg.write(|w| unsafe { w.bits(0) });
+        }
+
+        for chunk in aad.chunks(16).chain(ciphertext.chunks(16)) {
+            let mut block = [0; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.gf_mul(&block);
+        }
+        self.gf_mul(&length_block(aad.len(), ciphertext.len()));
+
+        let mut ghash = [0; 4];
+        for (word, reg) in ghash.iter_mut().zip(self.aes.ghash.iter()) {
+            *word = reg.read().bits();
+        }
+        let mut tag = bytes(&ghash);
+        for (t, m) in tag.iter_mut().zip(gcm.tag_mask.iter()) {
+            *t ^= m;
+        }
+        tag
+    }
+
+    /// Update the GHASH with `block`
+    #[inline]
+    fn gf_mul(&mut self, block: &[u8; 16]) {
+        self.write_input(block, 4);
+        self.aes.ctrlb.write(|w| w.gfmul().set_bit());
+        while self.aes.intflag.read().gfmcmp().bit_is_clear() {}
+        self.aes.intflag.write(|w| w.gfmcmp().set_bit());
+    }
+}
+
+/// Values derived from the key and nonce of a GCM message
+struct GcmState {
+    /// `H`, the encrypted zero block
+    hash_key: [u8; 16],
+    /// Encrypted initial counter block, masking the GHASH
+    tag_mask: [u8; 16],
+    /// First counter block of the data
+    counter: [u8; 16],
+}
+
+/// Convert a block to the words of the data registers
+#[inline]
+fn words(block: &[u8; 16]) -> [u32; 4] {
+    let mut words = [0; 4];
+    for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    words
+}
+
+/// Convert the words of the data registers to a block
+#[inline]
+fn bytes(words: &[u32; 4]) -> [u8; 16] {
+    let mut block = [0; 16];
+    for (bytes, word) in block.chunks_mut(4).zip(words.iter()) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    block
+}
+
+/// Increment the rightmost 32 bits of a counter block
+#[inline]
+fn inc32(block: [u8; 16]) -> [u8; 16] {
+    increment(block, 4)
+}
+
+/// Increment the rightmost `width` bytes of a counter block, as a big-endian
+/// integer
+#[inline]
+fn increment(mut block: [u8; 16], width: usize) -> [u8; 16] {
+    for byte in block[BLOCK_SIZE - width..].iter_mut().rev() {
+        *byte = byte.wrapping_add(1);
+        if *byte != 0 {
+            break;
+        }
+    }
+    block
+}
+
+/// Software copy of the CTR mode counter block
+#[derive(Clone, Copy)]
+struct Counter {
+    block: [u8; 16],
+    width: usize,
+}
+
+impl Counter {
+    /// Counter starting with the block `iv`, incrementing its rightmost
+    /// `width` bytes, or `None` outside of the CTR mode
+    #[inline]
+    fn new(mode: Mode, iv: &[u8; 16], width: usize) -> Option<Self> {
+        match mode {
+            Mode::Ctr => Some(Self { block: *iv, width }),
+            _ => None,
+        }
+    }
+
+    /// Advance to the next block, and return it if the low 16 bits of the
+    /// hardware counter wrapped around
+    #[inline]
+    fn next(&mut self) -> Option<[u8; 16]> {
+        self.block = increment(self.block, self.width);
+        if self.block[14..] == [0, 0] {
+            Some(self.block)
+        } else {
+            None
+        }
+    }
+}
+
+/// Last GHASH block, holding the bit lengths of the AAD and ciphertext
+#[inline]
+fn length_block(aad_len: usize, data_len: usize) -> [u8; 16] {
+    let mut block = [0; 16];
+    block[..8].copy_from_slice(&(aad_len as u64 * 8).to_be_bytes());
+    block[8..].copy_from_slice(&(data_len as u64 * 8).to_be_bytes());
+    block
+}
+
+//==============================================================================
+//  Keystream
+//==============================================================================
+
+/// CTR or OFB mode keystream, applied to data of any length
+///
+/// The keystream keeps the unused bytes of its last block, so that a message
+/// can be processed in pieces.
+pub struct Keystream<'a> {
+    aes: &'a mut Aes,
+    block: [u8; 16],
+    pos: usize,
+    counter: Option<Counter>,
+}
+
+impl<'a> Keystream<'a> {
+    #[inline]
+    fn new(aes: &'a mut Aes, mode: Mode, iv: &[u8; 16]) -> Self {
+        aes.configure_mode(mode, Direction::Encrypt, false);
+        aes.write_iv(iv);
+        let mut block = [0; 16];
+        aes.process(&mut block, 4, true);
+        Self {
+            aes,
+            block,
+            pos: 0,
+            counter: Counter::new(mode, iv, BLOCK_SIZE),
+        }
+    }
+
+    /// XOR the keystream with `data`
+    #[inline]
+    pub fn apply(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            if self.pos == BLOCK_SIZE {
+                let new_message = self.aes.advance(&mut self.counter);
+                self.block = [0; 16];
+                self.aes.process(&mut self.block, 4, new_message);
+                self.pos = 0;
+            }
+            *byte ^= self.block[self.pos];
+            self.pos += 1;
+        }
+    }
+}
+
+impl StreamCipher for Keystream<'_> {
+    #[inline]
+    fn try_apply_keystream(&mut self, data: &mut [u8]) -> Result<(), LoopError> {
+        self.apply(data);
+        Ok(())
+    }
+}
+
+//==============================================================================
+//  RustCrypto traits
+//==============================================================================
+
+impl BlockCipher for Aes {
+    type BlockSize = U16;
+    type ParBlocks = U1;
+}
+
+impl BlockEncryptMut for Aes {
+    #[inline]
+    fn encrypt_block_mut(&mut self, block: &mut Block<Self>) {
+        self.crypt_block(Direction::Encrypt, block);
+    }
+}
+
+impl BlockDecryptMut for Aes {
+    #[inline]
+    fn decrypt_block_mut(&mut self, block: &mut Block<Self>) {
+        self.crypt_block(Direction::Decrypt, block);
+    }
+}
+
+impl AeadCore for Aes {
+    type NonceSize = U12;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
+}
+
+impl AeadMutInPlace for Aes {
+    #[inline]
+    fn encrypt_in_place_detached(
+        &mut self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> aead::Result<Tag<Self>> {
+        let mut iv = [0; 12];
+        iv.copy_from_slice(nonce);
+        let tag = self
+            .gcm_encrypt(&iv, associated_data, buffer)
+            .map_err(|_| aead::Error)?;
+        Ok(tag.into())
+    }
+
+    #[inline]
+    fn decrypt_in_place_detached(
+        &mut self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<Self>,
+    ) -> aead::Result<()> {
+        let mut iv = [0; 12];
+        iv.copy_from_slice(nonce);
+        let mut expected = [0; 16];
+        expected.copy_from_slice(tag);
+        self.gcm_decrypt(&iv, associated_data, buffer, &expected)
+            .map_err(|_| aead::Error)
+    }
+}
+
+//==============================================================================
+//  DMA
+//==============================================================================
+
+/// Input data register, written by the DMAC
+#[cfg(feature = "dma")]
+pub struct InputData(());
+
+/// Output data register, read by the DMAC
+#[cfg(feature = "dma")]
+pub struct OutputData(());
+
+#[cfg(feature = "dma")]
+macro_rules! data_buffer {
+    ($Buffer:ident) => {
+        unsafe impl Buffer for $Buffer {
+            type Beat = u32;
+
+            #[inline]
+            fn dma_ptr(&mut self) -> *mut Self::Beat {
+                // SAFETY: The DMAC only accesses the data register, which is
+                // owned by the ongoing transfer
+                unsafe { &*AES::ptr() }.indata.as_ptr()
+            }
+
+            #[inline]
+            fn incrementing(&self) -> bool {
+                false
+            }
+
+            #[inline]
+            fn buffer_len(&self) -> usize {
+                1
+            }
+        }
+    };
+}
+
+#[cfg(feature = "dma")]
+data_buffer!(InputData);
+#[cfg(feature = "dma")]
+data_buffer!(OutputData);
+
+/// Encryption or decryption of a buffer by the DMAC
+#[cfg(feature = "dma")]
+pub struct DmaTransfer<W: ChId, R: ChId, S, D>
+where
+    S: Buffer<Beat = u32> + 'static,
+    D: Buffer<Beat = u32> + 'static,
+{
+    aes: Aes,
+    write: Transfer<Channel<W, Busy>, BufferPair<S, InputData>>,
+    read: Transfer<Channel<R, Busy>, BufferPair<OutputData, D>>,
+}
+
+#[cfg(feature = "dma")]
+impl Aes {
+    /// Encrypt `source` into `destination` using two DMA channels
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffers have different lengths or are not made of whole
+    /// blocks, or in the CFB mode with a feedback size other than 128 bits.
+    /// In the CTR mode, also panics if the low 16 bits of the counter block
+    /// wrap around, which the hardware can't carry.
+    #[inline]
+    pub fn encrypt_with_dma<Wr, Rd, S, D>(
+        self,
+        mode: Mode,
+        iv: &[u8; 16],
+        source: S,
+        destination: D,
+        write_channel: Wr,
+        read_channel: Rd,
+    ) -> DmaTransfer<Wr::Id, Rd::Id, S, D>
+    where
+        Wr: AnyChannel<Status = Ready>,
+        Rd: AnyChannel<Status = Ready>,
+        S: Buffer<Beat = u32> + 'static,
+        D: Buffer<Beat = u32> + 'static,
+    {
+        self.start_dma(
+            mode,
+            Direction::Encrypt,
+            iv,
+            (source, destination),
+            (write_channel, read_channel),
+        )
+    }
+
+    /// Decrypt `source` into `destination` using two DMA channels
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffers have different lengths or are not made of whole
+    /// blocks, or in the CFB mode with a feedback size other than 128 bits.
+    /// In the CTR mode, also panics if the low 16 bits of the counter block
+    /// wrap around, which the hardware can't carry.
+    #[inline]
+    pub fn decrypt_with_dma<Wr, Rd, S, D>(
+        self,
+        mode: Mode,
+        iv: &[u8; 16],
+        source: S,
+        destination: D,
+        write_channel: Wr,
+        read_channel: Rd,
+    ) -> DmaTransfer<Wr::Id, Rd::Id, S, D>
+    where
+        Wr: AnyChannel<Status = Ready>,
+        Rd: AnyChannel<Status = Ready>,
+        S: Buffer<Beat = u32> + 'static,
+        D: Buffer<Beat = u32> + 'static,
+    {
+        self.start_dma(
+            mode,
+            Direction::Decrypt,
+            iv,
+            (source, destination),
+            (write_channel, read_channel),
+        )
+    }
+
+    fn start_dma<Wr, Rd, S, D>(
+        mut self,
+        mode: Mode,
+        direction: Direction,
+        iv: &[u8; 16],
+        (source, destination): (S, D),
+        (mut write_channel, mut read_channel): (Wr, Rd),
+    ) -> DmaTransfer<Wr::Id, Rd::Id, S, D>
+    where
+        Wr: AnyChannel<Status = Ready>,
+        Rd: AnyChannel<Status = Ready>,
+        S: Buffer<Beat = u32> + 'static,
+        D: Buffer<Beat = u32> + 'static,
+    {
+        assert_eq!(mode.segment_size(), BLOCK_SIZE);
+        assert_eq!(source.buffer_len(), destination.buffer_len());
+        assert_eq!(source.buffer_len() & 3, 0);
+        if mode == Mode::Ctr {
+            let start = u16::from_be_bytes([iv[14], iv[15]]) as usize;
+            assert!(start + source.buffer_len() / 4 <= 0x1_0000);
+        }
+
+        self.configure_mode(mode, direction, true);
+        if mode != Mode::Ecb {
+            self.write_iv(iv);
+            self.aes.ctrlb.write(|w| w.newmsg().set_bit());
+        }
+        // SAFETY: 0 is a valid word index
+        self.aes
+            .databufptr
+            .write(|w| unsafe { w.indataptr().bits(0) });
+
+        write_channel.as_mut().burst_length(BurstLength::_4BEAT);
+        read_channel.as_mut().burst_length(BurstLength::_4BEAT);
+
+        // SAFETY: The data register is not a memory buffer, and the user
+        // buffers are 'static. Both buffer pairs only differ in length by
+        // the data register being of length 1.
+        let read = unsafe {
+            dmac::Transfer::new_unchecked(read_channel, OutputData(()), destination, false)
+        }
+        .begin(TriggerSource::AES_RD, TriggerAction::BURST);
+        // SAFETY: See above
+        let write =
+            unsafe { dmac::Transfer::new_unchecked(write_channel, source, InputData(()), false) }
+                .begin(TriggerSource::AES_WR, TriggerAction::BURST);
+
+        DmaTransfer {
+            aes: self,
+            write,
+            read,
+        }
+    }
+}
+
+#[cfg(feature = "dma")]
+impl<W: ChId, R: ChId, S, D> DmaTransfer<W, R, S, D>
+where
+    S: Buffer<Beat = u32> + 'static,
+    D: Buffer<Beat = u32> + 'static,
+{
+    /// Check whether the whole buffer was processed
+    #[inline]
+    pub fn is_complete(&mut self) -> bool {
+        self.write.complete() && self.read.complete()
+    }
+
+    /// Wait for the whole buffer to be processed, and return the peripheral,
+    /// buffers and channels
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    pub fn wait(self) -> (Aes, S, D, Channel<W, Ready>, Channel<R, Ready>) {
+        let (write_channel, source, _) = self.write.wait();
+        let (read_channel, _, destination) = self.read.wait();
+        let mut aes = self.aes;
+        aes.config = None;
+        (aes, source, destination, write_channel, read_channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bytes, inc32, increment, length_block, words, Counter, Mode};
+
+    #[test]
+    fn gcm_blocks() {
+        let mut j0 = [0; 16];
+        j0[15] = 1;
+        assert_eq!(inc32(j0)[12..], [0, 0, 0, 2]);
+        j0[12..].copy_from_slice(&[0, 0, 0xFF, 0xFF]);
+        assert_eq!(inc32(j0)[12..], [0, 1, 0, 0]);
+        j0[12..].copy_from_slice(&[0xFF; 4]);
+        assert_eq!(inc32(j0), [0; 16]);
+
+        let block = length_block(20, 60);
+        assert_eq!(block[..8], 160u64.to_be_bytes());
+        assert_eq!(block[8..], 480u64.to_be_bytes());
+
+        let data = *b"0123456789abcdef";
+        assert_eq!(words(&data)[0], 0x3332_3130);
+        assert_eq!(bytes(&words(&data)), data);
+    }
+
+    #[test]
+    fn ctr_counter() {
+        assert_eq!(increment([0xFF; 16], 16), [0; 16]);
+        let mut block = [0; 16];
+        block[15] = 0xFF;
+        assert_eq!(increment(block, 16)[14..], [1, 0]);
+
+        assert!(Counter::new(Mode::Ofb, &[0; 16], 16).is_none());
+
+        // The IV is written again when the low 16 bits wrap around
+        let mut iv = [0; 16];
+        iv[13..].copy_from_slice(&[0x12, 0xFF, 0xFE]);
+        let mut counter = Counter::new(Mode::Ctr, &iv, 16).unwrap();
+        assert_eq!(counter.next(), None);
+        let reseed = counter.next().unwrap();
+        assert_eq!(reseed[12..], [0, 0x13, 0, 0]);
+        for _ in 0..0xFFFF {
+            assert_eq!(counter.next(), None);
+        }
+        assert_eq!(counter.next().unwrap()[12..], [0, 0x14, 0, 0]);
+
+        // GCM only increments the rightmost 32 bits
+        let mut counter = Counter::new(Mode::Ctr, &[0xFF; 16], 4).unwrap();
+        assert_eq!(counter.next().unwrap()[11..], [0xFF, 0, 0, 0, 0]);
+    }
+}
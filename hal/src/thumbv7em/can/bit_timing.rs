@@ -0,0 +1,229 @@
+//! Bit timing calculation
+//!
+//! The M_CAN peripheral divides each bit into a number of time quanta (tq).
+//! A bit is made of one synchronization quantum, followed by `seg1` quanta
+//! before the sample point, and `seg2` quanta after it. The length of a time
+//! quantum is set by the baud rate prescaler, relative to the GCLK frequency
+//! feeding the peripheral.
+//!
+//! [`BitTiming::new`] searches for a prescaler that yields the requested
+//! bit rate *exactly*, preferring the smallest prescaler (i.e. the largest
+//! number of time quanta per bit), and places the sample point as close as
+//! possible to the requested position.
+
+use super::Error;
+use crate::time::Hertz;
+
+/// Default sample point, in tenths of a percent (87.5 %), as recommended by
+/// CiA 301 for nominal bit rates
+pub const DEFAULT_SAMPLE_POINT: u16 = 875;
+
+/// Default sample point for the data phase of CAN FD frames, in tenths of a
+/// percent (75 %)
+pub const DEFAULT_DATA_SAMPLE_POINT: u16 = 750;
+
+/// Allowed ranges for each field of a bit timing register
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Limits {
+    prescaler: (u16, u16),
+    seg1: (u16, u16),
+    seg2: (u16, u16),
+    sjw_max: u16,
+}
+
+/// Limits of the `NBTP` register, used for the arbitration phase
+pub(super) const NOMINAL: Limits = Limits {
+    prescaler: (1, 512),
+    seg1: (2, 256),
+    seg2: (2, 128),
+    sjw_max: 128,
+};
+
+/// Limits of the `DBTP` register, used for the data phase of CAN FD frames
+/// with bit rate switching
+pub(super) const DATA: Limits = Limits {
+    prescaler: (1, 32),
+    seg1: (1, 32),
+    seg2: (1, 16),
+    sjw_max: 16,
+};
+
+/// Bit timing parameters, expressed in time quanta
+///
+/// The values stored here are the *actual* values, not the register encoding
+/// (which is usually the value minus one).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitTiming {
+    /// Baud rate prescaler, i.e. the number of GCLK cycles per time quantum
+    pub prescaler: u16,
+    /// Time segment before the sample point, excluding the synchronization
+    /// segment
+    pub seg1: u16,
+    /// Time segment after the sample point
+    pub seg2: u16,
+    /// (Re)synchronization jump width
+    pub sjw: u16,
+}
+
+impl BitTiming {
+    /// Calculate the nominal (arbitration phase) bit timing for the given
+    /// GCLK frequency and bit rate, with the sample point at 87.5 %
+    #[inline]
+    pub fn new(clock: impl Into<Hertz>, bitrate: impl Into<Hertz>) -> Result<Self, Error> {
+        Self::with_sample_point(clock, bitrate, DEFAULT_SAMPLE_POINT)
+    }
+
+    /// Calculate the nominal (arbitration phase) bit timing for the given
+    /// GCLK frequency, bit rate and sample point, in tenths of a percent
+    #[inline]
+    pub fn with_sample_point(
+        clock: impl Into<Hertz>,
+        bitrate: impl Into<Hertz>,
+        sample_point: u16,
+    ) -> Result<Self, Error> {
+        calculate(clock.into(), bitrate.into(), sample_point, &NOMINAL)
+    }
+
+    /// Calculate the data phase bit timing for the given GCLK frequency and
+    /// bit rate, with the sample point at 75 %
+    #[inline]
+    pub fn new_data(clock: impl Into<Hertz>, bitrate: impl Into<Hertz>) -> Result<Self, Error> {
+        Self::data_with_sample_point(clock, bitrate, DEFAULT_DATA_SAMPLE_POINT)
+    }
+
+    /// Calculate the data phase bit timing for the given GCLK frequency, bit
+    /// rate and sample point, in tenths of a percent
+    #[inline]
+    pub fn data_with_sample_point(
+        clock: impl Into<Hertz>,
+        bitrate: impl Into<Hertz>,
+        sample_point: u16,
+    ) -> Result<Self, Error> {
+        calculate(clock.into(), bitrate.into(), sample_point, &DATA)
+    }
+
+    /// Number of time quanta per bit
+    #[inline]
+    pub fn quanta(&self) -> u32 {
+        1 + self.seg1 as u32 + self.seg2 as u32
+    }
+
+    /// Actual bit rate obtained with these parameters
+    #[inline]
+    pub fn bitrate(&self, clock: impl Into<Hertz>) -> Hertz {
+        Hertz(clock.into().0 / (self.prescaler as u32 * self.quanta()))
+    }
+
+    /// Actual sample point obtained with these parameters, in tenths of a
+    /// percent
+    #[inline]
+    pub fn sample_point(&self) -> u16 {
+        ((1 + self.seg1 as u32) * 1000 / self.quanta()) as u16
+    }
+
+    /// Check that every field fits the given register limits
+    #[inline]
+    pub(super) fn validate(&self, limits: &Limits) -> Result<(), Error> {
+        let in_range = |v: u16, (min, max): (u16, u16)| v >= min && v <= max;
+        if in_range(self.prescaler, limits.prescaler)
+            && in_range(self.seg1, limits.seg1)
+            && in_range(self.seg2, limits.seg2)
+            && self.sjw >= 1
+            && self.sjw <= limits.sjw_max
+            && self.sjw <= self.seg2
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidBitTiming)
+        }
+    }
+}
+
+fn calculate(
+    clock: Hertz,
+    bitrate: Hertz,
+    sample_point: u16,
+    limits: &Limits,
+) -> Result<BitTiming, Error> {
+    if bitrate.0 == 0 || sample_point == 0 || sample_point >= 1000 {
+        return Err(Error::InvalidBitTiming);
+    }
+
+    let clock = clock.0 as u64;
+    let bitrate = bitrate.0 as u64;
+    let min_quanta = 1 + limits.seg1.0 as u64 + limits.seg2.0 as u64;
+    let max_quanta = 1 + limits.seg1.1 as u64 + limits.seg2.1 as u64;
+
+    // Iterate from the longest bit (in time quanta) to the shortest, so that
+    // the first match uses the smallest prescaler, giving the best resolution
+    for quanta in (min_quanta..=max_quanta).rev() {
+        let cycles = bitrate * quanta;
+        let prescaler = clock / cycles;
+        if prescaler * cycles != clock {
+            continue;
+        }
+        if prescaler < limits.prescaler.0 as u64 || prescaler > limits.prescaler.1 as u64 {
+            continue;
+        }
+
+        // Round seg2 to the nearest time quantum, then clamp it to the range
+        // allowed by both fields
+        let seg2 = (quanta * (1000 - sample_point as u64) + 500) / 1000;
+        let seg2 = seg2
+            .max(limits.seg2.0 as u64)
+            .min(limits.seg2.1 as u64)
+            .max((quanta - 1).saturating_sub(limits.seg1.1 as u64));
+        let seg1 = quanta - 1 - seg2;
+        if seg1 < limits.seg1.0 as u64 || seg1 > limits.seg1.1 as u64 {
+            continue;
+        }
+
+        let timing = BitTiming {
+            prescaler: prescaler as u16,
+            seg1: seg1 as u16,
+            seg2: seg2 as u16,
+            sjw: (seg2 as u16).min(limits.sjw_max),
+        };
+        timing.validate(limits)?;
+        return Ok(timing);
+    }
+
+    Err(Error::InvalidBitTiming)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::U32Ext;
+
+    #[test]
+    fn nominal_500k_from_48mhz() {
+        let timing = BitTiming::new(48.mhz(), 500.khz()).unwrap();
+        assert_eq!(timing.bitrate(48.mhz()), 500.khz().into());
+        assert_eq!(timing.prescaler, 1);
+        assert_eq!(timing.quanta(), 96);
+        assert_eq!(timing.sample_point(), 875);
+    }
+
+    #[test]
+    fn nominal_1m_from_120mhz() {
+        let timing = BitTiming::new(120.mhz(), 1.mhz()).unwrap();
+        assert_eq!(timing.bitrate(120.mhz()), 1.mhz().into());
+        assert!(timing.validate(&NOMINAL).is_ok());
+    }
+
+    #[test]
+    fn data_4m_from_48mhz() {
+        let timing = BitTiming::new_data(48.mhz(), 4.mhz()).unwrap();
+        assert_eq!(timing.bitrate(48.mhz()), 4.mhz().into());
+        assert_eq!(timing.quanta(), 12);
+        assert_eq!(timing.sample_point(), 750);
+        assert!(timing.validate(&DATA).is_ok());
+    }
+
+    #[test]
+    fn inexact_bitrate_is_rejected() {
+        assert!(BitTiming::new(48.mhz(), 7.khz()).is_err());
+        assert!(BitTiming::new_data(48.mhz(), 11.mhz()).is_err());
+    }
+}
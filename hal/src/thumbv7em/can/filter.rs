@@ -0,0 +1,134 @@
+//! Acceptance filters
+//!
+//! Received frames are matched against the list of standard (11-bit) or
+//! extended (29-bit) ID filter elements stored in the message RAM. The first
+//! matching filter determines what happens to the frame. Frames matching no
+//! filter are handled according to the global filter configuration, see
+//! [`NonMatching`].
+
+use super::frame::{ExtendedId, StandardId};
+
+/// Action taken by the M_CAN when a received frame matches a filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterAction {
+    /// Store the frame in Rx FIFO 0
+    StoreFifo0 = 1,
+    /// Store the frame in Rx FIFO 1
+    StoreFifo1 = 2,
+    /// Reject the frame
+    Reject = 3,
+    /// Flag the frame as high priority, without storing it
+    SetPriority = 4,
+    /// Flag the frame as high priority and store it in Rx FIFO 0
+    SetPriorityFifo0 = 5,
+    /// Flag the frame as high priority and store it in Rx FIFO 1
+    SetPriorityFifo1 = 6,
+}
+
+/// Handling of frames that do not match any filter element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonMatching {
+    /// Accept non-matching frames into Rx FIFO 0
+    Fifo0 = 0,
+    /// Accept non-matching frames into Rx FIFO 1
+    Fifo1 = 1,
+    /// Reject non-matching frames
+    Reject = 2,
+}
+
+/// An ID filter element, generic over [`StandardId`] and [`ExtendedId`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter<I> {
+    /// Match all IDs in the range `from..=to`
+    ///
+    /// For extended filters, the ID is first ANDed with the extended ID mask
+    /// (`XIDAM`), see [`Config::extended_id_mask`](super::Config::extended_id_mask).
+    Range {
+        /// First ID of the range
+        from: I,
+        /// Last ID of the range
+        to: I,
+        /// Action on match
+        action: FilterAction,
+    },
+    /// Match either `id1` or `id2`
+    Dual {
+        /// First matching ID
+        id1: I,
+        /// Second matching ID
+        id2: I,
+        /// Action on match
+        action: FilterAction,
+    },
+    /// Match all IDs for which `received & mask == id & mask`
+    Classic {
+        /// Filter ID
+        id: I,
+        /// Filter mask. Bits set to `1` must match the filter ID.
+        mask: I,
+        /// Action on match
+        action: FilterAction,
+    },
+    /// Store frames with exactly this ID into the dedicated Rx buffer `index`
+    Buffer {
+        /// Matching ID
+        id: I,
+        /// Index of the dedicated Rx buffer, from 0 to 63
+        index: u8,
+    },
+    /// Disabled filter element
+    Disabled,
+}
+
+// Filter type field
+const RANGE: u32 = 0;
+const DUAL: u32 = 1;
+const CLASSIC: u32 = 2;
+const DISABLED: u32 = 3;
+
+// Filter element configuration field
+const STORE_BUFFER: u32 = 7;
+
+impl Filter<StandardId> {
+    /// Encode into a standard message ID filter element
+    #[inline]
+    pub(super) fn encode(&self) -> u32 {
+        let element = |sft: u32, sfec: u32, id1: StandardId, id2: u32| {
+            sft << 30 | sfec << 27 | (id1.as_raw() as u32) << 16 | (id2 & 0x7FF)
+        };
+        match *self {
+            Filter::Range { from, to, action } => {
+                element(RANGE, action as u32, from, to.as_raw() as u32)
+            }
+            Filter::Dual { id1, id2, action } => {
+                element(DUAL, action as u32, id1, id2.as_raw() as u32)
+            }
+            Filter::Classic { id, mask, action } => {
+                element(CLASSIC, action as u32, id, mask.as_raw() as u32)
+            }
+            // SFID2[10:9] = 0 selects "store into Rx buffer"
+            Filter::Buffer { id, index } => element(RANGE, STORE_BUFFER, id, (index & 0x3F) as u32),
+            Filter::Disabled => DISABLED << 30,
+        }
+    }
+}
+
+impl Filter<ExtendedId> {
+    /// Encode into the two words of an extended message ID filter element
+    #[inline]
+    pub(super) fn encode(&self) -> [u32; 2] {
+        let element = |eft: u32, efec: u32, id1: ExtendedId, id2: u32| {
+            [efec << 29 | id1.as_raw(), eft << 30 | (id2 & 0x1FFF_FFFF)]
+        };
+        match *self {
+            Filter::Range { from, to, action } => element(RANGE, action as u32, from, to.as_raw()),
+            Filter::Dual { id1, id2, action } => element(DUAL, action as u32, id1, id2.as_raw()),
+            Filter::Classic { id, mask, action } => {
+                element(CLASSIC, action as u32, id, mask.as_raw())
+            }
+            // EFID2[10:9] = 0 selects "store into Rx buffer"
+            Filter::Buffer { id, index } => element(RANGE, STORE_BUFFER, id, (index & 0x3F) as u32),
+            Filter::Disabled => [0, DISABLED << 30],
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! CAN and CAN FD frames
+//!
+//! [`Frame`] implements the embedded HAL [`can::Frame`](crate::ehal::can::Frame)
+//! trait for classic CAN frames, and extends it with CAN FD frames of up to 64
+//! data bytes, with or without bit rate switching.
+
+use crate::ehal::can;
+pub use crate::ehal::can::{ExtendedId, Id, StandardId};
+
+/// Valid CAN FD data lengths, indexed by DLC
+const FD_LENGTHS: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+
+/// Maximum number of data bytes in a CAN FD frame
+pub const MAX_DATA_LEN: usize = 64;
+
+// Bit fields shared by the first word of every Rx, Tx and Tx event element
+const ESI: u32 = 1 << 31;
+const XTD: u32 = 1 << 30;
+const RTR: u32 = 1 << 29;
+const STD_SHIFT: u32 = 18;
+const ID_MASK: u32 = 0x1FFF_FFFF;
+
+// Bit fields shared by the second word of every Rx, Tx and Tx event element
+const FDF: u32 = 1 << 21;
+const BRS: u32 = 1 << 20;
+const DLC_SHIFT: u32 = 16;
+
+/// Format of a [`Frame`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// Classic CAN 2.0 frame, up to 8 data bytes
+    Classic,
+    /// CAN FD frame, up to 64 data bytes, transmitted at the nominal bit rate
+    Fd,
+    /// CAN FD frame, up to 64 data bytes, with the data phase transmitted at
+    /// the data bit rate
+    FdBrs,
+}
+
+impl FrameFormat {
+    /// Whether this format uses the CAN FD frame layout
+    #[inline]
+    pub fn is_fd(self) -> bool {
+        !matches!(self, FrameFormat::Classic)
+    }
+}
+
+/// Convert a DLC into a number of data bytes
+#[inline]
+pub fn dlc_to_len(dlc: u8, format: FrameFormat) -> usize {
+    let dlc = (dlc & 0xF) as usize;
+    if format.is_fd() {
+        FD_LENGTHS[dlc] as usize
+    } else {
+        dlc.min(8)
+    }
+}
+
+/// Convert a number of data bytes into the smallest DLC able to hold them
+///
+/// Returns `None` if `len` exceeds the maximum length allowed by `format`.
+#[inline]
+pub fn len_to_dlc(len: usize, format: FrameFormat) -> Option<u8> {
+    if format.is_fd() {
+        FD_LENGTHS
+            .iter()
+            .position(|&l| l as usize >= len)
+            .map(|dlc| dlc as u8)
+    } else if len <= 8 {
+        Some(len as u8)
+    } else {
+        None
+    }
+}
+
+/// A CAN or CAN FD frame
+///
+/// Frames received through [`Can`](super::Can) additionally carry the
+/// receive timestamp, the index of the filter that accepted them and the
+/// error state indicator of the transmitter.
+#[derive(Clone, Copy)]
+pub struct Frame {
+    id: Id,
+    remote: bool,
+    format: FrameFormat,
+    error_passive: bool,
+    dlc: u8,
+    data: [u8; MAX_DATA_LEN],
+    timestamp: u16,
+    filter_index: Option<u8>,
+}
+
+impl Frame {
+    #[inline]
+    fn empty(id: Id, format: FrameFormat) -> Self {
+        Self {
+            id,
+            remote: false,
+            format,
+            error_passive: false,
+            dlc: 0,
+            data: [0; MAX_DATA_LEN],
+            timestamp: 0,
+            filter_index: None,
+        }
+    }
+
+    /// Create a CAN FD data frame
+    ///
+    /// If `data` does not match one of the lengths allowed by CAN FD, the
+    /// payload is padded with zeroes to the next valid length. Returns `None`
+    /// if `data` is longer than 64 bytes.
+    #[inline]
+    pub fn new_fd(id: impl Into<Id>, data: &[u8], bit_rate_switch: bool) -> Option<Self> {
+        let format = if bit_rate_switch {
+            FrameFormat::FdBrs
+        } else {
+            FrameFormat::Fd
+        };
+        let dlc = len_to_dlc(data.len(), format)?;
+        let mut frame = Self::empty(id.into(), format);
+        frame.dlc = dlc;
+        frame.data[..data.len()].copy_from_slice(data);
+        Some(frame)
+    }
+
+    /// Frame format
+    #[inline]
+    pub fn format(&self) -> FrameFormat {
+        self.format
+    }
+
+    /// Returns `true` if the data phase of this frame is (or was) transmitted
+    /// at the data bit rate
+    #[inline]
+    pub fn bit_rate_switch(&self) -> bool {
+        self.format == FrameFormat::FdBrs
+    }
+
+    /// Error state indicator of a received CAN FD frame. Returns `true` if the
+    /// transmitting node was error passive.
+    #[inline]
+    pub fn error_passive(&self) -> bool {
+        self.error_passive
+    }
+
+    /// Value of the timestamp counter when the frame was received
+    #[inline]
+    pub fn timestamp(&self) -> u16 {
+        self.timestamp
+    }
+
+    /// Index of the filter element that accepted a received frame, or `None`
+    /// if it was accepted as a non-matching frame
+    #[inline]
+    pub fn filter_index(&self) -> Option<u8> {
+        self.filter_index
+    }
+
+    /// Encode the two header words of a Tx buffer element
+    #[inline]
+    pub(super) fn tx_header(&self, marker: u8, store_event: bool) -> [u32; 2] {
+        let mut t0 = encode_id(self.id);
+        if self.remote {
+            t0 |= RTR;
+        }
+
+        let mut t1 = (marker as u32) << 24 | (self.dlc as u32 & 0xF) << DLC_SHIFT;
+        if store_event {
+            t1 |= 1 << 23;
+        }
+        if self.format.is_fd() {
+            t1 |= FDF;
+        }
+        if self.format == FrameFormat::FdBrs {
+            t1 |= BRS;
+        }
+
+        [t0, t1]
+    }
+
+    /// Encode the payload into the data words of a Tx buffer element
+    ///
+    /// Returns the number of meaningful words written to `words`.
+    #[inline]
+    pub(super) fn tx_data(&self, words: &mut [u32]) -> usize {
+        let mut count = 0;
+        for (word, chunk) in words.iter_mut().zip(can::Frame::data(self).chunks(4)) {
+            let mut bytes = [0; 4];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            *word = u32::from_le_bytes(bytes);
+            count += 1;
+        }
+        count
+    }
+
+    /// Decode an Rx buffer or FIFO element
+    #[inline]
+    pub(super) fn from_rx_element(r0: u32, r1: u32, data: &[u32]) -> Self {
+        let format = match (r1 & FDF != 0, r1 & BRS != 0) {
+            (false, _) => FrameFormat::Classic,
+            (true, false) => FrameFormat::Fd,
+            (true, true) => FrameFormat::FdBrs,
+        };
+        let mut frame = Self::empty(decode_id(r0), format);
+        frame.remote = r0 & RTR != 0;
+        frame.error_passive = r0 & ESI != 0;
+        frame.dlc = ((r1 >> DLC_SHIFT) & 0xF) as u8;
+        frame.timestamp = r1 as u16;
+        // ANMF is set when the frame was accepted as a non-matching frame
+        frame.filter_index = if r1 & (1 << 31) == 0 {
+            Some(((r1 >> 24) & 0x7F) as u8)
+        } else {
+            None
+        };
+
+        let len = dlc_to_len(frame.dlc, format);
+        for (chunk, word) in frame.data[..len].chunks_mut(4).zip(data.iter()) {
+            let bytes = word.to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        frame
+    }
+}
+
+impl can::Frame for Frame {
+    #[inline]
+    fn new(id: impl Into<Id>, data: &[u8]) -> Option<Self> {
+        let dlc = len_to_dlc(data.len(), FrameFormat::Classic)?;
+        let mut frame = Self::empty(id.into(), FrameFormat::Classic);
+        frame.dlc = dlc;
+        frame.data[..data.len()].copy_from_slice(data);
+        Some(frame)
+    }
+
+    #[inline]
+    fn new_remote(id: impl Into<Id>, dlc: usize) -> Option<Self> {
+        if dlc > 8 {
+            return None;
+        }
+        let mut frame = Self::empty(id.into(), FrameFormat::Classic);
+        frame.remote = true;
+        frame.dlc = dlc as u8;
+        Some(frame)
+    }
+
+    #[inline]
+    fn is_extended(&self) -> bool {
+        matches!(self.id, Id::Extended(_))
+    }
+
+    #[inline]
+    fn is_remote_frame(&self) -> bool {
+        self.remote
+    }
+
+    #[inline]
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    #[inline]
+    fn dlc(&self) -> usize {
+        self.dlc as usize
+    }
+
+    #[inline]
+    fn data(&self) -> &[u8] {
+        if self.remote {
+            &[]
+        } else {
+            &self.data[..dlc_to_len(self.dlc, self.format)]
+        }
+    }
+}
+
+impl core::fmt::Debug for Frame {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Frame")
+            .field("id", &self.id)
+            .field("remote", &self.remote)
+            .field("format", &self.format)
+            .field("data", &can::Frame::data(self))
+            .finish()
+    }
+}
+
+/// Event stored in the Tx event FIFO after a frame has been transmitted
+#[derive(Debug, Clone, Copy)]
+pub struct TxEvent {
+    /// Identifier of the transmitted frame
+    pub id: Id,
+    /// Message marker copied from the Tx buffer element
+    pub marker: u8,
+    /// Format of the transmitted frame
+    pub format: FrameFormat,
+    /// DLC of the transmitted frame
+    pub dlc: u8,
+    /// Value of the timestamp counter at the start of frame transmission
+    pub timestamp: u16,
+}
+
+impl TxEvent {
+    /// Decode a Tx event FIFO element
+    #[inline]
+    pub(super) fn from_element(e0: u32, e1: u32) -> Self {
+        let format = match (e1 & FDF != 0, e1 & BRS != 0) {
+            (false, _) => FrameFormat::Classic,
+            (true, false) => FrameFormat::Fd,
+            (true, true) => FrameFormat::FdBrs,
+        };
+        Self {
+            id: decode_id(e0),
+            marker: (e1 >> 24) as u8,
+            format,
+            dlc: ((e1 >> DLC_SHIFT) & 0xF) as u8,
+            timestamp: e1 as u16,
+        }
+    }
+}
+
+/// Encode an [`Id`] into the first word of a message RAM element
+#[inline]
+pub(super) fn encode_id(id: Id) -> u32 {
+    match id {
+        Id::Standard(id) => (id.as_raw() as u32) << STD_SHIFT,
+        Id::Extended(id) => XTD | id.as_raw(),
+    }
+}
+
+/// Decode an [`Id`] from the first word of a message RAM element
+#[inline]
+pub(super) fn decode_id(word: u32) -> Id {
+    if word & XTD != 0 {
+        // SAFETY: The value is masked to 29 bits
+        Id::Extended(unsafe { ExtendedId::new_unchecked(word & ID_MASK) })
+    } else {
+        // SAFETY: The value is masked to 11 bits
+        Id::Standard(unsafe { StandardId::new_unchecked(((word >> STD_SHIFT) & 0x7FF) as u16) })
+    }
+}
@@ -0,0 +1,347 @@
+//! Message RAM layout
+//!
+//! The M_CAN peripheral does not contain any memory of its own. Filters,
+//! received frames, frames waiting for transmission and Tx events are all
+//! stored in a section of system RAM, the *message RAM*, whose layout is
+//! described by a handful of configuration registers.
+//!
+//! [`RamConfig`] describes the number of elements of each section. The
+//! sections are packed back to back, in the following order:
+//!
+//! | Section                | Max. elements | Element size (words) |
+//! | ---------------------- | ------------- | -------------------- |
+//! | Standard ID filters    | 128           | 1                    |
+//! | Extended ID filters    | 64            | 2                    |
+//! | Rx FIFO 0              | 64            | 2 + data / 4         |
+//! | Rx FIFO 1              | 64            | 2 + data / 4         |
+//! | Rx buffers             | 64            | 2 + data / 4         |
+//! | Tx event FIFO          | 32            | 2                    |
+//! | Tx buffers + FIFO/queue| 32            | 2 + data / 4         |
+//!
+//! Because the start address of each section is programmed as a 16-bit
+//! offset, the whole message RAM must live in the first 64 KiB of SRAM
+//! (`0x2000_0000..0x2001_0000`).
+
+use core::ptr;
+
+use super::Error;
+
+/// Number of data bytes reserved in each Rx or Tx element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataSize {
+    /// 8 bytes, sufficient for classic CAN frames
+    Bytes8 = 0,
+    /// 12 bytes
+    Bytes12 = 1,
+    /// 16 bytes
+    Bytes16 = 2,
+    /// 20 bytes
+    Bytes20 = 3,
+    /// 24 bytes
+    Bytes24 = 4,
+    /// 32 bytes
+    Bytes32 = 5,
+    /// 48 bytes
+    Bytes48 = 6,
+    /// 64 bytes, sufficient for any CAN FD frame
+    Bytes64 = 7,
+}
+
+impl DataSize {
+    /// Number of data bytes
+    #[inline]
+    pub fn bytes(self) -> usize {
+        match self {
+            DataSize::Bytes8 => 8,
+            DataSize::Bytes12 => 12,
+            DataSize::Bytes16 => 16,
+            DataSize::Bytes20 => 20,
+            DataSize::Bytes24 => 24,
+            DataSize::Bytes32 => 32,
+            DataSize::Bytes48 => 48,
+            DataSize::Bytes64 => 64,
+        }
+    }
+
+    /// Size of an Rx or Tx element, header included, in 32-bit words
+    #[inline]
+    pub fn element_words(self) -> usize {
+        2 + self.bytes() / 4
+    }
+}
+
+/// Operating mode of the Tx FIFO/queue section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxQueueMode {
+    /// Frames are transmitted in the order they were queued
+    Fifo,
+    /// Frames are transmitted by order of priority, i.e. lowest ID first
+    Queue,
+}
+
+/// Number of elements in each section of the message RAM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RamConfig {
+    /// Number of standard ID filter elements, up to 128
+    pub standard_filters: u8,
+    /// Number of extended ID filter elements, up to 64
+    pub extended_filters: u8,
+    /// Number of Rx FIFO 0 elements, up to 64
+    pub rx_fifo0: u8,
+    /// Number of Rx FIFO 1 elements, up to 64
+    pub rx_fifo1: u8,
+    /// Number of dedicated Rx buffers, up to 64
+    pub rx_buffers: u8,
+    /// Number of Tx event FIFO elements, up to 32
+    pub tx_events: u8,
+    /// Number of dedicated Tx buffers. Together with `tx_queue`, up to 32.
+    pub tx_buffers: u8,
+    /// Number of Tx FIFO/queue elements. Together with `tx_buffers`, up to 32.
+    pub tx_queue: u8,
+    /// Operating mode of the Tx FIFO/queue
+    pub tx_queue_mode: TxQueueMode,
+    /// Data field size of Rx FIFO 0 elements
+    pub rx_fifo0_data: DataSize,
+    /// Data field size of Rx FIFO 1 elements
+    pub rx_fifo1_data: DataSize,
+    /// Data field size of dedicated Rx buffers
+    pub rx_buffer_data: DataSize,
+    /// Data field size of Tx buffers and Tx FIFO/queue elements
+    pub tx_data: DataSize,
+}
+
+impl Default for RamConfig {
+    /// A small layout suitable for classic CAN, using 144 words of RAM
+    #[inline]
+    fn default() -> Self {
+        Self {
+            standard_filters: 8,
+            extended_filters: 8,
+            rx_fifo0: 16,
+            rx_fifo1: 4,
+            rx_buffers: 0,
+            tx_events: 4,
+            tx_buffers: 0,
+            tx_queue: 8,
+            tx_queue_mode: TxQueueMode::Fifo,
+            rx_fifo0_data: DataSize::Bytes8,
+            rx_fifo1_data: DataSize::Bytes8,
+            rx_buffer_data: DataSize::Bytes8,
+            tx_data: DataSize::Bytes8,
+        }
+    }
+}
+
+impl RamConfig {
+    /// Same as [`RamConfig::default`], but with 64-byte data fields, suitable
+    /// for CAN FD
+    #[inline]
+    pub fn fd() -> Self {
+        Self {
+            rx_fifo0_data: DataSize::Bytes64,
+            rx_fifo1_data: DataSize::Bytes64,
+            rx_buffer_data: DataSize::Bytes64,
+            tx_data: DataSize::Bytes64,
+            ..Self::default()
+        }
+    }
+
+    /// Total size of the message RAM described by this configuration, in
+    /// 32-bit words
+    #[inline]
+    pub fn words(&self) -> usize {
+        self.layout().end
+    }
+
+    /// Check the element counts against the hardware limits
+    #[inline]
+    pub(super) fn validate(&self) -> Result<(), Error> {
+        if self.standard_filters <= 128
+            && self.extended_filters <= 64
+            && self.rx_fifo0 <= 64
+            && self.rx_fifo1 <= 64
+            && self.rx_buffers <= 64
+            && self.tx_events <= 32
+            && self.tx_buffers as usize + self.tx_queue as usize <= 32
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidRamConfig)
+        }
+    }
+
+    /// Compute the word offset of each section
+    #[inline]
+    pub(super) fn layout(&self) -> Layout {
+        let standard_filters = 0;
+        let extended_filters = standard_filters + self.standard_filters as usize;
+        let rx_fifo0 = extended_filters + 2 * self.extended_filters as usize;
+        let rx_fifo1 = rx_fifo0 + self.rx_fifo0 as usize * self.rx_fifo0_data.element_words();
+        let rx_buffers = rx_fifo1 + self.rx_fifo1 as usize * self.rx_fifo1_data.element_words();
+        let tx_events = rx_buffers + self.rx_buffers as usize * self.rx_buffer_data.element_words();
+        let tx_buffers = tx_events + 2 * self.tx_events as usize;
+        let end = tx_buffers
+            + (self.tx_buffers as usize + self.tx_queue as usize) * self.tx_data.element_words();
+        Layout {
+            standard_filters,
+            extended_filters,
+            rx_fifo0,
+            rx_fifo1,
+            rx_buffers,
+            tx_events,
+            tx_buffers,
+            end,
+        }
+    }
+}
+
+/// Word offsets of each message RAM section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Layout {
+    pub standard_filters: usize,
+    pub extended_filters: usize,
+    pub rx_fifo0: usize,
+    pub rx_fifo1: usize,
+    pub rx_buffers: usize,
+    pub tx_events: usize,
+    pub tx_buffers: usize,
+    pub end: usize,
+}
+
+/// Rx section of the message RAM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum RxSection {
+    Fifo0,
+    Fifo1,
+    Buffer,
+}
+
+/// Message RAM owned by a CAN peripheral
+pub(super) struct MessageRam {
+    ram: &'static mut [u32],
+    config: RamConfig,
+    layout: Layout,
+}
+
+impl MessageRam {
+    /// Take ownership of `ram` and check that it can hold `config`
+    #[inline]
+    pub(super) fn new(ram: &'static mut [u32], config: RamConfig) -> Result<Self, Error> {
+        config.validate()?;
+        let layout = config.layout();
+        if ram.len() < layout.end {
+            return Err(Error::MessageRamTooSmall);
+        }
+        let start = ram.as_ptr() as usize;
+        let last = start + layout.end.saturating_sub(1) * 4;
+        if start & 0xFFFF_0000 != 0x2000_0000 || last & 0xFFFF_0000 != 0x2000_0000 {
+            return Err(Error::MessageRamAddress);
+        }
+        // A zeroed filter element is disabled, which makes this a safe
+        // starting point for every section
+        for word in ram.iter_mut() {
+            // SAFETY: `word` is a valid, aligned reference
+            unsafe { ptr::write_volatile(word, 0) };
+        }
+        Ok(Self {
+            ram,
+            config,
+            layout,
+        })
+    }
+
+    #[inline]
+    pub(super) fn config(&self) -> &RamConfig {
+        &self.config
+    }
+
+    #[inline]
+    pub(super) fn free(self) -> &'static mut [u32] {
+        self.ram
+    }
+
+    /// 16-bit address of a section, as programmed into the `xxSA` fields
+    #[inline]
+    pub(super) fn address(&self, offset: usize) -> u16 {
+        (self.ram.as_ptr() as usize + offset * 4) as u16
+    }
+
+    #[inline]
+    pub(super) fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    #[inline]
+    fn read(&self, offset: usize) -> u32 {
+        // SAFETY: Reading through a shared reference to the slice, which is
+        // bounds-checked. Volatile because the M_CAN writes to this memory.
+        unsafe { ptr::read_volatile(&self.ram[offset]) }
+    }
+
+    #[inline]
+    fn write(&mut self, offset: usize, value: u32) {
+        // SAFETY: Writing through a unique reference to the slice, which is
+        // bounds-checked. Volatile because the M_CAN reads this memory.
+        unsafe { ptr::write_volatile(&mut self.ram[offset], value) }
+    }
+
+    /// Write a standard ID filter element
+    #[inline]
+    pub(super) fn write_standard_filter(&mut self, index: usize, element: u32) {
+        self.write(self.layout.standard_filters + index, element);
+    }
+
+    /// Write an extended ID filter element
+    #[inline]
+    pub(super) fn write_extended_filter(&mut self, index: usize, element: [u32; 2]) {
+        let offset = self.layout.extended_filters + 2 * index;
+        self.write(offset, element[0]);
+        self.write(offset + 1, element[1]);
+    }
+
+    /// Read the header words of an Rx element, and copy its data words into
+    /// `data`
+    #[inline]
+    pub(super) fn read_rx(
+        &self,
+        section: RxSection,
+        index: usize,
+        data: &mut [u32; 16],
+    ) -> (u32, u32) {
+        let (start, size) = match section {
+            RxSection::Fifo0 => (self.layout.rx_fifo0, self.config.rx_fifo0_data),
+            RxSection::Fifo1 => (self.layout.rx_fifo1, self.config.rx_fifo1_data),
+            RxSection::Buffer => (self.layout.rx_buffers, self.config.rx_buffer_data),
+        };
+        let offset = start + index * size.element_words();
+        for (i, word) in data.iter_mut().take(size.bytes() / 4).enumerate() {
+            *word = self.read(offset + 2 + i);
+        }
+        (self.read(offset), self.read(offset + 1))
+    }
+
+    /// Write a Tx buffer element
+    ///
+    /// Returns `false` if the payload does not fit in the element.
+    #[inline]
+    pub(super) fn write_tx(&mut self, index: usize, header: [u32; 2], data: &[u32]) -> bool {
+        let size = self.config.tx_data;
+        if data.len() > size.bytes() / 4 {
+            return false;
+        }
+        let offset = self.layout.tx_buffers + index * size.element_words();
+        self.write(offset, header[0]);
+        self.write(offset + 1, header[1]);
+        for (i, &word) in data.iter().enumerate() {
+            self.write(offset + 2 + i, word);
+        }
+        true
+    }
+
+    /// Read a Tx event FIFO element
+    #[inline]
+    pub(super) fn read_tx_event(&self, index: usize) -> (u32, u32) {
+        let offset = self.layout.tx_events + 2 * index;
+        (self.read(offset), self.read(offset + 1))
+    }
+}
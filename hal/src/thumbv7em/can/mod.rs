@@ -0,0 +1,1231 @@
+//! # Controller Area Network (CAN)
+//!
+//! The SAME51 and SAME54 contain one or two M_CAN controllers, supporting
+//! both classic CAN 2.0 and CAN FD, with bit rate switching.
+//!
+//! ## Clocking
+//!
+//! The CAN peripheral is clocked by a dedicated GCLK, whose frequency is used
+//! to compute the bit timing. The clock token ([`Can0Clock`] or [`Can1Clock`])
+//! is consumed by [`Config::new`]. CiA recommends a CAN clock of 20, 40 or
+//! 80 MHz; any frequency evenly divisible by the bit rate works.
+//!
+//! ## Message RAM
+//!
+//! The M_CAN has no memory of its own. Filters, received frames and frames
+//! waiting for transmission are stored in a section of system RAM, which
+//! must be provided by the user as a `&'static mut [u32]` located in the
+//! first 64 KiB of SRAM. Its layout is described by a [`RamConfig`], whose
+//! [`words`](RamConfig::words) method returns the required size.
+//!
+//! ## Example
+//!
+//! ```
+//! use atsamd_hal::can::{self, Config, Filter, FilterAction, RamConfig};
+//! use atsamd_hal::ehal::can::{nb::Can as _, Frame as _, StandardId};
+//! use atsamd_hal::time::U32Ext;
+//!
+//! static mut MESSAGE_RAM: [u32; 1024] = [0; 1024];
+//!
+//! let pads = can::Pads::new(pins.pa22, pins.pa23);
+//! let clock = clocks.can0(&gclk0).unwrap();
+//! let ram = unsafe { &mut MESSAGE_RAM };
+//!
+//! let mut can = Config::new(&mut mclk, peripherals.CAN0, pads, clock, ram, RamConfig::fd(), 500.khz())
+//!     .unwrap()
+//!     .data_bitrate(2.mhz())
+//!     .unwrap()
+//!     .fd_mode(can::FdMode::FdBrs)
+//!     .enable();
+//!
+//! can.set_standard_filter(
+//!     0,
+//!     Filter::Classic {
+//!         id: StandardId::new(0x100).unwrap(),
+//!         mask: StandardId::new(0x700).unwrap(),
+//!         action: FilterAction::StoreFifo1,
+//!     },
+//! )
+//! .unwrap();
+//!
+//! let frame = can::Frame::new(StandardId::new(0x123).unwrap(), &[1, 2, 3]).unwrap();
+//! nb::block!(can.transmit(&frame)).unwrap();
+//! let received = nb::block!(can.receive_fifo1()).unwrap();
+//! ```
+//!
+//! ## Test modes
+//!
+//! [`Mode::InternalLoopback`] connects the transmitter to the receiver
+//! internally and leaves the TX pin recessive, which allows testing without
+//! a bus. [`Mode::ExternalLoopback`] additionally drives the TX pin, so that
+//! frames can be observed on the bus.
+
+use core::ops::Deref;
+
+use bitflags::bitflags;
+
+use crate::ehal::can;
+use crate::gpio::v2::{
+    AlternateI, AnyPin, Pin, PinId, PinMode, SpecificPin, PA22, PA23, PA24, PA25,
+};
+use crate::pac::{can0, CAN0, MCLK};
+use crate::time::Hertz;
+use crate::typelevel::Sealed;
+
+pub use crate::clock::Can0Clock;
+#[cfg(any(feature = "same54", all(feature = "same51", feature = "min-samd51j")))]
+pub use crate::clock::Can1Clock;
+#[cfg(any(feature = "same54", all(feature = "same51", feature = "min-samd51j")))]
+use crate::gpio::v2::{AlternateH, PB12, PB13, PB14, PB15};
+#[cfg(any(feature = "same54", all(feature = "same51", feature = "min-samd51j")))]
+use crate::pac::CAN1;
+
+mod bit_timing;
+pub use bit_timing::*;
+
+mod frame;
+pub use frame::*;
+
+mod filter;
+pub use filter::*;
+
+mod message_ram;
+pub use message_ram::{DataSize, RamConfig, TxQueueMode};
+use message_ram::{MessageRam, RxSection};
+
+pub use can0::psr::{ACT_A as Activity, LEC_A as LastErrorCode};
+
+//==============================================================================
+//  Instance
+//==============================================================================
+
+/// Type-level `enum` representing a CAN peripheral
+pub trait Instance: Sealed + Deref<Target = can0::RegisterBlock> {
+    /// CAN peripheral number
+    const NUM: usize;
+    /// GCLK token of this peripheral
+    type Clock: Into<Hertz>;
+    /// Enable the corresponding AHB clock
+    fn enable_ahb_clock(mclk: &mut MCLK);
+    /// Disable the corresponding AHB clock
+    fn disable_ahb_clock(mclk: &mut MCLK);
+}
+
+macro_rules! can_instance {
+    ($Alias:ident, $Can:ident, $Clock:ident, $num:literal, $bit:ident) => {
+        /// Type alias for the corresponding CAN instance
+        pub type $Alias = $Can;
+        impl Sealed for $Alias {}
+        impl Instance for $Alias {
+            const NUM: usize = $num;
+            type Clock = $Clock;
+            #[inline]
+            fn enable_ahb_clock(mclk: &mut MCLK) {
+                mclk.ahbmask.modify(|_, w| w.$bit().set_bit());
+            }
+            #[inline]
+            fn disable_ahb_clock(mclk: &mut MCLK) {
+                mclk.ahbmask.modify(|_, w| w.$bit().clear_bit());
+            }
+        }
+    };
+}
+
+can_instance!(Can0, CAN0, Can0Clock, 0, can0_);
+#[cfg(any(feature = "same54", all(feature = "same51", feature = "min-samd51j")))]
+can_instance!(Can1, CAN1, Can1Clock, 1, can1_);
+
+//==============================================================================
+//  Pads
+//==============================================================================
+
+/// Type-level function mapping a [`PinId`] to the [`PinMode`] of its CAN TX
+/// function for the instance `C`
+pub trait GetTxPin<C: Instance>: PinId {
+    /// [`PinMode`] of the CAN TX function
+    type Mode: PinMode;
+}
+
+/// Type-level function mapping a [`PinId`] to the [`PinMode`] of its CAN RX
+/// function for the instance `C`
+pub trait GetRxPin<C: Instance>: PinId {
+    /// [`PinMode`] of the CAN RX function
+    type Mode: PinMode;
+}
+
+/// [`Pin`] configured as the TX pin of the CAN instance `C`
+pub type TxPin<C, I> = Pin<I, <I as GetTxPin<C>>::Mode>;
+
+/// [`Pin`] configured as the RX pin of the CAN instance `C`
+pub type RxPin<C, I> = Pin<I, <I as GetRxPin<C>>::Mode>;
+
+macro_rules! can_pins {
+    ($Can:ident: $Mode:ident, tx: [$($Tx:ident),+], rx: [$($Rx:ident),+]) => {
+        $(
+            impl GetTxPin<$Can> for $Tx {
+                type Mode = $Mode;
+            }
+        )+
+        $(
+            impl GetRxPin<$Can> for $Rx {
+                type Mode = $Mode;
+            }
+        )+
+    };
+}
+
+can_pins!(Can0: AlternateI, tx: [PA22, PA24], rx: [PA23, PA25]);
+#[cfg(any(feature = "same54", all(feature = "same51", feature = "min-samd51j")))]
+can_pins!(Can1: AlternateH, tx: [PB12, PB14], rx: [PB13, PB15]);
+
+/// TX and RX pins of a CAN peripheral
+pub struct Pads<C, TX, RX>
+where
+    C: Instance,
+    TX: GetTxPin<C>,
+    RX: GetRxPin<C>,
+{
+    tx: TxPin<C, TX>,
+    rx: RxPin<C, RX>,
+}
+
+impl<C, TX, RX> Pads<C, TX, RX>
+where
+    C: Instance,
+    TX: GetTxPin<C>,
+    RX: GetRxPin<C>,
+{
+    /// Configure a pair of pins for the CAN instance `C`
+    #[inline]
+    pub fn new<T, R>(tx: T, rx: R) -> Self
+    where
+        T: AnyPin<Id = TX>,
+        R: AnyPin<Id = RX>,
+    {
+        let tx: SpecificPin<T> = tx.into();
+        let rx: SpecificPin<R> = rx.into();
+        Self {
+            tx: tx.into_mode(),
+            rx: rx.into_mode(),
+        }
+    }
+
+    /// Return the pins
+    #[inline]
+    pub fn free(self) -> (TxPin<C, TX>, RxPin<C, RX>) {
+        (self.tx, self.rx)
+    }
+}
+
+//==============================================================================
+//  Error
+//==============================================================================
+
+/// Errors reported by the CAN driver
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Error {
+    /// The requested bit rate cannot be obtained from the CAN clock
+    InvalidBitTiming,
+    /// The [`RamConfig`] exceeds the hardware limits
+    InvalidRamConfig,
+    /// The message RAM slice is too small for the [`RamConfig`]
+    MessageRamTooSmall,
+    /// The message RAM slice is not located in the first 64 KiB of SRAM
+    MessageRamAddress,
+    /// A filter, buffer or FIFO index is out of range
+    InvalidIndex,
+    /// The frame does not fit in the Tx element, or is a CAN FD frame while
+    /// CAN FD is disabled
+    InvalidFrame,
+    /// The dedicated Tx buffer has a pending transmission request
+    BufferBusy,
+    /// A received frame was lost because the FIFO was full
+    Overrun,
+    /// The controller is in the bus-off state
+    BusOff,
+    /// A protocol error was detected on the bus
+    Protocol(LastErrorCode),
+}
+
+impl can::Error for Error {
+    #[inline]
+    fn kind(&self) -> can::ErrorKind {
+        match self {
+            Error::Overrun => can::ErrorKind::Overrun,
+            Error::Protocol(LastErrorCode::STUFF) => can::ErrorKind::Stuff,
+            Error::Protocol(LastErrorCode::FORM) => can::ErrorKind::Form,
+            Error::Protocol(LastErrorCode::ACK) => can::ErrorKind::Acknowledge,
+            Error::Protocol(LastErrorCode::BIT1) | Error::Protocol(LastErrorCode::BIT0) => {
+                can::ErrorKind::Bit
+            }
+            Error::Protocol(LastErrorCode::CRC) => can::ErrorKind::Crc,
+            _ => can::ErrorKind::Other,
+        }
+    }
+}
+
+//==============================================================================
+//  Configuration enums
+//==============================================================================
+
+/// Operating mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Normal operation
+    Normal,
+    /// Restricted operation: the node receives and acknowledges frames, but
+    /// does not transmit and does not send error or overload frames
+    Restricted,
+    /// Bus monitoring: the node receives frames but only sends recessive bits
+    BusMonitoring,
+    /// Internal loopback: transmitted frames are received internally, and the
+    /// TX pin stays recessive
+    InternalLoopback,
+    /// External loopback: transmitted frames are received internally and
+    /// driven on the TX pin
+    ExternalLoopback,
+}
+
+/// CAN FD operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FdMode {
+    /// Classic CAN only
+    Disabled,
+    /// CAN FD frames are allowed, without bit rate switching
+    Fd,
+    /// CAN FD frames are allowed, with bit rate switching
+    FdBrs,
+}
+
+/// Interrupt line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptLine {
+    /// Interrupt line 0
+    Line0,
+    /// Interrupt line 1
+    Line1,
+}
+
+bitflags! {
+    /// Interrupt flags
+    ///
+    /// The binary format of the underlying bits exactly matches the `IR`,
+    /// `IE` and `ILS` registers.
+    pub struct Interrupts: u32 {
+        /// Rx FIFO 0 new message
+        const RF0N = 1 << 0;
+        /// Rx FIFO 0 watermark reached
+        const RF0W = 1 << 1;
+        /// Rx FIFO 0 full
+        const RF0F = 1 << 2;
+        /// Rx FIFO 0 message lost
+        const RF0L = 1 << 3;
+        /// Rx FIFO 1 new message
+        const RF1N = 1 << 4;
+        /// Rx FIFO 1 watermark reached
+        const RF1W = 1 << 5;
+        /// Rx FIFO 1 full
+        const RF1F = 1 << 6;
+        /// Rx FIFO 1 message lost
+        const RF1L = 1 << 7;
+        /// High priority message
+        const HPM = 1 << 8;
+        /// Transmission completed
+        const TC = 1 << 9;
+        /// Transmission cancellation finished
+        const TCF = 1 << 10;
+        /// Tx FIFO empty
+        const TFE = 1 << 11;
+        /// Tx event FIFO new entry
+        const TEFN = 1 << 12;
+        /// Tx event FIFO watermark reached
+        const TEFW = 1 << 13;
+        /// Tx event FIFO full
+        const TEFF = 1 << 14;
+        /// Tx event FIFO element lost
+        const TEFL = 1 << 15;
+        /// Timestamp wraparound
+        const TSW = 1 << 16;
+        /// Message RAM access failure
+        const MRAF = 1 << 17;
+        /// Timeout occurred
+        const TOO = 1 << 18;
+        /// Message stored to a dedicated Rx buffer
+        const DRX = 1 << 19;
+        /// Bit error corrected
+        const BEC = 1 << 20;
+        /// Bit error uncorrected
+        const BEU = 1 << 21;
+        /// Error logging overflow
+        const ELO = 1 << 22;
+        /// Error passive status changed
+        const EP = 1 << 23;
+        /// Warning status changed
+        const EW = 1 << 24;
+        /// Bus-off status changed
+        const BO = 1 << 25;
+        /// Message RAM watchdog
+        const WDI = 1 << 26;
+        /// Protocol error in arbitration phase
+        const PEA = 1 << 27;
+        /// Protocol error in data phase
+        const PED = 1 << 28;
+        /// Access to reserved address
+        const ARA = 1 << 29;
+    }
+}
+
+/// Transmit and receive error counters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCounters {
+    /// Transmit error counter
+    pub transmit: u8,
+    /// Receive error counter
+    pub receive: u8,
+    /// The receive error counter has reached the error passive level of 128
+    pub receive_passive: bool,
+    /// Number of errors since the last read, saturating at 255
+    pub logging: u8,
+}
+
+/// Snapshot of the protocol status register
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProtocolStatus {
+    /// Last error in the arbitration phase
+    pub last_error: LastErrorCode,
+    /// Last error in the data phase of a CAN FD frame with bit rate switching
+    pub data_last_error: LastErrorCode,
+    /// Current bus activity
+    pub activity: Activity,
+    /// The node is error passive
+    pub error_passive: bool,
+    /// At least one error counter has reached the warning limit of 96
+    pub warning: bool,
+    /// The node is bus-off
+    pub bus_off: bool,
+}
+
+//==============================================================================
+//  Config
+//==============================================================================
+
+/// A configurable, disabled CAN peripheral
+///
+/// While a `Config` exists, the M_CAN is held in initialization mode with
+/// configuration change enabled. Call [`enable`](Config::enable) to join the
+/// bus.
+pub struct Config<C, TX, RX>
+where
+    C: Instance,
+    TX: GetTxPin<C>,
+    RX: GetRxPin<C>,
+{
+    can: C,
+    pads: Pads<C, TX, RX>,
+    ram: MessageRam,
+    freq: Hertz,
+}
+
+impl<C, TX, RX> Config<C, TX, RX>
+where
+    C: Instance,
+    TX: GetTxPin<C>,
+    RX: GetRxPin<C>,
+{
+    /// Create a new `Config`
+    ///
+    /// Enables the AHB clock, resets the message RAM, programs its layout and
+    /// sets the nominal bit rate. Non-matching frames are accepted into Rx
+    /// FIFO 0, CAN FD is disabled and the node operates in [`Mode::Normal`].
+    #[inline]
+    pub fn new(
+        mclk: &mut MCLK,
+        can: C,
+        pads: Pads<C, TX, RX>,
+        clock: C::Clock,
+        ram: &'static mut [u32],
+        ram_config: RamConfig,
+        bitrate: impl Into<Hertz>,
+    ) -> Result<Self, Error> {
+        let freq = clock.into();
+        let timing = BitTiming::new(freq, bitrate)?;
+        let ram = MessageRam::new(ram, ram_config)?;
+
+        C::enable_ahb_clock(mclk);
+        let mut config = Self {
+            can,
+            pads,
+            ram,
+            freq,
+        };
+        config.init();
+        config.configure_ram();
+        config.set_nominal_bit_timing(timing)?;
+        Ok(config)
+    }
+
+    /// Enter initialization mode and enable configuration changes
+    #[inline]
+    fn init(&mut self) {
+        self.can.cccr.modify(|_, w| w.init().set_bit());
+        while self.can.cccr.read().init().bit_is_clear() {}
+        self.can.cccr.modify(|_, w| w.cce().set_bit());
+    }
+
+    /// Program the start address and size of each message RAM section
+    #[inline]
+    fn configure_ram(&mut self) {
+        let ram = &self.ram;
+        let config = *ram.config();
+        let layout = *ram.layout();
+        let can = &self.can;
+        // SAFETY: Element counts are checked by `RamConfig::validate`, and
+        // addresses are 16-bit offsets by construction
+        unsafe {
+            can.sidfc.write(|w| {
+                w.flssa().bits(ram.address(layout.standard_filters));
+                w.lss().bits(config.standard_filters)
+            });
+            can.xidfc.write(|w| {
+                w.flesa().bits(ram.address(layout.extended_filters));
+                w.lse().bits(config.extended_filters)
+            });
+            can.rxf0c.write(|w| {
+                w.f0sa().bits(ram.address(layout.rx_fifo0));
+                w.f0s().bits(config.rx_fifo0)
+            });
+            can.rxf1c.write(|w| {
+                w.f1sa().bits(ram.address(layout.rx_fifo1));
+                w.f1s().bits(config.rx_fifo1)
+            });
+            can.rxbc
+                .write(|w| w.rbsa().bits(ram.address(layout.rx_buffers)));
+            can.rxesc.write(|w| {
+                w.f0ds().bits(config.rx_fifo0_data as u8);
+                w.f1ds().bits(config.rx_fifo1_data as u8);
+                w.rbds().bits(config.rx_buffer_data as u8)
+            });
+            can.txefc.write(|w| {
+                w.efsa().bits(ram.address(layout.tx_events));
+                w.efs().bits(config.tx_events)
+            });
+            can.txbc.write(|w| {
+                w.tbsa().bits(ram.address(layout.tx_buffers));
+                w.ndtb().bits(config.tx_buffers);
+                w.tfqs().bits(config.tx_queue);
+                w.tfqm().bit(config.tx_queue_mode == TxQueueMode::Queue)
+            });
+            can.txesc.write(|w| w.tbds().bits(config.tx_data as u8));
+        }
+    }
+
+    /// Frequency of the CAN clock
+    #[inline]
+    pub fn freq(&self) -> Hertz {
+        self.freq
+    }
+
+    /// Set the nominal bit timing, used for the arbitration phase and for
+    /// classic CAN frames
+    #[inline]
+    pub fn nominal_bit_timing(mut self, timing: BitTiming) -> Result<Self, Error> {
+        self.set_nominal_bit_timing(timing)?;
+        Ok(self)
+    }
+
+    /// Set the nominal bit timing, used for the arbitration phase and for
+    /// classic CAN frames
+    #[inline]
+    pub fn set_nominal_bit_timing(&mut self, timing: BitTiming) -> Result<(), Error> {
+        timing.validate(&bit_timing::NOMINAL)?;
+        // SAFETY: Values are checked against the register limits above
+        self.can.nbtp.write(|w| unsafe {
+            w.nbrp().bits(timing.prescaler - 1);
+            w.ntseg1().bits((timing.seg1 - 1) as u8);
+            w.ntseg2().bits((timing.seg2 - 1) as u8);
+            w.nsjw().bits((timing.sjw - 1) as u8)
+        });
+        Ok(())
+    }
+
+    /// Get the nominal bit timing
+    #[inline]
+    pub fn get_nominal_bit_timing(&self) -> BitTiming {
+        let nbtp = self.can.nbtp.read();
+        BitTiming {
+            prescaler: nbtp.nbrp().bits() + 1,
+            seg1: nbtp.ntseg1().bits() as u16 + 1,
+            seg2: nbtp.ntseg2().bits() as u16 + 1,
+            sjw: nbtp.nsjw().bits() as u16 + 1,
+        }
+    }
+
+    /// Calculate and set the data phase bit rate, used for CAN FD frames
+    /// with bit rate switching
+    #[inline]
+    pub fn data_bitrate(self, bitrate: impl Into<Hertz>) -> Result<Self, Error> {
+        let timing = BitTiming::new_data(self.freq, bitrate)?;
+        self.data_bit_timing(timing)
+    }
+
+    /// Set the data phase bit timing
+    #[inline]
+    pub fn data_bit_timing(mut self, timing: BitTiming) -> Result<Self, Error> {
+        self.set_data_bit_timing(timing)?;
+        Ok(self)
+    }
+
+    /// Set the data phase bit timing
+    #[inline]
+    pub fn set_data_bit_timing(&mut self, timing: BitTiming) -> Result<(), Error> {
+        timing.validate(&bit_timing::DATA)?;
+        // SAFETY: Values are checked against the register limits above
+        self.can.dbtp.modify(|_, w| unsafe {
+            w.dbrp().bits((timing.prescaler - 1) as u8);
+            w.dtseg1().bits((timing.seg1 - 1) as u8);
+            w.dtseg2().bits((timing.seg2 - 1) as u8);
+            w.dsjw().bits((timing.sjw - 1) as u8)
+        });
+        Ok(())
+    }
+
+    /// Get the data phase bit timing
+    #[inline]
+    pub fn get_data_bit_timing(&self) -> BitTiming {
+        let dbtp = self.can.dbtp.read();
+        BitTiming {
+            prescaler: dbtp.dbrp().bits() as u16 + 1,
+            seg1: dbtp.dtseg1().bits() as u16 + 1,
+            seg2: dbtp.dtseg2().bits() as u16 + 1,
+            sjw: dbtp.dsjw().bits() as u16 + 1,
+        }
+    }
+
+    /// Enable or disable transmitter delay compensation
+    ///
+    /// Required for data bit rates above roughly 1 Mbit/s, to compensate for
+    /// the transceiver loop delay. `offset` is the position of the secondary
+    /// sample point, in CAN clock periods, relative to the measured delay.
+    /// `filter` sets the minimum accepted delay measurement. Both values are
+    /// limited to 127.
+    #[inline]
+    pub fn transceiver_delay_compensation(mut self, tdc: Option<(u8, u8)>) -> Self {
+        self.set_transceiver_delay_compensation(tdc);
+        self
+    }
+
+    /// Enable or disable transmitter delay compensation
+    #[inline]
+    pub fn set_transceiver_delay_compensation(&mut self, tdc: Option<(u8, u8)>) {
+        match tdc {
+            Some((offset, filter)) => {
+                // SAFETY: Values are masked to 7 bits
+                self.can.tdcr.write(|w| unsafe {
+                    w.tdco().bits(offset & 0x7F);
+                    w.tdcf().bits(filter & 0x7F)
+                });
+                self.can.dbtp.modify(|_, w| w.tdc().set_bit());
+            }
+            None => self.can.dbtp.modify(|_, w| w.tdc().clear_bit()),
+        }
+    }
+
+    /// Select CAN FD operation
+    #[inline]
+    pub fn fd_mode(mut self, mode: FdMode) -> Self {
+        self.set_fd_mode(mode);
+        self
+    }
+
+    /// Select CAN FD operation
+    #[inline]
+    pub fn set_fd_mode(&mut self, mode: FdMode) {
+        self.can.cccr.modify(|_, w| {
+            w.fdoe().bit(mode != FdMode::Disabled);
+            w.brse().bit(mode == FdMode::FdBrs)
+        });
+    }
+
+    /// Get the CAN FD operation
+    #[inline]
+    pub fn get_fd_mode(&self) -> FdMode {
+        let cccr = self.can.cccr.read();
+        match (cccr.fdoe().bit(), cccr.brse().bit()) {
+            (false, _) => FdMode::Disabled,
+            (true, false) => FdMode::Fd,
+            (true, true) => FdMode::FdBrs,
+        }
+    }
+
+    /// Select the operating mode
+    #[inline]
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.set_mode(mode);
+        self
+    }
+
+    /// Select the operating mode
+    #[inline]
+    pub fn set_mode(&mut self, mode: Mode) {
+        let loopback = matches!(mode, Mode::InternalLoopback | Mode::ExternalLoopback);
+        self.can.cccr.modify(|_, w| {
+            w.asm().bit(mode == Mode::Restricted);
+            w.mon()
+                .bit(matches!(mode, Mode::BusMonitoring | Mode::InternalLoopback));
+            w.test().bit(loopback)
+        });
+        if loopback {
+            self.can.test.modify(|_, w| w.lbck().set_bit());
+        }
+    }
+
+    /// Get the operating mode
+    #[inline]
+    pub fn get_mode(&self) -> Mode {
+        let cccr = self.can.cccr.read();
+        let loopback = cccr.test().bit() && self.can.test.read().lbck().bit();
+        match (loopback, cccr.mon().bit(), cccr.asm().bit()) {
+            (true, true, _) => Mode::InternalLoopback,
+            (true, false, _) => Mode::ExternalLoopback,
+            (false, true, _) => Mode::BusMonitoring,
+            (false, false, true) => Mode::Restricted,
+            (false, false, false) => Mode::Normal,
+        }
+    }
+
+    /// Enable or disable automatic retransmission of frames that lost
+    /// arbitration or were disturbed by errors
+    #[inline]
+    pub fn auto_retransmit(mut self, enabled: bool) -> Self {
+        self.set_auto_retransmit(enabled);
+        self
+    }
+
+    /// Enable or disable automatic retransmission
+    #[inline]
+    pub fn set_auto_retransmit(&mut self, enabled: bool) {
+        self.can.cccr.modify(|_, w| w.dar().bit(!enabled));
+    }
+
+    /// Select what happens to frames matching no filter element, for standard
+    /// and extended IDs respectively
+    #[inline]
+    pub fn non_matching(mut self, standard: NonMatching, extended: NonMatching) -> Self {
+        self.set_non_matching(standard, extended);
+        self
+    }
+
+    /// Select what happens to frames matching no filter element
+    #[inline]
+    pub fn set_non_matching(&mut self, standard: NonMatching, extended: NonMatching) {
+        // SAFETY: `NonMatching` only contains valid field values
+        self.can.gfc.modify(|_, w| unsafe {
+            w.anfs().bits(standard as u8);
+            w.anfe().bits(extended as u8)
+        });
+    }
+
+    /// Reject all remote frames, for standard and extended IDs respectively
+    #[inline]
+    pub fn reject_remote(mut self, standard: bool, extended: bool) -> Self {
+        self.set_reject_remote(standard, extended);
+        self
+    }
+
+    /// Reject all remote frames
+    #[inline]
+    pub fn set_reject_remote(&mut self, standard: bool, extended: bool) {
+        self.can.gfc.modify(|_, w| {
+            w.rrfs().bit(standard);
+            w.rrfe().bit(extended)
+        });
+    }
+
+    /// Set the mask ANDed with extended IDs before filtering
+    #[inline]
+    pub fn extended_id_mask(mut self, mask: u32) -> Self {
+        self.set_extended_id_mask(mask);
+        self
+    }
+
+    /// Set the mask ANDed with extended IDs before filtering
+    #[inline]
+    pub fn set_extended_id_mask(&mut self, mask: u32) {
+        // SAFETY: The mask is limited to 29 bits
+        self.can
+            .xidam
+            .write(|w| unsafe { w.eidm().bits(mask & 0x1FFF_FFFF) });
+    }
+
+    /// Enable the internal timestamp counter, incremented every `prescaler`
+    /// bit times (1 to 16)
+    #[inline]
+    pub fn timestamp_prescaler(mut self, prescaler: u8) -> Self {
+        self.set_timestamp_prescaler(prescaler);
+        self
+    }
+
+    /// Enable the internal timestamp counter
+    #[inline]
+    pub fn set_timestamp_prescaler(&mut self, prescaler: u8) {
+        let tcp = prescaler.clamp(1, 16) - 1;
+        // SAFETY: TCP is limited to 4 bits
+        self.can.tscc.write(|w| unsafe {
+            w.tss().inc();
+            w.tcp().bits(tcp)
+        });
+    }
+
+    /// Set a standard ID filter element
+    #[inline]
+    pub fn standard_filter(
+        mut self,
+        index: usize,
+        filter: Filter<StandardId>,
+    ) -> Result<Self, Error> {
+        self.set_standard_filter(index, filter)?;
+        Ok(self)
+    }
+
+    /// Set a standard ID filter element
+    #[inline]
+    pub fn set_standard_filter(
+        &mut self,
+        index: usize,
+        filter: Filter<StandardId>,
+    ) -> Result<(), Error> {
+        if index >= self.ram.config().standard_filters as usize {
+            return Err(Error::InvalidIndex);
+        }
+        self.ram.write_standard_filter(index, filter.encode());
+        Ok(())
+    }
+
+    /// Set an extended ID filter element
+    #[inline]
+    pub fn extended_filter(
+        mut self,
+        index: usize,
+        filter: Filter<ExtendedId>,
+    ) -> Result<Self, Error> {
+        self.set_extended_filter(index, filter)?;
+        Ok(self)
+    }
+
+    /// Set an extended ID filter element
+    #[inline]
+    pub fn set_extended_filter(
+        &mut self,
+        index: usize,
+        filter: Filter<ExtendedId>,
+    ) -> Result<(), Error> {
+        if index >= self.ram.config().extended_filters as usize {
+            return Err(Error::InvalidIndex);
+        }
+        self.ram.write_extended_filter(index, filter.encode());
+        Ok(())
+    }
+
+    /// Leave initialization mode and start participating in bus activity
+    ///
+    /// The node becomes active after detecting 11 consecutive recessive bits.
+    #[inline]
+    pub fn enable(self) -> Can<C, TX, RX> {
+        self.can.cccr.modify(|_, w| w.init().clear_bit());
+        while self.can.cccr.read().init().bit_is_set() {}
+        Can { config: self }
+    }
+
+    /// Reset the peripheral, disable its AHB clock and return the resources
+    #[inline]
+    pub fn free(self, mclk: &mut MCLK) -> (C, Pads<C, TX, RX>, &'static mut [u32]) {
+        // There is no software reset. Restore the reset value of the
+        // registers that are not overwritten by `Config::new`.
+        self.can.test.reset();
+        self.can.cccr.modify(|_, w| {
+            w.test().clear_bit();
+            w.mon().clear_bit();
+            w.asm().clear_bit();
+            w.dar().clear_bit();
+            w.fdoe().clear_bit();
+            w.brse().clear_bit()
+        });
+        self.can.ie.reset();
+        self.can.ile.reset();
+        self.can.gfc.reset();
+        self.can.dbtp.reset();
+        self.can.tscc.reset();
+        C::disable_ahb_clock(mclk);
+        (self.can, self.pads, self.ram.free())
+    }
+}
+
+//==============================================================================
+//  Can
+//==============================================================================
+
+/// An enabled CAN peripheral
+pub struct Can<C, TX, RX>
+where
+    C: Instance,
+    TX: GetTxPin<C>,
+    RX: GetRxPin<C>,
+{
+    config: Config<C, TX, RX>,
+}
+
+impl<C, TX, RX> Can<C, TX, RX>
+where
+    C: Instance,
+    TX: GetTxPin<C>,
+    RX: GetRxPin<C>,
+{
+    /// Return to initialization mode and return the [`Config`]
+    ///
+    /// Pending transmissions are aborted.
+    #[inline]
+    pub fn disable(mut self) -> Config<C, TX, RX> {
+        self.config.init();
+        self.config
+    }
+
+    #[inline]
+    fn regs(&self) -> &can0::RegisterBlock {
+        &self.config.can
+    }
+
+    /// Check a frame against the CAN FD configuration
+    #[inline]
+    fn check_frame(&self, frame: &Frame) -> Result<(), Error> {
+        let cccr = self.regs().cccr.read();
+        match frame.format() {
+            FrameFormat::Classic => Ok(()),
+            FrameFormat::Fd if cccr.fdoe().bit() => Ok(()),
+            FrameFormat::FdBrs if cccr.brse().bit() => Ok(()),
+            _ => Err(Error::InvalidFrame),
+        }
+    }
+
+    /// Write a frame into the Tx buffer `index` and request its transmission
+    #[inline]
+    fn write_tx(&mut self, index: usize, frame: &Frame, marker: u8) -> Result<(), Error> {
+        self.check_frame(frame)?;
+        let store_event = self.config.ram.config().tx_events > 0;
+        let mut data = [0; 16];
+        let count = frame.tx_data(&mut data);
+        if !self
+            .config
+            .ram
+            .write_tx(index, frame.tx_header(marker, store_event), &data[..count])
+        {
+            return Err(Error::InvalidFrame);
+        }
+        // SAFETY: Each bit requests the transmission of one buffer
+        self.regs().txbar.write(|w| unsafe { w.bits(1 << index) });
+        Ok(())
+    }
+
+    /// Queue a frame in the Tx FIFO/queue
+    ///
+    /// `marker` is copied into the Tx event generated once the frame has been
+    /// transmitted. Returns the index of the Tx buffer used, or
+    /// [`WouldBlock`](nb::Error::WouldBlock) if the FIFO/queue is full.
+    #[inline]
+    pub fn transmit_queued(&mut self, frame: &Frame, marker: u8) -> nb::Result<u8, Error> {
+        if self.regs().psr.read().bo().bit_is_set() {
+            return Err(nb::Error::Other(Error::BusOff));
+        }
+        let txfqs = self.regs().txfqs.read();
+        if txfqs.tfqf().bit_is_set() || self.config.ram.config().tx_queue == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        let index = txfqs.tfqpi().bits();
+        self.write_tx(index as usize, frame, marker)?;
+        Ok(index)
+    }
+
+    /// Write a frame into the dedicated Tx buffer `index` and request its
+    /// transmission
+    #[inline]
+    pub fn transmit_buffer(
+        &mut self,
+        index: usize,
+        frame: &Frame,
+        marker: u8,
+    ) -> Result<(), Error> {
+        if index >= self.config.ram.config().tx_buffers as usize {
+            return Err(Error::InvalidIndex);
+        }
+        if self.regs().txbrp.read().bits() & (1 << index) != 0 {
+            return Err(Error::BufferBusy);
+        }
+        self.write_tx(index, frame, marker)
+    }
+
+    /// Request the cancellation of a pending transmission
+    #[inline]
+    pub fn cancel(&mut self, index: usize) {
+        if index < 32 {
+            // SAFETY: Each bit requests the cancellation of one buffer
+            self.regs().txbcr.write(|w| unsafe { w.bits(1 << index) });
+        }
+    }
+
+    /// Returns `true` if a transmission request is pending for the Tx buffer
+    /// `index`
+    #[inline]
+    pub fn is_pending(&self, index: usize) -> bool {
+        index < 32 && self.regs().txbrp.read().bits() & (1 << index) != 0
+    }
+
+    /// Returns `true` if the last transmission from the Tx buffer `index`
+    /// completed successfully
+    #[inline]
+    pub fn transmission_occurred(&self, index: usize) -> bool {
+        index < 32 && self.regs().txbto.read().bits() & (1 << index) != 0
+    }
+
+    /// Receive a frame from Rx FIFO 0
+    ///
+    /// Returns [`Error::Overrun`] once if frames were lost since the last
+    /// call.
+    #[inline]
+    pub fn receive_fifo0(&mut self) -> nb::Result<Frame, Error> {
+        let regs = self.regs();
+        if regs.ir.read().rf0l().bit_is_set() {
+            regs.ir.write(|w| w.rf0l().set_bit());
+            return Err(nb::Error::Other(Error::Overrun));
+        }
+        let status = regs.rxf0s.read();
+        if status.f0fl().bits() == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        let index = status.f0gi().bits();
+        let frame = self.read_rx(RxSection::Fifo0, index as usize);
+        // SAFETY: The index was read from F0GI
+        self.regs().rxf0a.write(|w| unsafe { w.f0ai().bits(index) });
+        Ok(frame)
+    }
+
+    /// Receive a frame from Rx FIFO 1
+    ///
+    /// Returns [`Error::Overrun`] once if frames were lost since the last
+    /// call.
+    #[inline]
+    pub fn receive_fifo1(&mut self) -> nb::Result<Frame, Error> {
+        let regs = self.regs();
+        if regs.ir.read().rf1l().bit_is_set() {
+            regs.ir.write(|w| w.rf1l().set_bit());
+            return Err(nb::Error::Other(Error::Overrun));
+        }
+        let status = regs.rxf1s.read();
+        if status.f1fl().bits() == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        let index = status.f1gi().bits();
+        let frame = self.read_rx(RxSection::Fifo1, index as usize);
+        // SAFETY: The index was read from F1GI
+        self.regs().rxf1a.write(|w| unsafe { w.f1ai().bits(index) });
+        Ok(frame)
+    }
+
+    /// Receive a frame from the dedicated Rx buffer `index`
+    #[inline]
+    pub fn receive_buffer(&mut self, index: usize) -> nb::Result<Frame, Error> {
+        if index >= self.config.ram.config().rx_buffers as usize {
+            return Err(nb::Error::Other(Error::InvalidIndex));
+        }
+        let regs = self.regs();
+        let mask = 1 << (index % 32);
+        let ndat = if index < 32 {
+            regs.ndat1.read().bits()
+        } else {
+            regs.ndat2.read().bits()
+        };
+        if ndat & mask == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        let frame = self.read_rx(RxSection::Buffer, index);
+        // SAFETY: New data flags are cleared by writing ones
+        let regs = self.regs();
+        if index < 32 {
+            regs.ndat1.write(|w| unsafe { w.bits(mask) });
+        } else {
+            regs.ndat2.write(|w| unsafe { w.bits(mask) });
+        }
+        Ok(frame)
+    }
+
+    #[inline]
+    fn read_rx(&self, section: RxSection, index: usize) -> Frame {
+        let mut data = [0; 16];
+        let (r0, r1) = self.config.ram.read_rx(section, index, &mut data);
+        Frame::from_rx_element(r0, r1, &data)
+    }
+
+    /// Pop an element from the Tx event FIFO
+    #[inline]
+    pub fn tx_event(&mut self) -> Option<TxEvent> {
+        let status = self.regs().txefs.read();
+        if status.effl().bits() == 0 {
+            return None;
+        }
+        let index = status.efgi().bits();
+        let (e0, e1) = self.config.ram.read_tx_event(index as usize);
+        // SAFETY: The index was read from EFGI
+        self.regs().txefa.write(|w| unsafe { w.efai().bits(index) });
+        Some(TxEvent::from_element(e0, e1))
+    }
+
+    /// Set a standard ID filter element
+    #[inline]
+    pub fn set_standard_filter(
+        &mut self,
+        index: usize,
+        filter: Filter<StandardId>,
+    ) -> Result<(), Error> {
+        self.config.set_standard_filter(index, filter)
+    }
+
+    /// Set an extended ID filter element
+    #[inline]
+    pub fn set_extended_filter(
+        &mut self,
+        index: usize,
+        filter: Filter<ExtendedId>,
+    ) -> Result<(), Error> {
+        self.config.set_extended_filter(index, filter)
+    }
+
+    /// Read the error counters. Resets the error logging counter.
+    #[inline]
+    pub fn error_counters(&self) -> ErrorCounters {
+        let ecr = self.regs().ecr.read();
+        ErrorCounters {
+            transmit: ecr.tec().bits(),
+            receive: ecr.rec().bits(),
+            receive_passive: ecr.rp().bit(),
+            logging: ecr.cel().bits(),
+        }
+    }
+
+    /// Read the protocol status. Resets the last error codes.
+    #[inline]
+    pub fn protocol_status(&self) -> ProtocolStatus {
+        let psr = self.regs().psr.read();
+        ProtocolStatus {
+            last_error: psr.lec().variant(),
+            data_last_error: decode_lec(psr.dlec().bits()),
+            activity: psr.act().variant(),
+            error_passive: psr.ep().bit(),
+            warning: psr.ew().bit(),
+            bus_off: psr.bo().bit(),
+        }
+    }
+
+    /// Returns `true` if the node is bus-off
+    #[inline]
+    pub fn is_bus_off(&self) -> bool {
+        self.regs().psr.read().bo().bit()
+    }
+
+    /// Start the bus-off recovery sequence
+    ///
+    /// Entering the bus-off state sets `CCCR.INIT`, which stops all bus
+    /// activity. Clearing it lets the node rejoin the bus after monitoring 128
+    /// occurrences of 11 consecutive recessive bits.
+    #[inline]
+    pub fn recover(&mut self) {
+        self.regs().cccr.modify(|_, w| w.init().clear_bit());
+    }
+
+    /// Enable interrupts
+    #[inline]
+    pub fn enable_interrupts(&mut self, flags: Interrupts) {
+        // SAFETY: `Interrupts` only contains valid bits
+        self.regs()
+            .ie
+            .modify(|r, w| unsafe { w.bits(r.bits() | flags.bits()) });
+    }
+
+    /// Disable interrupts
+    #[inline]
+    pub fn disable_interrupts(&mut self, flags: Interrupts) {
+        // SAFETY: `Interrupts` only contains valid bits
+        self.regs()
+            .ie
+            .modify(|r, w| unsafe { w.bits(r.bits() & !flags.bits()) });
+    }
+
+    /// Read the interrupt flags
+    #[inline]
+    pub fn read_interrupts(&self) -> Interrupts {
+        Interrupts::from_bits_truncate(self.regs().ir.read().bits())
+    }
+
+    /// Clear interrupt flags
+    #[inline]
+    pub fn clear_interrupts(&mut self, flags: Interrupts) {
+        // SAFETY: Flags are cleared by writing ones
+        self.regs().ir.write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Route interrupts to an interrupt line, and enable that line
+    ///
+    /// Interrupts are routed to line 0 by default.
+    #[inline]
+    pub fn select_interrupt_line(&mut self, flags: Interrupts, line: InterruptLine) {
+        let regs = self.regs();
+        // SAFETY: `Interrupts` only contains valid bits
+        regs.ils.modify(|r, w| unsafe {
+            match line {
+                InterruptLine::Line0 => w.bits(r.bits() & !flags.bits()),
+                InterruptLine::Line1 => w.bits(r.bits() | flags.bits()),
+            }
+        });
+        regs.ile.modify(|_, w| match line {
+            InterruptLine::Line0 => w.eint0().set_bit(),
+            InterruptLine::Line1 => w.eint1().set_bit(),
+        });
+    }
+}
+
+/// Decode the `DLEC` field, which shares the encoding of `LEC`
+#[inline]
+fn decode_lec(bits: u8) -> LastErrorCode {
+    match bits {
+        0 => LastErrorCode::NONE,
+        1 => LastErrorCode::STUFF,
+        2 => LastErrorCode::FORM,
+        3 => LastErrorCode::ACK,
+        4 => LastErrorCode::BIT1,
+        5 => LastErrorCode::BIT0,
+        6 => LastErrorCode::CRC,
+        _ => LastErrorCode::NC,
+    }
+}
+
+impl<C, TX, RX> can::nb::Can for Can<C, TX, RX>
+where
+    C: Instance,
+    TX: GetTxPin<C>,
+    RX: GetRxPin<C>,
+{
+    type Frame = Frame;
+    type Error = Error;
+
+    /// Queue a frame in the Tx FIFO/queue. Never replaces pending frames.
+    #[inline]
+    fn transmit(&mut self, frame: &Frame) -> nb::Result<Option<Frame>, Error> {
+        self.transmit_queued(frame, 0).map(|_| None)
+    }
+
+    /// Receive a frame from Rx FIFO 0 or, if it is empty, from Rx FIFO 1
+    #[inline]
+    fn receive(&mut self) -> nb::Result<Frame, Error> {
+        match self.receive_fifo0() {
+            Err(nb::Error::WouldBlock) => self.receive_fifo1(),
+            result => result,
+        }
+    }
+}
@@ -0,0 +1,507 @@
+//! # Configurable Custom Logic
+//!
+//! The CCL contains four 3-input look-up tables (LUTs). Each LUT computes an
+//! arbitrary boolean function of its inputs, described by a [`TruthTable`].
+//! Each pair of LUTs can additionally drive a sequential element, such as a
+//! flip-flop or a latch.
+//!
+//! The [`Ccl`] is created from the peripheral and its GCLK token, which also
+//! returns a [`LutToken`] for each LUT. Enabling a token with a [`LutConfig`]
+//! yields a running [`Lut`]:
+//!
+//! ```
+//! use atsamd_hal::ccl::{Ccl, Input, LutConfig, TruthTable};
+//!
+//! let clock = clocks.ccl(&gclk0).unwrap();
+//! let (mut ccl, luts) = Ccl::new(&mut peripherals.MCLK, peripherals.CCL, clock);
+//!
+//! // Gate the SPI MOSI signal with a TCC waveform
+//! let config = LutConfig::new(TruthTable::IN0 & TruthTable::IN1)
+//!     .input(0, Input::SERCOM)
+//!     .input(1, Input::TCC);
+//! let lut0 = ccl.enable_lut(luts.lut0, config);
+//! let _out = lut0.output_pin(pins.pa07);
+//! ```
+//!
+//! The GCLK is only required for the filters, the edge detectors and the
+//! sequential elements. Purely combinatorial LUTs run without a clock.
+//!
+//! ## Inputs
+//!
+//! Each LUT input is selected with an [`Input`]:
+//!
+//! * [`Input::IO`] uses the LUT's `IN` pin, see [`Lut::input_pin`]
+//! * [`Input::EVENT`] uses the LUT's event input, see
+//!   [`LutConfig::event_input`]
+//! * [`Input::FEEDBACK`] uses the LUT's own output, or the output of its
+//!   sequential element
+//! * [`Input::LINK`] uses the output of the next LUT (LUT0 for LUT3)
+//! * [`Input::AC`], [`Input::TC`], [`Input::ALTTC`], [`Input::TCC`] and
+//!   [`Input::SERCOM`] use the peripheral signal associated with the LUT and
+//!   input index, as listed in the datasheet
+//! * [`Input::MASK`] disables the input, which then reads as `false`
+
+use core::marker::PhantomData;
+use core::ops::{BitAnd, BitOr, BitXor, Not};
+
+use crate::gpio::v2::{AlternateN, AnyPin, Pin, PinId, SpecificPin};
+use crate::pac::{ccl, CCL, MCLK};
+use crate::typelevel::Sealed;
+
+pub use crate::clock::CclClock;
+
+/// Input source of a LUT
+pub use ccl::lutctrl::INSEL0_A as Input;
+
+/// Input filter of a LUT output
+pub use ccl::lutctrl::FILTSEL_A as Filter;
+
+/// Sequential element driven by a pair of LUTs
+pub use ccl::seqctrl::SEQSEL_A as Sequential;
+
+//==============================================================================
+//  TruthTable
+//==============================================================================
+
+/// LUT truth table
+///
+/// Bit `n` of the table is the LUT output when the inputs, read as the binary
+/// number `IN2 IN1 IN0`, equal `n`.
+///
+/// Truth tables can be combined with the boolean operators, starting from the
+/// [`IN0`](Self::IN0), [`IN1`](Self::IN1) and [`IN2`](Self::IN2) tables:
+///
+/// ```
+/// let table = (TruthTable::IN0 & TruthTable::IN1) | !TruthTable::IN2;
+/// ```
+///
+/// They can also be built from a closure with [`TruthTable::from_fn`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TruthTable(u8);
+
+impl TruthTable {
+    /// Output `IN0`
+    pub const IN0: Self = Self(0b1010_1010);
+    /// Output `IN1`
+    pub const IN1: Self = Self(0b1100_1100);
+    /// Output `IN2`
+    pub const IN2: Self = Self(0b1111_0000);
+    /// Always output `false`
+    pub const FALSE: Self = Self(0);
+    /// Always output `true`
+    pub const TRUE: Self = Self(0xFF);
+
+    /// Create a truth table from its raw value
+    #[inline]
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Create a truth table by evaluating `f(in0, in1, in2)` for every
+    /// combination of inputs
+    #[inline]
+    pub fn from_fn<F>(f: F) -> Self
+    where
+        F: Fn(bool, bool, bool) -> bool,
+    {
+        let bits = (0..8).fold(0, |bits, n| {
+            let output = f(n & 1 != 0, n & 2 != 0, n & 4 != 0);
+            bits | (output as u8) << n
+        });
+        Self(bits)
+    }
+
+    /// Get the raw value of the truth table
+    #[inline]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+}
+
+impl BitAnd for TruthTable {
+    type Output = Self;
+    #[inline]
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for TruthTable {
+    type Output = Self;
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitXor for TruthTable {
+    type Output = Self;
+    #[inline]
+    fn bitxor(self, rhs: Self) -> Self {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl Not for TruthTable {
+    type Output = Self;
+    #[inline]
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+//==============================================================================
+//  LUT IDs and pins
+//==============================================================================
+
+/// Type-level enum of the LUTs
+pub trait LutId: Sealed {
+    /// LUT number
+    const NUM: usize;
+}
+
+macro_rules! lut_id {
+    ($($Lut:ident: $num:literal),+) => {
+        $(
+            #[doc = concat!("LUT ", stringify!($num))]
+            pub enum $Lut {}
+            impl Sealed for $Lut {}
+            impl LutId for $Lut {
+                const NUM: usize = $num;
+            }
+        )+
+    };
+}
+
+lut_id!(Lut0: 0, Lut1: 1, Lut2: 2, Lut3: 3);
+
+/// [`PinId`]s that can be used as a LUT `IN` pin
+pub trait InPin: PinId {
+    /// LUT using this pin
+    type Lut: LutId;
+    /// Index of the LUT input
+    const INDEX: usize;
+}
+
+/// [`PinId`]s that can be used as a LUT `OUT` pin
+pub trait OutPin: PinId {
+    /// LUT driving this pin
+    type Lut: LutId;
+}
+
+macro_rules! in_pin {
+    ($($(#[$cfg:meta])* $Id:ident: $Lut:ident, $index:literal;)+) => {
+        $(
+            $(#[$cfg])*
+            impl InPin for crate::gpio::v2::$Id {
+                type Lut = $Lut;
+                const INDEX: usize = $index;
+            }
+        )+
+    };
+}
+
+macro_rules! out_pin {
+    ($($(#[$cfg:meta])* $Id:ident: $Lut:ident;)+) => {
+        $(
+            $(#[$cfg])*
+            impl OutPin for crate::gpio::v2::$Id {
+                type Lut = $Lut;
+            }
+        )+
+    };
+}
+
+in_pin! {
+    PA04: Lut0, 0;
+    PA05: Lut0, 1;
+    PA06: Lut0, 2;
+    PB22: Lut0, 0;
+    PA08: Lut1, 0;
+    PA09: Lut1, 1;
+    PA10: Lut1, 2;
+    PA22: Lut2, 0;
+    PA23: Lut2, 1;
+    PA24: Lut2, 2;
+    #[cfg(feature = "min-samd51j")]
+    PB14: Lut3, 0;
+    #[cfg(feature = "min-samd51j")]
+    PB15: Lut3, 1;
+    #[cfg(feature = "min-samd51j")]
+    PB16: Lut3, 2;
+}
+
+out_pin! {
+    PA07: Lut0;
+    PB23: Lut0;
+    PA11: Lut1;
+    PA25: Lut2;
+    #[cfg(feature = "min-samd51j")]
+    PB17: Lut3;
+}
+
+//==============================================================================
+//  LutConfig
+//==============================================================================
+
+/// Configuration of a LUT
+#[derive(Clone, Copy, Debug)]
+pub struct LutConfig {
+    truth: TruthTable,
+    inputs: [Input; 3],
+    filter: Filter,
+    edge_detection: bool,
+    event_input: bool,
+    invert_event_input: bool,
+    event_output: bool,
+}
+
+impl LutConfig {
+    /// Create a new `LutConfig` with the given truth table. The default
+    /// configuration is:
+    /// * All inputs masked
+    /// * No filter nor edge detection
+    /// * No event input or output
+    #[inline]
+    pub const fn new(truth: TruthTable) -> Self {
+        Self {
+            truth,
+            inputs: [Input::MASK; 3],
+            filter: Filter::DISABLE,
+            edge_detection: false,
+            event_input: false,
+            invert_event_input: false,
+            event_output: false,
+        }
+    }
+
+    /// Select the source of input `index`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not 0, 1 or 2.
+    #[inline]
+    pub fn input(mut self, index: usize, input: Input) -> Self {
+        self.inputs[index] = input;
+        self
+    }
+
+    /// Filter the LUT output
+    ///
+    /// The synchronizer delays the output by two GCLK cycles. The filter
+    /// additionally removes pulses shorter than a GCLK cycle.
+    #[inline]
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Output a pulse of one GCLK cycle on each rising edge of the LUT output,
+    /// instead of its level. Inverting the truth table detects falling edges.
+    #[inline]
+    pub fn edge_detection(mut self, enabled: bool) -> Self {
+        self.edge_detection = enabled;
+        self
+    }
+
+    /// Enable the LUT event input, optionally inverted
+    ///
+    /// The event is used by inputs selected as [`Input::EVENT`].
+    #[inline]
+    pub fn event_input(mut self, enabled: bool, inverted: bool) -> Self {
+        self.event_input = enabled;
+        self.invert_event_input = inverted;
+        self
+    }
+
+    /// Generate an event from the LUT output
+    #[inline]
+    pub fn event_output(mut self, enabled: bool) -> Self {
+        self.event_output = enabled;
+        self
+    }
+}
+
+//==============================================================================
+//  Ccl
+//==============================================================================
+
+/// Pair of LUTs driving a sequential element
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SequentialId {
+    /// Sequential element driven by LUT0 and LUT1
+    Seq0 = 0,
+    /// Sequential element driven by LUT2 and LUT3
+    Seq1 = 1,
+}
+
+/// Token representing a disabled LUT
+pub struct LutToken<L: LutId> {
+    _lut: PhantomData<L>,
+}
+
+impl<L: LutId> LutToken<L> {
+    #[inline]
+    fn new() -> Self {
+        Self { _lut: PhantomData }
+    }
+}
+
+/// Tokens for each LUT, returned by [`Ccl::new`]
+pub struct Luts {
+    pub lut0: LutToken<Lut0>,
+    pub lut1: LutToken<Lut1>,
+    pub lut2: LutToken<Lut2>,
+    pub lut3: LutToken<Lut3>,
+}
+
+/// The CCL peripheral
+pub struct Ccl {
+    ccl: CCL,
+}
+
+impl Ccl {
+    /// Reset and enable the CCL
+    #[inline]
+    pub fn new(mclk: &mut MCLK, ccl: CCL, _clock: CclClock) -> (Self, Luts) {
+        mclk.apbcmask.modify(|_, w| w.ccl_().set_bit());
+
+        ccl.ctrl.write(|w| w.swrst().set_bit());
+        while ccl.ctrl.read().swrst().bit_is_set() {}
+        ccl.ctrl.write(|w| w.enable().set_bit());
+
+        let luts = Luts {
+            lut0: LutToken::new(),
+            lut1: LutToken::new(),
+            lut2: LutToken::new(),
+            lut3: LutToken::new(),
+        };
+        (Self { ccl }, luts)
+    }
+
+    /// Run a closure with the CCL disabled
+    ///
+    /// The LUT and sequential configurations are enable-protected. Every LUT
+    /// output is briefly interrupted while they are modified.
+    #[inline]
+    fn with_disabled<T>(&mut self, f: impl FnOnce(&CCL) -> T) -> T {
+        self.ccl.ctrl.modify(|_, w| w.enable().clear_bit());
+        let result = f(&self.ccl);
+        self.ccl.ctrl.modify(|_, w| w.enable().set_bit());
+        result
+    }
+
+    /// Keep the CCL running in standby sleep mode
+    #[inline]
+    pub fn set_run_standby(&mut self, run_standby: bool) {
+        self.with_disabled(|ccl| ccl.ctrl.modify(|_, w| w.runstdby().bit(run_standby)));
+    }
+
+    /// Select the sequential element driven by a pair of LUTs
+    ///
+    /// The even LUT drives the `D`, `J`, `G` or `S` input, and the odd LUT
+    /// drives the `K` or `R` input. The element output replaces the output of
+    /// the even LUT.
+    #[inline]
+    pub fn set_sequential(&mut self, id: SequentialId, sequential: Sequential) {
+        self.with_disabled(|ccl| {
+            ccl.seqctrl[id as usize].write(|w| w.seqsel().variant(sequential))
+        });
+    }
+
+    /// Configure and enable a LUT
+    #[inline]
+    pub fn enable_lut<L: LutId>(&mut self, _token: LutToken<L>, config: LutConfig) -> Lut<L> {
+        let [in0, in1, in2] = config.inputs;
+        self.with_disabled(|ccl| {
+            ccl.lutctrl[L::NUM].write(|w| {
+                // SAFETY: All three input selection fields share the same
+                // encoding, and every truth table value is valid
+                unsafe {
+                    w.insel0().bits(in0.into());
+                    w.insel1().bits(in1.into());
+                    w.insel2().bits(in2.into());
+                    w.truth().bits(config.truth.bits());
+                }
+                w.filtsel().variant(config.filter);
+                w.edgesel().bit(config.edge_detection);
+                w.lutei().bit(config.event_input);
+                w.invei().bit(config.invert_event_input);
+                w.luteo().bit(config.event_output);
+                w.enable().set_bit()
+            })
+        });
+        Lut { _lut: PhantomData }
+    }
+
+    /// Disable a LUT
+    #[inline]
+    pub fn disable_lut<L: LutId>(&mut self, _lut: Lut<L>) -> LutToken<L> {
+        self.with_disabled(|ccl| ccl.lutctrl[L::NUM].reset());
+        LutToken::new()
+    }
+
+    /// Reset the CCL, disable it and return the underlying peripheral
+    #[inline]
+    pub fn free(self, _luts: Luts, mclk: &mut MCLK) -> CCL {
+        self.ccl.ctrl.write(|w| w.swrst().set_bit());
+        while self.ccl.ctrl.read().swrst().bit_is_set() {}
+        mclk.apbcmask.modify(|_, w| w.ccl_().clear_bit());
+        self.ccl
+    }
+}
+
+//==============================================================================
+//  Lut
+//==============================================================================
+
+/// An enabled LUT
+pub struct Lut<L: LutId> {
+    _lut: PhantomData<L>,
+}
+
+impl<L: LutId> Lut<L> {
+    /// Convert a pin to be used as a LUT input
+    ///
+    /// The corresponding input must be selected as [`Input::IO`].
+    #[inline]
+    pub fn input_pin<P>(&self, pin: P) -> Pin<P::Id, AlternateN>
+    where
+        P: AnyPin,
+        P::Id: InPin<Lut = L>,
+    {
+        let pin: SpecificPin<P> = pin.into();
+        pin.into_mode()
+    }
+
+    /// Convert a pin to be driven by the LUT output
+    #[inline]
+    pub fn output_pin<P>(&self, pin: P) -> Pin<P::Id, AlternateN>
+    where
+        P: AnyPin,
+        P::Id: OutPin<Lut = L>,
+    {
+        let pin: SpecificPin<P> = pin.into();
+        pin.into_mode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TruthTable;
+
+    #[test]
+    fn truth_table_from_fn() {
+        let and = TruthTable::from_fn(|a, b, _| a && b);
+        assert_eq!(and, TruthTable::IN0 & TruthTable::IN1);
+        assert_eq!(and.bits(), 0b1000_1000);
+
+        let mux = TruthTable::from_fn(|a, b, sel| if sel { b } else { a });
+        let expr = (TruthTable::IN0 & !TruthTable::IN2) | (TruthTable::IN1 & TruthTable::IN2);
+        assert_eq!(mux, expr);
+
+        assert_eq!(TruthTable::from_fn(|_, _, _| true), TruthTable::TRUE);
+        assert_eq!(TruthTable::from_fn(|a, _, _| a), TruthTable::IN0);
+        assert_eq!(TruthTable::from_fn(|_, _, c| c), TruthTable::IN2);
+    }
+}
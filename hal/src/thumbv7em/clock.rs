@@ -80,6 +80,18 @@ pub struct GClock {
     freq: Hertz,
 }
 
+impl GClock {
+    /// Returns the ID of the clock generator
+    pub fn id(&self) -> ClockGenId {
+        self.gclk
+    }
+
+    /// Returns the frequency of the clock generator
+    pub fn freq(&self) -> Hertz {
+        self.freq
+    }
+}
+
 impl Into<Hertz> for GClock {
     fn into(self) -> Hertz {
         self.freq
@@ -324,7 +336,10 @@ impl GenericClockController {
             XOSC0 | XOSC1 | GCLKIN | DPLL1 => unimplemented!(),
         };
         self.gclks[idx] = Hertz(freq.0 / divider as u32);
-        Some(GClock { gclk, freq })
+        Some(GClock {
+            gclk,
+            freq: self.gclks[idx],
+        })
     }
 
     /// Enables or disables the given GClk from operation in standby.
@@ -0,0 +1,182 @@
+//! # Cortex-M Cache Controller (CMCC)
+//!
+//! The CMCC is a 4kB, 4-way set associative cache, with 16-byte lines, for
+//! the instruction and data accesses of the CPU to the flash and to the QSPI
+//! memory. It greatly speeds up code executed in place from a QSPI flash
+//! (see [`Qspi<XIP>`](crate::qspi::Qspi)), and from the internal flash when
+//! it runs with wait states.
+//!
+//! The cache is not coherent with writes to the cached memories. The
+//! [`Nvm`](crate::nvm::Nvm) invalidates it after every erase and write;
+//! anything else writing to the flash or to the QSPI memory must call
+//! [`Cmcc::invalidate_all`].
+//!
+//! ```
+//! use atsamd_hal::cmcc::{Cmcc, MonitorMode};
+//!
+//! let mut cmcc = Cmcc::new(&mut peripherals.MCLK, peripherals.CMCC);
+//! cmcc.start_monitor(MonitorMode::IHIT_COUNT);
+//! cmcc.enable();
+//!
+//! // ...
+//!
+//! let hits = cmcc.monitor_count();
+//! ```
+
+use crate::pac::{cmcc, CMCC, MCLK};
+
+/// Size of the cache, the rest of the cache memory being unused
+pub use cmcc::cfg::CSIZESW_A as CacheSize;
+/// Events counted by the monitor
+pub use cmcc::mcfg::MODE_A as MonitorMode;
+
+/// Number of ways of the cache
+pub const WAYS: u8 = 4;
+
+/// Number of lines in each way of the 4kB cache
+pub const LINES_PER_WAY: u8 = 64;
+
+//==============================================================================
+//  Cmcc
+//==============================================================================
+
+/// CMCC peripheral
+pub struct Cmcc {
+    cmcc: CMCC,
+}
+
+impl Cmcc {
+    /// Take the CMCC
+    ///
+    /// The cache is left in its current state, disabled after a reset.
+    #[inline]
+    pub fn new(mclk: &mut MCLK, cmcc: CMCC) -> Self {
+        mclk.ahbmask.modify(|_, w| w.cmcc_().set_bit());
+        Self { cmcc }
+    }
+
+    /// Enable the cache
+    #[inline]
+    pub fn enable(&mut self) {
+        self.cmcc.ctrl.write(|w| w.cen().set_bit());
+    }
+
+    /// Disable the cache
+    #[inline]
+    pub fn disable(&mut self) {
+        self.cmcc.ctrl.write(|w| w.cen().clear_bit());
+        while self.cmcc.sr.read().csts().bit_is_set() {}
+    }
+
+    /// Whether the cache is enabled
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.cmcc.sr.read().csts().bit_is_set()
+    }
+
+    /// Run `f` with the cache disabled, and restore its previous state
+    #[inline]
+    fn while_disabled<T>(&mut self, f: impl FnOnce(&CMCC) -> T) -> T {
+        let enabled = self.is_enabled();
+        if enabled {
+            self.disable();
+        }
+        let result = f(&self.cmcc);
+        if enabled {
+            self.enable();
+        }
+        result
+    }
+
+    /// Enable or disable the caching of instructions and of data
+    #[inline]
+    pub fn set_caches(&mut self, instructions: bool, data: bool) {
+        self.while_disabled(|cmcc| {
+            cmcc.cfg.modify(|_, w| {
+                w.icdis().bit(!instructions);
+                w.dcdis().bit(!data)
+            })
+        });
+    }
+
+    /// Reduce the size of the cache
+    ///
+    /// The cache is invalidated.
+    #[inline]
+    pub fn set_size(&mut self, size: CacheSize) {
+        self.while_disabled(|cmcc| {
+            cmcc.cfg.modify(|_, w| w.csizesw().variant(size));
+            cmcc.maint0.write(|w| w.invall().set_bit());
+        });
+    }
+
+    /// Lock ways of the cache, so that their lines are never replaced
+    ///
+    /// Bit `n` of `ways` locks way `n`; the other ways are unlocked. The lines
+    /// to keep in the cache are loaded by accessing them while only their way
+    /// is unlocked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if all the ways are locked.
+    #[inline]
+    pub fn lock_ways(&mut self, ways: u8) {
+        assert!(ways & 0xF != 0xF);
+        // SAFETY: The mask was checked to leave a way unlocked
+        self.cmcc
+            .lckway
+            .write(|w| unsafe { w.lckway().bits(ways & 0xF) });
+    }
+
+    /// Invalidate the whole cache
+    #[inline]
+    pub fn invalidate_all(&mut self) {
+        self.while_disabled(|cmcc| cmcc.maint0.write(|w| w.invall().set_bit()));
+    }
+
+    /// Invalidate a single line of the cache
+    ///
+    /// # Panics
+    ///
+    /// Panics if `way` or `index` are out of range.
+    #[inline]
+    pub fn invalidate_line(&mut self, way: u8, index: u8) {
+        assert!(way < WAYS && index < LINES_PER_WAY);
+        self.while_disabled(|cmcc| {
+            // SAFETY: The way and index were checked above
+            cmcc.maint1.write(|w| unsafe {
+                w.way().bits(way);
+                w.index().bits(index)
+            })
+        });
+    }
+
+    /// Reset the counter of the monitor, and start counting `mode` events
+    #[inline]
+    pub fn start_monitor(&mut self, mode: MonitorMode) {
+        self.cmcc.men.write(|w| w.menable().clear_bit());
+        self.cmcc.mcfg.write(|w| w.mode().variant(mode));
+        self.cmcc.mctrl.write(|w| w.swrst().set_bit());
+        self.cmcc.men.write(|w| w.menable().set_bit());
+    }
+
+    /// Stop the monitor
+    #[inline]
+    pub fn stop_monitor(&mut self) {
+        self.cmcc.men.write(|w| w.menable().clear_bit());
+    }
+
+    /// Read the counter of the monitor
+    #[inline]
+    pub fn monitor_count(&self) -> u32 {
+        self.cmcc.msr.read().bits()
+    }
+
+    /// Return the underlying CMCC
+    ///
+    /// The cache is left in its current state.
+    #[inline]
+    pub fn free(self) -> CMCC {
+        self.cmcc
+    }
+}
@@ -0,0 +1,576 @@
+//! # Digital-to-Analog Converter
+//!
+//! The DAC contains two 12-bit channels, driving `VOUT0` ([`PA02`]) and
+//! `VOUT1` ([`PA05`]). Both channels share the same voltage reference, and
+//! can be combined into a single differential output.
+//!
+//! A [`Dac`] is created from the peripheral and its GCLK token. Each channel
+//! is then enabled individually, which yields a [`Channel`] owning the
+//! corresponding output pin:
+//!
+//! ```
+//! use atsamd_hal::dac::{ChannelConfig, Dac, Reference};
+//!
+//! let clock = clocks.dac(&gclk1).unwrap();
+//! let mut dac = Dac::new(&mut mclk, peripherals.DAC, clock, Reference::VDDANA);
+//! let mut vout0 = dac.enable_channel(pins.pa02, ChannelConfig::new());
+//! vout0.write(2048);
+//! ```
+//!
+//! ## Clocking
+//!
+//! Each conversion takes 12 `GCLK_DAC` cycles, so the maximum conversion
+//! rate is one twelfth of the GCLK frequency, which must not exceed 12 MHz.
+//! Unless specified in the [`ChannelConfig`], the output current of each
+//! channel is selected from the GCLK frequency.
+//!
+//! ## Event-triggered conversions
+//!
+//! When [`ChannelConfig::start_event`] is set, [`Channel::write`] stores the
+//! value in the data buffer of the channel instead, and the conversion only
+//! starts with the next `START` event from the event system. Setting
+//! [`ChannelConfig::empty_event_output`] generates an `EMPTY` event whenever
+//! the data buffer can accept a new value.
+//!
+//! ## Sample playback
+//!
+//! With the `dma` feature, a [`Channel`] can be fed by the DMAC, one sample
+//! per DMA trigger. Using a TC overflow as the trigger source plays a
+//! circular buffer back at the TC rate:
+//!
+//! ```
+//! use atsamd_hal::dmac::TriggerSource;
+//!
+//! static mut SAMPLES: [u16; 64] = [0; 64];
+//!
+//! let transfer = vout0.send_with_dma(
+//!     unsafe { &mut SAMPLES },
+//!     dma_channel,
+//!     TriggerSource::TC0_OVF,
+//!     true,
+//!     |_| {},
+//! );
+//! ```
+//!
+//! When the channel is paced by `START` events instead, the DMAC should be
+//! triggered by [`TriggerSource::DAC_EMPTY_0`] or
+//! [`TriggerSource::DAC_EMPTY_1`].
+//!
+//! [`TriggerSource::DAC_EMPTY_0`]: crate::dmac::TriggerSource::DAC_EMPTY_0
+//! [`TriggerSource::DAC_EMPTY_1`]: crate::dmac::TriggerSource::DAC_EMPTY_1
+
+use core::marker::PhantomData;
+
+use crate::gpio::v2::{AlternateB, AnyPin, Pin, PinId, SpecificPin, PA02, PA05};
+use crate::pac::{dac, DAC, MCLK};
+use crate::time::Hertz;
+use crate::typelevel::Sealed;
+
+#[cfg(feature = "dma")]
+use crate::dmac::{
+    self,
+    channel::{AnyChannel, Busy, CallbackStatus, Channel as DmaChannel, InterruptFlags, Ready},
+    transfer::BufferPair,
+    Buffer, Transfer, TriggerAction, TriggerSource,
+};
+
+pub use crate::clock::DacClock;
+
+/// Reference voltage (or its source)
+///
+/// The voltage of the internal bandgap reference is selected with
+/// [`Supc::set_reference`](crate::supc::Supc::set_reference).
+pub use dac::ctrlb::REFSEL_A as Reference;
+/// Output current control
+pub use dac::dacctrl::CCTRL_A as CurrentControl;
+/// Interpolation filter oversampling ratio
+pub use dac::dacctrl::OSR_A as Oversampling;
+/// Output refresh period
+pub use dac::dacctrl::REFRESH_A as Refresh;
+
+/// Maximum frequency of `GCLK_DAC`
+pub const MAX_CLOCK: Hertz = Hertz(12_000_000);
+
+/// Output current matching the conversion rate allowed by the GCLK frequency
+#[inline]
+fn current_for(freq: Hertz) -> CurrentControl {
+    if freq.0 <= 1_200_000 {
+        CurrentControl::CC100K
+    } else if freq.0 <= 6_000_000 {
+        CurrentControl::CC1M
+    } else {
+        CurrentControl::CC12M
+    }
+}
+
+//==============================================================================
+//  ChannelId
+//==============================================================================
+
+/// Type-level `enum` representing a DAC channel
+pub trait ChannelId: Sealed {
+    /// Channel number
+    const NUM: usize;
+    /// Output pin of the channel
+    type Pin: VoutPin<Channel = Self>;
+}
+
+/// Type-level variant of [`ChannelId`] for channel 0
+pub enum Dac0 {}
+impl Sealed for Dac0 {}
+impl ChannelId for Dac0 {
+    const NUM: usize = 0;
+    type Pin = PA02;
+}
+
+/// Type-level variant of [`ChannelId`] for channel 1
+pub enum Dac1 {}
+impl Sealed for Dac1 {}
+impl ChannelId for Dac1 {
+    const NUM: usize = 1;
+    type Pin = PA05;
+}
+
+/// Type-level function mapping a [`PinId`] to the DAC channel it outputs
+pub trait VoutPin: PinId {
+    /// Channel driving this pin
+    type Channel: ChannelId<Pin = Self>;
+}
+
+impl VoutPin for PA02 {
+    type Channel = Dac0;
+}
+
+impl VoutPin for PA05 {
+    type Channel = Dac1;
+}
+
+//==============================================================================
+//  ChannelConfig
+//==============================================================================
+
+/// Configuration of a DAC channel
+#[derive(Clone, Copy, Debug)]
+pub struct ChannelConfig {
+    current: Option<CurrentControl>,
+    oversampling: Oversampling,
+    refresh: Refresh,
+    dither: bool,
+    left_adjusted: bool,
+    run_standby: bool,
+    start_event: bool,
+    invert_start_event: bool,
+    empty_event_output: bool,
+}
+
+impl ChannelConfig {
+    /// Create a new `ChannelConfig`. The default configuration is:
+    /// * Output current selected from the GCLK frequency
+    /// * No oversampling, no dithering
+    /// * Output refreshed every 60 µs
+    /// * Right-adjusted data
+    /// * Conversions started by [`Channel::write`]
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            current: None,
+            oversampling: Oversampling::OSR_1,
+            refresh: Refresh::REFRESH_2,
+            dither: false,
+            left_adjusted: false,
+            run_standby: false,
+            start_event: false,
+            invert_start_event: false,
+            empty_event_output: false,
+        }
+    }
+
+    /// Force the output current, instead of deriving it from the GCLK
+    /// frequency
+    #[inline]
+    pub fn current(mut self, current: CurrentControl) -> Self {
+        self.current = Some(current);
+        self
+    }
+
+    /// Set the oversampling ratio of the interpolation filter
+    ///
+    /// Each value is then converted `ratio` times, so the data rate is
+    /// divided accordingly.
+    #[inline]
+    pub fn oversampling(mut self, oversampling: Oversampling) -> Self {
+        self.oversampling = oversampling;
+        self
+    }
+
+    /// Set the refresh period of the output
+    ///
+    /// The output is sampled and held, so a static value must be refreshed
+    /// periodically to prevent it from drifting.
+    #[inline]
+    pub fn refresh(mut self, refresh: Refresh) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Enable dithering
+    ///
+    /// In dithering mode, the 16-bit data is split into a 12-bit value and a
+    /// 4-bit dither, which is averaged by the interpolation filter.
+    #[inline]
+    pub fn dither(mut self, dither: bool) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// Left-adjust the data, so that the 12-bit value occupies the upper bits
+    /// of the 16-bit data register
+    #[inline]
+    pub fn left_adjusted(mut self, left_adjusted: bool) -> Self {
+        self.left_adjusted = left_adjusted;
+        self
+    }
+
+    /// Keep the channel running in standby sleep mode
+    #[inline]
+    pub fn run_standby(mut self, run_standby: bool) -> Self {
+        self.run_standby = run_standby;
+        self
+    }
+
+    /// Start conversions on the `START` event input, optionally inverted
+    #[inline]
+    pub fn start_event(mut self, enabled: bool, inverted: bool) -> Self {
+        self.start_event = enabled;
+        self.invert_start_event = inverted;
+        self
+    }
+
+    /// Generate an `EMPTY` event when the data buffer is empty
+    #[inline]
+    pub fn empty_event_output(mut self, enabled: bool) -> Self {
+        self.empty_event_output = enabled;
+        self
+    }
+}
+
+impl Default for ChannelConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//==============================================================================
+//  Dac
+//==============================================================================
+
+/// The DAC peripheral
+///
+/// Most settings can only be changed while the DAC is disabled. Changing
+/// them, or enabling another channel, therefore briefly interrupts the
+/// output of the channels already enabled.
+pub struct Dac {
+    dac: DAC,
+    freq: Hertz,
+}
+
+impl Dac {
+    /// Reset the DAC and select its voltage reference
+    ///
+    /// # Panics
+    ///
+    /// Panics if the GCLK frequency exceeds [`MAX_CLOCK`].
+    #[inline]
+    pub fn new(mclk: &mut MCLK, dac: DAC, clock: DacClock, reference: Reference) -> Self {
+        let freq: Hertz = clock.into();
+        assert!(freq.0 <= MAX_CLOCK.0, "GCLK_DAC must not exceed 12 MHz");
+
+        mclk.apbdmask.modify(|_, w| w.dac_().set_bit());
+
+        dac.ctrla.write(|w| w.swrst().set_bit());
+        while dac.syncbusy.read().swrst().bit_is_set() {}
+        dac.ctrlb.write(|w| w.refsel().variant(reference));
+
+        Self { dac, freq }
+    }
+
+    /// Run `f` with the DAC disabled, then enable it again if needed
+    #[inline]
+    fn with_disabled<R>(&mut self, f: impl FnOnce(&DAC) -> R) -> R {
+        let enabled = self.dac.ctrla.read().enable().bit_is_set();
+        if enabled {
+            self.dac.ctrla.write(|w| w.enable().clear_bit());
+            while self.dac.syncbusy.read().enable().bit_is_set() {}
+        }
+        let result = f(&self.dac);
+        if self
+            .dac
+            .dacctrl
+            .iter()
+            .any(|c| c.read().enable().bit_is_set())
+        {
+            self.dac.ctrla.write(|w| w.enable().set_bit());
+            while self.dac.syncbusy.read().enable().bit_is_set() {}
+        }
+        result
+    }
+
+    /// Wait for the enabled channels to be ready
+    #[inline]
+    fn wait_ready(&self) {
+        for (i, dacctrl) in self.dac.dacctrl.iter().enumerate() {
+            if dacctrl.read().enable().bit_is_set() {
+                while self.dac.status.read().bits() & (1 << i) == 0 {}
+            }
+        }
+    }
+
+    /// Select the voltage reference
+    #[inline]
+    pub fn set_reference(&mut self, reference: Reference) {
+        self.with_disabled(|dac| dac.ctrlb.modify(|_, w| w.refsel().variant(reference)));
+        self.wait_ready();
+    }
+
+    /// Get the voltage reference
+    #[inline]
+    pub fn get_reference(&self) -> Reference {
+        self.dac.ctrlb.read().refsel().variant()
+    }
+
+    /// Enable or disable differential mode
+    ///
+    /// In differential mode, channel 0 drives the difference between
+    /// `VOUT0` and `VOUT1`, and the data written to channel 1 is ignored.
+    /// Both channels must be enabled.
+    #[inline]
+    pub fn set_differential(&mut self, differential: bool) {
+        self.with_disabled(|dac| dac.ctrlb.modify(|_, w| w.diff().bit(differential)));
+        self.wait_ready();
+    }
+
+    /// Returns `true` if differential mode is enabled
+    #[inline]
+    pub fn get_differential(&self) -> bool {
+        self.dac.ctrlb.read().diff().bit()
+    }
+
+    /// Configure and enable the channel driving `pin`
+    #[inline]
+    pub fn enable_channel<P>(
+        &mut self,
+        pin: P,
+        config: ChannelConfig,
+    ) -> Channel<<P::Id as VoutPin>::Channel>
+    where
+        P: AnyPin,
+        P::Id: VoutPin,
+    {
+        let n = <P::Id as VoutPin>::Channel::NUM;
+        let current = config.current.unwrap_or_else(|| current_for(self.freq));
+        let pin: SpecificPin<P> = pin.into();
+
+        self.with_disabled(|dac| {
+            dac.dacctrl[n].write(|w| {
+                w.cctrl().variant(current);
+                w.osr().variant(config.oversampling);
+                w.refresh().variant(config.refresh);
+                w.dither().bit(config.dither);
+                w.leftadj().bit(config.left_adjusted);
+                w.runstdby().bit(config.run_standby);
+                w.enable().set_bit()
+            });
+            dac.evctrl.modify(|r, w| {
+                let mask = 0b0101_0101 << n;
+                let mut bits = r.bits() & !mask;
+                if config.start_event {
+                    bits |= 1 << n;
+                }
+                if config.empty_event_output {
+                    bits |= 1 << (2 + n);
+                }
+                if config.invert_start_event {
+                    bits |= 1 << (4 + n);
+                }
+                unsafe { w.bits(bits) }
+            });
+        });
+        self.wait_ready();
+
+        Channel {
+            pin: pin.into_mode(),
+            buffered: config.start_event,
+            id: PhantomData,
+        }
+    }
+
+    /// Disable a channel and return its pin
+    #[inline]
+    pub fn disable_channel<I: ChannelId>(
+        &mut self,
+        channel: Channel<I>,
+    ) -> Pin<I::Pin, AlternateB> {
+        let n = I::NUM;
+        self.with_disabled(|dac| {
+            dac.dacctrl[n].modify(|_, w| w.enable().clear_bit());
+            dac.evctrl
+                .modify(|r, w| unsafe { w.bits(r.bits() & !(0b0101_0101 << n)) });
+        });
+        self.wait_ready();
+        channel.pin
+    }
+
+    /// Disable the DAC and return the underlying peripheral
+    ///
+    /// Every [`Channel`] should be disabled first.
+    #[inline]
+    pub fn free(self, mclk: &mut MCLK) -> DAC {
+        self.dac.ctrla.write(|w| w.swrst().set_bit());
+        while self.dac.syncbusy.read().swrst().bit_is_set() {}
+        mclk.apbdmask.modify(|_, w| w.dac_().clear_bit());
+        self.dac
+    }
+}
+
+//==============================================================================
+//  Channel
+//==============================================================================
+
+/// An enabled DAC channel
+pub struct Channel<I: ChannelId> {
+    pin: Pin<I::Pin, AlternateB>,
+    buffered: bool,
+    id: PhantomData<I>,
+}
+
+impl<I: ChannelId> Channel<I> {
+    #[inline]
+    fn dac(&self) -> &dac::RegisterBlock {
+        // SAFETY: Each channel only writes to its own data registers, and
+        // interrupt flags are cleared by writing ones
+        unsafe { &*DAC::ptr() }
+    }
+
+    /// Returns `true` if the data buffer can accept a new value
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.dac().intflag.read().bits() & (1 << (2 + I::NUM)) != 0
+    }
+
+    /// Check and clear the underrun flag
+    ///
+    /// An underrun occurs when a `START` event is received while the data
+    /// buffer is empty.
+    #[inline]
+    pub fn underrun(&mut self) -> bool {
+        let flag = 1 << I::NUM;
+        let underrun = self.dac().intflag.read().bits() & flag != 0;
+        if underrun {
+            self.dac().intflag.write(|w| unsafe { w.bits(flag) });
+        }
+        underrun
+    }
+
+    /// Returns `true` once the last conversion is complete
+    #[inline]
+    pub fn is_converted(&self) -> bool {
+        self.dac().status.read().bits() & (1 << (2 + I::NUM)) != 0
+    }
+
+    /// Output a new value
+    ///
+    /// If the channel is started by events, the value is stored in the data
+    /// buffer, and converted on the next `START` event. This method blocks
+    /// until the data buffer is empty. Otherwise, the conversion starts
+    /// immediately, and this method blocks until it completes.
+    #[inline]
+    pub fn write(&mut self, value: u16) {
+        let dac = self.dac();
+        if self.buffered {
+            while !self.is_empty() {}
+            dac.databuf[I::NUM].write(|w| unsafe { w.databuf().bits(value) });
+            while dac.syncbusy.read().bits() & (1 << (4 + I::NUM)) != 0 {}
+        } else {
+            dac.data[I::NUM].write(|w| unsafe { w.data().bits(value) });
+            while dac.syncbusy.read().bits() & (1 << (2 + I::NUM)) != 0 {}
+            while !self.is_converted() {}
+        }
+    }
+}
+
+//==============================================================================
+//  DMA
+//==============================================================================
+
+#[cfg(feature = "dma")]
+unsafe impl<I: ChannelId> Buffer for Channel<I> {
+    type Beat = u16;
+
+    #[inline]
+    fn dma_ptr(&mut self) -> *mut Self::Beat {
+        let dac = self.dac();
+        if self.buffered {
+            dac.databuf[I::NUM].as_ptr()
+        } else {
+            dac.data[I::NUM].as_ptr()
+        }
+    }
+
+    #[inline]
+    fn incrementing(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn buffer_len(&self) -> usize {
+        1
+    }
+}
+
+#[cfg(feature = "dma")]
+impl<I: ChannelId> Channel<I> {
+    /// Transform a [`Channel`] into a DMA [`Transfer`] and start sending
+    /// the provided samples, one per `trigger`
+    ///
+    /// If `circular` is `true`, the buffer is played back repeatedly until
+    /// the transfer is stopped.
+    #[inline]
+    pub fn send_with_dma<Ch, B, W>(
+        self,
+        buf: B,
+        mut channel: Ch,
+        trigger: TriggerSource,
+        circular: bool,
+        waker: W,
+    ) -> Transfer<DmaChannel<Ch::Id, Busy>, BufferPair<B, Self>, W>
+    where
+        Ch: AnyChannel<Status = Ready>,
+        B: Buffer<Beat = u16> + 'static,
+        W: FnOnce(CallbackStatus) + 'static,
+    {
+        channel
+            .as_mut()
+            .enable_interrupts(InterruptFlags::new().with_tcmpl(true));
+
+        // SAFETY: We use new_unchecked to avoid having to pass a 'static self as the
+        // destination buffer. This is safe as long as we guarantee the source buffer is
+        // static.
+        unsafe { dmac::Transfer::new_unchecked(channel, buf, self, circular) }
+            .with_waker(waker)
+            .begin(trigger, TriggerAction::BURST)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_follows_clock_frequency() {
+        assert_eq!(current_for(Hertz(1_000_000)), CurrentControl::CC100K);
+        assert_eq!(current_for(Hertz(1_200_000)), CurrentControl::CC100K);
+        assert_eq!(current_for(Hertz(4_000_000)), CurrentControl::CC1M);
+        assert_eq!(current_for(Hertz(12_000_000)), CurrentControl::CC12M);
+    }
+}
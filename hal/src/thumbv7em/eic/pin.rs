@@ -73,8 +73,8 @@ crate::paste::item! {
         }
 
         pub fn enable_event(&mut self, eic: &mut super::ConfigurableEIC) {
-            eic.eic.evctrl.modify(|_, w| unsafe {
-                w.bits(1 << $num)
+            eic.eic.evctrl.modify(|r, w| unsafe {
+                w.bits(r.bits() | 1 << $num)
             });
         }
 
@@ -90,6 +90,21 @@ crate::paste::item! {
             })
         }
 
+        /// Return a wake source for the standby sleep mode
+        ///
+        /// Returns `None` unless the interrupt is enabled, and the EIC is
+        /// clocked by the ultra-low-power 32kHz oscillator, which runs in
+        /// standby.
+        pub fn wake_source(&self) -> Option<crate::power::ExtIntWake<'_>> {
+            let eic = unsafe { &(*pac::EIC::ptr()) };
+            let enabled = eic.intenset.read().bits() & (1 << $num) != 0;
+            if enabled && eic.ctrla.read().cksel().bit_is_set() {
+                Some(crate::power::ExtIntWake::new())
+            } else {
+                None
+            }
+        }
+
         pub fn is_interrupt(&mut self) -> bool {
             let intflag = unsafe { &(*pac::EIC::ptr()) }.intflag.read().bits();
             intflag & (1 << $num) != 0
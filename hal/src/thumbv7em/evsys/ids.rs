@@ -0,0 +1,417 @@
+//! Event generator and user IDs
+
+/// Event generators
+///
+/// The PORT has no event generator on these chips. Pin events are generated
+/// by the EIC instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Generator {
+    /// XOSC0 failure detection
+    XoscFail0 = 0x01,
+    /// XOSC1 failure detection
+    XoscFail1 = 0x02,
+    /// XOSC32K failure detection
+    Xosc32kFail = 0x03,
+    /// RTC period 0
+    RtcPer0 = 0x04,
+    /// RTC period 1
+    RtcPer1 = 0x05,
+    /// RTC period 2
+    RtcPer2 = 0x06,
+    /// RTC period 3
+    RtcPer3 = 0x07,
+    /// RTC period 4
+    RtcPer4 = 0x08,
+    /// RTC period 5
+    RtcPer5 = 0x09,
+    /// RTC period 6
+    RtcPer6 = 0x0A,
+    /// RTC period 7
+    RtcPer7 = 0x0B,
+    /// RTC compare 0
+    RtcCmp0 = 0x0C,
+    /// RTC compare 1
+    RtcCmp1 = 0x0D,
+    /// RTC compare 2
+    RtcCmp2 = 0x0E,
+    /// RTC compare 3
+    RtcCmp3 = 0x0F,
+    /// RTC tamper detection
+    RtcTamper = 0x10,
+    /// RTC overflow
+    RtcOvf = 0x11,
+    /// EIC external interrupt 0
+    EicExtint0 = 0x12,
+    /// EIC external interrupt 1
+    EicExtint1 = 0x13,
+    /// EIC external interrupt 2
+    EicExtint2 = 0x14,
+    /// EIC external interrupt 3
+    EicExtint3 = 0x15,
+    /// EIC external interrupt 4
+    EicExtint4 = 0x16,
+    /// EIC external interrupt 5
+    EicExtint5 = 0x17,
+    /// EIC external interrupt 6
+    EicExtint6 = 0x18,
+    /// EIC external interrupt 7
+    EicExtint7 = 0x19,
+    /// EIC external interrupt 8
+    EicExtint8 = 0x1A,
+    /// EIC external interrupt 9
+    EicExtint9 = 0x1B,
+    /// EIC external interrupt 10
+    EicExtint10 = 0x1C,
+    /// EIC external interrupt 11
+    EicExtint11 = 0x1D,
+    /// EIC external interrupt 12
+    EicExtint12 = 0x1E,
+    /// EIC external interrupt 13
+    EicExtint13 = 0x1F,
+    /// EIC external interrupt 14
+    EicExtint14 = 0x20,
+    /// EIC external interrupt 15
+    EicExtint15 = 0x21,
+    /// DMAC channel 0
+    DmacCh0 = 0x22,
+    /// DMAC channel 1
+    DmacCh1 = 0x23,
+    /// DMAC channel 2
+    DmacCh2 = 0x24,
+    /// DMAC channel 3
+    DmacCh3 = 0x25,
+    /// PAC access error
+    PacAccErr = 0x26,
+    /// TCC0 overflow
+    Tcc0Ovf = 0x29,
+    /// TCC0 trigger
+    Tcc0Trg = 0x2A,
+    /// TCC0 counter
+    Tcc0Cnt = 0x2B,
+    /// TCC0 match/capture 0
+    Tcc0Mc0 = 0x2C,
+    /// TCC0 match/capture 1
+    Tcc0Mc1 = 0x2D,
+    /// TCC0 match/capture 2
+    Tcc0Mc2 = 0x2E,
+    /// TCC0 match/capture 3
+    Tcc0Mc3 = 0x2F,
+    /// TCC0 match/capture 4
+    Tcc0Mc4 = 0x30,
+    /// TCC0 match/capture 5
+    Tcc0Mc5 = 0x31,
+    /// TCC1 overflow
+    Tcc1Ovf = 0x32,
+    /// TCC1 trigger
+    Tcc1Trg = 0x33,
+    /// TCC1 counter
+    Tcc1Cnt = 0x34,
+    /// TCC1 match/capture 0
+    Tcc1Mc0 = 0x35,
+    /// TCC1 match/capture 1
+    Tcc1Mc1 = 0x36,
+    /// TCC1 match/capture 2
+    Tcc1Mc2 = 0x37,
+    /// TCC1 match/capture 3
+    Tcc1Mc3 = 0x38,
+    /// TCC2 overflow
+    Tcc2Ovf = 0x39,
+    /// TCC2 trigger
+    Tcc2Trg = 0x3A,
+    /// TCC2 counter
+    Tcc2Cnt = 0x3B,
+    /// TCC2 match/capture 0
+    Tcc2Mc0 = 0x3C,
+    /// TCC2 match/capture 1
+    Tcc2Mc1 = 0x3D,
+    /// TCC2 match/capture 2
+    Tcc2Mc2 = 0x3E,
+    /// TCC3 overflow
+    #[cfg(feature = "min-samd51j")]
+    Tcc3Ovf = 0x3F,
+    /// TCC3 trigger
+    #[cfg(feature = "min-samd51j")]
+    Tcc3Trg = 0x40,
+    /// TCC3 counter
+    #[cfg(feature = "min-samd51j")]
+    Tcc3Cnt = 0x41,
+    /// TCC3 match/capture 0
+    #[cfg(feature = "min-samd51j")]
+    Tcc3Mc0 = 0x42,
+    /// TCC3 match/capture 1
+    #[cfg(feature = "min-samd51j")]
+    Tcc3Mc1 = 0x43,
+    /// TCC4 overflow
+    #[cfg(feature = "min-samd51j")]
+    Tcc4Ovf = 0x44,
+    /// TCC4 trigger
+    #[cfg(feature = "min-samd51j")]
+    Tcc4Trg = 0x45,
+    /// TCC4 counter
+    #[cfg(feature = "min-samd51j")]
+    Tcc4Cnt = 0x46,
+    /// TCC4 match/capture 0
+    #[cfg(feature = "min-samd51j")]
+    Tcc4Mc0 = 0x47,
+    /// TCC4 match/capture 1
+    #[cfg(feature = "min-samd51j")]
+    Tcc4Mc1 = 0x48,
+    /// TC0 overflow
+    Tc0Ovf = 0x49,
+    /// TC0 match/capture 0
+    Tc0Mc0 = 0x4A,
+    /// TC0 match/capture 1
+    Tc0Mc1 = 0x4B,
+    /// TC1 overflow
+    Tc1Ovf = 0x4C,
+    /// TC1 match/capture 0
+    Tc1Mc0 = 0x4D,
+    /// TC1 match/capture 1
+    Tc1Mc1 = 0x4E,
+    /// TC2 overflow
+    Tc2Ovf = 0x4F,
+    /// TC2 match/capture 0
+    Tc2Mc0 = 0x50,
+    /// TC2 match/capture 1
+    Tc2Mc1 = 0x51,
+    /// TC3 overflow
+    Tc3Ovf = 0x52,
+    /// TC3 match/capture 0
+    Tc3Mc0 = 0x53,
+    /// TC3 match/capture 1
+    Tc3Mc1 = 0x54,
+    /// TC4 overflow
+    #[cfg(feature = "min-samd51j")]
+    Tc4Ovf = 0x55,
+    /// TC4 match/capture 0
+    #[cfg(feature = "min-samd51j")]
+    Tc4Mc0 = 0x56,
+    /// TC4 match/capture 1
+    #[cfg(feature = "min-samd51j")]
+    Tc4Mc1 = 0x57,
+    /// TC5 overflow
+    #[cfg(feature = "min-samd51j")]
+    Tc5Ovf = 0x58,
+    /// TC5 match/capture 0
+    #[cfg(feature = "min-samd51j")]
+    Tc5Mc0 = 0x59,
+    /// TC5 match/capture 1
+    #[cfg(feature = "min-samd51j")]
+    Tc5Mc1 = 0x5A,
+    /// TC6 overflow
+    #[cfg(feature = "min-samd51n")]
+    Tc6Ovf = 0x5B,
+    /// TC6 match/capture 0
+    #[cfg(feature = "min-samd51n")]
+    Tc6Mc0 = 0x5C,
+    /// TC6 match/capture 1
+    #[cfg(feature = "min-samd51n")]
+    Tc6Mc1 = 0x5D,
+    /// TC7 overflow
+    #[cfg(feature = "min-samd51n")]
+    Tc7Ovf = 0x5E,
+    /// TC7 match/capture 0
+    #[cfg(feature = "min-samd51n")]
+    Tc7Mc0 = 0x5F,
+    /// TC7 match/capture 1
+    #[cfg(feature = "min-samd51n")]
+    Tc7Mc1 = 0x60,
+    /// PDEC overflow
+    PdecOvf = 0x61,
+    /// PDEC error
+    PdecErr = 0x62,
+    /// PDEC direction
+    PdecDir = 0x63,
+    /// PDEC velocity
+    PdecVlc = 0x64,
+    /// PDEC match/capture 0
+    PdecMc0 = 0x65,
+    /// PDEC match/capture 1
+    PdecMc1 = 0x66,
+    /// ADC0 result ready
+    Adc0ResRdy = 0x67,
+    /// ADC0 window monitor
+    Adc0WinMon = 0x68,
+    /// ADC1 result ready
+    Adc1ResRdy = 0x69,
+    /// ADC1 window monitor
+    Adc1WinMon = 0x6A,
+    /// AC comparator 0
+    AcComp0 = 0x6B,
+    /// AC comparator 1
+    AcComp1 = 0x6C,
+    /// AC window 0
+    AcWin0 = 0x6D,
+    /// DAC channel 0 data buffer empty
+    DacEmpty0 = 0x6E,
+    /// DAC channel 1 data buffer empty
+    DacEmpty1 = 0x6F,
+    /// DAC channel 0 filter result ready
+    DacResRdy0 = 0x70,
+    /// DAC channel 1 filter result ready
+    DacResRdy1 = 0x71,
+    /// GMAC timestamp comparison
+    #[cfg(any(feature = "same53", feature = "same54"))]
+    GmacTsuCmp = 0x72,
+    /// TRNG data ready
+    TrngReady = 0x73,
+    /// CCL LUT 0 output
+    CclLutOut0 = 0x74,
+    /// CCL LUT 1 output
+    CclLutOut1 = 0x75,
+    /// CCL LUT 2 output
+    CclLutOut2 = 0x76,
+    /// CCL LUT 3 output
+    CclLutOut3 = 0x77,
+}
+
+/// Event users
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum User {
+    /// RTC tamper detection
+    RtcTamper = 0,
+    /// PORT event 0
+    PortEv0 = 1,
+    /// PORT event 1
+    PortEv1 = 2,
+    /// PORT event 2
+    PortEv2 = 3,
+    /// PORT event 3
+    PortEv3 = 4,
+    /// DMAC channel 0 trigger
+    DmacCh0 = 5,
+    /// DMAC channel 1 trigger
+    DmacCh1 = 6,
+    /// DMAC channel 2 trigger
+    DmacCh2 = 7,
+    /// DMAC channel 3 trigger
+    DmacCh3 = 8,
+    /// DMAC channel 4 trigger
+    DmacCh4 = 9,
+    /// DMAC channel 5 trigger
+    DmacCh5 = 10,
+    /// DMAC channel 6 trigger
+    DmacCh6 = 11,
+    /// DMAC channel 7 trigger
+    DmacCh7 = 12,
+    /// Trace start
+    Cm4TraceStart = 13,
+    /// Trace stop
+    Cm4TraceStop = 14,
+    /// Trace trigger
+    Cm4TraceTrig = 15,
+    /// TCC0 event 0
+    Tcc0Ev0 = 16,
+    /// TCC0 event 1
+    Tcc0Ev1 = 17,
+    /// TCC0 match/capture 0
+    Tcc0Mc0 = 18,
+    /// TCC0 match/capture 1
+    Tcc0Mc1 = 19,
+    /// TCC0 match/capture 2
+    Tcc0Mc2 = 20,
+    /// TCC0 match/capture 3
+    Tcc0Mc3 = 21,
+    /// TCC0 match/capture 4
+    Tcc0Mc4 = 22,
+    /// TCC0 match/capture 5
+    Tcc0Mc5 = 23,
+    /// TCC1 event 0
+    Tcc1Ev0 = 24,
+    /// TCC1 event 1
+    Tcc1Ev1 = 25,
+    /// TCC1 match/capture 0
+    Tcc1Mc0 = 26,
+    /// TCC1 match/capture 1
+    Tcc1Mc1 = 27,
+    /// TCC1 match/capture 2
+    Tcc1Mc2 = 28,
+    /// TCC1 match/capture 3
+    Tcc1Mc3 = 29,
+    /// TCC2 event 0
+    Tcc2Ev0 = 30,
+    /// TCC2 event 1
+    Tcc2Ev1 = 31,
+    /// TCC2 match/capture 0
+    Tcc2Mc0 = 32,
+    /// TCC2 match/capture 1
+    Tcc2Mc1 = 33,
+    /// TCC2 match/capture 2
+    Tcc2Mc2 = 34,
+    /// TCC3 event 0
+    #[cfg(feature = "min-samd51j")]
+    Tcc3Ev0 = 35,
+    /// TCC3 event 1
+    #[cfg(feature = "min-samd51j")]
+    Tcc3Ev1 = 36,
+    /// TCC3 match/capture 0
+    #[cfg(feature = "min-samd51j")]
+    Tcc3Mc0 = 37,
+    /// TCC3 match/capture 1
+    #[cfg(feature = "min-samd51j")]
+    Tcc3Mc1 = 38,
+    /// TCC4 event 0
+    #[cfg(feature = "min-samd51j")]
+    Tcc4Ev0 = 39,
+    /// TCC4 event 1
+    #[cfg(feature = "min-samd51j")]
+    Tcc4Ev1 = 40,
+    /// TCC4 match/capture 0
+    #[cfg(feature = "min-samd51j")]
+    Tcc4Mc0 = 41,
+    /// TCC4 match/capture 1
+    #[cfg(feature = "min-samd51j")]
+    Tcc4Mc1 = 42,
+    /// TC0 event (count, retrigger or capture, depending on `EVCTRL.EVACT`)
+    Tc0Evu = 43,
+    /// TC1 event
+    Tc1Evu = 44,
+    /// TC2 event
+    Tc2Evu = 45,
+    /// TC3 event
+    Tc3Evu = 46,
+    /// TC4 event
+    #[cfg(feature = "min-samd51j")]
+    Tc4Evu = 47,
+    /// TC5 event
+    #[cfg(feature = "min-samd51j")]
+    Tc5Evu = 48,
+    /// TC6 event
+    #[cfg(feature = "min-samd51n")]
+    Tc6Evu = 49,
+    /// TC7 event
+    #[cfg(feature = "min-samd51n")]
+    Tc7Evu = 50,
+    /// PDEC event 0
+    PdecEvu0 = 51,
+    /// PDEC event 1
+    PdecEvu1 = 52,
+    /// PDEC event 2
+    PdecEvu2 = 53,
+    /// ADC0 start conversion
+    Adc0Start = 54,
+    /// ADC0 flush
+    Adc0Sync = 55,
+    /// ADC1 start conversion
+    Adc1Start = 56,
+    /// ADC1 flush
+    Adc1Sync = 57,
+    /// AC comparator 0 start comparison
+    AcSoc0 = 58,
+    /// AC comparator 1 start comparison
+    AcSoc1 = 59,
+    /// DAC channel 0 start conversion
+    DacStart0 = 60,
+    /// DAC channel 1 start conversion
+    DacStart1 = 61,
+    /// CCL LUT 0 input
+    CclLutIn0 = 62,
+    /// CCL LUT 1 input
+    CclLutIn1 = 63,
+    /// CCL LUT 2 input
+    CclLutIn2 = 64,
+    /// CCL LUT 3 input
+    CclLutIn3 = 65,
+}
@@ -0,0 +1,404 @@
+//! # Event System
+//!
+//! The EVSYS routes events from peripheral event generators to peripheral
+//! event users, without any CPU involvement. Each of its 32 channels connects
+//! a single [`Generator`] to any number of [`User`]s.
+//!
+//! The [`Evsys`] is created from the peripheral, then split into individual
+//! [`Channel`]s. Each channel must be initialized with a generator before
+//! users can be connected to it:
+//!
+//! ```
+//! use atsamd_hal::evsys::{Edge, Evsys, Generator, Path, User};
+//!
+//! let mut evsys = Evsys::new(&mut peripherals.MCLK, peripherals.EVSYS);
+//! let channels = evsys.split();
+//!
+//! // Start an ADC conversion on each RTC period event
+//! let clock = clocks.evsys0(&gclk0).unwrap();
+//! let mut ch0 = channels.0.init(Generator::RtcPer7, Path::RESYNCHRONIZED, Edge::RISING_EDGE, &clock);
+//! ch0.connect(User::Adc0Start);
+//! ```
+//!
+//! The generating and using peripherals must also have their event output and
+//! input enabled, for example with `ExtInt::enable_event` for the EIC, or
+//! [`ChannelConfig::start_event`] for the DAC.
+//!
+//! ## Paths
+//!
+//! * [`Path::ASYNCHRONOUS`] events are routed combinatorially from the
+//!   generator to the users. This path requires no clock, and is available on
+//!   every channel, but does not support edge detection nor interrupts.
+//! * [`Path::SYNCHRONOUS`] and [`Path::RESYNCHRONIZED`] events go through the
+//!   channel's edge detector, and can be observed with channel interrupts.
+//!   They are only available on channels 0 to 11, and require the channel's
+//!   GCLK. The synchronous path must only be used when the generator is
+//!   clocked by that same GCLK.
+//!
+//! [`ChannelConfig::start_event`]: crate::dac::ChannelConfig::start_event
+
+use core::marker::PhantomData;
+
+use bitflags::bitflags;
+use paste::paste;
+use seq_macro::seq;
+
+use crate::clock;
+use crate::pac::{evsys, EVSYS, MCLK};
+use crate::typelevel::Sealed;
+
+mod ids;
+pub use ids::*;
+
+/// Event path
+pub use evsys::channel::channel::PATH_A as Path;
+
+/// Edge detection
+pub use evsys::channel::channel::EDGSEL_A as Edge;
+
+//==============================================================================
+//  Channel IDs
+//==============================================================================
+
+/// Trait representing an event channel ID
+pub trait ChId {
+    const U8: u8;
+    const USIZE: usize;
+}
+
+/// Event channels with synchronous and resynchronized paths
+pub trait SyncChId: ChId {
+    /// GCLK token of the channel
+    type Clock;
+}
+
+macro_rules! define_channels_struct {
+    ($num_channels:literal) => {
+        seq!(N in 0..$num_channels {
+            paste! {
+                #(
+                    /// Type alias for a channel number
+                    pub struct [<Ch N>];
+
+                    impl ChId for [<Ch N>] {
+                        const U8: u8 = N;
+                        const USIZE: usize = N;
+                    }
+                )*
+
+                /// Struct generating individual handles to each event channel
+                pub struct Channels(
+                    #(
+                        pub Channel<[<Ch N>], Uninitialized>,
+                    )*
+                );
+            }
+        });
+    };
+}
+
+define_channels_struct!(32);
+
+macro_rules! sync_channels {
+    ($($num:literal),+) => {
+        paste! {
+            $(
+                impl SyncChId for [<Ch $num>] {
+                    type Clock = clock::[<Evsys $num Clock>];
+                }
+            )+
+        }
+    };
+}
+
+sync_channels!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11);
+
+//==============================================================================
+//  Status
+//==============================================================================
+
+/// Channel status
+pub trait Status: Sealed {}
+
+/// Uninitialized channel
+pub enum Uninitialized {}
+impl Sealed for Uninitialized {}
+impl Status for Uninitialized {}
+
+/// Initialized channel, with a generator selected
+pub enum Ready {}
+impl Sealed for Ready {}
+impl Status for Ready {}
+
+//==============================================================================
+//  Interrupts
+//==============================================================================
+
+bitflags! {
+    /// Channel interrupt flags
+    pub struct Interrupts: u8 {
+        /// A new event was detected before the previous one was handled by
+        /// all users
+        const OVR = 0x01;
+        /// An event was detected
+        const EVD = 0x02;
+    }
+}
+
+//==============================================================================
+//  Evsys
+//==============================================================================
+
+/// The EVSYS peripheral
+pub struct Evsys {
+    evsys: EVSYS,
+}
+
+impl Evsys {
+    /// Enable and reset the EVSYS
+    #[inline]
+    pub fn new(mclk: &mut MCLK, evsys: EVSYS) -> Self {
+        mclk.apbbmask.modify(|_, w| w.evsys_().set_bit());
+
+        evsys.ctrla.write(|w| w.swrst().set_bit());
+        while evsys.ctrla.read().swrst().bit_is_set() {}
+
+        Self { evsys }
+    }
+
+    /// Split the EVSYS into individual channels
+    #[inline]
+    pub fn split(&mut self) -> Channels {
+        Channels::new()
+    }
+
+    /// Returns a bit mask of the channels with a pending interrupt
+    ///
+    /// Bit `n` is set when channel `n` has an interrupt flag set. Only
+    /// channels 0 to 11 can have interrupts.
+    #[inline]
+    pub fn interrupt_status(&self) -> u32 {
+        self.evsys.intstatus.read().bits()
+    }
+
+    /// Reset the EVSYS, disable it and return the underlying peripheral
+    ///
+    /// Every channel is disconnected by the reset.
+    #[inline]
+    pub fn free(self, _channels: Channels, mclk: &mut MCLK) -> EVSYS {
+        self.evsys.ctrla.write(|w| w.swrst().set_bit());
+        while self.evsys.ctrla.read().swrst().bit_is_set() {}
+        mclk.apbbmask.modify(|_, w| w.evsys_().clear_bit());
+        self.evsys
+    }
+}
+
+impl Channels {
+    #[inline]
+    fn new() -> Self {
+        seq!(N in 0..32 {
+            Self(
+                #(
+                    Channel::new(),
+                )*
+            )
+        })
+    }
+}
+
+//==============================================================================
+//  Channel
+//==============================================================================
+
+/// A single event channel
+pub struct Channel<Id: ChId, S: Status> {
+    _id: PhantomData<Id>,
+    _status: PhantomData<S>,
+}
+
+impl<Id: ChId, S: Status> Channel<Id, S> {
+    #[inline]
+    fn new() -> Self {
+        Self {
+            _id: PhantomData,
+            _status: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn change_status<N: Status>(self) -> Channel<Id, N> {
+        Channel::new()
+    }
+
+    #[inline]
+    fn evsys(&self) -> &evsys::RegisterBlock {
+        // SAFETY: Each channel only accesses its own registers, the user
+        // multiplexers pointing to it, and its own bit in the shared
+        // write-only SWEVT register
+        unsafe { &*EVSYS::ptr() }
+    }
+
+    #[inline]
+    fn regs(&self) -> &evsys::CHANNEL {
+        &self.evsys().channel[Id::USIZE]
+    }
+}
+
+impl<Id: ChId> Channel<Id, Uninitialized> {
+    /// Select the channel generator, using the asynchronous path
+    #[inline]
+    pub fn init_async(self, generator: Generator) -> Channel<Id, Ready> {
+        self.regs().channel.write(|w| {
+            // SAFETY: Every `Generator` is a valid EVGEN value
+            unsafe { w.evgen().bits(generator as u8) };
+            w.path().asynchronous();
+            w.edgsel().no_evt_output()
+        });
+        self.change_status()
+    }
+}
+
+impl<Id: SyncChId> Channel<Id, Uninitialized> {
+    /// Select the channel generator, path and edge detection
+    ///
+    /// The GCLK token is only required to prove the channel is clocked. When
+    /// using [`Path::ASYNCHRONOUS`], `edge` is ignored.
+    #[inline]
+    pub fn init(
+        self,
+        generator: Generator,
+        path: Path,
+        edge: Edge,
+        _clock: &Id::Clock,
+    ) -> Channel<Id, Ready> {
+        let edge = match path {
+            Path::ASYNCHRONOUS => Edge::NO_EVT_OUTPUT,
+            _ => edge,
+        };
+        self.regs().channel.write(|w| {
+            // SAFETY: Every `Generator` is a valid EVGEN value
+            unsafe { w.evgen().bits(generator as u8) };
+            w.path().variant(path);
+            w.edgsel().variant(edge)
+        });
+        self.change_status()
+    }
+}
+
+impl<Id: ChId> Channel<Id, Ready> {
+    /// Connect a user to the channel
+    ///
+    /// A user can only be connected to a single channel. Connecting it to this
+    /// channel disconnects it from any other.
+    #[inline]
+    pub fn connect(&mut self, user: User) {
+        self.evsys().user[user as usize].write(|w| {
+            // SAFETY: The user multiplexer stores the channel number plus one
+            unsafe { w.channel().bits(Id::U8 + 1) }
+        });
+    }
+
+    /// Disconnect a user from the channel
+    ///
+    /// Nothing is done if the user is connected to another channel.
+    #[inline]
+    pub fn disconnect(&mut self, user: User) {
+        let reg = &self.evsys().user[user as usize];
+        if reg.read().channel().bits() == Id::U8 + 1 {
+            reg.reset();
+        }
+    }
+
+    /// Returns `true` if the user is connected to the channel
+    #[inline]
+    pub fn is_connected(&self, user: User) -> bool {
+        self.evsys().user[user as usize].read().channel().bits() == Id::U8 + 1
+    }
+
+    /// Trigger an event on the channel from software
+    #[inline]
+    pub fn software_event(&mut self) {
+        // SAFETY: Writing zeros to SWEVT has no effect
+        self.evsys().swevt.write(|w| unsafe { w.bits(1 << Id::U8) });
+    }
+
+    /// Keep the channel running in standby sleep mode
+    #[inline]
+    pub fn set_run_standby(&mut self, run_standby: bool) {
+        self.regs()
+            .channel
+            .modify(|_, w| w.runstdby().bit(run_standby));
+    }
+
+    /// Only request the generic clock when an event is detected, instead of
+    /// keeping it always running
+    #[inline]
+    pub fn set_on_demand(&mut self, on_demand: bool) {
+        self.regs()
+            .channel
+            .modify(|_, w| w.ondemand().bit(on_demand));
+    }
+
+    /// Returns `true` if all the users connected to the channel are ready to
+    /// handle a new event
+    ///
+    /// Only meaningful for the synchronous and resynchronized paths.
+    #[inline]
+    pub fn users_ready(&self) -> bool {
+        self.regs().chstatus.read().rdyusr().bit_is_set()
+    }
+
+    /// Returns `true` if an event is being handled by the channel
+    ///
+    /// Only meaningful for the synchronous and resynchronized paths.
+    #[inline]
+    pub fn is_busy(&self) -> bool {
+        self.regs().chstatus.read().busych().bit_is_set()
+    }
+
+    /// Disconnect every user of the channel and reset it
+    #[inline]
+    pub fn reset(self) -> Channel<Id, Uninitialized> {
+        let evsys = self.evsys();
+        for user in evsys.user.iter() {
+            if user.read().channel().bits() == Id::U8 + 1 {
+                user.reset();
+            }
+        }
+        let regs = self.regs();
+        regs.chintenclr
+            .write(|w| unsafe { w.bits(Interrupts::all().bits()) });
+        regs.chintflag
+            .write(|w| unsafe { w.bits(Interrupts::all().bits()) });
+        regs.channel.reset();
+        self.change_status()
+    }
+}
+
+impl<Id: SyncChId> Channel<Id, Ready> {
+    /// Enable the selected interrupts
+    #[inline]
+    pub fn enable_interrupts(&mut self, interrupts: Interrupts) {
+        self.regs()
+            .chintenset
+            .write(|w| unsafe { w.bits(interrupts.bits()) });
+    }
+
+    /// Disable the selected interrupts
+    #[inline]
+    pub fn disable_interrupts(&mut self, interrupts: Interrupts) {
+        self.regs()
+            .chintenclr
+            .write(|w| unsafe { w.bits(interrupts.bits()) });
+    }
+
+    /// Check the selected interrupt flags, clear them, and return the ones
+    /// that were set
+    #[inline]
+    pub fn check_and_clear_interrupts(&mut self, interrupts: Interrupts) -> Interrupts {
+        let regs = self.regs();
+        let flags = Interrupts::from_bits_truncate(regs.chintflag.read().bits()) & interrupts;
+        regs.chintflag.write(|w| unsafe { w.bits(flags.bits()) });
+        flags
+    }
+}
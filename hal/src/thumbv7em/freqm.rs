@@ -0,0 +1,207 @@
+//! # Frequency Meter (FREQM)
+//!
+//! The FREQM counts the periods of a measured clock (`GCLK_FREQM_MSR`)
+//! during a number of periods of a reference clock (`GCLK_FREQM_REF`), which
+//! gives the frequency of the measured clock relative to the reference:
+//!
+//! `f_msr = VALUE / REFNUM * f_ref`
+//!
+//! The measured clock is switched by [`Freqm::measure`] to any clock
+//! generator, including a generator fed by a `GCLK_IO` pin, to measure an
+//! external signal. [`Freqm::verify`] checks that a generator configured by
+//! the [`GenericClockController`](crate::clock::GenericClockController) runs
+//! at its expected frequency, which catches a misconfigured DPLL or a
+//! crystal that failed to start.
+//!
+//! A longer measurement, with more reference clock periods, is more
+//! accurate. The reference clock should be accurate itself, such as a
+//! generator fed by an external crystal.
+//!
+//! ```
+//! use atsamd_hal::clock::GenericClockController;
+//! use atsamd_hal::freqm::Freqm;
+//! use atsamd_hal::pac::gclk::pchctrl::GEN_A;
+//! use atsamd_hal::time::Hertz;
+//!
+//! let gclk0 = clocks.gclk0();
+//! let gclk1 = clocks.gclk1();
+//! let msr = clocks.freq_m_msr(&gclk0).unwrap();
+//! let reference = clocks.freq_m_ref(&gclk1).unwrap();
+//! let mut freqm = Freqm::new(&mut peripherals.MCLK, peripherals.FREQM, msr, reference);
+//!
+//! freqm.verify(&gclk0, Hertz(100_000)).unwrap();
+//! let frequency = freqm.measure(GEN_A::GCLK2).unwrap();
+//! ```
+
+use crate::clock::{ClockGenId, ClockId, FreqmMsrClock, FreqmRefClock, GClock};
+use crate::pac::{FREQM, GCLK, MCLK};
+use crate::time::Hertz;
+
+//==============================================================================
+//  Error
+//==============================================================================
+
+/// Errors related to the FREQM
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The counter of measured clock periods overflowed; the measured clock
+    /// is too fast for the number of reference clock periods
+    Overflow,
+    /// The measured frequency is not within the tolerance of the expected
+    /// frequency
+    Mismatch {
+        /// Expected frequency
+        expected: Hertz,
+        /// Measured frequency
+        measured: Hertz,
+    },
+}
+
+//==============================================================================
+//  Freqm
+//==============================================================================
+
+/// FREQM peripheral
+pub struct Freqm {
+    freqm: FREQM,
+    msr: FreqmMsrClock,
+    reference: FreqmRefClock,
+    refnum: u8,
+}
+
+impl Freqm {
+    /// Enable the FREQM
+    ///
+    /// Measurements last 255 reference clock periods; see
+    /// [`set_reference_periods`](Self::set_reference_periods).
+    #[inline]
+    pub fn new(
+        mclk: &mut MCLK,
+        freqm: FREQM,
+        msr: FreqmMsrClock,
+        reference: FreqmRefClock,
+    ) -> Self {
+        mclk.apbamask.modify(|_, w| w.freqm_().set_bit());
+        freqm.ctrla.write(|w| w.swrst().set_bit());
+        while freqm.syncbusy.read().swrst().bit_is_set() {}
+        let mut freqm = Self {
+            freqm,
+            msr,
+            reference,
+            refnum: 0,
+        };
+        freqm.set_reference_periods(255);
+        freqm
+    }
+
+    /// Set the number of reference clock periods of a measurement
+    ///
+    /// # Panics
+    ///
+    /// Panics if `periods` is zero.
+    #[inline]
+    pub fn set_reference_periods(&mut self, periods: u8) {
+        assert!(periods != 0);
+        self.freqm.ctrla.write(|w| w.enable().clear_bit());
+        while self.freqm.syncbusy.read().enable().bit_is_set() {}
+        self.freqm
+            .cfga
+            .write(|w| unsafe { w.refnum().bits(periods) });
+        self.freqm.ctrla.write(|w| w.enable().set_bit());
+        while self.freqm.syncbusy.read().enable().bit_is_set() {}
+        self.refnum = periods;
+    }
+
+    /// Measure the frequency of the clock generator `generator`
+    ///
+    /// The measured clock is left connected to `generator`.
+    #[inline]
+    pub fn measure(&mut self, generator: ClockGenId) -> Result<Hertz, Error> {
+        // SAFETY: The FreqmMsrClock token gives us ownership of the
+        // peripheral channel of the measured clock
+        let gclk = unsafe { &*GCLK::ptr() };
+        let pchctrl = &gclk.pchctrl[u8::from(ClockId::FREQM_MSR) as usize];
+        if pchctrl.read().gen().bits() != u8::from(generator) {
+            pchctrl.write(|w| w.chen().clear_bit());
+            while pchctrl.read().chen().bit_is_set() {}
+            pchctrl.write(|w| {
+                w.gen().variant(generator);
+                w.chen().set_bit()
+            });
+            while pchctrl.read().chen().bit_is_clear() {}
+        }
+        self.measure_current()
+    }
+
+    /// Measure the frequency of the clock generator currently connected to
+    /// the measured clock
+    #[inline]
+    pub fn measure_current(&mut self) -> Result<Hertz, Error> {
+        // Clear the overflow flag and the result of the previous measurement
+        self.freqm.status.write(|w| w.ovf().set_bit());
+        self.freqm.intflag.write(|w| w.done().set_bit());
+
+        self.freqm.ctrlb.write(|w| w.start().set_bit());
+        while self.freqm.intflag.read().done().bit_is_clear() {}
+        self.freqm.intflag.write(|w| w.done().set_bit());
+
+        if self.freqm.status.read().ovf().bit_is_set() {
+            return Err(Error::Overflow);
+        }
+        let value = self.freqm.value.read().value().bits();
+        Ok(frequency(value, self.reference.freq(), self.refnum))
+    }
+
+    /// Check that the clock generator `gclk` runs at its expected frequency,
+    /// give or take `tolerance`
+    ///
+    /// The measured frequency is returned. Keep in mind that the resolution
+    /// of a measurement is the reference frequency divided by the number of
+    /// reference clock periods.
+    #[inline]
+    pub fn verify(&mut self, gclk: &GClock, tolerance: Hertz) -> Result<Hertz, Error> {
+        let expected = gclk.freq();
+        let measured = self.measure(gclk.id())?;
+        if within(measured, expected, tolerance) {
+            Ok(measured)
+        } else {
+            Err(Error::Mismatch { expected, measured })
+        }
+    }
+
+    /// Disable the FREQM and return the underlying FREQM and clocks
+    #[inline]
+    pub fn free(self, mclk: &mut MCLK) -> (FREQM, FreqmMsrClock, FreqmRefClock) {
+        self.freqm.ctrla.write(|w| w.swrst().set_bit());
+        while self.freqm.syncbusy.read().swrst().bit_is_set() {}
+        mclk.apbamask.modify(|_, w| w.freqm_().clear_bit());
+        (self.freqm, self.msr, self.reference)
+    }
+}
+
+/// Frequency of `value` measured clock periods over `refnum` reference clock
+/// periods
+fn frequency(value: u32, reference: Hertz, refnum: u8) -> Hertz {
+    let freq = value as u64 * reference.0 as u64 / refnum as u64;
+    Hertz(freq as u32)
+}
+
+/// Whether `measured` is within `tolerance` of `expected`
+fn within(measured: Hertz, expected: Hertz, tolerance: Hertz) -> bool {
+    measured.0 >= expected.0.saturating_sub(tolerance.0)
+        && measured.0 <= expected.0.saturating_add(tolerance.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{frequency, within};
+    use crate::time::Hertz;
+
+    #[test]
+    fn measurement() {
+        assert_eq!(frequency(915_527, Hertz(32_768), 250), Hertz(119_999_954));
+        assert_eq!(frequency(255, Hertz(48_000_000), 255), Hertz(48_000_000));
+        assert!(within(Hertz(119_999_954), Hertz(120_000_000), Hertz(100)));
+        assert!(!within(Hertz(48_000_000), Hertz(120_000_000), Hertz(100)));
+    }
+}
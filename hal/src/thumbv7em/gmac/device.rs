@@ -0,0 +1,113 @@
+//! [`smoltcp::phy::Device`] implementation
+//!
+//! Received frames are handed to `smoltcp` directly from the Rx ring, and
+//! outgoing frames are written directly into the Tx ring, without any
+//! intermediate copy.
+
+use smoltcp::phy::{self, DeviceCapabilities, Medium};
+use smoltcp::time::Instant;
+
+use crate::pac::GMAC;
+
+use super::reg::Registers;
+use super::ring::{RxRing, TxRing};
+use super::{Gmac, MdcPin, MdioPin, RxDvPin, MTU};
+
+/// Token granting access to one received frame
+pub struct RxToken<'a, const N: usize> {
+    ring: &'a mut RxRing<N>,
+}
+
+impl<const N: usize> phy::RxToken for RxToken<'_, N> {
+    #[inline]
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        // Tokens are only created when a frame is ready, and the GMAC never
+        // takes a filled descriptor back
+        self.ring
+            .receive_with(f)
+            .expect("RxToken created without a received frame")
+    }
+}
+
+/// Token granting access to one Tx buffer
+pub struct TxToken<'a, const N: usize> {
+    ring: &'a mut TxRing<N>,
+    regs: &'a mut Registers<GMAC>,
+}
+
+impl<const N: usize> phy::TxToken for TxToken<'_, N> {
+    #[inline]
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        // Tokens are only created when a descriptor is available, and smoltcp
+        // never exceeds the MTU advertised in the device capabilities
+        let result = self
+            .ring
+            .transmit_with(len, f)
+            .unwrap_or_else(|_| panic!("TxToken consumed with an invalid length"));
+        self.regs.start_transmission();
+        result
+    }
+}
+
+impl<RXDV, MDC, MDIO, const RX: usize, const TX: usize> phy::Device
+    for Gmac<RXDV, MDC, MDIO, RX, TX>
+where
+    RXDV: RxDvPin,
+    MDC: MdcPin,
+    MDIO: MdioPin,
+{
+    type RxToken<'a>
+        = RxToken<'a, RX>
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = TxToken<'a, TX>
+    where
+        Self: 'a;
+
+    #[inline]
+    fn receive(&mut self, _timestamp: Instant) -> Option<(RxToken<'_, RX>, TxToken<'_, TX>)> {
+        let config = &mut self.config;
+        // A lost frame is of no interest to smoltcp
+        config.regs.rx_overrun();
+        if config.rx.peek().is_none() || !config.tx.is_available() {
+            return None;
+        }
+        Some((
+            RxToken {
+                ring: &mut *config.rx,
+            },
+            TxToken {
+                ring: &mut *config.tx,
+                regs: &mut config.regs,
+            },
+        ))
+    }
+
+    #[inline]
+    fn transmit(&mut self, _timestamp: Instant) -> Option<TxToken<'_, TX>> {
+        let config = &mut self.config;
+        if !config.tx.is_available() {
+            return None;
+        }
+        Some(TxToken {
+            ring: &mut *config.tx,
+            regs: &mut config.regs,
+        })
+    }
+
+    #[inline]
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut capabilities = DeviceCapabilities::default();
+        capabilities.medium = Medium::Ethernet;
+        capabilities.max_transmission_unit = MTU;
+        capabilities.max_burst_size = Some(RX.min(TX));
+        capabilities
+    }
+}
@@ -0,0 +1,595 @@
+//! # Ethernet MAC (GMAC)
+//!
+//! The SAME53 and SAME54 contain a 10/100 Mbit/s Ethernet MAC, connected to
+//! an external PHY through the Reduced Media Independent Interface (RMII).
+//!
+//! ## Descriptor rings
+//!
+//! Frames are moved between the MAC and system RAM by a dedicated DMA engine,
+//! which walks circular lists of buffer descriptors. The [`RxRing`] and
+//! [`TxRing`] types hold both the descriptors and the frame buffers, and must
+//! be placed in `static` storage. The number of buffers in each ring is set
+//! by a const generic parameter; each buffer holds one frame of up to
+//! [`BUFFER_SIZE`] bytes.
+//!
+//! ## PHY management
+//!
+//! The PHY is configured through the MDIO management interface. The MDC clock
+//! is derived from the main clock (MCK), whose frequency must be passed to
+//! [`Config::new`]. [`Gmac::start_autonegotiation`] and
+//! [`Gmac::poll_link`] negotiate the link with the link partner and
+//! configure the MAC accordingly.
+//!
+//! ## Filtering
+//!
+//! Frames addressed to one of four specific MAC addresses are always
+//! accepted, along with broadcast frames unless disabled. Multicast and
+//! unicast frames can additionally be matched against a 64-bit hash table,
+//! see [`Gmac::add_hash`].
+//!
+//! ## Example
+//!
+//! ```
+//! use atsamd_hal::gmac::{Config, Pins, RxRing, TxRing};
+//!
+//! static mut RX_RING: RxRing<8> = RxRing::new();
+//! static mut TX_RING: TxRing<4> = TxRing::new();
+//!
+//! let pins = Pins::new(
+//!     pins.pa14, pins.pa17, pins.pa18, pins.pa19, pins.pc20, pins.pa13, pins.pa12, pins.pa15,
+//!     pins.pc11, pins.pc12,
+//! );
+//! let mck = clocks.gclk0().freq();
+//! let (rx, tx) = unsafe { (&mut RX_RING, &mut TX_RING) };
+//!
+//! let mut gmac = Config::new(&mut peripherals.MCLK, peripherals.GMAC, pins, mck, rx, tx)
+//!     .mac_address([0x02, 0x00, 0x00, 0x12, 0x34, 0x56])
+//!     .phy_address(0)
+//!     .enable();
+//!
+//! gmac.start_autonegotiation();
+//! let link = nb::block!(gmac.poll_link()).unwrap();
+//!
+//! let mut frame = [0; 1514];
+//! let len = nb::block!(gmac.receive(&mut frame)).unwrap();
+//! nb::block!(gmac.transmit(&frame[..len])).unwrap();
+//! ```
+//!
+//! ## smoltcp
+//!
+//! When the `smoltcp` feature is enabled, [`Gmac`] implements
+//! [`smoltcp::phy::Device`], and can be used directly as the device of a
+//! `smoltcp` interface.
+
+use bitflags::bitflags;
+
+use crate::gpio::v2::{
+    AlternateL, AnyPin, Pin, PinId, SpecificPin, PA12, PA13, PA14, PA15, PA17, PA18, PA19, PA20,
+    PA21,
+};
+use crate::pac::{GMAC, MCLK};
+use crate::time::Hertz;
+
+#[cfg(feature = "min-samd51j")]
+use crate::gpio::v2::{PB14, PB15};
+#[cfg(feature = "min-samd51n")]
+use crate::gpio::v2::{PC11, PC12, PC20};
+
+mod phy;
+pub use phy::{Duplex, Link, Speed, ANAR, ANLPAR, BMCR, BMSR, PHYID1, PHYID2};
+
+mod reg;
+pub use reg::hash_index;
+use reg::Registers;
+
+mod ring;
+pub use ring::{RxRing, TxRing, BUFFER_SIZE};
+
+#[cfg(feature = "smoltcp")]
+mod device;
+#[cfg(feature = "smoltcp")]
+pub use device::*;
+
+/// Maximum transmission unit, i.e. the largest Ethernet frame without FCS
+pub const MTU: usize = 1514;
+
+//==============================================================================
+//  Pins
+//==============================================================================
+
+/// [`PinId`]s that can be used as the RMII receive data valid signal
+pub trait RxDvPin: PinId {}
+
+/// [`PinId`]s that can be used as the MDIO management clock
+pub trait MdcPin: PinId {}
+
+/// [`PinId`]s that can be used as the MDIO management data
+pub trait MdioPin: PinId {}
+
+#[cfg(feature = "min-samd51n")]
+impl RxDvPin for PC20 {}
+
+impl MdcPin for PA20 {}
+#[cfg(feature = "min-samd51j")]
+impl MdcPin for PB14 {}
+#[cfg(feature = "min-samd51n")]
+impl MdcPin for PC11 {}
+
+impl MdioPin for PA21 {}
+#[cfg(feature = "min-samd51j")]
+impl MdioPin for PB15 {}
+#[cfg(feature = "min-samd51n")]
+impl MdioPin for PC12 {}
+
+/// Set of RMII and MDIO pins
+pub struct Pins<RXDV, MDC, MDIO>
+where
+    RXDV: RxDvPin,
+    MDC: MdcPin,
+    MDIO: MdioPin,
+{
+    /// 50 MHz reference clock
+    pub ref_clk: Pin<PA14, AlternateL>,
+    /// Transmit enable
+    pub tx_en: Pin<PA17, AlternateL>,
+    /// Transmit data bit 0
+    pub tx0: Pin<PA18, AlternateL>,
+    /// Transmit data bit 1
+    pub tx1: Pin<PA19, AlternateL>,
+    /// Carrier sense / receive data valid
+    pub rx_dv: Pin<RXDV, AlternateL>,
+    /// Receive data bit 0
+    pub rx0: Pin<PA13, AlternateL>,
+    /// Receive data bit 1
+    pub rx1: Pin<PA12, AlternateL>,
+    /// Receive error
+    pub rx_er: Pin<PA15, AlternateL>,
+    /// Management data clock
+    pub mdc: Pin<MDC, AlternateL>,
+    /// Management data input/output
+    pub mdio: Pin<MDIO, AlternateL>,
+}
+
+impl<RXDV, MDC, MDIO> Pins<RXDV, MDC, MDIO>
+where
+    RXDV: RxDvPin,
+    MDC: MdcPin,
+    MDIO: MdioPin,
+{
+    /// Configure the RMII and MDIO pins
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    pub fn new(
+        ref_clk: impl AnyPin<Id = PA14>,
+        tx_en: impl AnyPin<Id = PA17>,
+        tx0: impl AnyPin<Id = PA18>,
+        tx1: impl AnyPin<Id = PA19>,
+        rx_dv: impl AnyPin<Id = RXDV>,
+        rx0: impl AnyPin<Id = PA13>,
+        rx1: impl AnyPin<Id = PA12>,
+        rx_er: impl AnyPin<Id = PA15>,
+        mdc: impl AnyPin<Id = MDC>,
+        mdio: impl AnyPin<Id = MDIO>,
+    ) -> Self {
+        fn alternate<P: AnyPin>(pin: P) -> Pin<P::Id, AlternateL> {
+            let pin: SpecificPin<P> = pin.into();
+            pin.into_mode()
+        }
+        Self {
+            ref_clk: alternate(ref_clk),
+            tx_en: alternate(tx_en),
+            tx0: alternate(tx0),
+            tx1: alternate(tx1),
+            rx_dv: alternate(rx_dv),
+            rx0: alternate(rx0),
+            rx1: alternate(rx1),
+            rx_er: alternate(rx_er),
+            mdc: alternate(mdc),
+            mdio: alternate(mdio),
+        }
+    }
+}
+
+//==============================================================================
+//  Error and flags
+//==============================================================================
+
+/// Errors reported by the GMAC driver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The frame does not fit in a buffer
+    FrameTooLong,
+    /// The receiver ran out of buffers, and frames were dropped
+    Overrun,
+    /// A specific address filter index is out of range
+    InvalidIndex,
+    /// Auto-negotiation completed without finding a mode supported by both
+    /// link partners
+    AutoNegotiation,
+}
+
+bitflags! {
+    /// Interrupt flags
+    ///
+    /// The binary format of the underlying bits exactly matches the `ISR`,
+    /// `IER` and `IDR` registers.
+    pub struct Interrupts: u32 {
+        /// Management frame sent
+        const MFS = 1 << 0;
+        /// Receive complete
+        const RCOMP = 1 << 1;
+        /// Rx used bit read, i.e. no free Rx buffer
+        const RXUBR = 1 << 2;
+        /// Tx used bit read, i.e. the Tx ring is empty
+        const TXUBR = 1 << 3;
+        /// Transmit underrun
+        const TUR = 1 << 4;
+        /// Retry limit exceeded
+        const RLEX = 1 << 5;
+        /// Transmit frame corruption due to AHB error
+        const TFC = 1 << 6;
+        /// Transmit complete
+        const TCOMP = 1 << 7;
+        /// Receive overrun
+        const ROVR = 1 << 10;
+        /// HRESP not OK
+        const HRESP = 1 << 11;
+        /// Pause frame with non-zero pause quantum received
+        const PFNZ = 1 << 12;
+        /// Pause time zero
+        const PTZ = 1 << 13;
+        /// Pause frame transmitted
+        const PFTR = 1 << 14;
+        /// Wake on LAN
+        const WOL = 1 << 28;
+    }
+}
+
+//==============================================================================
+//  Config
+//==============================================================================
+
+/// A configurable, disabled GMAC
+pub struct Config<RXDV, MDC, MDIO, const RX: usize, const TX: usize>
+where
+    RXDV: RxDvPin,
+    MDC: MdcPin,
+    MDIO: MdioPin,
+{
+    regs: Registers<GMAC>,
+    pins: Pins<RXDV, MDC, MDIO>,
+    rx: &'static mut RxRing<RX>,
+    tx: &'static mut TxRing<TX>,
+    phy: u8,
+}
+
+impl<RXDV, MDC, MDIO, const RX: usize, const TX: usize> Config<RXDV, MDC, MDIO, RX, TX>
+where
+    RXDV: RxDvPin,
+    MDC: MdcPin,
+    MDIO: MdioPin,
+{
+    /// Create a new `Config`
+    ///
+    /// Enables the GMAC clocks, initializes the descriptor rings and sets the
+    /// MDC clock divider from the MCK frequency. The MAC is configured for a
+    /// 100 Mbit/s full duplex link until the link is negotiated, and accepts
+    /// broadcast frames. The PHY address defaults to 0.
+    #[inline]
+    pub fn new(
+        mclk: &mut MCLK,
+        gmac: GMAC,
+        pins: Pins<RXDV, MDC, MDIO>,
+        mck: impl Into<Hertz>,
+        rx: &'static mut RxRing<RX>,
+        tx: &'static mut TxRing<TX>,
+    ) -> Self {
+        mclk.ahbmask.modify(|_, w| w.gmac_().set_bit());
+        mclk.apbcmask.modify(|_, w| w.gmac_().set_bit());
+
+        rx.init();
+        tx.init();
+        let mut regs = Registers::new(gmac);
+        regs.init(reg::mdc_divider(mck.into()), rx.base(), tx.base());
+        regs.set_link(Link {
+            speed: Speed::Mbps100,
+            duplex: Duplex::Full,
+        });
+        Self {
+            regs,
+            pins,
+            rx,
+            tx,
+            phy: 0,
+        }
+    }
+
+    /// Set the primary MAC address, i.e. specific address filter 0
+    #[inline]
+    pub fn mac_address(mut self, address: [u8; 6]) -> Self {
+        self.regs.set_mac_address(0, Some(address));
+        self
+    }
+
+    /// Get the primary MAC address
+    #[inline]
+    pub fn get_mac_address(&self) -> [u8; 6] {
+        self.regs.get_mac_address(0)
+    }
+
+    /// Set the MDIO address of the PHY
+    #[inline]
+    pub fn phy_address(mut self, address: u8) -> Self {
+        self.phy = address & 0x1F;
+        self
+    }
+
+    /// Get the MDIO address of the PHY
+    #[inline]
+    pub fn get_phy_address(&self) -> u8 {
+        self.phy
+    }
+
+    /// Force the link speed and duplex of the MAC, e.g. when the PHY is not
+    /// auto-negotiating
+    #[inline]
+    pub fn link(mut self, link: Link) -> Self {
+        self.regs.set_link(link);
+        self
+    }
+
+    /// Accept or reject broadcast frames
+    #[inline]
+    pub fn broadcast(mut self, enabled: bool) -> Self {
+        self.regs.set_broadcast(enabled);
+        self
+    }
+
+    /// Enable or disable promiscuous mode, in which all valid frames are
+    /// accepted
+    #[inline]
+    pub fn copy_all_frames(mut self, enabled: bool) -> Self {
+        self.regs.set_copy_all(enabled);
+        self
+    }
+
+    /// Enable or disable the local loopback, which connects the transmitter
+    /// to the receiver inside the MAC
+    #[inline]
+    pub fn loopback(mut self, enabled: bool) -> Self {
+        self.regs.set_loopback(enabled);
+        self
+    }
+
+    /// Enable the transmitter, receiver and management port
+    #[inline]
+    pub fn enable(mut self) -> Gmac<RXDV, MDC, MDIO, RX, TX> {
+        self.regs.enable();
+        Gmac { config: self }
+    }
+
+    /// Disable the GMAC clocks and return the resources
+    #[inline]
+    pub fn free(
+        self,
+        mclk: &mut MCLK,
+    ) -> (
+        GMAC,
+        Pins<RXDV, MDC, MDIO>,
+        &'static mut RxRing<RX>,
+        &'static mut TxRing<TX>,
+    ) {
+        mclk.apbcmask.modify(|_, w| w.gmac_().clear_bit());
+        mclk.ahbmask.modify(|_, w| w.gmac_().clear_bit());
+        (self.regs.free(), self.pins, self.rx, self.tx)
+    }
+}
+
+//==============================================================================
+//  Gmac
+//==============================================================================
+
+/// An enabled GMAC
+pub struct Gmac<RXDV, MDC, MDIO, const RX: usize, const TX: usize>
+where
+    RXDV: RxDvPin,
+    MDC: MdcPin,
+    MDIO: MdioPin,
+{
+    config: Config<RXDV, MDC, MDIO, RX, TX>,
+}
+
+impl<RXDV, MDC, MDIO, const RX: usize, const TX: usize> Gmac<RXDV, MDC, MDIO, RX, TX>
+where
+    RXDV: RxDvPin,
+    MDC: MdcPin,
+    MDIO: MdioPin,
+{
+    /// Disable the transmitter, receiver and management port, and return
+    /// the [`Config`]
+    ///
+    /// The descriptor rings are reset, and frames pending in them are lost.
+    #[inline]
+    pub fn disable(mut self) -> Config<RXDV, MDC, MDIO, RX, TX> {
+        let config = &mut self.config;
+        config.regs.disable();
+        config.rx.init();
+        config.tx.init();
+        config.regs.set_queues(config.rx.base(), config.tx.base());
+        self.config
+    }
+
+    /// Queue a frame for transmission
+    ///
+    /// `frame` must start with the destination MAC address, and must not
+    /// include the FCS, which is appended by the MAC. Returns
+    /// [`WouldBlock`](nb::Error::WouldBlock) if the Tx ring is full.
+    #[inline]
+    pub fn transmit(&mut self, frame: &[u8]) -> nb::Result<(), Error> {
+        self.transmit_with(frame.len(), |buffer| buffer.copy_from_slice(frame))
+    }
+
+    /// Queue a `len`-byte frame for transmission, letting `f` write it
+    /// directly into the Tx buffer
+    #[inline]
+    pub fn transmit_with<R>(
+        &mut self,
+        len: usize,
+        f: impl FnOnce(&mut [u8]) -> R,
+    ) -> nb::Result<R, Error> {
+        let result = self.config.tx.transmit_with(len, f)?;
+        self.config.regs.start_transmission();
+        Ok(result)
+    }
+
+    /// Copy the next received frame into `buffer`, and return its length
+    ///
+    /// The FCS is stripped by the MAC. If `buffer` is too small, the frame is
+    /// dropped and [`Error::FrameTooLong`] is returned. [`Error::Overrun`] is
+    /// returned once if frames were dropped because the Rx ring was full.
+    #[inline]
+    pub fn receive(&mut self, buffer: &mut [u8]) -> nb::Result<usize, Error> {
+        self.receive_with(|frame| {
+            let dst = buffer
+                .get_mut(..frame.len())
+                .ok_or(nb::Error::Other(Error::FrameTooLong))?;
+            dst.copy_from_slice(frame);
+            Ok(frame.len())
+        })?
+    }
+
+    /// Pass the next received frame to `f`, then release its buffer
+    #[inline]
+    pub fn receive_with<R>(&mut self, f: impl FnOnce(&mut [u8]) -> R) -> nb::Result<R, Error> {
+        if self.config.regs.rx_overrun() {
+            return Err(nb::Error::Other(Error::Overrun));
+        }
+        self.config.rx.receive_with(f).ok_or(nb::Error::WouldBlock)
+    }
+
+    /// Read a register of the PHY
+    #[inline]
+    pub fn read_phy(&mut self, reg: u8) -> u16 {
+        let phy = self.config.phy;
+        self.config.regs.mdio_read(phy, reg)
+    }
+
+    /// Write a register of the PHY
+    #[inline]
+    pub fn write_phy(&mut self, reg: u8, value: u16) {
+        let phy = self.config.phy;
+        self.config.regs.mdio_write(phy, reg, value)
+    }
+
+    /// Reset the PHY, blocking until the reset completes
+    #[inline]
+    pub fn reset_phy(&mut self) {
+        self.write_phy(BMCR, phy::BMCR_RESET);
+        while self.read_phy(BMCR) & phy::BMCR_RESET != 0 {}
+    }
+
+    /// Advertise all 10/100 modes and (re)start auto-negotiation
+    #[inline]
+    pub fn start_autonegotiation(&mut self) {
+        self.write_phy(ANAR, phy::ANAR_ALL);
+        self.write_phy(BMCR, phy::BMCR_AN_ENABLE | phy::BMCR_AN_RESTART);
+    }
+
+    /// Force the PHY and the MAC into the given link mode, disabling
+    /// auto-negotiation
+    #[inline]
+    pub fn force_link(&mut self, link: Link) {
+        self.write_phy(BMCR, link.bmcr());
+        self.config.regs.set_link(link);
+    }
+
+    /// Returns `true` if the PHY reports the link as up
+    #[inline]
+    pub fn link_up(&mut self) -> bool {
+        // The link status bit latches low, read twice to get the current state
+        self.read_phy(BMSR);
+        self.read_phy(BMSR) & phy::BMSR_LINK != 0
+    }
+
+    /// Wait for auto-negotiation to complete, then configure the MAC for the
+    /// resolved link mode
+    #[inline]
+    pub fn poll_link(&mut self) -> nb::Result<Link, Error> {
+        self.read_phy(BMSR);
+        let bmsr = self.read_phy(BMSR);
+        if bmsr & (phy::BMSR_AN_COMPLETE | phy::BMSR_LINK) != phy::BMSR_AN_COMPLETE | phy::BMSR_LINK
+        {
+            return Err(nb::Error::WouldBlock);
+        }
+        let anar = self.read_phy(ANAR);
+        let anlpar = self.read_phy(ANLPAR);
+        let link = phy::resolve(anar, anlpar).ok_or(nb::Error::Other(Error::AutoNegotiation))?;
+        self.config.regs.set_link(link);
+        Ok(link)
+    }
+
+    /// Get the link mode the MAC is configured for
+    #[inline]
+    pub fn get_link(&self) -> Link {
+        self.config.regs.get_link()
+    }
+
+    /// Program one of the four specific address filters (0 to 3), or disable
+    /// it with `None`
+    #[inline]
+    pub fn set_mac_address(&mut self, index: usize, address: Option<[u8; 6]>) -> Result<(), Error> {
+        if index >= 4 {
+            return Err(Error::InvalidIndex);
+        }
+        self.config.regs.set_mac_address(index, address);
+        Ok(())
+    }
+
+    /// Add an address to the hash table
+    ///
+    /// Hash matching must be enabled with
+    /// [`set_hash_filtering`](Gmac::set_hash_filtering). Since 64 buckets are
+    /// shared by all addresses, frames sent to other addresses with the same
+    /// [`hash_index`] are accepted as well.
+    #[inline]
+    pub fn add_hash(&mut self, address: &[u8; 6]) {
+        let hash = self.config.regs.get_hash() | 1 << hash_index(address);
+        self.config.regs.set_hash(hash);
+    }
+
+    /// Clear the hash table
+    #[inline]
+    pub fn clear_hash(&mut self) {
+        self.config.regs.set_hash(0);
+    }
+
+    /// Enable or disable hash matching for multicast and unicast frames
+    #[inline]
+    pub fn set_hash_filtering(&mut self, multicast: bool, unicast: bool) {
+        self.config.regs.set_hash_filtering(multicast, unicast);
+    }
+
+    /// Enable or disable promiscuous mode
+    #[inline]
+    pub fn set_copy_all_frames(&mut self, enabled: bool) {
+        self.config.regs.set_copy_all(enabled);
+    }
+
+    /// Enable interrupts
+    #[inline]
+    pub fn enable_interrupts(&mut self, flags: Interrupts) {
+        self.config.regs.enable_interrupts(flags);
+    }
+
+    /// Disable interrupts
+    #[inline]
+    pub fn disable_interrupts(&mut self, flags: Interrupts) {
+        self.config.regs.disable_interrupts(flags);
+    }
+
+    /// Read the interrupt flags. Reading clears them.
+    #[inline]
+    pub fn read_interrupts(&mut self) -> Interrupts {
+        self.config.regs.read_interrupts()
+    }
+}
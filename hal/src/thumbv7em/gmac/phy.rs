@@ -0,0 +1,129 @@
+//! Ethernet PHY management
+//!
+//! The GMAC talks to the external PHY through the MDIO management interface,
+//! using IEEE 802.3 clause 22 frames. Only the standard registers, common to
+//! every 10/100 PHY, are used by this driver. Vendor-specific registers can
+//! be accessed through [`Gmac::read_phy`](super::Gmac::read_phy) and
+//! [`Gmac::write_phy`](super::Gmac::write_phy).
+
+/// Basic mode control register
+pub const BMCR: u8 = 0;
+/// Basic mode status register
+pub const BMSR: u8 = 1;
+/// PHY identifier, high word
+pub const PHYID1: u8 = 2;
+/// PHY identifier, low word
+pub const PHYID2: u8 = 3;
+/// Auto-negotiation advertisement register
+pub const ANAR: u8 = 4;
+/// Auto-negotiation link partner ability register
+pub const ANLPAR: u8 = 5;
+
+// BMCR bits
+pub(super) const BMCR_RESET: u16 = 1 << 15;
+pub(super) const BMCR_SPEED_100: u16 = 1 << 13;
+pub(super) const BMCR_AN_ENABLE: u16 = 1 << 12;
+pub(super) const BMCR_AN_RESTART: u16 = 1 << 9;
+pub(super) const BMCR_FULL_DUPLEX: u16 = 1 << 8;
+
+// BMSR bits
+pub(super) const BMSR_AN_COMPLETE: u16 = 1 << 5;
+pub(super) const BMSR_LINK: u16 = 1 << 2;
+
+// ANAR and ANLPAR bits
+const AN_100_FULL: u16 = 1 << 8;
+const AN_100_HALF: u16 = 1 << 7;
+const AN_10_FULL: u16 = 1 << 6;
+const AN_10_HALF: u16 = 1 << 5;
+const AN_SELECTOR_802_3: u16 = 0x01;
+
+/// Advertise every mode supported by the GMAC in RMII mode
+pub(super) const ANAR_ALL: u16 =
+    AN_100_FULL | AN_100_HALF | AN_10_FULL | AN_10_HALF | AN_SELECTOR_802_3;
+
+/// Link speed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speed {
+    /// 10 Mbit/s
+    Mbps10,
+    /// 100 Mbit/s
+    Mbps100,
+}
+
+/// Link duplex mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplex {
+    /// Half duplex
+    Half,
+    /// Full duplex
+    Full,
+}
+
+/// Link parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Link {
+    /// Link speed
+    pub speed: Speed,
+    /// Duplex mode
+    pub duplex: Duplex,
+}
+
+impl Link {
+    /// `BMCR` value forcing this link mode, with auto-negotiation disabled
+    #[inline]
+    pub(super) fn bmcr(self) -> u16 {
+        let mut bmcr = 0;
+        if self.speed == Speed::Mbps100 {
+            bmcr |= BMCR_SPEED_100;
+        }
+        if self.duplex == Duplex::Full {
+            bmcr |= BMCR_FULL_DUPLEX;
+        }
+        bmcr
+    }
+}
+
+/// Resolve the highest common denominator of the local advertisement and the
+/// link partner abilities, as specified by IEEE 802.3 annex 28B
+#[inline]
+pub(super) fn resolve(anar: u16, anlpar: u16) -> Option<Link> {
+    let common = anar & anlpar;
+    let (speed, duplex) = if common & AN_100_FULL != 0 {
+        (Speed::Mbps100, Duplex::Full)
+    } else if common & AN_100_HALF != 0 {
+        (Speed::Mbps100, Duplex::Half)
+    } else if common & AN_10_FULL != 0 {
+        (Speed::Mbps10, Duplex::Full)
+    } else if common & AN_10_HALF != 0 {
+        (Speed::Mbps10, Duplex::Half)
+    } else {
+        return None;
+    };
+    Some(Link { speed, duplex })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_picks_highest_common_mode() {
+        let link = resolve(ANAR_ALL, AN_100_HALF | AN_10_FULL | AN_SELECTOR_802_3);
+        assert_eq!(
+            link,
+            Some(Link {
+                speed: Speed::Mbps100,
+                duplex: Duplex::Half
+            })
+        );
+        let link = resolve(AN_10_FULL | AN_10_HALF, ANAR_ALL);
+        assert_eq!(
+            link,
+            Some(Link {
+                speed: Speed::Mbps10,
+                duplex: Duplex::Full
+            })
+        );
+        assert_eq!(resolve(AN_100_FULL, AN_10_HALF), None);
+    }
+}
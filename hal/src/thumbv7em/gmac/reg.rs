@@ -0,0 +1,418 @@
+//! Register-level access to the GMAC
+//!
+//! [`Registers`] is generic over anything that dereferences to the GMAC
+//! register block, so that it can be exercised on the host against a plain
+//! in-memory register block.
+
+use core::ops::Deref;
+
+use crate::pac::gmac::RegisterBlock;
+use crate::time::Hertz;
+
+use super::phy::{Duplex, Link, Speed};
+use super::ring::BUFFER_SIZE;
+use super::Interrupts;
+
+/// MDC clock dividers, indexed by the value of `NCFGR.CLK`
+const MDC_DIVIDERS: [u32; 6] = [8, 16, 32, 48, 64, 96];
+
+/// Maximum MDC frequency allowed by IEEE 802.3
+const MDC_MAX: u32 = 2_500_000;
+
+/// Select the smallest MCK divider keeping MDC within the IEEE 802.3 limit
+#[inline]
+pub(super) fn mdc_divider(mck: Hertz) -> u8 {
+    MDC_DIVIDERS
+        .iter()
+        .position(|&div| mck.0 / div <= MDC_MAX)
+        .unwrap_or(MDC_DIVIDERS.len() - 1) as u8
+}
+
+/// Compute the index of a MAC address in the 64-bit hash table
+///
+/// Each bit of the index is the XOR of every sixth bit of the destination
+/// address, starting from the least significant bit of the first byte.
+#[inline]
+pub fn hash_index(address: &[u8; 6]) -> u8 {
+    let bit = |n: usize| (address[n / 8] >> (n % 8)) & 1;
+    (0..6).fold(0, |index, j| {
+        let parity = (0..8).fold(0, |acc, k| acc ^ bit(j + 6 * k));
+        index | parity << j
+    })
+}
+
+// MAN register fields for clause 22 frames
+const MAN_CLTTO: u32 = 1 << 30;
+const MAN_OP_WRITE: u32 = 0b01 << 28;
+const MAN_OP_READ: u32 = 0b10 << 28;
+const MAN_WTN: u32 = 0b10 << 16;
+
+pub(super) struct Registers<R> {
+    gmac: R,
+}
+
+// SAFETY: It is safe to implement Sync for Registers, because it erases the
+// interior mutability of the PAC GMAC struct.
+unsafe impl<R> Sync for Registers<R> {}
+
+impl<R: Deref<Target = RegisterBlock>> Registers<R> {
+    /// Create a new `Registers` instance
+    #[inline]
+    pub(super) fn new(gmac: R) -> Self {
+        Self { gmac }
+    }
+
+    /// Free the `Registers` struct and return the underlying register block
+    #[inline]
+    pub(super) fn free(self) -> R {
+        self.gmac
+    }
+
+    /// Stop the transmitter and receiver, clear all status flags, and
+    /// configure the DMA, RMII interface and MDC clock
+    #[inline]
+    pub(super) fn init(&mut self, mdc_divider: u8, rx_base: u32, tx_base: u32) {
+        let gmac = &self.gmac;
+        gmac.ncr.reset();
+        gmac.ncr.write(|w| w.clrstat().set_bit());
+        // SAFETY: Status bits are cleared by writing ones
+        unsafe {
+            gmac.tsr.write(|w| w.bits(0xFFFF_FFFF));
+            gmac.rsr.write(|w| w.bits(0xFFFF_FFFF));
+            gmac.idr.write(|w| w.bits(0xFFFF_FFFF));
+        }
+        // Reading ISR clears it
+        gmac.isr.read();
+
+        gmac.ur.write(|w| w.mii().clear_bit());
+        // SAFETY: Values are within the range of each field. DRBS is expressed
+        // in units of 64 bytes. FBLDO = INCR4 bursts, and the full 4 KiB
+        // packet buffers are used in both directions.
+        unsafe {
+            gmac.dcfgr.write(|w| {
+                w.fbldo().bits(4);
+                w.rxbms().bits(3);
+                w.txpbms().set_bit();
+                w.drbs().bits((BUFFER_SIZE / 64) as u8)
+            });
+            gmac.ncfgr.modify(|_, w| {
+                w.clk().bits(mdc_divider);
+                w.maxfs().set_bit();
+                w.rfcs().set_bit();
+                w.lferd().set_bit()
+            });
+        }
+        self.set_queues(rx_base, tx_base);
+    }
+
+    /// Set the base address of the Rx and Tx descriptor lists
+    ///
+    /// Must only be called while the transmitter and receiver are disabled.
+    #[inline]
+    pub(super) fn set_queues(&mut self, rx_base: u32, tx_base: u32) {
+        // SAFETY: Descriptor lists are word-aligned
+        unsafe {
+            self.gmac.rbqb.write(|w| w.bits(rx_base));
+            self.gmac.tbqb.write(|w| w.bits(tx_base));
+        }
+    }
+
+    /// Enable the transmitter, receiver and management port
+    #[inline]
+    pub(super) fn enable(&mut self) {
+        self.gmac.ncr.modify(|_, w| {
+            w.mpe().set_bit();
+            w.rxen().set_bit();
+            w.txen().set_bit()
+        });
+    }
+
+    /// Disable the transmitter, receiver and management port
+    #[inline]
+    pub(super) fn disable(&mut self) {
+        self.gmac.ncr.modify(|_, w| {
+            w.mpe().clear_bit();
+            w.rxen().clear_bit();
+            w.txen().clear_bit()
+        });
+    }
+
+    /// Start transmitting the frames queued in the Tx ring
+    #[inline]
+    pub(super) fn start_transmission(&mut self) {
+        self.gmac.ncr.modify(|_, w| w.tstart().set_bit());
+    }
+
+    /// Enable or disable the local loopback
+    #[inline]
+    pub(super) fn set_loopback(&mut self, enabled: bool) {
+        self.gmac.ncr.modify(|_, w| w.lbl().bit(enabled));
+    }
+
+    /// Configure the MAC for the link speed and duplex negotiated by the PHY
+    #[inline]
+    pub(super) fn set_link(&mut self, link: Link) {
+        self.gmac.ncfgr.modify(|_, w| {
+            w.spd().bit(link.speed == Speed::Mbps100);
+            w.fd().bit(link.duplex == Duplex::Full)
+        });
+    }
+
+    /// Get the link speed and duplex the MAC is configured for
+    #[inline]
+    pub(super) fn get_link(&self) -> Link {
+        let ncfgr = self.gmac.ncfgr.read();
+        Link {
+            speed: if ncfgr.spd().bit() {
+                Speed::Mbps100
+            } else {
+                Speed::Mbps10
+            },
+            duplex: if ncfgr.fd().bit() {
+                Duplex::Full
+            } else {
+                Duplex::Half
+            },
+        }
+    }
+
+    /// Program one of the four specific address filters, or disable it
+    #[inline]
+    pub(super) fn set_mac_address(&mut self, index: usize, address: Option<[u8; 6]>) {
+        let sa = &self.gmac.sa[index];
+        match address {
+            Some(a) => {
+                let bottom = u32::from_le_bytes([a[0], a[1], a[2], a[3]]);
+                let top = u16::from_le_bytes([a[4], a[5]]);
+                // SAFETY: Writing SAB disables the filter, writing SAT enables
+                // it again. Both fields use the full register width.
+                unsafe {
+                    sa.sab.write(|w| w.addr().bits(bottom));
+                    sa.sat.write(|w| w.addr().bits(top));
+                }
+            }
+            // SAFETY: Writing SAB alone leaves the filter disabled
+            None => sa.sab.write(|w| unsafe { w.addr().bits(0) }),
+        }
+    }
+
+    /// Get the address programmed into one of the specific address filters
+    #[inline]
+    pub(super) fn get_mac_address(&self, index: usize) -> [u8; 6] {
+        let sa = &self.gmac.sa[index];
+        let bottom = sa.sab.read().addr().bits().to_le_bytes();
+        let top = sa.sat.read().addr().bits().to_le_bytes();
+        [bottom[0], bottom[1], bottom[2], bottom[3], top[0], top[1]]
+    }
+
+    /// Set the 64-bit hash table
+    #[inline]
+    pub(super) fn set_hash(&mut self, hash: u64) {
+        // SAFETY: Both fields use the full register width
+        unsafe {
+            self.gmac.hrb.write(|w| w.addr().bits(hash as u32));
+            self.gmac.hrt.write(|w| w.addr().bits((hash >> 32) as u32));
+        }
+    }
+
+    /// Get the 64-bit hash table
+    #[inline]
+    pub(super) fn get_hash(&self) -> u64 {
+        let bottom = self.gmac.hrb.read().addr().bits() as u64;
+        let top = self.gmac.hrt.read().addr().bits() as u64;
+        top << 32 | bottom
+    }
+
+    /// Enable or disable hash matching for multicast and unicast frames
+    #[inline]
+    pub(super) fn set_hash_filtering(&mut self, multicast: bool, unicast: bool) {
+        self.gmac.ncfgr.modify(|_, w| {
+            w.mtihen().bit(multicast);
+            w.unihen().bit(unicast)
+        });
+    }
+
+    /// Enable or disable promiscuous mode
+    #[inline]
+    pub(super) fn set_copy_all(&mut self, enabled: bool) {
+        self.gmac.ncfgr.modify(|_, w| w.caf().bit(enabled));
+    }
+
+    /// Accept or reject broadcast frames
+    #[inline]
+    pub(super) fn set_broadcast(&mut self, enabled: bool) {
+        self.gmac.ncfgr.modify(|_, w| w.nbc().bit(!enabled));
+    }
+
+    /// Wait for the end of the current management frame
+    #[inline]
+    fn wait_mdio(&self) {
+        while self.gmac.nsr.read().idle().bit_is_clear() {}
+    }
+
+    /// Read a PHY register, blocking until the management frame completes
+    #[inline]
+    pub(super) fn mdio_read(&mut self, phy: u8, reg: u8) -> u16 {
+        self.wait_mdio();
+        let man = MAN_CLTTO | MAN_OP_READ | MAN_WTN | frame_address(phy, reg);
+        // SAFETY: This is a valid clause 22 read frame
+        self.gmac.man.write(|w| unsafe { w.bits(man) });
+        self.wait_mdio();
+        self.gmac.man.read().data().bits()
+    }
+
+    /// Write a PHY register, blocking until the management frame completes
+    #[inline]
+    pub(super) fn mdio_write(&mut self, phy: u8, reg: u8, value: u16) {
+        self.wait_mdio();
+        let man = MAN_CLTTO | MAN_OP_WRITE | MAN_WTN | frame_address(phy, reg) | value as u32;
+        // SAFETY: This is a valid clause 22 write frame
+        self.gmac.man.write(|w| unsafe { w.bits(man) });
+        self.wait_mdio();
+    }
+
+    /// Enable interrupts
+    #[inline]
+    pub(super) fn enable_interrupts(&mut self, flags: Interrupts) {
+        // SAFETY: `Interrupts` only contains valid bits
+        self.gmac.ier.write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Disable interrupts
+    #[inline]
+    pub(super) fn disable_interrupts(&mut self, flags: Interrupts) {
+        // SAFETY: `Interrupts` only contains valid bits
+        self.gmac.idr.write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Read and clear the interrupt flags
+    #[inline]
+    pub(super) fn read_interrupts(&mut self) -> Interrupts {
+        Interrupts::from_bits_truncate(self.gmac.isr.read().bits())
+    }
+
+    /// Returns `true` if the receiver ran out of buffers or overran since the
+    /// last call
+    #[inline]
+    pub(super) fn rx_overrun(&mut self) -> bool {
+        let rsr = self.gmac.rsr.read();
+        let overrun = rsr.bna().bit() || rsr.rxovr().bit();
+        if overrun {
+            self.gmac.rsr.write(|w| {
+                w.bna().set_bit();
+                w.rxovr().set_bit()
+            });
+        }
+        overrun
+    }
+}
+
+/// PHY and register address fields of a management frame
+#[inline]
+fn frame_address(phy: u8, reg: u8) -> u32 {
+    ((phy as u32) & 0x1F) << 23 | ((reg as u32) & 0x1F) << 18
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::MaybeUninit;
+
+    /// A zeroed register block standing in for the GMAC
+    fn fake_gmac() -> RegisterBlock {
+        // SAFETY: The register block only contains plain integers
+        unsafe { MaybeUninit::zeroed().assume_init() }
+    }
+
+    /// Set NSR.IDLE, which is read-only through the PAC API
+    fn set_idle(gmac: &RegisterBlock) {
+        // SAFETY: The register block lives in ordinary memory
+        unsafe { gmac.nsr.as_ptr().write_volatile(1 << 2) };
+    }
+
+    #[test]
+    fn mdc_divider_respects_2_5_mhz() {
+        assert_eq!(mdc_divider(Hertz(20_000_000)), 0);
+        assert_eq!(mdc_divider(Hertz(48_000_000)), 2);
+        assert_eq!(mdc_divider(Hertz(120_000_000)), 3);
+        assert_eq!(mdc_divider(Hertz(200_000_000)), 5);
+    }
+
+    #[test]
+    fn hash_index_xors_every_sixth_bit() {
+        assert_eq!(hash_index(&[0xFF; 6]), 0);
+        assert_eq!(hash_index(&[0x01, 0, 0, 0, 0, 0]), 0b000001);
+        assert_eq!(hash_index(&[0x41, 0, 0, 0, 0, 0]), 0);
+        assert_eq!(hash_index(&[0x80, 0, 0, 0, 0, 0]), 0b000010);
+        assert_eq!(hash_index(&[0, 0, 0, 0, 0, 0x80]), 0b100000);
+    }
+
+    #[test]
+    fn init_programs_queues_and_dma() {
+        let gmac = fake_gmac();
+        let mut regs = Registers::new(&gmac);
+        regs.init(3, 0x2000_1000, 0x2000_2000);
+        assert_eq!(gmac.rbqb.read().bits(), 0x2000_1000);
+        assert_eq!(gmac.tbqb.read().bits(), 0x2000_2000);
+        assert_eq!(gmac.dcfgr.read().drbs().bits(), 24);
+        let ncfgr = gmac.ncfgr.read();
+        assert_eq!(ncfgr.clk().bits(), 3);
+        assert!(ncfgr.maxfs().bit() && ncfgr.rfcs().bit());
+        assert!(!gmac.ur.read().mii().bit());
+
+        regs.enable();
+        let ncr = gmac.ncr.read();
+        assert!(ncr.rxen().bit() && ncr.txen().bit() && ncr.mpe().bit());
+    }
+
+    #[test]
+    fn mac_address_round_trips() {
+        let gmac = fake_gmac();
+        let mut regs = Registers::new(&gmac);
+        let address = [0x02, 0x11, 0x22, 0x33, 0x44, 0x55];
+        regs.set_mac_address(1, Some(address));
+        assert_eq!(gmac.sa[1].sab.read().bits(), 0x3322_1102);
+        assert_eq!(gmac.sa[1].sat.read().bits(), 0x5544);
+        assert_eq!(regs.get_mac_address(1), address);
+    }
+
+    #[test]
+    fn hash_table_spans_both_registers() {
+        let gmac = fake_gmac();
+        let mut regs = Registers::new(&gmac);
+        regs.set_hash(1 << 40 | 1 << 3);
+        assert_eq!(gmac.hrb.read().bits(), 1 << 3);
+        assert_eq!(gmac.hrt.read().bits(), 1 << 8);
+        assert_eq!(regs.get_hash(), 1 << 40 | 1 << 3);
+    }
+
+    #[test]
+    fn mdio_frames_are_clause_22() {
+        let gmac = fake_gmac();
+        set_idle(&gmac);
+        let mut regs = Registers::new(&gmac);
+
+        regs.mdio_write(0x1F, 4, 0x01E1);
+        assert_eq!(gmac.man.read().bits(), 0x5F92_01E1);
+
+        regs.mdio_read(0, 1);
+        let man = gmac.man.read();
+        assert_eq!(man.op().bits(), 0b10);
+        assert_eq!(man.phya().bits(), 0);
+        assert_eq!(man.rega().bits(), 1);
+    }
+
+    #[test]
+    fn link_configures_speed_and_duplex() {
+        let gmac = fake_gmac();
+        let mut regs = Registers::new(&gmac);
+        let link = Link {
+            speed: Speed::Mbps100,
+            duplex: Duplex::Full,
+        };
+        regs.set_link(link);
+        assert_eq!(regs.get_link(), link);
+        let ncfgr = gmac.ncfgr.read();
+        assert!(ncfgr.spd().bit() && ncfgr.fd().bit());
+    }
+}
@@ -0,0 +1,372 @@
+//! DMA descriptor rings
+//!
+//! The GMAC DMA engine walks circular lists of two-word descriptors, each
+//! pointing to a buffer in system RAM. Ownership of a descriptor is handed
+//! back and forth between the software and the GMAC through a single bit:
+//!
+//! * In the Rx ring, the GMAC sets the *ownership* bit of the address word
+//!   after storing a frame. The software clears it once the frame has been
+//!   consumed.
+//! * In the Tx ring, the software clears the *used* bit of the status word to
+//!   request a transmission. The GMAC sets it again once the frame has been
+//!   sent.
+//!
+//! Each buffer is [`BUFFER_SIZE`] bytes long, so that every frame accepted by
+//! the GMAC fits in a single descriptor.
+
+use core::sync::atomic::{fence, Ordering};
+
+use vcell::VolatileCell;
+
+use super::Error;
+
+/// Size of each Rx and Tx buffer, in bytes
+///
+/// This is the largest frame accepted by the GMAC when `NCFGR.MAXFS` is set.
+/// It must be a multiple of 64 bytes.
+pub const BUFFER_SIZE: usize = 1536;
+
+// Rx descriptor, address word
+const RX_OWNERSHIP: u32 = 1 << 0;
+const RX_WRAP: u32 = 1 << 1;
+
+// Rx descriptor, status word
+const RX_LENGTH: u32 = 0x1FFF;
+const RX_SOF: u32 = 1 << 14;
+const RX_EOF: u32 = 1 << 15;
+
+// Tx descriptor, status word
+const TX_LENGTH: u32 = 0x3FFF;
+const TX_LAST: u32 = 1 << 15;
+const TX_WRAP: u32 = 1 << 30;
+const TX_USED: u32 = 1 << 31;
+
+/// A GMAC DMA descriptor
+#[repr(C)]
+struct Descriptor {
+    address: VolatileCell<u32>,
+    status: VolatileCell<u32>,
+}
+
+impl Descriptor {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const NEW: Self = Self {
+        address: VolatileCell::new(0),
+        status: VolatileCell::new(0),
+    };
+}
+
+/// A frame buffer. Buffer addresses must be word-aligned.
+#[derive(Clone, Copy)]
+#[repr(C, align(8))]
+struct Buffer([u8; BUFFER_SIZE]);
+
+/// Address of a descriptor list or buffer, as seen by the GMAC
+#[inline]
+fn address<T>(ptr: *const T) -> u32 {
+    ptr as usize as u32
+}
+
+//==============================================================================
+//  RxRing
+//==============================================================================
+
+/// Receive descriptor ring with `N` buffers
+///
+/// Because the descriptors are accessed by the GMAC DMA engine, the ring must
+/// have a `'static` lifetime:
+///
+/// ```
+/// use atsamd_hal::gmac::RxRing;
+///
+/// static mut RX_RING: RxRing<8> = RxRing::new();
+/// ```
+#[repr(C, align(8))]
+pub struct RxRing<const N: usize> {
+    descriptors: [Descriptor; N],
+    buffers: [Buffer; N],
+    next: usize,
+}
+
+impl<const N: usize> RxRing<N> {
+    /// Create a new `RxRing`
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            descriptors: [Descriptor::NEW; N],
+            buffers: [Buffer([0; BUFFER_SIZE]); N],
+            next: 0,
+        }
+    }
+
+    /// Hand every descriptor over to the GMAC
+    #[inline]
+    pub(super) fn init(&mut self) {
+        for (i, (descriptor, buffer)) in
+            self.descriptors.iter().zip(self.buffers.iter()).enumerate()
+        {
+            let mut word = address(buffer.0.as_ptr());
+            if i == N - 1 {
+                word |= RX_WRAP;
+            }
+            descriptor.status.set(0);
+            descriptor.address.set(word);
+        }
+        self.next = 0;
+        fence(Ordering::SeqCst);
+    }
+
+    /// Address of the descriptor list, as programmed into `RBQB`
+    #[inline]
+    pub(super) fn base(&self) -> u32 {
+        address(self.descriptors.as_ptr())
+    }
+
+    /// Length of the next received frame, if any
+    ///
+    /// Frames spanning more than one buffer cannot be produced with
+    /// [`BUFFER_SIZE`] buffers. Should one appear anyway, for instance after
+    /// an overrun, its descriptors are silently released.
+    #[inline]
+    pub(super) fn peek(&mut self) -> Option<usize> {
+        loop {
+            let descriptor = &self.descriptors[self.next];
+            if descriptor.address.get() & RX_OWNERSHIP == 0 {
+                return None;
+            }
+            // Don't read the status word or the buffer before the ownership
+            // bit
+            fence(Ordering::Acquire);
+            let status = descriptor.status.get();
+            if status & (RX_SOF | RX_EOF) == RX_SOF | RX_EOF {
+                return Some((status & RX_LENGTH) as usize);
+            }
+            self.release();
+        }
+    }
+
+    /// Return the current descriptor to the GMAC and advance to the next one
+    #[inline]
+    pub(super) fn release(&mut self) {
+        // Make sure the buffer is no longer accessed when the GMAC gets it
+        fence(Ordering::Release);
+        let descriptor = &self.descriptors[self.next];
+        descriptor
+            .address
+            .set(descriptor.address.get() & !RX_OWNERSHIP);
+        self.next = (self.next + 1) % N;
+    }
+
+    /// Pass the next received frame to `f`, then release its buffer
+    #[inline]
+    pub(super) fn receive_with<R>(&mut self, f: impl FnOnce(&mut [u8]) -> R) -> Option<R> {
+        let len = self.peek()?;
+        let result = f(&mut self.buffers[self.next].0[..len]);
+        self.release();
+        Some(result)
+    }
+}
+
+impl<const N: usize> Default for RxRing<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//==============================================================================
+//  TxRing
+//==============================================================================
+
+/// Transmit descriptor ring with `N` buffers
+///
+/// Because the descriptors are accessed by the GMAC DMA engine, the ring must
+/// have a `'static` lifetime:
+///
+/// ```
+/// use atsamd_hal::gmac::TxRing;
+///
+/// static mut TX_RING: TxRing<4> = TxRing::new();
+/// ```
+#[repr(C, align(8))]
+pub struct TxRing<const N: usize> {
+    descriptors: [Descriptor; N],
+    buffers: [Buffer; N],
+    next: usize,
+}
+
+impl<const N: usize> TxRing<N> {
+    /// Create a new `TxRing`
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            descriptors: [Descriptor::NEW; N],
+            buffers: [Buffer([0; BUFFER_SIZE]); N],
+            next: 0,
+        }
+    }
+
+    /// Mark every descriptor as owned by the software
+    #[inline]
+    pub(super) fn init(&mut self) {
+        for (i, (descriptor, buffer)) in
+            self.descriptors.iter().zip(self.buffers.iter()).enumerate()
+        {
+            let mut status = TX_USED;
+            if i == N - 1 {
+                status |= TX_WRAP;
+            }
+            descriptor.address.set(address(buffer.0.as_ptr()));
+            descriptor.status.set(status);
+        }
+        self.next = 0;
+        fence(Ordering::SeqCst);
+    }
+
+    /// Address of the descriptor list, as programmed into `TBQB`
+    #[inline]
+    pub(super) fn base(&self) -> u32 {
+        address(self.descriptors.as_ptr())
+    }
+
+    /// Returns `true` if the next descriptor is available for transmission
+    #[inline]
+    pub(super) fn is_available(&self) -> bool {
+        self.descriptors[self.next].status.get() & TX_USED != 0
+    }
+
+    /// Let `f` fill a `len`-byte frame into the next buffer, then hand the
+    /// descriptor over to the GMAC
+    ///
+    /// The caller is responsible for starting the transmission.
+    #[inline]
+    pub(super) fn transmit_with<R>(
+        &mut self,
+        len: usize,
+        f: impl FnOnce(&mut [u8]) -> R,
+    ) -> nb::Result<R, Error> {
+        if len > BUFFER_SIZE {
+            return Err(nb::Error::Other(Error::FrameTooLong));
+        }
+        if !self.is_available() {
+            return Err(nb::Error::WouldBlock);
+        }
+        // Don't write the buffer before checking the used bit
+        fence(Ordering::Acquire);
+        let result = f(&mut self.buffers[self.next].0[..len]);
+
+        let mut status = (len as u32 & TX_LENGTH) | TX_LAST;
+        if self.next == N - 1 {
+            status |= TX_WRAP;
+        }
+        // The buffer must be written before the descriptor is handed over
+        fence(Ordering::Release);
+        self.descriptors[self.next].status.set(status);
+        self.next = (self.next + 1) % N;
+        Ok(result)
+    }
+}
+
+impl<const N: usize> Default for TxRing<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Store a frame into the next Rx descriptor, the way the GMAC would
+    fn gmac_receive<const N: usize>(ring: &mut RxRing<N>, index: usize, frame: &[u8]) {
+        ring.buffers[index].0[..frame.len()].copy_from_slice(frame);
+        let descriptor = &ring.descriptors[index];
+        descriptor.status.set(frame.len() as u32 | RX_SOF | RX_EOF);
+        descriptor
+            .address
+            .set(descriptor.address.get() | RX_OWNERSHIP);
+    }
+
+    #[test]
+    fn rx_init_sets_wrap_on_last_descriptor() {
+        let mut ring = RxRing::<3>::new();
+        ring.init();
+        for (i, descriptor) in ring.descriptors.iter().enumerate() {
+            let word = descriptor.address.get();
+            assert_eq!(word & !0b11, address(ring.buffers[i].0.as_ptr()));
+            assert_eq!(word & RX_WRAP != 0, i == 2);
+            assert_eq!(word & RX_OWNERSHIP, 0);
+        }
+        assert_eq!(ring.base(), address(ring.descriptors.as_ptr()));
+    }
+
+    #[test]
+    fn rx_receives_frames_in_order_and_wraps() {
+        let mut ring = RxRing::<2>::new();
+        ring.init();
+        assert_eq!(ring.receive_with(|_| ()), None);
+
+        for round in 0..3u8 {
+            gmac_receive(&mut ring, 0, &[round; 60]);
+            gmac_receive(&mut ring, 1, &[round + 1; 64]);
+            assert_eq!(ring.receive_with(|f| f == &[round; 60][..]), Some(true));
+            assert_eq!(ring.receive_with(|f| f.len()), Some(64));
+            assert_eq!(ring.receive_with(|_| ()), None);
+        }
+
+        // The wrap bit survives the ownership handover
+        assert_ne!(ring.descriptors[1].address.get() & RX_WRAP, 0);
+        assert_eq!(ring.descriptors[1].address.get() & RX_OWNERSHIP, 0);
+    }
+
+    #[test]
+    fn rx_drops_fragmented_frames() {
+        let mut ring = RxRing::<4>::new();
+        ring.init();
+        gmac_receive(&mut ring, 0, &[1; 10]);
+        gmac_receive(&mut ring, 1, &[2; 20]);
+        ring.descriptors[0].status.set(10 | RX_SOF);
+
+        assert_eq!(ring.receive_with(|f| f[0]), Some(2));
+        assert_eq!(ring.descriptors[0].address.get() & RX_OWNERSHIP, 0);
+        assert_eq!(ring.next, 2);
+    }
+
+    #[test]
+    fn tx_fills_descriptors_until_full() {
+        let mut ring = TxRing::<2>::new();
+        ring.init();
+
+        assert!(ring.transmit_with(60, |b| b.fill(0xAA)).is_ok());
+        assert!(ring.transmit_with(1514, |b| b.fill(0x55)).is_ok());
+        assert!(matches!(
+            ring.transmit_with(60, |_| ()),
+            Err(nb::Error::WouldBlock)
+        ));
+
+        let status = ring.descriptors[0].status.get();
+        assert_eq!(status, 60 | TX_LAST);
+        let status = ring.descriptors[1].status.get();
+        assert_eq!(status, 1514 | TX_LAST | TX_WRAP);
+        assert_eq!(ring.buffers[1].0[1513], 0x55);
+
+        // The GMAC sets the used bit once the first frame is sent
+        ring.descriptors[0]
+            .status
+            .set(ring.descriptors[0].status.get() | TX_USED);
+        assert!(ring.transmit_with(42, |_| ()).is_ok());
+        assert_eq!(ring.next, 1);
+    }
+
+    #[test]
+    fn tx_rejects_oversized_frames() {
+        let mut ring = TxRing::<1>::new();
+        ring.init();
+        assert!(matches!(
+            ring.transmit_with(BUFFER_SIZE + 1, |_| ()),
+            Err(nb::Error::Other(Error::FrameTooLong))
+        ));
+        assert!(ring.is_available());
+    }
+}
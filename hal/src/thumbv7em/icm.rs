@@ -0,0 +1,708 @@
+//! # Integrity Check Monitor (ICM)
+//!
+//! The ICM is a DMA-capable SHA engine, computing SHA-1, SHA-224 and SHA-256
+//! digests of memory regions without CPU intervention. It can be used in two
+//! ways:
+//!
+//! - As a hash accelerator, through [`Icm::hasher`], which returns a
+//!   [`Hasher`] implementing the traits of the `digest` crate.
+//! - As a memory monitor, through [`Icm::monitor`], which computes a
+//!   reference digest of up to four regions, then continuously hashes them
+//!   again and raises an interrupt whenever a digest does not match its
+//!   reference.
+//!
+//! ```
+//! use atsamd_hal::icm::{Algorithm, Icm, Interrupts, MonitorMemory, Region, Sha256};
+//! use digest::{FixedOutput, Update};
+//!
+//! let mut icm = Icm::new(&mut peripherals.MCLK, peripherals.ICM);
+//! let digest = icm.hasher::<Sha256>().chain(b"abc").finalize_fixed();
+//!
+//! let memory = cortex_m::singleton!(: MonitorMemory = MonitorMemory::new()).unwrap();
+//! let firmware = Region::new(firmware, Algorithm::SHA256).unwrap();
+//! let mut monitor = icm.monitor(memory, &[firmware]);
+//! monitor.enable_interrupts(Interrupts::DIGEST_MISMATCH);
+//! ```
+//!
+//! ## Monitoring
+//!
+//! The monitored regions are hashed as is: the ICM does not pad them, so the
+//! reference digests are not the standard SHA digests of the regions. They
+//! are computed on the first pass, and can be read back with
+//! [`Monitor::reference_digest`]. A region which is legitimately modified
+//! should be disabled with [`Monitor::disable_region`], then rehashed with
+//! [`Monitor::rehash`].
+
+use core::cmp;
+use core::marker::PhantomData;
+use core::ptr;
+use core::sync::atomic::{fence, Ordering};
+
+use bitflags::bitflags;
+use digest::consts::{U20, U28, U32, U64};
+use digest::generic_array::{ArrayLength, GenericArray};
+use digest::{BlockInput, FixedOutputDirty, Reset, Update};
+
+use crate::pac::{icm, ICM, MCLK};
+use crate::typelevel::Sealed;
+
+/// SHA algorithm
+pub use icm::cfg::UALGO_A as Algorithm;
+
+/// Maximum number of blocks hashed in a single region
+const MAX_BLOCKS: usize = 0x1_0000;
+
+/// Maximum number of monitored regions
+const MAX_REGIONS: usize = 4;
+
+// Bits of the region configuration word
+const RCFG_WRAP: u32 = 1 << 1;
+const RCFG_EOM: u32 = 1 << 2;
+const RCFG_ALGO_SHIFT: u32 = 12;
+
+//==============================================================================
+// Errors and interrupts
+//==============================================================================
+
+/// Errors related to the ICM
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The start of a region is not aligned on a word
+    Unaligned,
+    /// A region is empty, is not made of whole 64-byte blocks, or is longer
+    /// than 65536 blocks
+    InvalidLength,
+    /// A bus error occurred while the ICM was reading memory
+    BusError,
+}
+
+bitflags! {
+    /// ICM interrupt flags
+    ///
+    /// Each flag is set by any of the four regions; use
+    /// [`Interrupts::region`] to select those of a single region. The binary
+    /// format of the flags matches the `ISR` register.
+    pub struct Interrupts: u32 {
+        /// The hash of a region was computed
+        const HASH_COMPLETE = 0xF;
+        /// The digest of a region does not match its reference
+        const DIGEST_MISMATCH = 0xF << 4;
+        /// A bus error occurred while hashing a region
+        const BUS_ERROR = 0xF << 8;
+        /// The monitoring wrapped to the first region
+        const WRAP = 0xF << 12;
+        /// The end of the monitored regions was reached
+        const END = 0xF << 16;
+        /// The status of a region was updated
+        const STATUS_UPDATED = 0xF << 20;
+        /// A configuration register was accessed while monitoring
+        const UNDEFINED_ACCESS = 1 << 24;
+    }
+}
+
+impl Interrupts {
+    /// Interrupt flags of the given region
+    ///
+    /// # Panics
+    ///
+    /// Panics if `region` is not lower than four.
+    #[inline]
+    pub fn region(region: usize) -> Self {
+        assert!(region < MAX_REGIONS);
+        Self::from_bits_truncate(0x11_1111 << region)
+    }
+}
+
+//==============================================================================
+// Memory structures
+//==============================================================================
+
+/// Region descriptor, read by the ICM
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RegionDescriptor {
+    raddr: u32,
+    rcfg: u32,
+    rctrl: u32,
+    rnext: u32,
+}
+
+impl RegionDescriptor {
+    const EMPTY: Self = Self {
+        raddr: 0,
+        rcfg: 0,
+        rctrl: 0,
+        rnext: 0,
+    };
+}
+
+/// Descriptor area, which must be aligned on 64 bytes
+#[repr(C, align(64))]
+struct DescriptorArea([RegionDescriptor; MAX_REGIONS]);
+
+/// Hash area, holding eight words per region, which must be aligned on 128
+/// bytes
+#[repr(C, align(128))]
+struct HashArea([u32; 8 * MAX_REGIONS]);
+
+/// Memory used by the ICM in the monitoring mode
+///
+/// It holds the descriptors of the regions and their reference digests, and
+/// is accessed by the ICM for as long as the monitoring is running.
+#[repr(C)]
+pub struct MonitorMemory {
+    hashes: HashArea,
+    descriptors: DescriptorArea,
+}
+
+impl MonitorMemory {
+    /// Create a new `MonitorMemory`
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            hashes: HashArea([0; 8 * MAX_REGIONS]),
+            descriptors: DescriptorArea([RegionDescriptor::EMPTY; MAX_REGIONS]),
+        }
+    }
+}
+
+impl Default for MonitorMemory {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//==============================================================================
+// Icm
+//==============================================================================
+
+/// ICM peripheral
+pub struct Icm {
+    icm: ICM,
+}
+
+impl Icm {
+    /// Enable the ICM peripheral
+    #[inline]
+    pub fn new(mclk: &mut MCLK, icm: ICM) -> Self {
+        mclk.ahbmask.modify(|_, w| w.icm_().set_bit());
+        mclk.apbcmask.modify(|_, w| w.icm_().set_bit());
+        icm.ctrl.write(|w| w.swrst().set_bit());
+        Self { icm }
+    }
+
+    /// Create a [`Hasher`] computing digests with the algorithm `A`
+    #[inline]
+    pub fn hasher<A: HashAlgorithm>(&mut self) -> Hasher<'_, A> {
+        Hasher {
+            icm: self,
+            state: initial_state::<A>(),
+            blocks: Blocks([0; 128]),
+            buffered: 0,
+            length: 0,
+            status: Ok(()),
+            algorithm: PhantomData,
+        }
+    }
+
+    /// Compute the digest of `data` with the algorithm `A`
+    #[inline]
+    pub fn digest<A: HashAlgorithm>(
+        &mut self,
+        data: &[u8],
+    ) -> Result<GenericArray<u8, A::OutputSize>, Error> {
+        let mut hasher = self.hasher::<A>();
+        let mut digest = GenericArray::default();
+        hasher.update(data);
+        hasher.finalize_into_dirty(&mut digest);
+        hasher.status().map(|()| digest)
+    }
+
+    /// Start monitoring the given regions
+    ///
+    /// The reference digests of the regions are computed on the first pass;
+    /// the regions are then hashed again in a loop, and compared to their
+    /// reference.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `regions` is empty or holds more than four regions.
+    #[inline]
+    pub fn monitor(self, memory: &'static mut MonitorMemory, regions: &[Region]) -> Monitor {
+        assert!(!regions.is_empty() && regions.len() <= MAX_REGIONS);
+
+        memory.hashes.0 = [0; 8 * MAX_REGIONS];
+        for (i, descriptor) in memory.descriptors.0.iter_mut().enumerate() {
+            *descriptor = match regions.get(i) {
+                Some(region) => RegionDescriptor {
+                    raddr: region.address,
+                    rcfg: ((region.algorithm as u32) << RCFG_ALGO_SHIFT)
+                        | if i == regions.len() - 1 { RCFG_WRAP } else { 0 },
+                    rctrl: (region.blocks - 1) as u32,
+                    rnext: 0,
+                },
+                None => RegionDescriptor::EMPTY,
+            };
+        }
+
+        self.icm.ctrl.write(|w| w.swrst().set_bit());
+        self.icm.cfg.write(|w| {
+            w.slbdis().set_bit();
+            w.ascd().set_bit()
+        });
+        // SAFETY: Both areas are aligned as required by the DSCR and HASH
+        // registers, and live as long as the monitoring
+        self.icm
+            .dscr
+            .write(|w| unsafe { w.bits(&mut memory.descriptors as *mut _ as u32) });
+        self.icm
+            .hash
+            .write(|w| unsafe { w.bits(&mut memory.hashes as *mut _ as u32) });
+
+        fence(Ordering::SeqCst);
+        self.icm.ctrl.write(|w| w.enable().set_bit());
+
+        Monitor {
+            icm: self,
+            memory,
+            regions: regions.len(),
+        }
+    }
+
+    /// Disable the ICM peripheral and return the underlying ICM
+    #[inline]
+    pub fn free(self, mclk: &mut MCLK) -> ICM {
+        self.icm.ctrl.write(|w| w.swrst().set_bit());
+        mclk.apbcmask.modify(|_, w| w.icm_().clear_bit());
+        mclk.ahbmask.modify(|_, w| w.icm_().clear_bit());
+        self.icm
+    }
+
+    /// Disable the ICM and wait until it is done with the memory
+    #[inline]
+    fn disable(&mut self) {
+        self.icm.ctrl.write(|w| w.disable().set_bit());
+        while self.icm.sr.read().enable().bit_is_set() {}
+        fence(Ordering::SeqCst);
+    }
+
+    /// Update `state` with whole 64-byte blocks of `data`
+    ///
+    /// The ICM starts from `state` and writes the resulting state back,
+    /// without padding the data. On a bus error, `state` is invalid.
+    fn hash_blocks(
+        &mut self,
+        algorithm: Algorithm,
+        state: &mut [u8; 32],
+        data: &[u8],
+    ) -> Result<(), Error> {
+        debug_assert!(data.as_ptr() as usize & 3 == 0 && data.len() & 63 == 0);
+
+        for chunk in data.chunks(MAX_BLOCKS * 64) {
+            let mut descriptors = DescriptorArea([RegionDescriptor::EMPTY; MAX_REGIONS]);
+            descriptors.0[0] = RegionDescriptor {
+                raddr: chunk.as_ptr() as u32,
+                rcfg: ((algorithm as u32) << RCFG_ALGO_SHIFT) | RCFG_EOM,
+                rctrl: (chunk.len() / 64 - 1) as u32,
+                rnext: 0,
+            };
+            let mut hashes = HashArea([0; 8 * MAX_REGIONS]);
+
+            self.icm.ctrl.write(|w| w.swrst().set_bit());
+            self.icm.cfg.write(|w| {
+                w.slbdis().set_bit();
+                w.uihash().set_bit();
+                w.ualgo().variant(algorithm)
+            });
+            for (reg, word) in self.icm.uihval.iter().zip(state.chunks(4)) {
+                let word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+                reg.write(|w| unsafe { w.val().bits(word) });
+            }
+            // SAFETY: Both areas are aligned as required by the DSCR and HASH
+            // registers, and outlive the hash computation
+            self.icm
+                .dscr
+                .write(|w| unsafe { w.bits(&mut descriptors as *mut _ as u32) });
+            self.icm
+                .hash
+                .write(|w| unsafe { w.bits(&mut hashes as *mut _ as u32) });
+
+            fence(Ordering::SeqCst);
+            self.icm.ctrl.write(|w| w.enable().set_bit());
+            let result = loop {
+                let isr = self.icm.isr.read();
+                if isr.rbe().bits() & 1 != 0 {
+                    break Err(Error::BusError);
+                }
+                if isr.rhc().bits() & 1 != 0 {
+                    break Ok(());
+                }
+            };
+            // Disabling the ICM fences its writes to the hash area
+            self.disable();
+            result?;
+
+            for (bytes, word) in state.chunks_mut(4).zip(hashes.0.iter_mut()) {
+                // SAFETY: The ICM wrote the hash area behind the compiler's back
+                let word = unsafe { ptr::read_volatile(word) };
+                bytes.copy_from_slice(&word.to_le_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+//==============================================================================
+// Hashing
+//==============================================================================
+
+/// Algorithm of a [`Hasher`]
+pub trait HashAlgorithm: Sealed {
+    /// Size of the digest
+    type OutputSize: ArrayLength<u8>;
+    #[doc(hidden)]
+    const ALGORITHM: Algorithm;
+    #[doc(hidden)]
+    const INITIAL_HASH: [u32; 8];
+}
+
+/// SHA-1 algorithm
+pub enum Sha1 {}
+
+impl Sealed for Sha1 {}
+
+impl HashAlgorithm for Sha1 {
+    type OutputSize = U20;
+    const ALGORITHM: Algorithm = Algorithm::SHA1;
+    const INITIAL_HASH: [u32; 8] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+        0,
+        0,
+        0,
+    ];
+}
+
+/// SHA-224 algorithm
+pub enum Sha224 {}
+
+impl Sealed for Sha224 {}
+
+// SHA-224 is processed as SHA-256 with its own initial hash, so that the whole
+// state is written back by the ICM
+impl HashAlgorithm for Sha224 {
+    type OutputSize = U28;
+    const ALGORITHM: Algorithm = Algorithm::SHA256;
+    const INITIAL_HASH: [u32; 8] = [
+        0xC105_9ED8,
+        0x367C_D507,
+        0x3070_DD17,
+        0xF70E_5939,
+        0xFFC0_0B31,
+        0x6858_1511,
+        0x64F9_8FA7,
+        0xBEFA_4FA4,
+    ];
+}
+
+/// SHA-256 algorithm
+pub enum Sha256 {}
+
+impl Sealed for Sha256 {}
+
+impl HashAlgorithm for Sha256 {
+    type OutputSize = U32;
+    const ALGORITHM: Algorithm = Algorithm::SHA256;
+    const INITIAL_HASH: [u32; 8] = [
+        0x6A09_E667,
+        0xBB67_AE85,
+        0x3C6E_F372,
+        0xA54F_F53A,
+        0x510E_527F,
+        0x9B05_688C,
+        0x1F83_D9AB,
+        0x5BE0_CD19,
+    ];
+}
+
+/// Initial state of the algorithm `A`, in memory order
+fn initial_state<A: HashAlgorithm>() -> [u8; 32] {
+    let mut state = [0; 32];
+    for (bytes, word) in state.chunks_mut(4).zip(A::INITIAL_HASH.iter()) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    state
+}
+
+/// Pad a message of `length` bytes, whose last `buffered` bytes are at the
+/// start of `blocks`, and return the length of the padded blocks
+fn pad(blocks: &mut [u8; 128], buffered: usize, length: u64) -> usize {
+    let end = if buffered < 56 { 64 } else { 128 };
+    blocks[buffered] = 0x80;
+    for byte in &mut blocks[buffered + 1..end - 8] {
+        *byte = 0;
+    }
+    blocks[end - 8..end].copy_from_slice(&(length * 8).to_be_bytes());
+    end
+}
+
+/// Word-aligned buffer of two blocks
+#[repr(C, align(4))]
+struct Blocks([u8; 128]);
+
+/// Hasher using the ICM
+///
+/// The data is buffered until whole blocks are available. Word-aligned data
+/// is directly hashed from its location, without being copied.
+///
+/// The `digest` traits can't report errors: once a bus error occurs, the
+/// remaining data is ignored and the digest is invalid, which is reported by
+/// [`Hasher::status`] until the hasher is reset.
+pub struct Hasher<'a, A: HashAlgorithm> {
+    icm: &'a mut Icm,
+    state: [u8; 32],
+    blocks: Blocks,
+    buffered: usize,
+    length: u64,
+    status: Result<(), Error>,
+    algorithm: PhantomData<A>,
+}
+
+impl<A: HashAlgorithm> Hasher<'_, A> {
+    /// Check whether a bus error occurred while hashing the data
+    #[inline]
+    pub fn status(&self) -> Result<(), Error> {
+        self.status
+    }
+
+    /// Update the state with whole blocks, unless a bus error occurred
+    #[inline]
+    fn hash_blocks(
+        icm: &mut Icm,
+        status: &mut Result<(), Error>,
+        state: &mut [u8; 32],
+        data: &[u8],
+    ) {
+        if status.is_ok() {
+            *status = icm.hash_blocks(A::ALGORITHM, state, data);
+        }
+    }
+}
+
+impl<A: HashAlgorithm> Update for Hasher<'_, A> {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        let mut data = data.as_ref();
+        self.length += data.len() as u64;
+
+        while !data.is_empty() {
+            if self.buffered == 0 && data.len() >= 64 && data.as_ptr() as usize & 3 == 0 {
+                let (blocks, rest) = data.split_at(data.len() & !63);
+                Self::hash_blocks(self.icm, &mut self.status, &mut self.state, blocks);
+                data = rest;
+            } else {
+                let count = cmp::min(64 - self.buffered, data.len());
+                let (bytes, rest) = data.split_at(count);
+                self.blocks.0[self.buffered..self.buffered + count].copy_from_slice(bytes);
+                self.buffered += count;
+                data = rest;
+                if self.buffered == 64 {
+                    Self::hash_blocks(
+                        self.icm,
+                        &mut self.status,
+                        &mut self.state,
+                        &self.blocks.0[..64],
+                    );
+                    self.buffered = 0;
+                }
+            }
+        }
+    }
+}
+
+impl<A: HashAlgorithm> FixedOutputDirty for Hasher<'_, A> {
+    type OutputSize = A::OutputSize;
+
+    fn finalize_into_dirty(&mut self, out: &mut GenericArray<u8, Self::OutputSize>) {
+        let end = pad(&mut self.blocks.0, self.buffered, self.length);
+        Self::hash_blocks(
+            self.icm,
+            &mut self.status,
+            &mut self.state,
+            &self.blocks.0[..end],
+        );
+        let len = out.len();
+        out.copy_from_slice(&self.state[..len]);
+    }
+}
+
+impl<A: HashAlgorithm> Reset for Hasher<'_, A> {
+    #[inline]
+    fn reset(&mut self) {
+        self.state = initial_state::<A>();
+        self.buffered = 0;
+        self.length = 0;
+        self.status = Ok(());
+    }
+}
+
+impl<A: HashAlgorithm> BlockInput for Hasher<'_, A> {
+    type BlockSize = U64;
+}
+
+//==============================================================================
+// Monitoring
+//==============================================================================
+
+/// Memory region monitored by the ICM
+#[derive(Clone, Copy)]
+pub struct Region {
+    address: u32,
+    blocks: usize,
+    algorithm: Algorithm,
+}
+
+impl Region {
+    /// Create a region covering `data`, hashed with `algorithm`
+    ///
+    /// `data` must be word-aligned, and made of at most 65536 whole 64-byte
+    /// blocks.
+    #[inline]
+    pub fn new(data: &'static [u8], algorithm: Algorithm) -> Result<Self, Error> {
+        if data.as_ptr() as usize & 3 != 0 {
+            return Err(Error::Unaligned);
+        }
+        if data.is_empty() || data.len() & 63 != 0 || data.len() / 64 > MAX_BLOCKS {
+            return Err(Error::InvalidLength);
+        }
+        Ok(Self {
+            address: data.as_ptr() as u32,
+            blocks: data.len() / 64,
+            algorithm,
+        })
+    }
+}
+
+/// ICM monitoring memory regions
+pub struct Monitor {
+    icm: Icm,
+    memory: &'static mut MonitorMemory,
+    regions: usize,
+}
+
+impl Monitor {
+    /// Enable the given interrupts
+    #[inline]
+    pub fn enable_interrupts(&mut self, interrupts: Interrupts) {
+        // SAFETY: Interrupts only holds valid bits of the register
+        self.icm
+            .icm
+            .ier
+            .write(|w| unsafe { w.bits(interrupts.bits()) });
+    }
+
+    /// Disable the given interrupts
+    #[inline]
+    pub fn disable_interrupts(&mut self, interrupts: Interrupts) {
+        // SAFETY: Interrupts only holds valid bits of the register
+        self.icm
+            .icm
+            .idr
+            .write(|w| unsafe { w.bits(interrupts.bits()) });
+    }
+
+    /// Read and clear the interrupt flags
+    #[inline]
+    pub fn status(&mut self) -> Interrupts {
+        Interrupts::from_bits_truncate(self.icm.icm.isr.read().bits())
+    }
+
+    /// Stop monitoring `region`
+    #[inline]
+    pub fn disable_region(&mut self, region: usize) {
+        let mask = self.region_mask(region);
+        self.icm.icm.ctrl.write(|w| unsafe { w.rmdis().bits(mask) });
+    }
+
+    /// Resume monitoring `region`
+    #[inline]
+    pub fn enable_region(&mut self, region: usize) {
+        let mask = self.region_mask(region);
+        self.icm.icm.ctrl.write(|w| unsafe { w.rmen().bits(mask) });
+    }
+
+    /// Compute the reference digest of `region` again
+    ///
+    /// The region should be disabled while it is modified, then rehashed
+    /// before being enabled again.
+    #[inline]
+    pub fn rehash(&mut self, region: usize) {
+        let mask = self.region_mask(region);
+        self.icm
+            .icm
+            .ctrl
+            .write(|w| unsafe { w.rehash().bits(mask) });
+    }
+
+    /// Reference digest of `region`
+    ///
+    /// Only the first 20 bytes are used by SHA-1, and the first 28 bytes by
+    /// SHA-224.
+    #[inline]
+    pub fn reference_digest(&self, region: usize) -> [u8; 32] {
+        let _ = self.region_mask(region);
+        let mut digest = [0; 32];
+        let hashes = &self.memory.hashes.0[8 * region..8 * (region + 1)];
+        for (bytes, word) in digest.chunks_mut(4).zip(hashes.iter()) {
+            // SAFETY: The ICM writes the hash area behind the compiler's back
+            let word = unsafe { ptr::read_volatile(word) };
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        digest
+    }
+
+    /// Stop monitoring and return the [`Icm`] and its memory
+    #[inline]
+    pub fn stop(mut self) -> (Icm, &'static mut MonitorMemory) {
+        self.icm.disable();
+        self.icm.icm.ctrl.write(|w| w.swrst().set_bit());
+        (self.icm, self.memory)
+    }
+
+    #[inline]
+    fn region_mask(&self, region: usize) -> u8 {
+        assert!(region < self.regions);
+        1 << region
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padding() {
+        let mut blocks = [0xFF; 128];
+        blocks[..3].copy_from_slice(b"abc");
+        assert_eq!(pad(&mut blocks, 3, 3), 64);
+        assert_eq!(blocks[3], 0x80);
+        assert!(blocks[4..63].iter().all(|&byte| byte == 0));
+        assert_eq!(blocks[63], 24);
+
+        let mut blocks = [0xFF; 128];
+        assert_eq!(pad(&mut blocks, 56, 120), 128);
+        assert_eq!(blocks[56], 0x80);
+        assert!(blocks[57..126].iter().all(|&byte| byte == 0));
+        assert_eq!(blocks[126..], [0x03, 0xC0]);
+    }
+
+    #[test]
+    fn initial_states() {
+        assert_eq!(initial_state::<Sha1>()[..4], [0x67, 0x45, 0x23, 0x01]);
+        assert_eq!(initial_state::<Sha1>()[20..], [0; 12]);
+        assert_eq!(initial_state::<Sha256>()[28..], [0x5B, 0xE0, 0xCD, 0x19]);
+    }
+}
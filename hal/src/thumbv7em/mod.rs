@@ -1,8 +1,34 @@
+pub mod aes;
 pub mod calibration;
+
+#[cfg(any(feature = "same51", feature = "same54"))]
+pub mod can;
+
+pub mod ccl;
 pub mod clock;
+pub mod cmcc;
+pub mod dac;
 pub mod eic;
+pub mod evsys;
+pub mod freqm;
+
+#[cfg(feature = "min-samd51j")]
+pub mod i2s;
+
+#[cfg(any(feature = "same53", feature = "same54"))]
+pub mod gmac;
+
+pub mod icm;
+
+pub mod nvm;
+pub mod pcc;
+pub mod pdec;
+pub mod power;
 pub mod qspi;
+pub mod ramecc;
+pub mod sdhc;
 pub(crate) mod sercom;
+pub mod supc;
 pub mod timer;
 pub mod trng;
 
@@ -0,0 +1,417 @@
+//! # Non-Volatile Memory Controller
+//!
+//! The [`Nvm`] gives access to the internal flash. The flash is erased by
+//! blocks of [`BLOCK_SIZE`] bytes, and written by quad-words of
+//! [`QUAD_WORD_SIZE`] bytes, or by whole pages of [`PAGE_SIZE`] bytes, through
+//! the page buffer. Because of the error correction codes, each quad-word can
+//! only be written once between erases.
+//!
+//! Erasing or writing flash that holds the running program, or data in use,
+//! is undefined behavior, which is why [`Nvm::erase`] and [`Nvm::write`] are
+//! `unsafe`. A [`FlashStorage`] restricts the accesses to a region reserved
+//! for storage, and implements the [`embedded-storage`] [`NorFlash`] traits:
+//!
+//! ```
+//! use atsamd_hal::nvm::{FlashStorage, Nvm};
+//! use embedded_storage::nor_flash::NorFlash;
+//!
+//! let nvm = Nvm::new(peripherals.NVMCTRL);
+//! // The last 16 kB of flash are reserved for storage by the linker script
+//! let mut storage = unsafe { FlashStorage::new(nvm, 0x7_C000, 0x4000) }.unwrap();
+//! storage.erase(0, 0x2000).unwrap();
+//! storage.write(0, &[0xAB; 16]).unwrap();
+//! ```
+//!
+//! ## Dual bank
+//!
+//! The flash is split in two banks, which can be read and programmed
+//! independently. Programming the bank that does not hold the running program
+//! does not stall it. [`Nvm::swap_banks`] exchanges the banks, so that a new
+//! firmware written to the second bank starts after the reset.
+//!
+//! ## Region locks
+//!
+//! The flash is divided in 32 regions, which can be locked against erases and
+//! writes. [`Nvm::lock_region`] and [`Nvm::unlock_region`] only last until
+//! the next reset. Permanent locks are configured in the user page, see
+//! [`user_row`].
+//!
+//! ## SmartEEPROM
+//!
+//! The end of the flash can be reserved for an emulated EEPROM, see
+//! [`smart_eeprom`].
+//!
+//! [`embedded-storage`]: embedded_storage
+//! [`NorFlash`]: embedded_storage::nor_flash::NorFlash
+
+use core::ptr;
+
+use embedded_storage::nor_flash::{
+    check_erase, check_read, check_write, ErrorType, NorFlash, NorFlashError, NorFlashErrorKind,
+    ReadNorFlash,
+};
+
+use crate::pac::nvmctrl::ctrlb::CMD_AW;
+use crate::pac::{CMCC, NVMCTRL};
+
+pub mod smart_eeprom;
+pub mod user_row;
+
+/// Size of a flash page, in bytes
+pub const PAGE_SIZE: usize = 512;
+
+/// Size of a flash block, the smallest erasable unit, in bytes
+pub const BLOCK_SIZE: usize = 16 * PAGE_SIZE;
+
+/// Size of a quad-word, the smallest writable unit, in bytes
+pub const QUAD_WORD_SIZE: usize = 16;
+
+/// Number of lock regions
+const NUM_REGIONS: usize = 32;
+
+//==============================================================================
+//  Error
+//==============================================================================
+
+/// NVM errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The address or length is not properly aligned
+    Alignment,
+    /// The address range is outside of the flash
+    OutOfBounds,
+    /// The NVM controller rejected the address (`ADDRE`)
+    Address,
+    /// An invalid command was issued (`PROGE`)
+    Programming,
+    /// The address is in a locked region (`LOCKE`)
+    Lock,
+    /// The NVM controller reported an error (`NVME`)
+    Nvm,
+}
+
+impl NorFlashError for Error {
+    #[inline]
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            Error::Alignment => NorFlashErrorKind::NotAligned,
+            Error::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+            _ => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+impl From<NorFlashErrorKind> for Error {
+    #[inline]
+    fn from(kind: NorFlashErrorKind) -> Self {
+        match kind {
+            NorFlashErrorKind::NotAligned => Error::Alignment,
+            NorFlashErrorKind::OutOfBounds => Error::OutOfBounds,
+            _ => Error::Nvm,
+        }
+    }
+}
+
+//==============================================================================
+//  Nvm
+//==============================================================================
+
+/// The NVMCTRL peripheral
+pub struct Nvm {
+    nvm: NVMCTRL,
+}
+
+impl Nvm {
+    /// Take control of the NVMCTRL
+    ///
+    /// Automatic writes are disabled, so that the page buffer is only written
+    /// to flash by [`Nvm::write`].
+    #[inline]
+    pub fn new(nvm: NVMCTRL) -> Self {
+        nvm.ctrla.modify(|_, w| w.wmode().man());
+        Self { nvm }
+    }
+
+    /// Size of the flash, in bytes
+    #[inline]
+    pub fn flash_size(&self) -> usize {
+        self.nvm.param.read().nvmp().bits() as usize * PAGE_SIZE
+    }
+
+    /// Size of a flash bank, in bytes
+    #[inline]
+    pub fn bank_size(&self) -> usize {
+        self.flash_size() / 2
+    }
+
+    /// Size of a lock region, in bytes
+    #[inline]
+    pub fn region_size(&self) -> usize {
+        self.flash_size() / NUM_REGIONS
+    }
+
+    #[inline]
+    fn wait_ready(&self) {
+        while self.nvm.status.read().ready().bit_is_clear() {}
+    }
+
+    /// Execute a command and check for errors
+    #[inline]
+    fn command(&mut self, command: CMD_AW, addr: Option<u32>) -> Result<(), Error> {
+        self.wait_ready();
+        self.nvm.intflag.write(|w| {
+            w.addre().set_bit();
+            w.proge().set_bit();
+            w.locke().set_bit();
+            w.nvme().set_bit()
+        });
+        if let Some(addr) = addr {
+            self.nvm.addr.write(|w| unsafe { w.addr().bits(addr) });
+        }
+        self.nvm.ctrlb.write(|w| {
+            w.cmd().variant(command);
+            w.cmdex().key()
+        });
+        self.wait_ready();
+
+        let flags = self.nvm.intflag.read();
+        if flags.addre().bit_is_set() {
+            Err(Error::Address)
+        } else if flags.proge().bit_is_set() {
+            Err(Error::Programming)
+        } else if flags.locke().bit_is_set() {
+            Err(Error::Lock)
+        } else if flags.nvme().bit_is_set() {
+            Err(Error::Nvm)
+        } else {
+            Ok(())
+        }
+    }
+
+    // All alignments are powers of two
+    #[inline]
+    fn check_range(&self, addr: u32, len: usize, align: usize) -> Result<(), Error> {
+        if (addr as usize | len) & (align - 1) != 0 {
+            return Err(Error::Alignment);
+        }
+        match (addr as usize).checked_add(len) {
+            Some(end) if end <= self.flash_size() => Ok(()),
+            _ => Err(Error::OutOfBounds),
+        }
+    }
+
+    /// Read flash contents
+    #[inline]
+    pub fn read(&self, addr: u32, buf: &mut [u8]) -> Result<(), Error> {
+        self.check_range(addr, buf.len(), 1)?;
+        for (i, byte) in buf.iter_mut().enumerate() {
+            // SAFETY: The range was checked to be within the flash. The flash
+            // starts at address 0, which can't be dereferenced as a regular
+            // pointer.
+            *byte = unsafe { ptr::read_volatile((addr as usize + i) as *const u8) };
+        }
+        Ok(())
+    }
+
+    /// Erase the blocks in `addr..addr + len`
+    ///
+    /// Both `addr` and `len` must be multiples of [`BLOCK_SIZE`].
+    ///
+    /// # Safety
+    ///
+    /// The range must not hold the running program, nor any data referenced
+    /// by it.
+    #[inline]
+    pub unsafe fn erase(&mut self, addr: u32, len: usize) -> Result<(), Error> {
+        self.check_range(addr, len, BLOCK_SIZE)?;
+        for block in (addr..addr + len as u32).step_by(BLOCK_SIZE) {
+            self.command(CMD_AW::EB, Some(block))?;
+        }
+        self.invalidate_cache();
+        Ok(())
+    }
+
+    /// Write `data` to flash at `addr`
+    ///
+    /// Both `addr` and the length of `data` must be multiples of
+    /// [`QUAD_WORD_SIZE`]. Whole pages are written at once, and the remaining
+    /// data one quad-word at a time. The flash must have been erased
+    /// beforehand.
+    ///
+    /// # Safety
+    ///
+    /// The range must not hold the running program, nor any data referenced
+    /// by it.
+    #[inline]
+    pub unsafe fn write(&mut self, addr: u32, data: &[u8]) -> Result<(), Error> {
+        self.check_range(addr, data.len(), QUAD_WORD_SIZE)?;
+
+        let mut addr = addr;
+        let mut data = data;
+        while !data.is_empty() {
+            // Write up to the end of the current page
+            let len = data.len().min(PAGE_SIZE - addr as usize % PAGE_SIZE);
+            let (page, rest) = data.split_at(len);
+
+            self.command(CMD_AW::PBC, None)?;
+            for (i, word) in page.chunks_exact(4).enumerate() {
+                let word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+                ptr::write_volatile((addr as *mut u32).add(i), word);
+            }
+            if len == PAGE_SIZE {
+                self.command(CMD_AW::WP, Some(addr))?;
+            } else {
+                // Writing a whole page would also program the unused
+                // quad-words, which could then not be written anymore
+                for quad_word in (addr..addr + len as u32).step_by(QUAD_WORD_SIZE) {
+                    self.command(CMD_AW::WQW, Some(quad_word))?;
+                }
+            }
+
+            addr += len as u32;
+            data = rest;
+        }
+        self.invalidate_cache();
+        Ok(())
+    }
+
+    /// Temporarily lock the region containing `addr`
+    #[inline]
+    pub fn lock_region(&mut self, addr: u32) -> Result<(), Error> {
+        self.check_range(addr, 1, 1)?;
+        self.command(CMD_AW::LR, Some(addr))
+    }
+
+    /// Temporarily unlock the region containing `addr`
+    #[inline]
+    pub fn unlock_region(&mut self, addr: u32) -> Result<(), Error> {
+        self.check_range(addr, 1, 1)?;
+        self.command(CMD_AW::UR, Some(addr))
+    }
+
+    /// Returns `true` if the region containing `addr` is locked
+    #[inline]
+    pub fn is_region_locked(&self, addr: u32) -> Result<bool, Error> {
+        self.check_range(addr, 1, 1)?;
+        let region = addr as usize / self.region_size();
+        Ok(self.nvm.runlock.read().runlock().bits() & (1 << region) == 0)
+    }
+
+    /// Returns `true` if bank A is mapped at the start of the flash
+    #[inline]
+    pub fn is_bank_a_first(&self) -> bool {
+        self.nvm.status.read().afirst().bit_is_set()
+    }
+
+    /// Swap the flash banks and reset the device
+    ///
+    /// # Safety
+    ///
+    /// The second bank must hold a valid program.
+    #[inline]
+    pub unsafe fn swap_banks(&mut self) -> ! {
+        let _ = self.command(CMD_AW::BKSWRST, None);
+        loop {
+            cortex_m::asm::nop();
+        }
+    }
+
+    /// Invalidate the CMCC cache, so that the new flash contents are read
+    ///
+    /// This is done after every erase and write through the [`Nvm`].
+    #[inline]
+    pub fn invalidate_cache(&mut self) {
+        // SAFETY: The cache is restored to its previous state
+        let cmcc = unsafe { &*CMCC::ptr() };
+        let enabled = cmcc.sr.read().csts().bit_is_set();
+        if enabled {
+            cmcc.ctrl.write(|w| w.cen().clear_bit());
+            while cmcc.sr.read().csts().bit_is_set() {}
+        }
+        cmcc.maint0.write(|w| w.invall().set_bit());
+        if enabled {
+            cmcc.ctrl.write(|w| w.cen().set_bit());
+        }
+    }
+
+    /// Return the underlying peripheral
+    #[inline]
+    pub fn free(self) -> NVMCTRL {
+        self.nvm
+    }
+}
+
+//==============================================================================
+//  FlashStorage
+//==============================================================================
+
+/// A flash region reserved for storage
+///
+/// Offsets used with the [`NorFlash`] traits are relative to the start of the
+/// region.
+pub struct FlashStorage {
+    nvm: Nvm,
+    start: u32,
+    len: usize,
+}
+
+impl FlashStorage {
+    /// Reserve the region `start..start + len` for storage
+    ///
+    /// Both `start` and `len` must be multiples of [`BLOCK_SIZE`].
+    ///
+    /// # Safety
+    ///
+    /// The region must not hold the program, nor any data referenced by it,
+    /// for as long as the [`FlashStorage`] exists. It is typically reserved
+    /// in the linker script.
+    #[inline]
+    pub unsafe fn new(nvm: Nvm, start: u32, len: usize) -> Result<Self, Error> {
+        nvm.check_range(start, len, BLOCK_SIZE)?;
+        Ok(Self { nvm, start, len })
+    }
+
+    /// Return the [`Nvm`]
+    #[inline]
+    pub fn free(self) -> Nvm {
+        self.nvm
+    }
+}
+
+impl ErrorType for FlashStorage {
+    type Error = Error;
+}
+
+impl ReadNorFlash for FlashStorage {
+    const READ_SIZE: usize = 1;
+
+    #[inline]
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Error> {
+        check_read(self, offset, bytes.len())?;
+        self.nvm.read(self.start + offset, bytes)
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.len
+    }
+}
+
+impl NorFlash for FlashStorage {
+    const WRITE_SIZE: usize = QUAD_WORD_SIZE;
+    const ERASE_SIZE: usize = BLOCK_SIZE;
+
+    #[inline]
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Error> {
+        check_erase(self, from, to)?;
+        // SAFETY: The region is reserved for storage
+        unsafe { self.nvm.erase(self.start + from, (to - from) as usize) }
+    }
+
+    #[inline]
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Error> {
+        check_write(self, offset, bytes.len())?;
+        // SAFETY: The region is reserved for storage
+        unsafe { self.nvm.write(self.start + offset, bytes) }
+    }
+}
@@ -0,0 +1,342 @@
+//! # SmartEEPROM
+//!
+//! The SmartEEPROM emulates a byte-addressable EEPROM in the flash. The NVM
+//! controller spreads the writes over two sectors at the end of the flash,
+//! and transparently reallocates the pages when the active sector is full.
+//!
+//! The SmartEEPROM is configured by the `SBLK` and `PSZ` fuses of the user
+//! page. `SBLK` selects the number of flash blocks of each sector, and `PSZ`
+//! the size of the virtual pages. Both are only loaded at reset.
+//!
+//! ```
+//! use atsamd_hal::nvm::{smart_eeprom::WriteMode, Nvm};
+//!
+//! let mut nvm = Nvm::new(peripherals.NVMCTRL);
+//! let mut eeprom = nvm.smart_eeprom().unwrap();
+//! eeprom.set_write_mode(WriteMode::UNBUFFERED);
+//! eeprom.write_u32(0, 0xDEAD_BEEF).unwrap();
+//! assert_eq!(eeprom.read_u32(0), Ok(0xDEAD_BEEF));
+//! ```
+//!
+//! ## Write modes
+//!
+//! In [`WriteMode::UNBUFFERED`] mode, each write is committed to flash
+//! immediately. In [`WriteMode::BUFFERED`] mode, writes to the same page are
+//! gathered in the page buffer, and only committed when another page is
+//! written, or when [`SmartEeprom::flush`] is called.
+
+use core::ptr;
+
+use crate::pac::nvmctrl::ctrlb::CMD_AW;
+
+use super::Nvm;
+
+/// SmartEEPROM write mode
+pub use crate::pac::nvmctrl::seecfg::WMODE_A as WriteMode;
+
+/// Start address of the SmartEEPROM
+const SEEPROM_ADDR: usize = 0x4400_0000;
+
+/// Address of the user page word holding the `SBLK` and `PSZ` fuses
+const FUSES_ADDR: usize = 0x0080_4004;
+
+//==============================================================================
+//  Error
+//==============================================================================
+
+/// SmartEEPROM errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The device does not support the SmartEEPROM
+    Unsupported,
+    /// The SmartEEPROM is disabled by the `SBLK` fuse
+    Disabled,
+    /// The `SBLK` fuse holds an invalid block count
+    InvalidBlockCount(u8),
+    /// The fuses were changed, but the device was not reset since
+    ResetRequired,
+    /// The SmartEEPROM is locked
+    Locked,
+    /// The address is not aligned to the access size
+    Alignment,
+    /// The address range is outside of the SmartEEPROM
+    OutOfBounds,
+    /// An NVM command failed
+    Nvm(super::Error),
+}
+
+impl From<super::Error> for Error {
+    #[inline]
+    fn from(error: super::Error) -> Self {
+        Error::Nvm(error)
+    }
+}
+
+/// Size of the SmartEEPROM in bytes, for the given `SBLK` and `PSZ` fuses
+///
+/// Returns `None` if the fuses are invalid, or if the SmartEEPROM is
+/// disabled.
+#[inline]
+pub fn size(sblk: u8, psz: u8) -> Option<usize> {
+    let max = match sblk {
+        1 => 4096,
+        2 => 8192,
+        3..=4 => 16384,
+        5..=8 => 32768,
+        9..=10 => 65536,
+        _ => return None,
+    };
+    if psz > 7 {
+        return None;
+    }
+    Some(max.min(512 << psz))
+}
+
+//==============================================================================
+//  SmartEeprom
+//==============================================================================
+
+impl Nvm {
+    /// Access the SmartEEPROM
+    ///
+    /// The `SBLK` and `PSZ` fuses are validated, and checked to match the
+    /// configuration loaded at the last reset.
+    #[inline]
+    pub fn smart_eeprom(&mut self) -> Result<SmartEeprom<'_>, Error> {
+        if self.nvm.param.read().see().bit_is_clear() {
+            return Err(Error::Unsupported);
+        }
+
+        // SAFETY: The user page is always readable
+        let fuses = unsafe { ptr::read_volatile(FUSES_ADDR as *const u32) };
+        let sblk = (fuses & 0xF) as u8;
+        let psz = (fuses >> 4 & 0x7) as u8;
+        let size = match sblk {
+            0 => return Err(Error::Disabled),
+            _ => size(sblk, psz).ok_or(Error::InvalidBlockCount(sblk))?,
+        };
+
+        let status = self.nvm.seestat.read();
+        if status.sblk().bits() != sblk || status.psz().bits() != psz {
+            return Err(Error::ResetRequired);
+        }
+
+        Ok(SmartEeprom { nvm: self, size })
+    }
+}
+
+/// Access to the SmartEEPROM
+pub struct SmartEeprom<'a> {
+    nvm: &'a mut Nvm,
+    size: usize,
+}
+
+impl SmartEeprom<'_> {
+    /// Size of the SmartEEPROM, in bytes
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Select the write mode
+    #[inline]
+    pub fn set_write_mode(&mut self, mode: WriteMode) {
+        self.nvm.nvm.seecfg.modify(|_, w| w.wmode().variant(mode));
+    }
+
+    /// Get the write mode
+    #[inline]
+    pub fn get_write_mode(&self) -> WriteMode {
+        self.nvm.nvm.seecfg.read().wmode().variant()
+    }
+
+    /// Enable or disable the automatic page reallocation
+    ///
+    /// When disabled, writes to a full sector fail with an overflow, and
+    /// [`SmartEeprom::reallocate`] must be called instead.
+    #[inline]
+    pub fn set_auto_reallocation(&mut self, enabled: bool) {
+        self.nvm.nvm.seecfg.modify(|_, w| w.aprdis().bit(!enabled));
+    }
+
+    /// Returns `true` if the active sector is full, so that the next write
+    /// requires a reallocation
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.nvm.nvm.intflag.read().seesfull().bit_is_set()
+    }
+
+    /// Check and clear the overflow flag, set when a write to a full sector
+    /// was discarded
+    #[inline]
+    pub fn overflow(&mut self) -> bool {
+        let intflag = &self.nvm.nvm.intflag;
+        let overflow = intflag.read().seesovf().bit_is_set();
+        if overflow {
+            intflag.write(|w| w.seesovf().set_bit());
+        }
+        overflow
+    }
+
+    /// Returns `true` if the page buffer holds data that is not committed yet
+    #[inline]
+    pub fn is_page_buffer_loaded(&self) -> bool {
+        self.nvm.nvm.seestat.read().load().bit_is_set()
+    }
+
+    /// Index of the active sector
+    #[inline]
+    pub fn active_sector(&self) -> u8 {
+        self.nvm.nvm.seestat.read().asees().bit() as u8
+    }
+
+    /// Returns `true` if the SmartEEPROM is locked
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.nvm.nvm.seestat.read().lock().bit_is_set()
+    }
+
+    /// Returns `true` if the SmartEEPROM register address space is locked
+    #[inline]
+    pub fn is_register_locked(&self) -> bool {
+        self.nvm.nvm.seestat.read().rlock().bit_is_set()
+    }
+
+    /// Lock the SmartEEPROM against any access, until [`SmartEeprom::unlock`]
+    /// or the next reset
+    #[inline]
+    pub fn lock(&mut self) -> Result<(), Error> {
+        Ok(self.nvm.command(CMD_AW::LSEE, None)?)
+    }
+
+    /// Unlock the SmartEEPROM
+    #[inline]
+    pub fn unlock(&mut self) -> Result<(), Error> {
+        Ok(self.nvm.command(CMD_AW::USEE, None)?)
+    }
+
+    /// Commit the page buffer to flash, in buffered mode
+    #[inline]
+    pub fn flush(&mut self) -> Result<(), Error> {
+        Ok(self.nvm.command(CMD_AW::SEEFLUSH, None)?)
+    }
+
+    /// Copy the valid data to the other sector, and make it active
+    #[inline]
+    pub fn reallocate(&mut self) -> Result<(), Error> {
+        Ok(self.nvm.command(CMD_AW::SEERALOC, None)?)
+    }
+
+    #[inline]
+    fn wait_busy(&self) {
+        while self.nvm.nvm.seestat.read().busy().bit_is_set() {}
+    }
+
+    #[inline]
+    fn check_access(&self, offset: usize, len: usize, align: usize) -> Result<(), Error> {
+        if self.is_locked() {
+            Err(Error::Locked)
+        } else if offset & (align - 1) != 0 {
+            Err(Error::Alignment)
+        } else if offset + len > self.size {
+            Err(Error::OutOfBounds)
+        } else {
+            Ok(())
+        }
+    }
+
+    #[inline]
+    fn read_at<T: Copy>(&self, offset: usize) -> Result<T, Error> {
+        let len = core::mem::size_of::<T>();
+        self.check_access(offset, len, len)?;
+        self.wait_busy();
+        // SAFETY: The access was checked to be aligned and in bounds
+        Ok(unsafe { ptr::read_volatile((SEEPROM_ADDR + offset) as *const T) })
+    }
+
+    #[inline]
+    fn write_at<T: Copy>(&mut self, offset: usize, value: T) -> Result<(), Error> {
+        let len = core::mem::size_of::<T>();
+        self.check_access(offset, len, len)?;
+        self.wait_busy();
+        // SAFETY: The access was checked to be aligned and in bounds
+        unsafe { ptr::write_volatile((SEEPROM_ADDR + offset) as *mut T, value) };
+        Ok(())
+    }
+
+    /// Read a byte
+    #[inline]
+    pub fn read_u8(&self, offset: usize) -> Result<u8, Error> {
+        self.read_at(offset)
+    }
+
+    /// Read a half-word, at an even offset
+    #[inline]
+    pub fn read_u16(&self, offset: usize) -> Result<u16, Error> {
+        self.read_at(offset)
+    }
+
+    /// Read a word, at an offset multiple of 4
+    #[inline]
+    pub fn read_u32(&self, offset: usize) -> Result<u32, Error> {
+        self.read_at(offset)
+    }
+
+    /// Write a byte
+    #[inline]
+    pub fn write_u8(&mut self, offset: usize, value: u8) -> Result<(), Error> {
+        self.write_at(offset, value)
+    }
+
+    /// Write a half-word, at an even offset
+    #[inline]
+    pub fn write_u16(&mut self, offset: usize, value: u16) -> Result<(), Error> {
+        self.write_at(offset, value)
+    }
+
+    /// Write a word, at an offset multiple of 4
+    #[inline]
+    pub fn write_u32(&mut self, offset: usize, value: u32) -> Result<(), Error> {
+        self.write_at(offset, value)
+    }
+
+    /// Read consecutive bytes
+    #[inline]
+    pub fn read(&self, offset: usize, buf: &mut [u8]) -> Result<(), Error> {
+        self.check_access(offset, buf.len(), 1)?;
+        for (i, byte) in buf.iter_mut().enumerate() {
+            *byte = self.read_u8(offset + i)?;
+        }
+        Ok(())
+    }
+
+    /// Write consecutive bytes
+    #[inline]
+    pub fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Error> {
+        self.check_access(offset, data.len(), 1)?;
+        for (i, &byte) in data.iter().enumerate() {
+            self.write_u8(offset + i, byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::size;
+
+    #[test]
+    fn virtual_size() {
+        assert_eq!(size(0, 0), None);
+        assert_eq!(size(11, 0), None);
+        assert_eq!(size(1, 8), None);
+        assert_eq!(size(1, 0), Some(512));
+        assert_eq!(size(1, 3), Some(4096));
+        assert_eq!(size(1, 7), Some(4096));
+        assert_eq!(size(2, 7), Some(8192));
+        assert_eq!(size(4, 5), Some(16384));
+        assert_eq!(size(8, 2), Some(2048));
+        assert_eq!(size(8, 7), Some(32768));
+        assert_eq!(size(10, 7), Some(65536));
+    }
+}
@@ -0,0 +1,339 @@
+//! # User page
+//!
+//! The first 128 bits of the NVM user page hold the fuses loaded at reset:
+//! BOD33 and watchdog defaults, bootloader protection, SmartEEPROM size, RAM
+//! ECC and NVM region locks. A [`UserRow`] holds these fuses:
+//!
+//! ```
+//! use atsamd_hal::nvm::{user_row::UserRow, Nvm};
+//!
+//! let mut nvm = Nvm::new(peripherals.NVMCTRL);
+//! let mut row = UserRow::read();
+//! // Protect a 16 kB bootloader
+//! row.set_bootprot(13);
+//! row.set_bod33_level(200);
+//! nvm.write_user_row(&row).unwrap();
+//! ```
+//!
+//! The new values only take effect after a reset. Factory-reserved bits,
+//! such as the BOD12 calibration, cannot be modified, and the rest of the
+//! user page is preserved.
+
+use core::ptr;
+
+use crate::pac::nvmctrl::ctrlb::CMD_AW;
+
+use super::{Nvm, NUM_REGIONS, QUAD_WORD_SIZE};
+
+/// Address of the user page
+const USER_PAGE_ADDR: u32 = 0x0080_4000;
+
+/// Size of the user page, in bytes
+const USER_PAGE_SIZE: usize = 512;
+
+/// Factory-reserved bits, which must never be changed
+///
+/// These are the BOD12 calibration (bits 15 to 25), and bits 30 to 31, 40 to
+/// 47, 63 and 96 to 127.
+const RESERVED: u128 = 0xFFFF_FFFF_0000_0000_8000_FF00_C3FF_8000;
+
+//==============================================================================
+//  Error
+//==============================================================================
+
+/// User row errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Factory-reserved bits were modified
+    ReservedBits,
+    /// A field holds a reserved value
+    InvalidValue,
+    /// The lock region does not exist
+    InvalidRegion,
+    /// An NVM command failed
+    Nvm(super::Error),
+}
+
+impl From<super::Error> for Error {
+    #[inline]
+    fn from(error: super::Error) -> Self {
+        Error::Nvm(error)
+    }
+}
+
+//==============================================================================
+//  UserRow
+//==============================================================================
+
+/// Fuses of the NVM user page
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UserRow(u128);
+
+/// Types of the [`UserRow`] fields
+trait Field {
+    fn from_bits(bits: u128) -> Self;
+    fn into_bits(self) -> u128;
+}
+
+impl Field for bool {
+    #[inline]
+    fn from_bits(bits: u128) -> Self {
+        bits != 0
+    }
+
+    #[inline]
+    fn into_bits(self) -> u128 {
+        self as u128
+    }
+}
+
+impl Field for u8 {
+    #[inline]
+    fn from_bits(bits: u128) -> Self {
+        bits as u8
+    }
+
+    #[inline]
+    fn into_bits(self) -> u128 {
+        self as u128
+    }
+}
+
+impl Field for u32 {
+    #[inline]
+    fn from_bits(bits: u128) -> Self {
+        bits as u32
+    }
+
+    #[inline]
+    fn into_bits(self) -> u128 {
+        self as u128
+    }
+}
+
+macro_rules! fields {
+    ($(
+        $(#[$attr:meta])*
+        $get:ident, $set:ident: $T:ty = $offset:literal, $width:literal;
+    )+) => {
+        impl UserRow {
+            $(
+                $(#[$attr])*
+                #[inline]
+                pub fn $get(&self) -> $T {
+                    <$T as Field>::from_bits(self.get($offset, $width))
+                }
+
+                #[doc = concat!("Set the value returned by [`UserRow::", stringify!($get), "`]")]
+                ///
+                /// # Panics
+                ///
+                #[doc = concat!("Panics if the value does not fit in ", stringify!($width), " bits.")]
+                #[inline]
+                pub fn $set(&mut self, value: $T) {
+                    self.set($offset, $width, value.into_bits());
+                }
+            )+
+        }
+    };
+}
+
+fields! {
+    /// BOD33 disabled at power-on
+    bod33_disable, set_bod33_disable: bool = 0, 1;
+    /// BOD33 threshold level at power-on
+    bod33_level, set_bod33_level: u8 = 1, 8;
+    /// BOD33 action at power-on
+    ///
+    /// `0` does nothing, `1` resets the device, `2` generates an interrupt
+    /// and `3` enters backup sleep mode.
+    bod33_action, set_bod33_action: u8 = 9, 2;
+    /// BOD33 hysteresis configuration at power-on
+    bod33_hysteresis, set_bod33_hysteresis: u8 = 11, 4;
+    /// Bootloader protection size, see [`UserRow::bootloader_size`]
+    bootprot, set_bootprot: u8 = 26, 4;
+    /// Number of flash blocks of each SmartEEPROM sector, `0` disabling the
+    /// SmartEEPROM
+    see_sblk, set_see_sblk: u8 = 32, 4;
+    /// SmartEEPROM virtual page size
+    see_psz, set_see_psz: u8 = 36, 3;
+    /// RAM ECC disabled
+    ram_ecc_disable, set_ram_ecc_disable: bool = 39, 1;
+    /// Watchdog enabled at power-on
+    wdt_enable, set_wdt_enable: bool = 48, 1;
+    /// Watchdog always-on at power-on
+    wdt_always_on, set_wdt_always_on: bool = 49, 1;
+    /// Watchdog timeout period at power-on
+    wdt_period, set_wdt_period: u8 = 50, 4;
+    /// Watchdog window mode time-out period at power-on
+    wdt_window, set_wdt_window: u8 = 54, 4;
+    /// Watchdog early warning interrupt time offset at power-on
+    wdt_ewoffset, set_wdt_ewoffset: u8 = 58, 4;
+    /// Watchdog window mode enabled at power-on
+    wdt_window_enable, set_wdt_window_enable: bool = 62, 1;
+    /// NVM region lock bits, where a cleared bit locks the region
+    region_locks, set_region_locks: u32 = 64, 32;
+}
+
+impl UserRow {
+    /// Read the user row
+    #[inline]
+    pub fn read() -> Self {
+        // SAFETY: The user page is always readable
+        let bytes = unsafe { ptr::read_volatile(USER_PAGE_ADDR as *const [u8; 16]) };
+        Self(u128::from_le_bytes(bytes))
+    }
+
+    /// Create a user row from its raw value
+    #[inline]
+    pub const fn from_bits(bits: u128) -> Self {
+        Self(bits)
+    }
+
+    /// Get the raw value of the user row
+    #[inline]
+    pub const fn bits(&self) -> u128 {
+        self.0
+    }
+
+    #[inline]
+    fn get(&self, offset: u32, width: u32) -> u128 {
+        (self.0 >> offset) & ((1 << width) - 1)
+    }
+
+    #[inline]
+    fn set(&mut self, offset: u32, width: u32, value: u128) {
+        let mask = (1 << width) - 1;
+        assert!(value <= mask, "value does not fit in the field");
+        self.0 = (self.0 & !(mask << offset)) | value << offset;
+    }
+
+    /// Size of the protected bootloader section, in bytes
+    #[inline]
+    pub fn bootloader_size(&self) -> usize {
+        (15 - self.bootprot() as usize) * 8192
+    }
+
+    /// Number of NVM lock regions
+    #[inline]
+    pub fn regions(&self) -> u8 {
+        NUM_REGIONS as u8
+    }
+
+    /// Returns `true` if the NVM region is locked at power-on
+    #[inline]
+    pub fn is_region_locked(&self, region: u8) -> Result<bool, Error> {
+        let mask = self.region_mask(region)?;
+        Ok(self.region_locks() & mask == 0)
+    }
+
+    /// Lock or unlock the NVM region at power-on
+    #[inline]
+    pub fn set_region_locked(&mut self, region: u8, locked: bool) -> Result<(), Error> {
+        let mask = self.region_mask(region)?;
+        let locks = self.region_locks();
+        self.set_region_locks(if locked { locks & !mask } else { locks | mask });
+        Ok(())
+    }
+
+    #[inline]
+    fn region_mask(&self, region: u8) -> Result<u32, Error> {
+        if region < self.regions() {
+            Ok(1 << region)
+        } else {
+            Err(Error::InvalidRegion)
+        }
+    }
+
+    /// Check that the reserved bits match `current`, and that every field
+    /// holds a valid value
+    #[inline]
+    fn validate(&self, current: &UserRow) -> Result<(), Error> {
+        if (self.0 ^ current.0) & RESERVED != 0 {
+            return Err(Error::ReservedBits);
+        }
+        let valid = self.see_sblk() <= 10
+            && self.wdt_period() <= 11
+            && self.wdt_window() <= 11
+            && self.wdt_ewoffset() <= 11;
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidValue)
+        }
+    }
+}
+
+impl Nvm {
+    /// Write the user row
+    ///
+    /// The row is validated before the user page is erased. The rest of the
+    /// user page is preserved.
+    #[inline]
+    pub fn write_user_row(&mut self, row: &UserRow) -> Result<(), Error> {
+        row.validate(&UserRow::read())?;
+
+        let mut page = [0; USER_PAGE_SIZE];
+        // SAFETY: The user page is always readable
+        unsafe {
+            ptr::copy_nonoverlapping(USER_PAGE_ADDR as *const u8, page.as_mut_ptr(), page.len())
+        };
+        page[..16].copy_from_slice(&row.0.to_le_bytes());
+
+        self.command(CMD_AW::EP, Some(USER_PAGE_ADDR))?;
+        for (i, quad_word) in page.chunks_exact(QUAD_WORD_SIZE).enumerate() {
+            // Leave erased quad-words writable
+            if quad_word.iter().all(|&b| b == 0xFF) {
+                continue;
+            }
+            let addr = USER_PAGE_ADDR + (i * QUAD_WORD_SIZE) as u32;
+            self.command(CMD_AW::PBC, None)?;
+            for (j, word) in quad_word.chunks_exact(4).enumerate() {
+                let word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+                // SAFETY: Writes to the user page only load the page buffer
+                unsafe { ptr::write_volatile((addr as *mut u32).add(j), word) };
+            }
+            self.command(CMD_AW::WQW, Some(addr))?;
+        }
+        self.invalidate_cache();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, UserRow};
+
+    #[test]
+    fn fields() {
+        let mut row = UserRow::from_bits(0);
+        row.set_bod33_level(0xAB);
+        row.set_bootprot(13);
+        row.set_wdt_ewoffset(0xB);
+        row.set_region_locked(31, false).unwrap();
+        assert_eq!(row.bod33_level(), 0xAB);
+        assert_eq!(row.bootloader_size(), 16384);
+        assert_eq!(row.wdt_ewoffset(), 0xB);
+        assert_eq!(row.is_region_locked(31), Ok(false));
+        assert_eq!(row.is_region_locked(0), Ok(true));
+        assert_eq!(row.is_region_locked(32), Err(Error::InvalidRegion));
+        assert_eq!(row.set_region_locked(32, false), Err(Error::InvalidRegion));
+        assert_eq!(
+            row.bits(),
+            0xAB << 1 | 13 << 26 | 0xB << 58 | 1 << 95,
+            "fields overlap"
+        );
+    }
+
+    #[test]
+    fn validation() {
+        let current = UserRow::from_bits(0xFFFF_FFFF_0000_0000_8000_FF00_C3FF_8000);
+        let mut row = current;
+        row.set_see_sblk(10);
+        assert_eq!(row.validate(&current), Ok(()));
+        row.set_wdt_period(12);
+        assert_eq!(row.validate(&current), Err(Error::InvalidValue));
+        let row = UserRow::from_bits(current.bits() & !(1 << 20));
+        assert_eq!(row.validate(&current), Err(Error::ReservedBits));
+    }
+}
@@ -0,0 +1,642 @@
+//! # Parallel Capture Controller (PCC)
+//!
+//! The PCC samples an 8, 10 or 14-bit parallel bus, such as the output of a
+//! CMOS camera module, on the rising edges of the `PCC_CLK` pin. Samples are
+//! packed into 1, 2 or 4-byte words, and read from the receive holding
+//! register, either by the CPU or by the DMAC.
+//!
+//! ## Sampling modes
+//!
+//! In the [`Always`] mode, every clock edge produces a sample. In the
+//! [`Gated`] mode, samples are only taken while both data enable pins,
+//! `PCC_DEN1` and `PCC_DEN2`, are high; these are typically driven by the
+//! `VSYNC` and `HREF` outputs of a camera.
+//!
+//! ```
+//! use atsamd_hal::pcc::{Config, Data8, Pads, Pcc};
+//!
+//! let data = Data8::new(
+//!     pins.pa16, pins.pa17, pins.pa18, pins.pa19, pins.pa20, pins.pa21, pins.pa22, pins.pa23,
+//! );
+//! let pads = Pads::new(pins.pa14, data).enable_pins(pins.pa12, pins.pa13);
+//! let pcc = Pcc::new(&mut peripherals.MCLK, peripherals.PCC, pads, Config::default()).unwrap();
+//!
+//! let frame = cortex_m::singleton!(: [u32; 160 * 120 / 2] = [0; 160 * 120 / 2]).unwrap();
+//! let (pcc, frame, channel) = pcc.capture(frame, channel).wait();
+//! ```
+//!
+//! ## DMA
+//!
+//! With the `dma` feature, [`Pcc::capture`] fills a buffer with samples
+//! using a DMA channel. The data size is then set from the beat size of the
+//! buffer: each `u8`, `u16` or `u32` beat holds 1, 2 or 4 bytes of samples.
+//! Samples of the 10 and 14-bit buses take two bytes each, so these can only
+//! be captured into `u16` or `u32` buffers; see [`CaptureBeat`].
+
+use bitflags::bitflags;
+
+use crate::gpio::v2::{
+    AlternateK, AnyPin, Pin, PA12, PA13, PA14, PA16, PA17, PA18, PA19, PA20, PA21, PA22, PA23,
+};
+use crate::pac::{MCLK, PCC};
+use crate::typelevel::Sealed;
+
+#[cfg(feature = "min-samd51j")]
+use crate::gpio::v2::{PB14, PB15};
+
+#[cfg(feature = "min-samd51n")]
+use crate::gpio::v2::{PC12, PC13, PC14, PC15};
+
+#[cfg(feature = "dma")]
+use core::marker::PhantomData;
+
+#[cfg(feature = "dma")]
+use crate::dmac::{
+    self,
+    channel::{AnyChannel, Busy, Channel, Ready},
+    transfer::BufferPair,
+    Beat, Buffer, ChId, Transfer, TriggerAction, TriggerSource,
+};
+
+//==============================================================================
+//  Data pins
+//==============================================================================
+
+/// Data bus of the PCC
+pub trait DataPins: Sealed {
+    #[doc(hidden)]
+    const ISIZE: u8;
+}
+
+macro_rules! data_pins {
+    (
+        $(#[$attr:meta])*
+        $Data:ident, $isize:literal, ($($pin:ident: $Id:ident),+)
+    ) => {
+        $(#[$attr])*
+        pub struct $Data {
+            $($pin: Pin<$Id, AlternateK>,)+
+        }
+
+        $(#[$attr])*
+        impl $Data {
+            /// Configure the data pins
+            #[allow(clippy::too_many_arguments)]
+            #[inline]
+            pub fn new($($pin: impl AnyPin<Id = $Id>),+) -> Self {
+                Self {
+                    $($pin: $pin.into().into_mode(),)+
+                }
+            }
+
+            /// Return the data pins
+            #[allow(clippy::type_complexity)]
+            #[inline]
+            pub fn free(self) -> ($(Pin<$Id, AlternateK>,)+) {
+                ($(self.$pin,)+)
+            }
+        }
+
+        $(#[$attr])*
+        impl Sealed for $Data {}
+
+        $(#[$attr])*
+        impl DataPins for $Data {
+            const ISIZE: u8 = $isize;
+        }
+    };
+}
+
+data_pins!(
+    /// 8-bit data bus, on `PCC_DATA[7:0]`
+    Data8,
+    0,
+    (
+        d0: PA16,
+        d1: PA17,
+        d2: PA18,
+        d3: PA19,
+        d4: PA20,
+        d5: PA21,
+        d6: PA22,
+        d7: PA23
+    )
+);
+
+data_pins!(
+    /// 10-bit data bus, on `PCC_DATA[9:0]`
+    #[cfg(feature = "min-samd51j")]
+    Data10,
+    1,
+    (
+        d0: PA16,
+        d1: PA17,
+        d2: PA18,
+        d3: PA19,
+        d4: PA20,
+        d5: PA21,
+        d6: PA22,
+        d7: PA23,
+        d8: PB14,
+        d9: PB15
+    )
+);
+
+data_pins!(
+    /// 14-bit data bus, on `PCC_DATA[13:0]`
+    #[cfg(feature = "min-samd51n")]
+    Data14,
+    3,
+    (
+        d0: PA16,
+        d1: PA17,
+        d2: PA18,
+        d3: PA19,
+        d4: PA20,
+        d5: PA21,
+        d6: PA22,
+        d7: PA23,
+        d8: PB14,
+        d9: PB15,
+        d10: PC12,
+        d11: PC13,
+        d12: PC14,
+        d13: PC15
+    )
+);
+
+//==============================================================================
+//  Sampling modes
+//==============================================================================
+
+/// Sampling mode of the PCC
+pub trait SamplingMode: Sealed {
+    #[doc(hidden)]
+    const ALWAYS: bool;
+}
+
+/// Sample the data bus on every clock edge
+pub struct Always;
+
+impl Sealed for Always {}
+
+impl SamplingMode for Always {
+    const ALWAYS: bool = true;
+}
+
+/// Sample the data bus while both data enable pins are high
+pub struct Gated {
+    den1: Pin<PA12, AlternateK>,
+    den2: Pin<PA13, AlternateK>,
+}
+
+impl Gated {
+    /// Return the data enable pins
+    #[inline]
+    pub fn free(self) -> (Pin<PA12, AlternateK>, Pin<PA13, AlternateK>) {
+        (self.den1, self.den2)
+    }
+}
+
+impl Sealed for Gated {}
+
+impl SamplingMode for Gated {
+    const ALWAYS: bool = false;
+}
+
+//==============================================================================
+//  Pads
+//==============================================================================
+
+/// Clock, data and data enable pins of the PCC
+pub struct Pads<D: DataPins, M: SamplingMode> {
+    clk: Pin<PA14, AlternateK>,
+    data: D,
+    mode: M,
+}
+
+impl<D: DataPins> Pads<D, Always> {
+    /// Configure the clock pin and the data bus, sampling on every clock edge
+    #[inline]
+    pub fn new(clk: impl AnyPin<Id = PA14>, data: D) -> Self {
+        Self {
+            clk: clk.into().into_mode(),
+            data,
+            mode: Always,
+        }
+    }
+
+    /// Configure the data enable pins, to only sample while both are high
+    #[inline]
+    pub fn enable_pins(
+        self,
+        den1: impl AnyPin<Id = PA12>,
+        den2: impl AnyPin<Id = PA13>,
+    ) -> Pads<D, Gated> {
+        Pads {
+            clk: self.clk,
+            data: self.data,
+            mode: Gated {
+                den1: den1.into().into_mode(),
+                den2: den2.into().into_mode(),
+            },
+        }
+    }
+}
+
+impl<D: DataPins, M: SamplingMode> Pads<D, M> {
+    /// Return the clock pin, the data bus and the sampling mode
+    #[inline]
+    pub fn free(self) -> (Pin<PA14, AlternateK>, D, M) {
+        (self.clk, self.data, self.mode)
+    }
+}
+
+//==============================================================================
+//  Configuration
+//==============================================================================
+
+/// Number of bytes of samples held by each read of the data register
+///
+/// Samples of the 8-bit bus take one byte, and samples of the 10 and 14-bit
+/// buses take two bytes, so that a word holds four or two samples. A single
+/// byte can't hold a sample of the 10 and 14-bit buses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataSize {
+    /// One byte
+    Byte,
+    /// Two bytes
+    HalfWord,
+    /// Four bytes
+    Word,
+}
+
+impl DataSize {
+    /// Number of samples per read, as written to `MR.DSIZE`, for a data bus
+    /// of the given `ISIZE`
+    #[inline]
+    fn dsize(self, input_size: u8) -> Option<u8> {
+        match (self, input_size) {
+            (DataSize::Byte, 0) => Some(0),
+            (DataSize::HalfWord, 0) => Some(1),
+            (DataSize::Word, 0) => Some(2),
+            (DataSize::Byte, _) => None,
+            (DataSize::HalfWord, _) => Some(0),
+            (DataSize::Word, _) => Some(1),
+        }
+    }
+}
+
+/// Data enable pins clearing the receive data counter when low
+///
+/// Clearing the counter discards a partially filled word, so that each line
+/// or frame starts on a word boundary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClearIfDisabled {
+    /// The counter is never cleared
+    Never = 0,
+    /// The counter is cleared while `PCC_DEN1` is low
+    Den1 = 1,
+    /// The counter is cleared while `PCC_DEN2` is low
+    Den2 = 2,
+    /// The counter is cleared while either data enable pin is low
+    Both = 3,
+}
+
+/// PCC configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// Number of bytes held by each read of the data register
+    pub data_size: DataSize,
+    /// Data enable pins clearing the receive data counter
+    pub clear_if_disabled: ClearIfDisabled,
+}
+
+impl Default for Config {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            data_size: DataSize::Word,
+            clear_if_disabled: ClearIfDisabled::Never,
+        }
+    }
+}
+
+//==============================================================================
+//  Errors and interrupts
+//==============================================================================
+
+/// Errors related to the PCC
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Samples were received before the previous data was read
+    Overrun,
+    /// The data size can't hold a sample of the data bus
+    InvalidDataSize,
+}
+
+bitflags! {
+    /// PCC interrupt flags
+    ///
+    /// The binary format of the flags matches the `IER`, `IDR` and `ISR`
+    /// registers.
+    pub struct Interrupts: u32 {
+        /// Data is available in the data register
+        const DATA_READY = 1;
+        /// Samples were received before the previous data was read
+        const OVERRUN = 2;
+    }
+}
+
+//==============================================================================
+//  Pcc
+//==============================================================================
+
+/// PCC peripheral
+pub struct Pcc<D: DataPins, M: SamplingMode> {
+    pcc: PCC,
+    pads: Pads<D, M>,
+    config: Config,
+    dsize: u8,
+}
+
+impl<D: DataPins, M: SamplingMode> Pcc<D, M> {
+    /// Enable the PCC and start sampling
+    ///
+    /// Fails if the data size can't hold a sample of the data bus.
+    #[inline]
+    pub fn new(mclk: &mut MCLK, pcc: PCC, pads: Pads<D, M>, config: Config) -> Result<Self, Error> {
+        let dsize = config
+            .data_size
+            .dsize(D::ISIZE)
+            .ok_or(Error::InvalidDataSize)?;
+        mclk.apbdmask.modify(|_, w| w.pcc_().set_bit());
+        let mut pcc = Self {
+            pcc,
+            pads,
+            config,
+            dsize,
+        };
+        pcc.configure(dsize);
+        Ok(pcc)
+    }
+
+    /// Change the configuration
+    ///
+    /// Fails if the data size can't hold a sample of the data bus, in which
+    /// case the configuration is unchanged.
+    #[inline]
+    pub fn set_config(&mut self, config: Config) -> Result<(), Error> {
+        let dsize = config
+            .data_size
+            .dsize(D::ISIZE)
+            .ok_or(Error::InvalidDataSize)?;
+        self.config = config;
+        self.dsize = dsize;
+        self.configure(dsize);
+        Ok(())
+    }
+
+    /// Read the next samples
+    ///
+    /// An overrun is reported once, and the data of the register is
+    /// discarded.
+    #[inline]
+    pub fn read(&mut self) -> nb::Result<u32, Error> {
+        let status = self.status();
+        if status.contains(Interrupts::OVERRUN) {
+            let _ = self.pcc.rhr.read();
+            Err(nb::Error::Other(Error::Overrun))
+        } else if status.contains(Interrupts::DATA_READY) {
+            Ok(self.pcc.rhr.read().bits())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Enable the given interrupts
+    #[inline]
+    pub fn enable_interrupts(&mut self, interrupts: Interrupts) {
+        // SAFETY: Interrupts only holds valid bits of the register
+        self.pcc.ier.write(|w| unsafe { w.bits(interrupts.bits()) });
+    }
+
+    /// Disable the given interrupts
+    #[inline]
+    pub fn disable_interrupts(&mut self, interrupts: Interrupts) {
+        // SAFETY: Interrupts only holds valid bits of the register
+        self.pcc.idr.write(|w| unsafe { w.bits(interrupts.bits()) });
+    }
+
+    /// Read the interrupt flags, clearing the overrun flag
+    #[inline]
+    pub fn status(&mut self) -> Interrupts {
+        Interrupts::from_bits_truncate(self.pcc.isr.read().bits())
+    }
+
+    /// Disable the PCC and return the underlying PCC and pads
+    #[inline]
+    pub fn free(self, mclk: &mut MCLK) -> (PCC, Pads<D, M>) {
+        self.pcc.mr.reset();
+        mclk.apbdmask.modify(|_, w| w.pcc_().clear_bit());
+        (self.pcc, self.pads)
+    }
+
+    /// Write the mode register and enable sampling
+    fn configure(&mut self, dsize: u8) {
+        let cid = self.config.clear_if_disabled as u8;
+        self.pcc.mr.write(|w| unsafe {
+            w.isize().bits(D::ISIZE);
+            w.dsize().bits(dsize);
+            w.alwys().bit(M::ALWAYS);
+            w.cid().bits(cid)
+        });
+        self.pcc.mr.modify(|_, w| w.pcen().set_bit());
+    }
+}
+
+//==============================================================================
+//  DMA
+//==============================================================================
+
+/// Receive holding register, read by the DMAC
+#[cfg(feature = "dma")]
+pub struct RxData<T: Beat>(PhantomData<T>);
+
+#[cfg(feature = "dma")]
+unsafe impl<T: Beat> Buffer for RxData<T> {
+    type Beat = T;
+
+    #[inline]
+    fn dma_ptr(&mut self) -> *mut Self::Beat {
+        // SAFETY: The DMAC only reads the data register, which is owned by
+        // the ongoing transfer
+        unsafe { &*PCC::ptr() }.rhr.as_ptr() as *mut T
+    }
+
+    #[inline]
+    fn incrementing(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn buffer_len(&self) -> usize {
+        1
+    }
+}
+
+/// DMA beat holding whole samples of the data bus `D`
+///
+/// Samples of the 10 and 14-bit buses don't fit in a byte, so these can't be
+/// captured into `u8` buffers.
+#[cfg(feature = "dma")]
+pub trait CaptureBeat<D: DataPins>: Beat {
+    #[doc(hidden)]
+    const DSIZE: u8;
+}
+
+macro_rules! capture_beat {
+    (
+        $(#[$attr:meta])*
+        $Data:ident, $(($Type:ty, $dsize:literal)),+
+    ) => {
+        $(
+            $(#[$attr])*
+            #[cfg(feature = "dma")]
+            impl CaptureBeat<$Data> for $Type {
+                const DSIZE: u8 = $dsize;
+            }
+        )+
+    };
+}
+
+capture_beat!(
+    Data8,
+    (u8, 0),
+    (i8, 0),
+    (u16, 1),
+    (i16, 1),
+    (u32, 2),
+    (i32, 2)
+);
+
+capture_beat!(
+    #[cfg(feature = "min-samd51j")]
+    Data10,
+    (u16, 0),
+    (i16, 0),
+    (u32, 1),
+    (i32, 1)
+);
+
+capture_beat!(
+    #[cfg(feature = "min-samd51n")]
+    Data14,
+    (u16, 0),
+    (i16, 0),
+    (u32, 1),
+    (i32, 1)
+);
+
+#[cfg(feature = "dma")]
+type RxTransfer<Id, B> = Transfer<Channel<Id, Busy>, BufferPair<RxData<<B as Buffer>::Beat>, B>>;
+
+/// Capture of samples into a buffer by the DMAC
+#[cfg(feature = "dma")]
+pub struct DmaTransfer<Id: ChId, D: DataPins, M: SamplingMode, B>
+where
+    B: Buffer + 'static,
+{
+    pcc: Pcc<D, M>,
+    transfer: RxTransfer<Id, B>,
+}
+
+#[cfg(feature = "dma")]
+impl<D: DataPins, M: SamplingMode> Pcc<D, M> {
+    /// Fill `buffer` with samples using a DMA channel
+    ///
+    /// The data size is set from the beat size of the buffer for the
+    /// duration of the capture. Data received before the capture is
+    /// discarded.
+    #[inline]
+    pub fn capture<Ch, B>(mut self, buffer: B, channel: Ch) -> DmaTransfer<Ch::Id, D, M, B>
+    where
+        Ch: AnyChannel<Status = Ready>,
+        B: Buffer + 'static,
+        B::Beat: CaptureBeat<D>,
+    {
+        self.configure(<B::Beat as CaptureBeat<D>>::DSIZE);
+        let _ = self.pcc.rhr.read();
+        let _ = self.pcc.isr.read();
+
+        // SAFETY: The data register is not a memory buffer, and the user
+        // buffer is 'static
+        let transfer =
+            unsafe { dmac::Transfer::new_unchecked(channel, RxData(PhantomData), buffer, false) }
+                .begin(TriggerSource::PCC_RX, TriggerAction::BURST);
+
+        DmaTransfer {
+            pcc: self,
+            transfer,
+        }
+    }
+}
+
+#[cfg(feature = "dma")]
+impl<Id: ChId, D: DataPins, M: SamplingMode, B> DmaTransfer<Id, D, M, B>
+where
+    B: Buffer + 'static,
+{
+    /// Check whether the buffer is full
+    #[inline]
+    pub fn is_complete(&mut self) -> bool {
+        self.transfer.complete()
+    }
+
+    /// Wait for the buffer to be filled, and return the peripheral, buffer
+    /// and channel
+    #[inline]
+    pub fn wait(self) -> (Pcc<D, M>, B, Channel<Id, Ready>) {
+        let (channel, _, buffer) = self.transfer.wait();
+        Self::finish(self.pcc, buffer, channel)
+    }
+
+    /// Stop the capture before the buffer is filled, and return the
+    /// peripheral, buffer and channel
+    #[inline]
+    pub fn stop(self) -> (Pcc<D, M>, B, Channel<Id, Ready>) {
+        let (channel, _, buffer) = self.transfer.stop();
+        Self::finish(self.pcc, buffer, channel)
+    }
+
+    #[inline]
+    fn finish(
+        mut pcc: Pcc<D, M>,
+        buffer: B,
+        channel: Channel<Id, Ready>,
+    ) -> (Pcc<D, M>, B, Channel<Id, Ready>) {
+        let dsize = pcc.dsize;
+        pcc.configure(dsize);
+        (pcc, buffer, channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DataSize;
+
+    #[test]
+    fn dsize() {
+        // 8-bit samples
+        assert_eq!(DataSize::Byte.dsize(0), Some(0));
+        assert_eq!(DataSize::HalfWord.dsize(0), Some(1));
+        assert_eq!(DataSize::Word.dsize(0), Some(2));
+
+        // 10 and 14-bit samples
+        for input_size in [1, 3] {
+            assert_eq!(DataSize::Byte.dsize(input_size), None);
+            assert_eq!(DataSize::HalfWord.dsize(input_size), Some(0));
+            assert_eq!(DataSize::Word.dsize(input_size), Some(1));
+        }
+    }
+}
@@ -0,0 +1,650 @@
+//! # Position Decoder (PDEC)
+//!
+//! The PDEC decodes the signals of a quadrature encoder or of Hall effect
+//! sensors, on up to three inputs (`QDI0`, `QDI1` and `QDI2`). Its 16-bit
+//! counter works in one of three [`Mode`]s:
+//!
+//! - In the quadrature decoder mode, `QDI0` and `QDI1` are the phases A and
+//!   B of the encoder, and `QDI2` its index. The counter is split into an
+//!   angular position, in its `angular_bits` least significant bits, and a
+//!   revolution counter in its remaining bits. The index resets the angular
+//!   position, and increments or decrements the revolution counter.
+//! - In the Hall mode, the three inputs are the Hall sensors of a motor, and
+//!   invalid sequences are reported as errors.
+//! - In the counter mode, the PDEC is a plain counter, clocked by its GCLK or
+//!   incremented by input events.
+//!
+//! Each input can either be read from a pin, set in the [`Pads`], or from the
+//! event system. Inputs can be inverted, and the pins filtered: an edge is
+//! only taken into account once the pin has been stable for a number of
+//! periods of the prescaled GCLK.
+//!
+//! ```
+//! use atsamd_hal::pdec::{Config, Pads, Pdec};
+//!
+//! let pads = Pads::default()
+//!     .qdi0(pins.pa24)
+//!     .qdi1(pins.pa25)
+//!     .qdi2(pins.pb22);
+//! let clock = clocks.pdec(&gclk0).unwrap();
+//! let mut pdec = Pdec::new(&mut mclk, peripherals.PDEC, clock, pads, Config::default());
+//! pdec.start();
+//!
+//! let angle = pdec.angle();
+//! let revolutions = pdec.revolutions();
+//! ```
+
+use bitflags::bitflags;
+
+use crate::clock::PdecClock;
+use crate::gpio::v2::{AlternateG, AnyPin, Pin, PinId, PA24, PA25};
+use crate::pac::{pdec, MCLK, PDEC};
+use crate::time::Hertz;
+use crate::typelevel::{NoneT, Sealed};
+
+#[cfg(feature = "min-samd51g")]
+use crate::gpio::v2::{PB22, PB23};
+
+#[cfg(feature = "min-samd51n")]
+use crate::gpio::v2::{PB18, PB19, PB20, PB24, PB25, PC16, PC17, PC18};
+
+/// Operating mode
+pub use pdec::ctrla::MODE_A as Mode;
+
+/// Decoding configuration of the quadrature decoder mode
+pub use pdec::ctrla::CONF_A as Decoding;
+
+/// Prescaler of the GCLK, used by the input filter and the counter mode
+pub use pdec::presc::PRESC_A as Prescaler;
+
+/// Action taken on input events
+pub use pdec::evctrl::EVACT_A as EventAction;
+
+use pdec::ctrlbset::CMD_A as Command;
+
+//==============================================================================
+//  Inputs
+//==============================================================================
+
+/// Type-level enum of the PDEC inputs
+pub trait InputNum: Sealed {}
+
+/// Input `QDI0`, the phase A of an encoder
+pub enum Qdi0 {}
+
+/// Input `QDI1`, the phase B of an encoder
+pub enum Qdi1 {}
+
+/// Input `QDI2`, the index of an encoder
+pub enum Qdi2 {}
+
+impl Sealed for Qdi0 {}
+impl Sealed for Qdi1 {}
+impl Sealed for Qdi2 {}
+impl InputNum for Qdi0 {}
+impl InputNum for Qdi1 {}
+impl InputNum for Qdi2 {}
+
+/// [`PinId`]s that can be used as the input `N`
+pub trait QdiPin<N: InputNum>: PinId {}
+
+macro_rules! qdi_pins {
+    ($($(#[$cfg:meta])* $Id:ident: $Num:ident;)+) => {
+        $(
+            $(#[$cfg])*
+            impl QdiPin<$Num> for $Id {}
+        )+
+    };
+}
+
+qdi_pins! {
+    PA24: Qdi0;
+    PA25: Qdi1;
+    #[cfg(feature = "min-samd51g")]
+    PB22: Qdi2;
+    #[cfg(feature = "min-samd51g")]
+    PB23: Qdi0;
+    #[cfg(feature = "min-samd51n")]
+    PB18: Qdi0;
+    #[cfg(feature = "min-samd51n")]
+    PB19: Qdi1;
+    #[cfg(feature = "min-samd51n")]
+    PB20: Qdi2;
+    #[cfg(feature = "min-samd51n")]
+    PB24: Qdi1;
+    #[cfg(feature = "min-samd51n")]
+    PB25: Qdi2;
+    #[cfg(feature = "min-samd51n")]
+    PC16: Qdi0;
+    #[cfg(feature = "min-samd51n")]
+    PC17: Qdi1;
+    #[cfg(feature = "min-samd51n")]
+    PC18: Qdi2;
+}
+
+/// Either a pin configured as the input `N`, or [`NoneT`]
+pub trait OptionalInput<N: InputNum>: Sealed {
+    #[doc(hidden)]
+    const PIN: bool;
+}
+
+impl<N: InputNum> OptionalInput<N> for NoneT {
+    const PIN: bool = false;
+}
+
+impl<N: InputNum, I: QdiPin<N>> OptionalInput<N> for Pin<I, AlternateG> {
+    const PIN: bool = true;
+}
+
+//==============================================================================
+//  Pads
+//==============================================================================
+
+/// Input pins of the PDEC
+///
+/// Inputs without a pin can be driven by the event system.
+pub struct Pads<I0 = NoneT, I1 = NoneT, I2 = NoneT>
+where
+    I0: OptionalInput<Qdi0>,
+    I1: OptionalInput<Qdi1>,
+    I2: OptionalInput<Qdi2>,
+{
+    qdi0: I0,
+    qdi1: I1,
+    qdi2: I2,
+}
+
+impl Default for Pads {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            qdi0: NoneT,
+            qdi1: NoneT,
+            qdi2: NoneT,
+        }
+    }
+}
+
+impl<I0, I1, I2> Pads<I0, I1, I2>
+where
+    I0: OptionalInput<Qdi0>,
+    I1: OptionalInput<Qdi1>,
+    I2: OptionalInput<Qdi2>,
+{
+    /// Set the `QDI0` pin
+    #[inline]
+    pub fn qdi0<Id: QdiPin<Qdi0>>(
+        self,
+        pin: impl AnyPin<Id = Id>,
+    ) -> Pads<Pin<Id, AlternateG>, I1, I2> {
+        Pads {
+            qdi0: pin.into().into_mode(),
+            qdi1: self.qdi1,
+            qdi2: self.qdi2,
+        }
+    }
+
+    /// Set the `QDI1` pin
+    #[inline]
+    pub fn qdi1<Id: QdiPin<Qdi1>>(
+        self,
+        pin: impl AnyPin<Id = Id>,
+    ) -> Pads<I0, Pin<Id, AlternateG>, I2> {
+        Pads {
+            qdi0: self.qdi0,
+            qdi1: pin.into().into_mode(),
+            qdi2: self.qdi2,
+        }
+    }
+
+    /// Set the `QDI2` pin
+    #[inline]
+    pub fn qdi2<Id: QdiPin<Qdi2>>(
+        self,
+        pin: impl AnyPin<Id = Id>,
+    ) -> Pads<I0, I1, Pin<Id, AlternateG>> {
+        Pads {
+            qdi0: self.qdi0,
+            qdi1: self.qdi1,
+            qdi2: pin.into().into_mode(),
+        }
+    }
+
+    /// Return the pins
+    #[inline]
+    pub fn free(self) -> (I0, I1, I2) {
+        (self.qdi0, self.qdi1, self.qdi2)
+    }
+}
+
+//==============================================================================
+//  Configuration
+//==============================================================================
+
+/// PDEC configuration
+///
+/// These settings can only be changed while the PDEC is disabled; see
+/// [`Pdec::set_config`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Config {
+    /// Operating mode
+    pub mode: Mode,
+    /// Decoding configuration, in the quadrature decoder mode
+    pub decoding: Decoding,
+    /// Swap the phases A and B
+    pub swap: bool,
+    /// Invert each input
+    pub invert: [bool; 3],
+    /// Enable the event input of each input without a pin
+    pub event_inputs: [bool; 3],
+    /// Action taken on input events, in the counter mode
+    pub event_action: EventAction,
+    /// Number of bits of the angular position, from 10 to 16
+    pub angular_bits: u8,
+    /// Number of consecutive missing pulses of the quadrature decoder
+    /// raising an error, from 0 (disabled) to 15
+    pub max_missing_pulses: u8,
+    /// Counter period, also dividing the angular position and the
+    /// revolution counter in the quadrature decoder mode
+    pub period: Option<u16>,
+    /// Prescaler of the GCLK
+    pub prescaler: Prescaler,
+    /// Number of prescaled GCLK periods an input must be stable for
+    pub filter: u8,
+    /// Output events
+    pub events: Events,
+    /// Keep running in standby
+    pub run_in_standby: bool,
+}
+
+impl Default for Config {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            mode: Mode::QDEC,
+            decoding: Decoding::X4,
+            swap: false,
+            invert: [false; 3],
+            event_inputs: [false; 3],
+            event_action: EventAction::OFF,
+            angular_bits: 16,
+            max_missing_pulses: 0,
+            period: None,
+            prescaler: Prescaler::DIV1,
+            filter: 0,
+            events: Events::empty(),
+            run_in_standby: false,
+        }
+    }
+}
+
+//==============================================================================
+//  Flags
+//==============================================================================
+
+bitflags! {
+    /// PDEC interrupt flags
+    ///
+    /// The binary format of the flags matches the `INTENSET`, `INTENCLR` and
+    /// `INTFLAG` registers.
+    pub struct Flags: u8 {
+        /// The counter overflowed or underflowed
+        const OVERFLOW = 1;
+        /// An error was detected; see [`Pdec::status`]
+        const ERROR = 1 << 1;
+        /// The direction of rotation changed
+        const DIRECTION = 1 << 2;
+        /// The velocity exceeded the compare value
+        const VELOCITY = 1 << 3;
+        /// The counter matched the compare channel 0
+        const MATCH0 = 1 << 4;
+        /// The counter matched the compare channel 1
+        const MATCH1 = 1 << 5;
+    }
+}
+
+bitflags! {
+    /// PDEC output events
+    ///
+    /// The binary format of the flags matches the `EVCTRL` register.
+    pub struct Events: u16 {
+        /// The counter overflowed or underflowed
+        const OVERFLOW = 1 << 8;
+        /// An error was detected
+        const ERROR = 1 << 9;
+        /// The direction of rotation changed
+        const DIRECTION = 1 << 10;
+        /// The velocity exceeded the compare value
+        const VELOCITY = 1 << 11;
+        /// The counter matched the compare channel 0
+        const MATCH0 = 1 << 12;
+        /// The counter matched the compare channel 1
+        const MATCH1 = 1 << 13;
+    }
+}
+
+bitflags! {
+    /// PDEC status
+    ///
+    /// The binary format of the flags matches the `STATUS` register.
+    pub struct Status: u16 {
+        /// Invalid quadrature sequence
+        const QUADRATURE_ERROR = 1;
+        /// The index was missed, or seen at an unexpected position
+        const INDEX_ERROR = 1 << 1;
+        /// Too many consecutive pulses were missed
+        const MISSING_PULSE_ERROR = 1 << 2;
+        /// The Hall sensors changed outside of the expected window
+        const WINDOW_ERROR = 1 << 4;
+        /// Invalid Hall sensor pattern
+        const HALL_ERROR = 1 << 5;
+        /// The counter is stopped
+        const STOPPED = 1 << 6;
+        /// The counter is counting down
+        const DOWN = 1 << 7;
+    }
+}
+
+impl Status {
+    /// Error flags of the status
+    pub const ERRORS: Self = Self::from_bits_truncate(
+        Self::QUADRATURE_ERROR.bits
+            | Self::INDEX_ERROR.bits
+            | Self::MISSING_PULSE_ERROR.bits
+            | Self::WINDOW_ERROR.bits
+            | Self::HALL_ERROR.bits,
+    );
+}
+
+/// Compare channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareChannel {
+    /// Channel 0, also holding the period
+    Cc0 = 0,
+    /// Channel 1
+    Cc1 = 1,
+}
+
+//==============================================================================
+//  Pdec
+//==============================================================================
+
+/// PDEC peripheral
+pub struct Pdec<I0, I1, I2>
+where
+    I0: OptionalInput<Qdi0>,
+    I1: OptionalInput<Qdi1>,
+    I2: OptionalInput<Qdi2>,
+{
+    pdec: PDEC,
+    pads: Pads<I0, I1, I2>,
+    freq: Hertz,
+    angular_bits: u8,
+}
+
+impl<I0, I1, I2> Pdec<I0, I1, I2>
+where
+    I0: OptionalInput<Qdi0>,
+    I1: OptionalInput<Qdi1>,
+    I2: OptionalInput<Qdi2>,
+{
+    /// Reset and configure the PDEC
+    ///
+    /// The counter is enabled, but does not count before
+    /// [`start`](Self::start) is called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `angular_bits` is not between 10 and 16, or if
+    /// `max_missing_pulses` exceeds 15.
+    #[inline]
+    pub fn new(
+        mclk: &mut MCLK,
+        pdec: PDEC,
+        clock: PdecClock,
+        pads: Pads<I0, I1, I2>,
+        config: Config,
+    ) -> Self {
+        mclk.apbcmask.modify(|_, w| w.pdec_().set_bit());
+        let mut pdec = Self {
+            pdec,
+            pads,
+            freq: clock.into(),
+            angular_bits: 16,
+        };
+        pdec.pdec.ctrla.write(|w| w.swrst().set_bit());
+        pdec.sync();
+        pdec.set_config(config);
+        pdec
+    }
+
+    /// Frequency of the GCLK
+    #[inline]
+    pub fn clock_frequency(&self) -> Hertz {
+        self.freq
+    }
+
+    /// Change the configuration
+    ///
+    /// The PDEC is briefly disabled, and the counter is stopped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `angular_bits` is not between 10 and 16, or if
+    /// `max_missing_pulses` exceeds 15.
+    #[inline]
+    pub fn set_config(&mut self, config: Config) {
+        assert!((10..=16).contains(&config.angular_bits));
+        assert!(config.max_missing_pulses < 16);
+
+        self.pdec.ctrla.modify(|_, w| w.enable().clear_bit());
+        self.sync();
+
+        self.pdec.ctrla.write(|w| unsafe {
+            w.mode().variant(config.mode);
+            w.runstdby().bit(config.run_in_standby);
+            w.conf().variant(config.decoding);
+            w.swap().bit(config.swap);
+            w.peren().bit(config.period.is_some());
+            w.pinen0().bit(I0::PIN);
+            w.pinen1().bit(I1::PIN);
+            w.pinen2().bit(I2::PIN);
+            w.pinven0().bit(config.invert[0]);
+            w.pinven1().bit(config.invert[1]);
+            w.pinven2().bit(config.invert[2]);
+            w.angular().bits(config.angular_bits - 10);
+            w.maxcmp().bits(config.max_missing_pulses)
+        });
+
+        let event_inputs = mask(config.event_inputs) & !mask([I0::PIN, I1::PIN, I2::PIN]);
+        let inverted = mask(config.invert);
+        self.pdec.evctrl.write(|w| unsafe {
+            w.bits(config.events.bits());
+            w.evact().variant(config.event_action);
+            w.evinv().bits(inverted & event_inputs);
+            w.evei().bits(event_inputs)
+        });
+        self.angular_bits = config.angular_bits;
+
+        self.pdec.ctrla.modify(|_, w| w.enable().set_bit());
+        self.sync();
+
+        self.pdec
+            .presc
+            .write(|w| w.presc().variant(config.prescaler));
+        self.pdec
+            .filter
+            .write(|w| unsafe { w.filter().bits(config.filter) });
+        if let Some(period) = config.period {
+            self.set_compare(CompareChannel::Cc0, period);
+        }
+        self.sync();
+    }
+
+    /// Start counting
+    #[inline]
+    pub fn start(&mut self) {
+        self.command(Command::START);
+    }
+
+    /// Stop counting
+    #[inline]
+    pub fn stop(&mut self) {
+        self.command(Command::STOP);
+    }
+
+    /// Restart the counter from zero
+    #[inline]
+    pub fn retrigger(&mut self) {
+        self.command(Command::RETRIGGER);
+    }
+
+    /// Read the counter
+    #[inline]
+    pub fn count(&mut self) -> u16 {
+        self.command(Command::READSYNC);
+        self.pdec.count.read().count().bits()
+    }
+
+    /// Set the counter
+    #[inline]
+    pub fn set_count(&mut self, count: u16) {
+        self.pdec.count.write(|w| unsafe { w.count().bits(count) });
+        self.sync();
+    }
+
+    /// Read the angular position of the quadrature decoder
+    #[inline]
+    pub fn angle(&mut self) -> u16 {
+        angle(self.count(), self.angular_bits)
+    }
+
+    /// Read the revolution counter of the quadrature decoder
+    #[inline]
+    pub fn revolutions(&mut self) -> u16 {
+        revolutions(self.count(), self.angular_bits)
+    }
+
+    /// Read the counter period, if enabled in the [`Config`]
+    #[inline]
+    pub fn period(&self) -> Option<u16> {
+        if self.pdec.ctrla.read().peren().bit_is_set() {
+            Some(self.compare(CompareChannel::Cc0))
+        } else {
+            None
+        }
+    }
+
+    /// Read the value of a compare channel
+    #[inline]
+    pub fn compare(&self, channel: CompareChannel) -> u16 {
+        self.pdec.cc[channel as usize].read().cc().bits()
+    }
+
+    /// Set the value of a compare channel
+    ///
+    /// The value of the channel 0 is the period, if enabled in the
+    /// [`Config`].
+    #[inline]
+    pub fn set_compare(&mut self, channel: CompareChannel, value: u16) {
+        self.pdec.cc[channel as usize].write(|w| unsafe { w.cc().bits(value) });
+        self.sync();
+    }
+
+    /// Read the status
+    #[inline]
+    pub fn status(&self) -> Status {
+        Status::from_bits_truncate(self.pdec.status.read().bits())
+    }
+
+    /// Clear error flags of the status
+    #[inline]
+    pub fn clear_status(&mut self, status: Status) {
+        let errors = status & Status::ERRORS;
+        self.pdec.status.write(|w| unsafe { w.bits(errors.bits()) });
+        self.sync();
+    }
+
+    /// Read the interrupt flags
+    #[inline]
+    pub fn read_flags(&self) -> Flags {
+        Flags::from_bits_truncate(self.pdec.intflag.read().bits())
+    }
+
+    /// Clear interrupt flags
+    #[inline]
+    pub fn clear_flags(&mut self, flags: Flags) {
+        // SAFETY: Flags only holds valid bits of the register
+        self.pdec.intflag.write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Enable interrupts for the given flags
+    #[inline]
+    pub fn enable_interrupts(&mut self, flags: Flags) {
+        // SAFETY: Flags only holds valid bits of the register
+        self.pdec
+            .intenset
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Disable interrupts for the given flags
+    #[inline]
+    pub fn disable_interrupts(&mut self, flags: Flags) {
+        // SAFETY: Flags only holds valid bits of the register
+        self.pdec
+            .intenclr
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Disable the PDEC and return the underlying PDEC and pads
+    #[inline]
+    pub fn free(self, mclk: &mut MCLK) -> (PDEC, Pads<I0, I1, I2>) {
+        self.pdec.ctrla.write(|w| w.swrst().set_bit());
+        self.sync();
+        mclk.apbcmask.modify(|_, w| w.pdec_().clear_bit());
+        (self.pdec, self.pads)
+    }
+
+    #[inline]
+    fn command(&mut self, command: Command) {
+        self.pdec.ctrlbset.write(|w| w.cmd().variant(command));
+        self.sync();
+    }
+
+    #[inline]
+    fn sync(&self) {
+        while self.pdec.syncbusy.read().bits() != 0 {}
+    }
+}
+
+/// Bit mask of the inputs set in `inputs`
+fn mask(inputs: [bool; 3]) -> u8 {
+    inputs
+        .iter()
+        .enumerate()
+        .fold(0, |mask, (i, &set)| mask | ((set as u8) << i))
+}
+
+/// Angular position held by `count`
+fn angle(count: u16, angular_bits: u8) -> u16 {
+    (count as u32 & ((1 << angular_bits) - 1)) as u16
+}
+
+/// Revolution counter held by `count`
+fn revolutions(count: u16, angular_bits: u8) -> u16 {
+    (count as u32 >> angular_bits) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{angle, mask, revolutions};
+
+    #[test]
+    fn counter_fields() {
+        assert_eq!(angle(0x1234, 10), 0x234);
+        assert_eq!(revolutions(0x1234, 10), 0x4);
+        assert_eq!(angle(0xFFFF, 16), 0xFFFF);
+        assert_eq!(revolutions(0xFFFF, 16), 0);
+        assert_eq!(mask([true, false, true]), 0b101);
+    }
+}
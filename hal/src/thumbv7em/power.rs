@@ -0,0 +1,373 @@
+//! # Sleep modes
+//!
+//! The Power Manager (PM) selects the sleep mode entered by the `WFI`
+//! instruction, and what stays powered in each mode:
+//!
+//! - In idle sleep mode, only the CPU is stopped. Any interrupt wakes it up.
+//! - In standby sleep mode, all clocks are stopped, except those of the
+//!   peripherals which run in standby. The RAM is retained as configured with
+//!   [`Power::configure_standby`].
+//! - In hibernate sleep mode, only the backup domain and, optionally, the
+//!   RAM are powered. Waking up resets the device.
+//! - In backup sleep mode, only the backup domain is powered. Waking up
+//!   resets the device.
+//! - In off sleep mode, everything is powered down. Only a reset wakes the
+//!   device up.
+//!
+//! A sleep mode other than idle is only entered with a [`WakeSource`] for
+//! that mode, which proves that a peripheral keeps running in it, and has its
+//! interrupt enabled. This makes sure the device does not sleep forever
+//! because the only peripheral able to wake it up was stopped. Wake sources
+//! are obtained with [`RtcWake::new`], [`TamperWake::new`],
+//! [`PowerSwitchWake::new`] and the `wake_source` method of the EIC pins,
+//! which check the configuration of the peripheral.
+//!
+//! ```
+//! use atsamd_hal::power::{Power, Retention, RtcWake, StandbyConfig};
+//!
+//! let mut power = Power::new(peripherals.PM);
+//! power.configure_standby(StandbyConfig {
+//!     ram: Retention::Partial,
+//!     ..Default::default()
+//! });
+//!
+//! // Sleep until the RTC compare interrupt fires
+//! rtc.enable_interrupt();
+//! let wake = RtcWake::new(&rtc).unwrap();
+//! power.standby(&wake);
+//! ```
+//!
+//! After waking up from hibernate or backup sleep mode, the cause of the
+//! reset is [`ResetCause::Backup`](crate::ResetCause::Backup).
+
+use core::marker::PhantomData;
+
+use cortex_m::asm;
+
+use crate::pac::{pm, PM};
+use crate::rtc::{Rtc, RtcMode, TamperInput};
+use crate::supc::Supc;
+use crate::typelevel::Sealed;
+
+/// Fast wakeup from standby sleep mode, which keeps the NVM or the main
+/// voltage regulator powered
+pub use pm::stdbycfg::FASTWKUP_A as FastWakeup;
+
+//==============================================================================
+//  Sleep modes
+//==============================================================================
+
+/// Type-level marker of a sleep mode
+pub trait SleepMode: Sealed {}
+
+/// Type-level marker of the standby sleep mode
+pub enum Standby {}
+impl Sealed for Standby {}
+impl SleepMode for Standby {}
+
+/// Type-level marker of the hibernate sleep mode
+pub enum Hibernate {}
+impl Sealed for Hibernate {}
+impl SleepMode for Hibernate {}
+
+/// Type-level marker of the backup sleep mode
+pub enum Backup {}
+impl Sealed for Backup {}
+impl SleepMode for Backup {}
+
+/// Peripheral which keeps running in the sleep mode `M`, and can wake the
+/// device up from it
+///
+/// This trait is sealed. It is only implemented by types whose constructors
+/// check that the peripheral is configured to wake the device up.
+pub trait WakeSource<M: SleepMode>: Sealed {}
+
+/// RTC able to wake the device up from standby, hibernate and backup sleep
+/// modes
+///
+/// The RTC is clocked by OSC32KCTRL in the backup domain, and runs in every
+/// sleep mode but off.
+pub struct RtcWake<'a, R: RtcMode> {
+    _rtc: &'a Rtc<R>,
+}
+
+impl<'a, R: RtcMode> RtcWake<'a, R> {
+    /// Check that the RTC can wake the device up
+    ///
+    /// Returns `None` unless an interrupt of the RTC is enabled.
+    #[inline]
+    pub fn new(rtc: &'a Rtc<R>) -> Option<Self> {
+        if rtc.interrupt_enabled() {
+            Some(Self { _rtc: rtc })
+        } else {
+            None
+        }
+    }
+}
+
+impl<R: RtcMode> Sealed for RtcWake<'_, R> {}
+impl<R: RtcMode> WakeSource<Standby> for RtcWake<'_, R> {}
+impl<R: RtcMode> WakeSource<Hibernate> for RtcWake<'_, R> {}
+impl<R: RtcMode> WakeSource<Backup> for RtcWake<'_, R> {}
+
+/// Backup pin able to wake the device up from standby, hibernate and backup
+/// sleep modes
+///
+/// The backup pins are the tamper inputs of the RTC, which remain powered in
+/// the backup domain.
+pub struct TamperWake<'a, R: RtcMode> {
+    _rtc: &'a Rtc<R>,
+}
+
+impl<'a, R: RtcMode> TamperWake<'a, R> {
+    /// Check that edges of the tamper input wake the device up
+    ///
+    /// Returns `None` unless the input was configured with
+    /// [`Rtc::enable_tamper_wake`].
+    #[inline]
+    pub fn new(rtc: &'a Rtc<R>, input: TamperInput) -> Option<Self> {
+        if rtc.tamper_wake_enabled(input) {
+            Some(Self { _rtc: rtc })
+        } else {
+            None
+        }
+    }
+}
+
+impl<R: RtcMode> Sealed for TamperWake<'_, R> {}
+impl<R: RtcMode> WakeSource<Standby> for TamperWake<'_, R> {}
+impl<R: RtcMode> WakeSource<Hibernate> for TamperWake<'_, R> {}
+impl<R: RtcMode> WakeSource<Backup> for TamperWake<'_, R> {}
+
+/// Battery backup power switch able to wake the device up from backup sleep
+/// mode, when the main power is restored
+pub struct PowerSwitchWake<'a> {
+    _supc: &'a Supc,
+}
+
+impl<'a> PowerSwitchWake<'a> {
+    /// Check that the power switch wakes the device up
+    ///
+    /// Returns `None` unless the switch was configured with
+    /// [`BackupPowerSwitch::BOD33`] and `wake` set, with
+    /// [`Supc::configure_backup_power_switch`].
+    ///
+    /// [`BackupPowerSwitch::BOD33`]: crate::supc::BackupPowerSwitch::BOD33
+    #[inline]
+    pub fn new(supc: &'a Supc) -> Option<Self> {
+        if supc.backup_power_switch_wake_enabled() {
+            Some(Self { _supc: supc })
+        } else {
+            None
+        }
+    }
+}
+
+impl Sealed for PowerSwitchWake<'_> {}
+impl WakeSource<Backup> for PowerSwitchWake<'_> {}
+
+/// External interrupt able to wake the device up from standby sleep mode
+///
+/// Returned by the `wake_source` method of the EIC pins.
+pub struct ExtIntWake<'a> {
+    _pin: PhantomData<&'a ()>,
+}
+
+impl ExtIntWake<'_> {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self { _pin: PhantomData }
+    }
+}
+
+impl Sealed for ExtIntWake<'_> {}
+impl WakeSource<Standby> for ExtIntWake<'_> {}
+
+//==============================================================================
+//  Configurations
+//==============================================================================
+
+/// Retention of a RAM in sleep mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Retention {
+    /// The whole RAM is retained
+    Full = 0,
+    /// Only the first 32kB of the system RAM, or the first 4kB of the backup
+    /// RAM, are retained
+    Partial = 1,
+    /// The RAM is powered down
+    Off = 2,
+}
+
+/// Standby sleep mode configuration
+#[derive(Clone, Copy, Debug)]
+pub struct StandbyConfig {
+    /// Retention of the system RAM
+    pub ram: Retention,
+    /// Fast wakeup
+    pub fast_wakeup: FastWakeup,
+}
+
+impl Default for StandbyConfig {
+    fn default() -> Self {
+        Self {
+            ram: Retention::Full,
+            fast_wakeup: FastWakeup::NO,
+        }
+    }
+}
+
+/// Hibernate sleep mode configuration
+#[derive(Clone, Copy, Debug)]
+pub struct HibernateConfig {
+    /// Retention of the system RAM
+    pub ram: Retention,
+    /// Retention of the backup RAM
+    pub backup_ram: Retention,
+    /// Keep the I/O pins in their current state until
+    /// [`Power::release_io_retention`] is called after waking up
+    pub io_retention: bool,
+}
+
+impl Default for HibernateConfig {
+    fn default() -> Self {
+        Self {
+            ram: Retention::Full,
+            backup_ram: Retention::Full,
+            io_retention: false,
+        }
+    }
+}
+
+/// Backup sleep mode configuration
+#[derive(Clone, Copy, Debug)]
+pub struct BackupConfig {
+    /// Retention of the backup RAM
+    pub backup_ram: Retention,
+    /// Keep the I/O pins in their current state until
+    /// [`Power::release_io_retention`] is called after waking up
+    pub io_retention: bool,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            backup_ram: Retention::Full,
+            io_retention: false,
+        }
+    }
+}
+
+//==============================================================================
+//  Power
+//==============================================================================
+
+/// Sleep mode control of the PM peripheral
+pub struct Power {
+    pm: PM,
+}
+
+impl Power {
+    /// Take the PM
+    #[inline]
+    pub fn new(pm: PM) -> Self {
+        Self { pm }
+    }
+
+    /// Configure the standby sleep mode
+    #[inline]
+    pub fn configure_standby(&mut self, config: StandbyConfig) {
+        // SAFETY: Retention only holds valid values of the field
+        self.pm.stdbycfg.write(|w| unsafe {
+            w.ramcfg().bits(config.ram as u8);
+            w.fastwkup().variant(config.fast_wakeup)
+        });
+    }
+
+    /// Release the I/O pins retained after waking up from hibernate or
+    /// backup sleep mode
+    ///
+    /// The pins should be configured again before they are released.
+    #[inline]
+    pub fn release_io_retention(&mut self) {
+        self.pm.ctrla.modify(|_, w| w.ioret().clear_bit());
+    }
+
+    /// Enter idle sleep mode until an interrupt fires
+    #[inline]
+    pub fn idle(&mut self) {
+        self.sleep(pm::sleepcfg::SLEEPMODE_A::IDLE);
+    }
+
+    /// Enter standby sleep mode until the wake source, or another peripheral
+    /// running in standby, fires an interrupt
+    #[inline]
+    pub fn standby<W: WakeSource<Standby>>(&mut self, _source: &W) {
+        self.sleep(pm::sleepcfg::SLEEPMODE_A::STANDBY);
+    }
+
+    /// Enter hibernate sleep mode
+    ///
+    /// The device is reset when the wake source wakes it up.
+    #[inline]
+    pub fn hibernate<W: WakeSource<Hibernate>>(
+        mut self,
+        config: HibernateConfig,
+        _source: &W,
+    ) -> ! {
+        // SAFETY: Retention only holds valid values of the fields
+        self.pm.hibcfg.write(|w| unsafe {
+            w.ramcfg().bits(config.ram as u8);
+            w.bramcfg().bits(config.backup_ram as u8)
+        });
+        self.pm
+            .ctrla
+            .modify(|_, w| w.ioret().bit(config.io_retention));
+        loop {
+            self.sleep(pm::sleepcfg::SLEEPMODE_A::HIBERNATE);
+        }
+    }
+
+    /// Enter backup sleep mode
+    ///
+    /// The device is reset when the wake source wakes it up.
+    #[inline]
+    pub fn backup<W: WakeSource<Backup>>(mut self, config: BackupConfig, _source: &W) -> ! {
+        // SAFETY: Retention only holds valid values of the field
+        self.pm
+            .bkupcfg
+            .write(|w| unsafe { w.bramcfg().bits(config.backup_ram as u8) });
+        self.pm
+            .ctrla
+            .modify(|_, w| w.ioret().bit(config.io_retention));
+        loop {
+            self.sleep(pm::sleepcfg::SLEEPMODE_A::BACKUP);
+        }
+    }
+
+    /// Enter off sleep mode
+    ///
+    /// Only a reset wakes the device up.
+    #[inline]
+    pub fn off(mut self) -> ! {
+        loop {
+            self.sleep(pm::sleepcfg::SLEEPMODE_A::OFF);
+        }
+    }
+
+    #[inline]
+    fn sleep(&mut self, mode: pm::sleepcfg::SLEEPMODE_A) {
+        let bits = u8::from(mode);
+        self.pm.sleepcfg.write(|w| w.sleepmode().variant(mode));
+        // The sleep mode must be written before WFI is executed
+        while self.pm.sleepcfg.read().sleepmode().bits() != bits {}
+        asm::dsb();
+        asm::wfi();
+    }
+
+    /// Return the underlying PM
+    #[inline]
+    pub fn free(self) -> PM {
+        self.pm
+    }
+}
@@ -0,0 +1,156 @@
+//! # RAM Error Correction Code (RAMECC)
+//!
+//! When enabled by the `RAM_ECCDIS` fuse of the NVM user row, the system RAM
+//! is protected by an error correction code. Single-bit errors are corrected
+//! when the RAM is read, and dual-bit errors are detected. The RAMECC
+//! reports the address of the last error, and can raise an interrupt for
+//! each kind of error.
+//!
+//! ```
+//! use atsamd_hal::ramecc::{Flags, Ramecc};
+//!
+//! let mut ramecc = Ramecc::new(&mut peripherals.MCLK, peripherals.RAMECC);
+//! ramecc.enable_interrupts(Flags::SINGLE | Flags::DUAL);
+//!
+//! // In the RAMECC interrupt handler
+//! if let Some(error) = ramecc.error() {
+//!     // ...
+//! }
+//! ```
+
+use bitflags::bitflags;
+
+use crate::pac::{MCLK, RAMECC};
+
+//==============================================================================
+//  Errors
+//==============================================================================
+
+bitflags! {
+    /// RAMECC interrupt flags
+    ///
+    /// The binary format of the flags matches the `INTENSET`, `INTENCLR` and
+    /// `INTFLAG` registers.
+    pub struct Flags: u8 {
+        /// A single-bit error was detected and corrected
+        const SINGLE = 1;
+        /// A dual-bit error was detected; the data read is corrupted
+        const DUAL = 1 << 1;
+    }
+}
+
+/// Kind of ECC error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A single-bit error, which was corrected
+    Single,
+    /// A dual-bit error, which cannot be corrected
+    Dual,
+}
+
+/// ECC error reported by the RAMECC
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EccError {
+    /// Kind of error
+    pub kind: ErrorKind,
+    /// Address of the error, as reported by the `ERRADDR` register
+    pub address: u32,
+}
+
+//==============================================================================
+//  Ramecc
+//==============================================================================
+
+/// RAMECC peripheral
+pub struct Ramecc {
+    ramecc: RAMECC,
+}
+
+impl Ramecc {
+    /// Take the RAMECC
+    #[inline]
+    pub fn new(mclk: &mut MCLK, ramecc: RAMECC) -> Self {
+        mclk.apbbmask.modify(|_, w| w.ramecc_().set_bit());
+        Self { ramecc }
+    }
+
+    /// Whether the ECC is enabled
+    ///
+    /// The ECC is disabled by the `RAM_ECCDIS` fuse, or while debugging with
+    /// [`set_debug_mode`](Self::set_debug_mode).
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.ramecc.status.read().eccdis().bit_is_clear()
+    }
+
+    /// Configure the ECC while the CPU is halted by a debugger
+    ///
+    /// With `disable`, the ECC is disabled so that the debugger can access
+    /// the RAM freely. With `log`, errors caused by the debugger are
+    /// reported.
+    #[inline]
+    pub fn set_debug_mode(&mut self, disable: bool, log: bool) {
+        self.ramecc.dbgctrl.write(|w| {
+            w.eccdis().bit(disable);
+            w.eccelog().bit(log)
+        });
+    }
+
+    /// Read the last error, and clear the interrupt flags
+    ///
+    /// If both a single-bit and a dual-bit error occurred, the dual-bit error
+    /// is reported.
+    #[inline]
+    pub fn error(&mut self) -> Option<EccError> {
+        let flags = self.read_flags();
+        let kind = if flags.contains(Flags::DUAL) {
+            ErrorKind::Dual
+        } else if flags.contains(Flags::SINGLE) {
+            ErrorKind::Single
+        } else {
+            return None;
+        };
+        let address = self.ramecc.erraddr.read().bits();
+        self.clear_flags(flags);
+        Some(EccError { kind, address })
+    }
+
+    /// Read the interrupt flags
+    #[inline]
+    pub fn read_flags(&self) -> Flags {
+        Flags::from_bits_truncate(self.ramecc.intflag.read().bits())
+    }
+
+    /// Clear interrupt flags
+    #[inline]
+    pub fn clear_flags(&mut self, flags: Flags) {
+        // SAFETY: Flags only holds valid bits of the register
+        self.ramecc
+            .intflag
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Enable interrupts for the given flags
+    #[inline]
+    pub fn enable_interrupts(&mut self, flags: Flags) {
+        // SAFETY: Flags only holds valid bits of the register
+        self.ramecc
+            .intenset
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Disable interrupts for the given flags
+    #[inline]
+    pub fn disable_interrupts(&mut self, flags: Flags) {
+        // SAFETY: Flags only holds valid bits of the register
+        self.ramecc
+            .intenclr
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Return the underlying RAMECC
+    #[inline]
+    pub fn free(self) -> RAMECC {
+        self.ramecc
+    }
+}
@@ -0,0 +1,200 @@
+//! Card registers and commands
+
+/// Size of a block, in bytes
+pub const BLOCK_SIZE: usize = 512;
+
+/// SD commands used by the driver
+pub(super) mod cmd {
+    pub const GO_IDLE_STATE: u8 = 0;
+    pub const ALL_SEND_CID: u8 = 2;
+    pub const SEND_RELATIVE_ADDR: u8 = 3;
+    pub const SWITCH_FUNC: u8 = 6;
+    pub const SELECT_CARD: u8 = 7;
+    pub const SEND_IF_COND: u8 = 8;
+    pub const SEND_CSD: u8 = 9;
+    pub const SEND_STATUS: u8 = 13;
+    pub const SET_BLOCKLEN: u8 = 16;
+    pub const READ_SINGLE_BLOCK: u8 = 17;
+    pub const READ_MULTIPLE_BLOCK: u8 = 18;
+    pub const WRITE_BLOCK: u8 = 24;
+    pub const WRITE_MULTIPLE_BLOCK: u8 = 25;
+    pub const APP_CMD: u8 = 55;
+    pub const SET_BUS_WIDTH: u8 = 6;
+    pub const SD_SEND_OP_COND: u8 = 41;
+}
+
+/// Argument of `SEND_IF_COND`: 2.7-3.6 V, with a check pattern
+pub(super) const IF_COND: u32 = 0x1AA;
+
+/// Argument of `SD_SEND_OP_COND`: 3.2-3.4 V window
+pub(super) const OP_COND_VOLTAGE: u32 = 0x0030_0000;
+/// `SD_SEND_OP_COND` high capacity support bit
+pub(super) const OP_COND_HCS: u32 = 1 << 30;
+/// `SD_SEND_OP_COND` power up status bit, cleared while the card is busy
+pub(super) const OP_COND_READY: u32 = 1 << 31;
+
+/// Argument of `SWITCH_FUNC` selecting the high speed access mode
+pub(super) const SWITCH_HIGH_SPEED: u32 = 0x80FF_FFF1;
+
+/// Card status error bits, returned in R1 responses
+pub(super) const STATUS_ERRORS: u32 = 0xFDF9_8008;
+/// Card status bit set when the card is ready for data
+pub(super) const STATUS_READY_FOR_DATA: u32 = 1 << 8;
+/// Card status current state of a card in the transfer state
+pub(super) const STATE_TRAN: u32 = 4;
+
+//==============================================================================
+//  CardInfo
+//==============================================================================
+
+/// Version of the card's physical layer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CardVersion {
+    /// Version 1.x standard capacity card
+    V1,
+    /// Version 2.0 or later standard capacity card
+    V2,
+    /// Version 2.0 or later high or extended capacity card (SDHC/SDXC)
+    HighCapacity,
+}
+
+/// Information about an initialized card
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CardInfo {
+    /// Card version and capacity class
+    pub version: CardVersion,
+    /// Relative card address
+    pub rca: u16,
+    /// Number of 512-byte blocks
+    pub num_blocks: u32,
+    /// Card uses the 4-bit data bus
+    pub wide_bus: bool,
+    /// Card uses the high speed (50 MHz) mode
+    pub high_speed: bool,
+}
+
+impl CardInfo {
+    /// Capacity of the card, in bytes
+    #[inline]
+    pub fn capacity(&self) -> u64 {
+        self.num_blocks as u64 * BLOCK_SIZE as u64
+    }
+
+    /// Command argument addressing the given block
+    ///
+    /// Standard capacity cards are addressed in bytes, while high capacity
+    /// cards are addressed in blocks.
+    #[inline]
+    pub(super) fn address(&self, block: u32) -> u32 {
+        match self.version {
+            CardVersion::HighCapacity => block,
+            _ => block * BLOCK_SIZE as u32,
+        }
+    }
+}
+
+//==============================================================================
+//  Csd
+//==============================================================================
+
+/// Card-specific data register
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Csd(u128);
+
+impl Csd {
+    /// Build the CSD from the four response registers of an R2 response
+    ///
+    /// The host controller strips the CRC, so the response registers hold
+    /// bits 8 to 127 of the CSD.
+    #[inline]
+    pub fn from_response(rr: [u32; 4]) -> Self {
+        let bits = rr
+            .iter()
+            .rev()
+            .fold(0u128, |acc, &word| acc << 32 | word as u128);
+        Self(bits << 8)
+    }
+
+    /// Raw value of the CSD
+    #[inline]
+    pub fn bits(&self) -> u128 {
+        self.0
+    }
+
+    #[inline]
+    fn field(&self, offset: u32, width: u32) -> u32 {
+        ((self.0 >> offset) & ((1 << width) - 1)) as u32
+    }
+
+    /// CSD structure version: `0` for standard capacity cards, `1` for high
+    /// and extended capacity cards
+    #[inline]
+    pub fn version(&self) -> u8 {
+        self.field(126, 2) as u8
+    }
+
+    /// Capacity of the card, in 512-byte blocks
+    #[inline]
+    pub fn num_blocks(&self) -> u32 {
+        match self.version() {
+            0 => {
+                let c_size = self.field(62, 12);
+                let c_size_mult = self.field(47, 3);
+                let read_bl_len = self.field(80, 4);
+                ((c_size + 1) << (c_size_mult + 2) << read_bl_len) >> 9
+            }
+            _ => (self.field(48, 22) + 1) * 1024,
+        }
+    }
+}
+
+//==============================================================================
+//  Clock divider
+//==============================================================================
+
+/// Divider value `N` of the SD clock, so that `base / 2N` does not exceed
+/// `target`
+///
+/// `N = 0` selects the base clock itself. The divider is limited to 10 bits.
+#[inline]
+pub fn clock_divider(base: u32, target: u32) -> u16 {
+    if base <= target {
+        0
+    } else {
+        let n = (base - 1) / (2 * target) + 1;
+        n.min(0x3FF) as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clock_divider, Csd};
+
+    #[test]
+    fn csd_capacity() {
+        // 8 GB SDHC card, with C_SIZE = 15159
+        let csd = Csd(0x400E_0032_5B59_0000_3B37_7F80_0A40_4000);
+        assert_eq!(csd.version(), 1);
+        assert_eq!(csd.num_blocks(), 15160 * 1024);
+
+        // 1 GB SDSC card, with C_SIZE = 3879, C_SIZE_MULT = 7 and
+        // READ_BL_LEN = 10
+        let csd = Csd(0x005E_0032_5F5A_83C9_F6DB_DFFF_0E80_0000);
+        assert_eq!(csd.version(), 0);
+        assert_eq!(csd.num_blocks(), 3880 << 10);
+
+        let rr = [0x800A_4040, 0x003B_377F, 0x325B_5900, 0x0040_0E00];
+        let csd = Csd::from_response(rr);
+        assert_eq!(csd.bits(), 0x400E_0032_5B59_0000_3B37_7F80_0A40_4000);
+        assert_eq!(csd.num_blocks(), 15160 * 1024);
+    }
+
+    #[test]
+    fn divider() {
+        assert_eq!(clock_divider(48_000_000, 400_000), 60);
+        assert_eq!(clock_divider(120_000_000, 400_000), 150);
+        assert_eq!(clock_divider(120_000_000, 50_000_000), 2);
+        assert_eq!(clock_divider(48_000_000, 50_000_000), 0);
+        assert_eq!(clock_divider(120_000_000, 1), 0x3FF);
+    }
+}
@@ -0,0 +1,951 @@
+//! # SD/MMC Host Controller (SDHC)
+//!
+//! The SAMD51 and SAME5x contain one or two SD host controllers, driving an
+//! SD card over its native 4-bit bus. This is much faster than SPI access,
+//! and the data is moved by the controller's own DMA engine (ADMA2).
+//!
+//! ## Clocking
+//!
+//! The SD clock is derived from the GCLK of the peripheral ([`Sdhc0Clock`]
+//! or [`Sdhc1Clock`]). The card is identified at 400 kHz, then clocked at
+//! 25 MHz, or 50 MHz if both the card and the controller support the high
+//! speed mode. A GCLK of 100 MHz or more reaches these frequencies exactly.
+//!
+//! ## Example
+//!
+//! ```
+//! use atsamd_hal::sdhc::{self, Sdhc};
+//!
+//! let pads = sdhc::Pads::new(pins.pb11, pins.pa08, pins.pa09, pins.pa10, pins.pa11, pins.pb10);
+//! let clock = clocks.sdhc0(&gclk_100m).unwrap();
+//! let mut sdhc = Sdhc::new(&mut mclk, peripherals.SDHC0, pads, clock);
+//!
+//! let info = sdhc.init_card().unwrap();
+//! let mut buffer = [0u8; 4 * sdhc::BLOCK_SIZE];
+//! sdhc.read_blocks(0, &mut buffer).unwrap();
+//! ```
+//!
+//! ## Card detect and write protect
+//!
+//! By default, the controller assumes that a card is always inserted, and
+//! ignores the write protect switch. [`Sdhc::card_detect_pin`] and
+//! [`Sdhc::write_protect_pin`] route the `SDCD` and `SDWP` signals of the
+//! card socket to the controller. A card that was removed must be
+//! initialized again with [`Sdhc::init_card`] once it is reinserted.
+//!
+//! ## `embedded-sdmmc`
+//!
+//! With the `sdmmc` feature, [`Sdhc`] implements the `BlockDevice` trait of
+//! `embedded-sdmmc`, and can replace `SdMmcSpi` in a `Controller`. The card
+//! must be initialized before it is handed to the `Controller`.
+//!
+//! ## Transfers
+//!
+//! Buffers aligned to 4 bytes are transferred by ADMA2, in chunks of up to
+//! 128 blocks. Unaligned buffers, such as `embedded-sdmmc` blocks, are
+//! transferred by the CPU through the data port.
+
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::sync::atomic::{fence, Ordering};
+
+use crate::gpio::v2::{AlternateI, AnyPin, Pin, PinId, SpecificPin};
+use crate::pac::{sdhc0, MCLK, SDHC0};
+use crate::time::Hertz;
+use crate::typelevel::Sealed;
+
+#[cfg(feature = "min-samd51n")]
+use crate::pac::SDHC1;
+
+pub use crate::clock::Sdhc0Clock;
+#[cfg(feature = "min-samd51n")]
+pub use crate::clock::Sdhc1Clock;
+
+mod card;
+pub use card::*;
+
+/// SD clock frequency during card identification
+const INIT_FREQ: u32 = 400_000;
+/// SD clock frequency in the default speed mode
+const DEFAULT_SPEED_FREQ: u32 = 25_000_000;
+/// SD clock frequency in the high speed mode
+const HIGH_SPEED_FREQ: u32 = 50_000_000;
+
+/// Number of `SD_SEND_OP_COND` attempts while the card powers up
+const OP_COND_RETRIES: u32 = 4000;
+
+/// Normal interrupt status bits latched by the controller
+const NORMAL_STATUS: u16 = 0x00FF;
+/// Error interrupt status bits latched by the controller
+const ERROR_STATUS: u16 = 0x03FF;
+
+//==============================================================================
+//  Instance
+//==============================================================================
+
+/// Type-level `enum` representing an SDHC peripheral
+pub trait Instance: Sealed + Deref<Target = sdhc0::RegisterBlock> {
+    /// SDHC peripheral number
+    const NUM: usize;
+    /// GCLK token of this peripheral
+    type Clock: Into<Hertz>;
+    /// Enable the corresponding AHB clock
+    fn enable_ahb_clock(mclk: &mut MCLK);
+    /// Disable the corresponding AHB clock
+    fn disable_ahb_clock(mclk: &mut MCLK);
+}
+
+macro_rules! sdhc_instance {
+    ($Alias:ident, $Sdhc:ident, $Clock:ident, $num:literal, $bit:ident) => {
+        /// Type alias for the corresponding SDHC instance
+        pub type $Alias = $Sdhc;
+        impl Sealed for $Alias {}
+        impl Instance for $Alias {
+            const NUM: usize = $num;
+            type Clock = $Clock;
+            #[inline]
+            fn enable_ahb_clock(mclk: &mut MCLK) {
+                mclk.ahbmask.modify(|_, w| w.$bit().set_bit());
+            }
+            #[inline]
+            fn disable_ahb_clock(mclk: &mut MCLK) {
+                mclk.ahbmask.modify(|_, w| w.$bit().clear_bit());
+            }
+        }
+    };
+}
+
+sdhc_instance!(Sdhc0, SDHC0, Sdhc0Clock, 0, sdhc0_);
+#[cfg(feature = "min-samd51n")]
+sdhc_instance!(Sdhc1, SDHC1, Sdhc1Clock, 1, sdhc1_);
+
+//==============================================================================
+//  Pads
+//==============================================================================
+
+/// [`PinId`]s that can be used as the `SDCK` pin of the instance `S`
+pub trait CkPin<S: Instance>: PinId {}
+
+/// [`PinId`]s that can be used as the `SDCMD` pin of the instance `S`
+pub trait CmdPin<S: Instance>: PinId {}
+
+/// [`PinId`]s that can be used as the `SDDAT0` pin of the instance `S`
+pub trait Dat0Pin<S: Instance>: PinId {}
+
+/// [`PinId`]s that can be used as the `SDDAT1` pin of the instance `S`
+pub trait Dat1Pin<S: Instance>: PinId {}
+
+/// [`PinId`]s that can be used as the `SDDAT2` pin of the instance `S`
+pub trait Dat2Pin<S: Instance>: PinId {}
+
+/// [`PinId`]s that can be used as the `SDDAT3` pin of the instance `S`
+pub trait Dat3Pin<S: Instance>: PinId {}
+
+/// [`PinId`]s that can be used as the `SDCD` pin of the instance `S`
+pub trait CdPin<S: Instance>: PinId {}
+
+/// [`PinId`]s that can be used as the `SDWP` pin of the instance `S`
+pub trait WpPin<S: Instance>: PinId {}
+
+macro_rules! sdhc_pins {
+    ($Sdhc:ident: $($(#[$cfg:meta])* $Trait:ident: $Id:ident;)+) => {
+        $(
+            $(#[$cfg])*
+            impl $Trait<$Sdhc> for crate::gpio::v2::$Id {}
+        )+
+    };
+}
+
+sdhc_pins! { Sdhc0:
+    CkPin: PB11;
+    CmdPin: PA08;
+    Dat0Pin: PA09;
+    Dat1Pin: PA10;
+    Dat2Pin: PA11;
+    Dat3Pin: PB10;
+    CdPin: PA12;
+    WpPin: PA13;
+}
+
+#[cfg(feature = "min-samd51n")]
+sdhc_pins! { Sdhc1:
+    CkPin: PA21;
+    CmdPin: PA20;
+    Dat0Pin: PB18;
+    Dat1Pin: PB19;
+    Dat2Pin: PB20;
+    Dat3Pin: PB21;
+    #[cfg(feature = "min-samd51p")]
+    CdPin: PD20;
+    #[cfg(feature = "min-samd51p")]
+    WpPin: PD21;
+}
+
+/// Clock, command and data pins of an SDHC peripheral
+pub struct Pads<S, CK, CMD, D0, D1, D2, D3>
+where
+    S: Instance,
+    CK: CkPin<S>,
+    CMD: CmdPin<S>,
+    D0: Dat0Pin<S>,
+    D1: Dat1Pin<S>,
+    D2: Dat2Pin<S>,
+    D3: Dat3Pin<S>,
+{
+    ck: Pin<CK, AlternateI>,
+    cmd: Pin<CMD, AlternateI>,
+    dat0: Pin<D0, AlternateI>,
+    dat1: Pin<D1, AlternateI>,
+    dat2: Pin<D2, AlternateI>,
+    dat3: Pin<D3, AlternateI>,
+    _sdhc: PhantomData<S>,
+}
+
+impl<S, CK, CMD, D0, D1, D2, D3> Pads<S, CK, CMD, D0, D1, D2, D3>
+where
+    S: Instance,
+    CK: CkPin<S>,
+    CMD: CmdPin<S>,
+    D0: Dat0Pin<S>,
+    D1: Dat1Pin<S>,
+    D2: Dat2Pin<S>,
+    D3: Dat3Pin<S>,
+{
+    /// Configure the pins of the SDHC instance `S`
+    #[inline]
+    pub fn new(
+        ck: impl AnyPin<Id = CK>,
+        cmd: impl AnyPin<Id = CMD>,
+        dat0: impl AnyPin<Id = D0>,
+        dat1: impl AnyPin<Id = D1>,
+        dat2: impl AnyPin<Id = D2>,
+        dat3: impl AnyPin<Id = D3>,
+    ) -> Self {
+        Self {
+            ck: ck.into().into_mode(),
+            cmd: cmd.into().into_mode(),
+            dat0: dat0.into().into_mode(),
+            dat1: dat1.into().into_mode(),
+            dat2: dat2.into().into_mode(),
+            dat3: dat3.into().into_mode(),
+            _sdhc: PhantomData,
+        }
+    }
+
+    /// Return the pins
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    pub fn free(
+        self,
+    ) -> (
+        Pin<CK, AlternateI>,
+        Pin<CMD, AlternateI>,
+        Pin<D0, AlternateI>,
+        Pin<D1, AlternateI>,
+        Pin<D2, AlternateI>,
+        Pin<D3, AlternateI>,
+    ) {
+        (
+            self.ck, self.cmd, self.dat0, self.dat1, self.dat2, self.dat3,
+        )
+    }
+}
+
+//==============================================================================
+//  Error
+//==============================================================================
+
+/// Errors reported by the SDHC driver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// No card is inserted
+    NoCard,
+    /// The card is write protected
+    WriteProtected,
+    /// The card has not been initialized with [`Sdhc::init_card`]
+    NotInitialized,
+    /// The card did not answer the command
+    CommandTimeout,
+    /// The CRC of the command response is invalid
+    CommandCrc,
+    /// The end bit of the command response is invalid
+    CommandEndBit,
+    /// The command response holds the wrong command index
+    CommandIndex,
+    /// The card did not send or acknowledge data in time
+    DataTimeout,
+    /// The CRC of the data is invalid
+    DataCrc,
+    /// The end bit of the data is invalid
+    DataEndBit,
+    /// The card draws too much current
+    CurrentLimit,
+    /// The automatic `STOP_TRANSMISSION` command failed
+    AutoCmd,
+    /// The ADMA2 engine failed to fetch a descriptor or transfer data
+    Adma,
+    /// The card does not support the 3.3 V supply, or is not an SD card
+    UnsupportedCard,
+    /// The card did not complete its power up
+    InitTimeout,
+    /// The card reported an error in its status
+    CardStatus(u32),
+    /// The buffer length is not a multiple of [`BLOCK_SIZE`], or the blocks
+    /// are out of the card's range
+    InvalidLength,
+}
+
+//==============================================================================
+//  Commands
+//==============================================================================
+
+/// Response expected from a command
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Response {
+    /// No response
+    None,
+    /// 48-bit response, with CRC and index: R1, R6, R7
+    Short,
+    /// 48-bit response, followed by a busy signal on `DAT0`: R1b
+    ShortBusy,
+    /// 48-bit response, without CRC and index: R3
+    ShortNoCrc,
+    /// 136-bit response: R2
+    Long,
+}
+
+/// Direction of a data transfer
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Read,
+    Write,
+}
+
+/// ADMA2 descriptor, transferring up to 64 KiB
+#[derive(Clone, Copy, Default)]
+#[repr(C, align(4))]
+struct Descriptor {
+    attr: u16,
+    len: u16,
+    addr: u32,
+}
+
+impl Descriptor {
+    const VALID: u16 = 1 << 0;
+    const END: u16 = 1 << 1;
+    const ACT_TRAN: u16 = 0b10 << 4;
+}
+
+/// Number of descriptors in the ADMA2 table
+const DESCRIPTORS: usize = 4;
+
+/// Number of blocks transferred by each descriptor
+const BLOCKS_PER_DESCRIPTOR: usize = 64;
+
+/// Maximum number of blocks transferred by a single command
+const BLOCKS_PER_CHUNK: usize = DESCRIPTORS * BLOCKS_PER_DESCRIPTOR;
+
+//==============================================================================
+//  Sdhc
+//==============================================================================
+
+/// SD host controller driving a single card
+pub struct Sdhc<S, CK, CMD, D0, D1, D2, D3>
+where
+    S: Instance,
+    CK: CkPin<S>,
+    CMD: CmdPin<S>,
+    D0: Dat0Pin<S>,
+    D1: Dat1Pin<S>,
+    D2: Dat2Pin<S>,
+    D3: Dat3Pin<S>,
+{
+    sdhc: S,
+    pads: Pads<S, CK, CMD, D0, D1, D2, D3>,
+    base_freq: u32,
+    write_protect: bool,
+    card: Option<CardInfo>,
+}
+
+impl<S, CK, CMD, D0, D1, D2, D3> Sdhc<S, CK, CMD, D0, D1, D2, D3>
+where
+    S: Instance,
+    CK: CkPin<S>,
+    CMD: CmdPin<S>,
+    D0: Dat0Pin<S>,
+    D1: Dat1Pin<S>,
+    D2: Dat2Pin<S>,
+    D3: Dat3Pin<S>,
+{
+    /// Reset the controller, and power the card at 3.3 V
+    ///
+    /// The SD clock runs at 400 kHz until the card is initialized with
+    /// [`init_card`](Self::init_card).
+    pub fn new(
+        mclk: &mut MCLK,
+        sdhc: S,
+        pads: Pads<S, CK, CMD, D0, D1, D2, D3>,
+        clock: S::Clock,
+    ) -> Self {
+        S::enable_ahb_clock(mclk);
+
+        sdhc.srr.write(|w| w.swrstall().reset());
+        while sdhc.srr.read().swrstall().bit_is_set() {}
+
+        sdhc.pcr.write(|w| w.sdbvsel()._3v3().sdbpwr().on());
+        // SAFETY: Only the implemented status bits are enabled
+        sdhc.nister().write(|w| unsafe { w.bits(NORMAL_STATUS) });
+        sdhc.eister().write(|w| unsafe { w.bits(ERROR_STATUS) });
+        // SAFETY: 0xE selects the longest data timeout, 2^27 clock cycles
+        sdhc.tcr.write(|w| unsafe { w.dtcval().bits(0xE) });
+        sdhc.mc1r.modify(|_, w| w.fcd().set_bit());
+
+        let base_freq = clock.into().0;
+        let sdhc = Self {
+            sdhc,
+            pads,
+            base_freq,
+            write_protect: false,
+            card: None,
+        };
+        sdhc.set_clock(INIT_FREQ);
+        sdhc
+    }
+
+    /// Convert a pin to carry the card detect signal
+    ///
+    /// Once called, [`is_card_present`](Self::is_card_present) follows the
+    /// level of the pin rather than assuming a card is inserted.
+    #[inline]
+    pub fn card_detect_pin<P>(&mut self, pin: P) -> Pin<P::Id, AlternateI>
+    where
+        P: AnyPin,
+        P::Id: CdPin<S>,
+    {
+        let pin: SpecificPin<P> = pin.into();
+        let pin = pin.into_mode();
+        self.sdhc.mc1r.modify(|_, w| w.fcd().clear_bit());
+        pin
+    }
+
+    /// Convert a pin to carry the write protect signal
+    ///
+    /// Once called, writes to a write protected card fail with
+    /// [`Error::WriteProtected`].
+    #[inline]
+    pub fn write_protect_pin<P>(&mut self, pin: P) -> Pin<P::Id, AlternateI>
+    where
+        P: AnyPin,
+        P::Id: WpPin<S>,
+    {
+        let pin: SpecificPin<P> = pin.into();
+        self.write_protect = true;
+        pin.into_mode()
+    }
+
+    /// Check whether a card is inserted
+    #[inline]
+    pub fn is_card_present(&self) -> bool {
+        self.sdhc.psr.read().cardins().bit_is_set()
+    }
+
+    /// Check whether the write protect switch of the card is set
+    ///
+    /// Always `false` without a write protect pin.
+    #[inline]
+    pub fn is_write_protected(&self) -> bool {
+        self.write_protect && self.sdhc.psr.read().wrppl().is_protected()
+    }
+
+    /// Information about the initialized card
+    #[inline]
+    pub fn card_info(&self) -> Option<CardInfo> {
+        self.card
+    }
+
+    /// Identify and initialize the card
+    ///
+    /// The card is switched to the 4-bit bus, and to the high speed mode when
+    /// both the card and the controller support it.
+    pub fn init_card(&mut self) -> Result<CardInfo, Error> {
+        self.card = None;
+        if !self.is_card_present() {
+            return Err(Error::NoCard);
+        }
+
+        self.set_clock(INIT_FREQ);
+        self.sdhc
+            .hc1r()
+            .modify(|_, w| w.dw()._1bit().hsen().normal());
+        // The card needs 74 clock cycles after power up before the first
+        // command
+        cortex_m::asm::delay(250_000);
+
+        self.command(cmd::GO_IDLE_STATE, 0, Response::None, false)?;
+
+        let mut version = match self.command(cmd::SEND_IF_COND, IF_COND, Response::Short, false) {
+            Ok(r) if r & 0xFFF == IF_COND => CardVersion::V2,
+            Ok(_) => return Err(Error::UnsupportedCard),
+            Err(Error::CommandTimeout) => CardVersion::V1,
+            Err(e) => return Err(e),
+        };
+
+        let hcs = if version == CardVersion::V2 {
+            OP_COND_HCS
+        } else {
+            0
+        };
+        let mut ocr = 0;
+        for _ in 0..OP_COND_RETRIES {
+            self.command(cmd::APP_CMD, 0, Response::Short, false)?;
+            ocr = self.command(
+                cmd::SD_SEND_OP_COND,
+                OP_COND_VOLTAGE | hcs,
+                Response::ShortNoCrc,
+                false,
+            )?;
+            if ocr & OP_COND_READY != 0 {
+                break;
+            }
+        }
+        if ocr & OP_COND_READY == 0 {
+            return Err(Error::InitTimeout);
+        }
+        if ocr & OP_COND_VOLTAGE == 0 {
+            return Err(Error::UnsupportedCard);
+        }
+        if ocr & OP_COND_HCS != 0 {
+            version = CardVersion::HighCapacity;
+        }
+
+        self.command(cmd::ALL_SEND_CID, 0, Response::Long, false)?;
+        let rca = (self.command(cmd::SEND_RELATIVE_ADDR, 0, Response::Short, false)? >> 16) as u16;
+        let rca_arg = (rca as u32) << 16;
+
+        self.command(cmd::SEND_CSD, rca_arg, Response::Long, false)?;
+        let mut rr = [0; 4];
+        for (r, reg) in rr.iter_mut().zip(self.sdhc.rr.iter()) {
+            *r = reg.read().cmdresp().bits();
+        }
+        let num_blocks = Csd::from_response(rr).num_blocks();
+
+        self.command(cmd::SELECT_CARD, rca_arg, Response::ShortBusy, false)?;
+        if version != CardVersion::HighCapacity {
+            self.command(cmd::SET_BLOCKLEN, BLOCK_SIZE as u32, Response::Short, false)?;
+        }
+
+        // ACMD6, argument 2: 4-bit bus
+        self.command(cmd::APP_CMD, rca_arg, Response::Short, false)?;
+        self.command(cmd::SET_BUS_WIDTH, 2, Response::Short, false)?;
+        self.sdhc.hc1r().modify(|_, w| w.dw()._4bit());
+
+        let high_speed = version != CardVersion::V1
+            && self.sdhc.ca0r.read().hssup().bit_is_set()
+            && self.switch_high_speed()?;
+        if high_speed {
+            self.sdhc.hc1r().modify(|_, w| w.hsen().high());
+            self.set_clock(HIGH_SPEED_FREQ);
+        } else {
+            self.set_clock(DEFAULT_SPEED_FREQ);
+        }
+
+        let info = CardInfo {
+            version,
+            rca,
+            num_blocks,
+            wide_bus: true,
+            high_speed,
+        };
+        self.card = Some(info);
+        Ok(info)
+    }
+
+    /// Read consecutive blocks, starting at block `start`
+    ///
+    /// The length of `buffer` must be a multiple of [`BLOCK_SIZE`].
+    #[inline]
+    pub fn read_blocks(&mut self, start: u32, buffer: &mut [u8]) -> Result<(), Error> {
+        self.read(start, buffer)
+    }
+
+    /// Write consecutive blocks, starting at block `start`
+    ///
+    /// The length of `buffer` must be a multiple of [`BLOCK_SIZE`].
+    #[inline]
+    pub fn write_blocks(&mut self, start: u32, buffer: &[u8]) -> Result<(), Error> {
+        self.write(start, buffer)
+    }
+
+    /// Power off the card, and return the peripheral and pins
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    pub fn free(self, mclk: &mut MCLK) -> (S, Pads<S, CK, CMD, D0, D1, D2, D3>) {
+        self.sdhc
+            .ccr
+            .write(|w| w.sdclken().disable().intclken().off());
+        self.sdhc.pcr.write(|w| w.sdbpwr().off());
+        S::disable_ahb_clock(mclk);
+        (self.sdhc, self.pads)
+    }
+
+    //==========================================================================
+    //  Internals
+    //==========================================================================
+
+    /// Set the SD clock to the highest frequency not exceeding `freq`
+    fn set_clock(&self, freq: u32) {
+        let divider = clock_divider(self.base_freq, freq);
+        self.sdhc.ccr.write(|w| w.sdclken().disable());
+        // SAFETY: The divider is limited to 10 bits by `clock_divider`
+        self.sdhc.ccr.write(|w| unsafe {
+            w.sdclkfsel()
+                .bits(divider as u8)
+                .usdclkfsel()
+                .bits((divider >> 8) as u8)
+                .clkgsel()
+                .div()
+                .intclken()
+                .on()
+        });
+        while self.sdhc.ccr.read().intclks().bit_is_clear() {}
+        self.sdhc.ccr.modify(|_, w| w.sdclken().enable());
+    }
+
+    /// Send a command, and return the first word of its response
+    ///
+    /// When `data` is set, the command is followed by a data transfer, which
+    /// must be configured beforehand.
+    fn command(&self, index: u8, arg: u32, response: Response, data: bool) -> Result<u32, Error> {
+        let sdhc = &self.sdhc;
+        while sdhc.psr.read().cmdinhc().bit_is_set() {}
+        if data || response == Response::ShortBusy {
+            while sdhc.psr.read().cmdinhd().bit_is_set() {}
+        }
+
+        // SAFETY: Any command argument is valid
+        sdhc.arg1r.write(|w| unsafe { w.arg().bits(arg) });
+        sdhc.cr.write(|w| {
+            match response {
+                Response::None => w.resptyp().none(),
+                Response::Short => w.resptyp()._48_bit().cmdccen().enable().cmdicen().enable(),
+                Response::ShortBusy => w
+                    .resptyp()
+                    ._48_bit_busy()
+                    .cmdccen()
+                    .enable()
+                    .cmdicen()
+                    .enable(),
+                Response::ShortNoCrc => w.resptyp()._48_bit(),
+                Response::Long => w.resptyp()._136_bit().cmdccen().enable(),
+            };
+            w.dpsel().bit(data);
+            // SAFETY: Command indices are 6-bit values
+            unsafe { w.cmdidx().bits(index) }
+        });
+
+        self.wait(|s| s.cmdc().bit_is_set())?;
+        sdhc.nistr().write(|w| w.cmdc().set_bit());
+        if response == Response::ShortBusy {
+            self.wait(|s| s.trfc().bit_is_set())?;
+            sdhc.nistr().write(|w| w.trfc().set_bit());
+        }
+        Ok(sdhc.rr[0].read().cmdresp().bits())
+    }
+
+    /// Wait for a normal status flag, or for an error
+    fn wait(&self, done: impl Fn(&sdhc0::nistr::R) -> bool) -> Result<(), Error> {
+        loop {
+            let status = self.sdhc.nistr().read();
+            if status.errint().bit_is_set() {
+                return Err(self.recover());
+            }
+            if done(&status) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Decode and clear the error status, and reset the command and data
+    /// lines
+    fn recover(&self) -> Error {
+        let sdhc = &self.sdhc;
+        let status = sdhc.eistr().read();
+        let error = if status.cmdteo().bit_is_set() {
+            Error::CommandTimeout
+        } else if status.cmdcrc().bit_is_set() {
+            Error::CommandCrc
+        } else if status.cmdend().bit_is_set() {
+            Error::CommandEndBit
+        } else if status.cmdidx().bit_is_set() {
+            Error::CommandIndex
+        } else if status.datteo().bit_is_set() {
+            Error::DataTimeout
+        } else if status.datcrc().bit_is_set() {
+            Error::DataCrc
+        } else if status.datend().bit_is_set() {
+            Error::DataEndBit
+        } else if status.curlim().bit_is_set() {
+            Error::CurrentLimit
+        } else if status.acmd().bit_is_set() {
+            Error::AutoCmd
+        } else {
+            Error::Adma
+        };
+
+        // SAFETY: Status flags are cleared by writing ones
+        sdhc.eistr().write(|w| unsafe { w.bits(status.bits()) });
+        sdhc.nistr().write(|w| unsafe { w.bits(NORMAL_STATUS) });
+        sdhc.srr.write(|w| w.swrstcmd().reset().swrstdat().reset());
+        while sdhc.srr.read().bits() != 0 {}
+        error
+    }
+
+    /// Switch the card to the high speed mode, and report whether it
+    /// succeeded
+    fn switch_high_speed(&self) -> Result<bool, Error> {
+        let mut status = [0u32; 16];
+        self.setup_transfer(64, 1, Direction::Read, false);
+        self.command(cmd::SWITCH_FUNC, SWITCH_HIGH_SPEED, Response::Short, true)?;
+        self.wait(|s| s.brdrdy().bit_is_set())?;
+        self.sdhc.nistr().write(|w| w.brdrdy().set_bit());
+        for word in status.iter_mut() {
+            *word = self.sdhc.bdpr.read().bufdata().bits();
+        }
+        self.wait(|s| s.trfc().bit_is_set())?;
+        self.sdhc.nistr().write(|w| w.trfc().set_bit());
+        // Bits 379:376 of the status, in byte 16, hold the function selected
+        // in group 1
+        Ok(status[4] & 0xF == 1)
+    }
+
+    /// Configure the block size, block count and transfer mode of the next
+    /// data transfer
+    fn setup_transfer(&self, block_size: u16, count: u16, direction: Direction, dma: bool) {
+        let sdhc = &self.sdhc;
+        // SAFETY: Block sizes up to 512 and any block count are valid
+        sdhc.bsr
+            .write(|w| unsafe { w.blocksize().bits(block_size) });
+        sdhc.bcr.write(|w| unsafe { w.bcnt().bits(count) });
+        sdhc.tmr.write(|w| {
+            match direction {
+                Direction::Read => w.dtdsel().read(),
+                Direction::Write => w.dtdsel().write(),
+            };
+            if count > 1 {
+                w.msbsel().multiple().bcen().enable().acmden().cmd12();
+            }
+            w.dmaen().bit(dma)
+        });
+    }
+
+    /// Check that the card is ready for a transfer of `len` bytes, starting
+    /// at block `start`
+    fn check(&self, start: u32, len: usize) -> Result<CardInfo, Error> {
+        let info = self.card.ok_or(Error::NotInitialized)?;
+        if !self.is_card_present() {
+            return Err(Error::NoCard);
+        }
+        let blocks = len / BLOCK_SIZE;
+        if len & (BLOCK_SIZE - 1) != 0 || start as usize + blocks > info.num_blocks as usize {
+            return Err(Error::InvalidLength);
+        }
+        Ok(info)
+    }
+
+    fn read(&self, start: u32, buffer: &mut [u8]) -> Result<(), Error> {
+        let info = self.check(start, buffer.len())?;
+        let mut block = start;
+        for chunk in buffer.chunks_mut(BLOCKS_PER_CHUNK * BLOCK_SIZE) {
+            self.transfer(
+                &info,
+                block,
+                chunk.as_mut_ptr(),
+                chunk.len(),
+                Direction::Read,
+            )?;
+            block += (chunk.len() / BLOCK_SIZE) as u32;
+        }
+        Ok(())
+    }
+
+    fn write(&self, start: u32, buffer: &[u8]) -> Result<(), Error> {
+        let info = self.check(start, buffer.len())?;
+        if self.is_write_protected() {
+            return Err(Error::WriteProtected);
+        }
+        let mut block = start;
+        for chunk in buffer.chunks(BLOCKS_PER_CHUNK * BLOCK_SIZE) {
+            self.transfer(
+                &info,
+                block,
+                chunk.as_ptr() as *mut u8,
+                chunk.len(),
+                Direction::Write,
+            )?;
+            block += (chunk.len() / BLOCK_SIZE) as u32;
+            self.wait_ready(&info)?;
+        }
+        Ok(())
+    }
+
+    /// Transfer up to [`BLOCKS_PER_CHUNK`] blocks between the card and
+    /// `len` bytes at `ptr`
+    ///
+    /// `ptr` is only written to for reads.
+    fn transfer(
+        &self,
+        info: &CardInfo,
+        block: u32,
+        ptr: *mut u8,
+        len: usize,
+        direction: Direction,
+    ) -> Result<(), Error> {
+        let count = len / BLOCK_SIZE;
+        let index = match (direction, count) {
+            (Direction::Read, 1) => cmd::READ_SINGLE_BLOCK,
+            (Direction::Read, _) => cmd::READ_MULTIPLE_BLOCK,
+            (Direction::Write, 1) => cmd::WRITE_BLOCK,
+            (Direction::Write, _) => cmd::WRITE_MULTIPLE_BLOCK,
+        };
+        let arg = info.address(block);
+        let dma = ptr as usize & 3 == 0;
+
+        if dma {
+            let mut table = [Descriptor::default(); DESCRIPTORS];
+            let per_descriptor = BLOCKS_PER_DESCRIPTOR * BLOCK_SIZE;
+            let used = (len - 1) / per_descriptor + 1;
+            for (i, descriptor) in table.iter_mut().take(used).enumerate() {
+                let offset = i * per_descriptor;
+                descriptor.attr = Descriptor::VALID | Descriptor::ACT_TRAN;
+                descriptor.len = (len - offset).min(per_descriptor) as u16;
+                descriptor.addr = ptr as u32 + offset as u32;
+            }
+            table[used - 1].attr |= Descriptor::END;
+
+            self.sdhc.hc1r().modify(|_, w| w.dmasel()._32bit());
+            // SAFETY: The table lives until the transfer completes or fails
+            self.sdhc.asar[0].write(|w| unsafe { w.admasa().bits(table.as_ptr() as u32) });
+            self.setup_transfer(BLOCK_SIZE as u16, count as u16, direction, true);
+            // The table and the data must be written before the ADMA2 engine
+            // starts
+            fence(Ordering::Release);
+            let result = self
+                .command(index, arg, Response::Short, true)
+                .and_then(|_| self.wait(|s| s.trfc().bit_is_set()));
+            // The received data must not be read before the transfer
+            // completes
+            fence(Ordering::Acquire);
+            result?;
+        } else {
+            self.setup_transfer(BLOCK_SIZE as u16, count as u16, direction, false);
+            self.command(index, arg, Response::Short, true)?;
+            for n in 0..count {
+                let words = (0..BLOCK_SIZE / 4).map(|i| n * BLOCK_SIZE + 4 * i);
+                match direction {
+                    Direction::Read => {
+                        self.wait(|s| s.brdrdy().bit_is_set())?;
+                        self.sdhc.nistr().write(|w| w.brdrdy().set_bit());
+                        for offset in words {
+                            let word = self.sdhc.bdpr.read().bufdata().bits();
+                            // SAFETY: `offset + 4 <= len`, and the buffer is
+                            // writable for reads
+                            unsafe {
+                                core::ptr::write_unaligned(
+                                    ptr.add(offset) as *mut [u8; 4],
+                                    word.to_le_bytes(),
+                                );
+                            }
+                        }
+                    }
+                    Direction::Write => {
+                        self.wait(|s| s.bwrrdy().bit_is_set())?;
+                        self.sdhc.nistr().write(|w| w.bwrrdy().set_bit());
+                        for offset in words {
+                            // SAFETY: `offset + 4 <= len`
+                            let bytes = unsafe {
+                                core::ptr::read_unaligned(ptr.add(offset) as *const [u8; 4])
+                            };
+                            // SAFETY: Any word is valid data
+                            self.sdhc
+                                .bdpr
+                                .write(|w| unsafe { w.bufdata().bits(u32::from_le_bytes(bytes)) });
+                        }
+                    }
+                }
+            }
+            self.wait(|s| s.trfc().bit_is_set())?;
+        }
+        self.sdhc.nistr().write(|w| w.trfc().set_bit());
+        Ok(())
+    }
+
+    /// Wait for the card to finish programming, and check its status
+    fn wait_ready(&self, info: &CardInfo) -> Result<(), Error> {
+        let rca_arg = (info.rca as u32) << 16;
+        loop {
+            let status = self.command(cmd::SEND_STATUS, rca_arg, Response::Short, false)?;
+            if status & STATUS_ERRORS != 0 {
+                return Err(Error::CardStatus(status));
+            }
+            let state = (status >> 9) & 0xF;
+            if status & STATUS_READY_FOR_DATA != 0 && state == STATE_TRAN {
+                return Ok(());
+            }
+        }
+    }
+}
+
+//==============================================================================
+//  embedded-sdmmc
+//==============================================================================
+
+#[cfg(feature = "sdmmc")]
+impl<S, CK, CMD, D0, D1, D2, D3> embedded_sdmmc::BlockDevice for Sdhc<S, CK, CMD, D0, D1, D2, D3>
+where
+    S: Instance,
+    CK: CkPin<S>,
+    CMD: CmdPin<S>,
+    D0: Dat0Pin<S>,
+    D1: Dat1Pin<S>,
+    D2: Dat2Pin<S>,
+    D3: Dat3Pin<S>,
+{
+    type Error = Error;
+
+    fn read(
+        &self,
+        blocks: &mut [embedded_sdmmc::Block],
+        start_block_idx: embedded_sdmmc::BlockIdx,
+        _reason: &str,
+    ) -> Result<(), Self::Error> {
+        assert_eq!(core::mem::size_of::<embedded_sdmmc::Block>(), BLOCK_SIZE);
+        // SAFETY: A `Block` only holds its 512-byte contents, so the blocks
+        // form a contiguous byte buffer
+        let buffer = unsafe {
+            core::slice::from_raw_parts_mut(
+                blocks.as_mut_ptr() as *mut u8,
+                blocks.len() * BLOCK_SIZE,
+            )
+        };
+        Sdhc::read(self, start_block_idx.0, buffer)
+    }
+
+    fn write(
+        &self,
+        blocks: &[embedded_sdmmc::Block],
+        start_block_idx: embedded_sdmmc::BlockIdx,
+    ) -> Result<(), Self::Error> {
+        assert_eq!(core::mem::size_of::<embedded_sdmmc::Block>(), BLOCK_SIZE);
+        // SAFETY: A `Block` only holds its 512-byte contents, so the blocks
+        // form a contiguous byte buffer
+        let buffer = unsafe {
+            core::slice::from_raw_parts(blocks.as_ptr() as *const u8, blocks.len() * BLOCK_SIZE)
+        };
+        Sdhc::write(self, start_block_idx.0, buffer)
+    }
+
+    fn num_blocks(&self) -> Result<embedded_sdmmc::BlockCount, Self::Error> {
+        let info = self.card.ok_or(Error::NotInitialized)?;
+        Ok(embedded_sdmmc::BlockCount(info.num_blocks))
+    }
+}
@@ -0,0 +1,395 @@
+//! # Supply Controller (SUPC)
+//!
+//! The SUPC holds the 3.3V brown-out detector (BOD33), the main voltage
+//! regulator, the internal voltage reference used by the ADC and DAC, and
+//! the battery backup power switch.
+//!
+//! The BOD33 is configured from the NVM user row at reset; see
+//! [`UserRow`](crate::nvm::UserRow). [`Supc::enable_bod33`] reconfigures it at
+//! runtime, for instance to raise an interrupt instead of resetting the
+//! device when the supply voltage drops, which leaves time to save state
+//! before power is lost. The 1.2V brown-out detector (BOD12) is calibrated in
+//! the factory and cannot be configured.
+//!
+//! ```
+//! use atsamd_hal::supc::{Bod33Action, Bod33Config, Flags, Reference, Regulator, Supc};
+//!
+//! let mut supc = Supc::new(peripherals.SUPC);
+//! supc.enable_bod33(Bod33Config {
+//!     level: 0x40,
+//!     action: Bod33Action::INT,
+//!     ..Default::default()
+//! });
+//! supc.enable_interrupts(Flags::BOD33_DETECTION);
+//!
+//! supc.set_regulator(Regulator::BUCK);
+//! supc.set_reference(Reference::_2V0);
+//! ```
+
+use bitflags::bitflags;
+
+use crate::pac::{supc, SUPC};
+
+/// Source of the backup domain power
+pub use supc::bbps::CONF_A as BackupPowerSwitch;
+/// Action of the BOD33 when the supply voltage drops below the threshold
+pub use supc::bod33::ACTION_A as Bod33Action;
+/// Division of the BOD33 sampling clock, `CLK_ULP1K`
+pub use supc::bod33::PSEL_A as Bod33Prescaler;
+/// Voltage of the internal reference (`INTREF`) of the ADC and DAC
+pub use supc::vref::SEL_A as Reference;
+/// Main voltage regulator
+pub use supc::vreg::SEL_A as Regulator;
+
+//==============================================================================
+//  BOD33
+//==============================================================================
+
+/// BOD33 configuration
+///
+/// The default configuration matches the factory settings of the NVM user
+/// row.
+#[derive(Clone, Copy, Debug)]
+pub struct Bod33Config {
+    /// Threshold level of the `VDD` supply; see the electrical
+    /// characteristics of the datasheet for the voltage of each level
+    pub level: u8,
+    /// Threshold level of the `VBAT` supply in backup sleep mode
+    pub backup_level: u8,
+    /// Hysteresis, from 0 (none) to 15
+    pub hysteresis: u8,
+    /// Action when the supply voltage drops below the threshold
+    pub action: Bod33Action,
+    /// Division of the sampling clock
+    pub prescaler: Bod33Prescaler,
+    /// Sample the supply voltage in standby sleep mode, rather than monitor
+    /// it continuously
+    pub sample_in_standby: bool,
+    /// Keep the BOD33 enabled in standby sleep mode
+    pub run_in_standby: bool,
+    /// Keep the BOD33 enabled in hibernate sleep mode
+    pub run_in_hibernate: bool,
+    /// Keep the BOD33 enabled in backup sleep mode
+    pub run_in_backup: bool,
+}
+
+impl Default for Bod33Config {
+    fn default() -> Self {
+        Self {
+            level: 0x1C,
+            backup_level: 0,
+            hysteresis: 2,
+            action: Bod33Action::RESET,
+            prescaler: Bod33Prescaler::NODIV,
+            sample_in_standby: false,
+            run_in_standby: true,
+            run_in_hibernate: false,
+            run_in_backup: false,
+        }
+    }
+}
+
+//==============================================================================
+//  Vref
+//==============================================================================
+
+/// Internal voltage reference configuration
+#[derive(Clone, Copy, Debug)]
+pub struct VrefConfig {
+    /// Voltage of the reference
+    pub reference: Reference,
+    /// Route the reference to an ADC input channel
+    pub output: bool,
+    /// Only enable the reference when a peripheral requests it
+    pub on_demand: bool,
+    /// Keep the reference enabled in standby sleep mode
+    pub run_in_standby: bool,
+}
+
+impl Default for VrefConfig {
+    fn default() -> Self {
+        Self {
+            reference: Reference::_1V0,
+            output: false,
+            on_demand: false,
+            run_in_standby: false,
+        }
+    }
+}
+
+//==============================================================================
+//  Backup domain
+//==============================================================================
+
+/// Output pin of the backup domain
+///
+/// The outputs remain driven by the SUPC in backup sleep mode. See the I/O
+/// multiplexing table of the datasheet for the pin of each output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackupOutput {
+    Out0,
+    Out1,
+}
+
+impl BackupOutput {
+    #[inline]
+    fn mask(self) -> u32 {
+        match self {
+            BackupOutput::Out0 => 1,
+            BackupOutput::Out1 => 1 << 1,
+        }
+    }
+}
+
+/// Input pin of the backup domain
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackupInput {
+    In0,
+    In1,
+}
+
+//==============================================================================
+//  Flags
+//==============================================================================
+
+bitflags! {
+    /// SUPC interrupt flags
+    ///
+    /// The binary format of the flags matches the `INTENSET`, `INTENCLR`,
+    /// `INTFLAG` and `STATUS` registers.
+    pub struct Flags: u32 {
+        /// The BOD33 is ready
+        const BOD33_READY = 1;
+        /// The supply voltage is below the BOD33 threshold
+        const BOD33_DETECTION = 1 << 1;
+        /// The BOD33 configuration is synchronized
+        const BOD33_SYNC_READY = 1 << 2;
+        /// The voltage regulator is ready
+        const REGULATOR_READY = 1 << 8;
+        /// `VDDCORE` is ready
+        const VDDCORE_READY = 1 << 10;
+    }
+}
+
+//==============================================================================
+//  Supc
+//==============================================================================
+
+/// SUPC peripheral
+pub struct Supc {
+    supc: SUPC,
+}
+
+impl Supc {
+    /// Take the SUPC
+    ///
+    /// The current configuration, loaded from the NVM user row at reset, is
+    /// left untouched.
+    #[inline]
+    pub fn new(supc: SUPC) -> Self {
+        Self { supc }
+    }
+
+    /// Configure and enable the BOD33
+    ///
+    /// The BOD33 is disabled while it is reconfigured. This function blocks
+    /// until the BOD33 is ready.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the hysteresis is greater than 15.
+    #[inline]
+    pub fn enable_bod33(&mut self, config: Bod33Config) {
+        assert!(config.hysteresis < 16);
+        self.disable_bod33();
+        // SAFETY: The level fields accept any value, and the hysteresis was
+        // checked above
+        self.supc.bod33.write(|w| unsafe {
+            w.level().bits(config.level);
+            w.vbatlevel().bits(config.backup_level);
+            w.hyst().bits(config.hysteresis);
+            w.action().variant(config.action);
+            w.psel().variant(config.prescaler);
+            w.stdbycfg().bit(config.sample_in_standby);
+            w.runstdby().bit(config.run_in_standby);
+            w.runhib().bit(config.run_in_hibernate);
+            w.runbkup().bit(config.run_in_backup)
+        });
+        self.supc.bod33.modify(|_, w| w.enable().set_bit());
+        self.sync_bod33();
+        while self.supc.status.read().bod33rdy().bit_is_clear() {}
+    }
+
+    /// Disable the BOD33
+    #[inline]
+    pub fn disable_bod33(&mut self) {
+        self.supc.bod33.modify(|_, w| w.enable().clear_bit());
+        self.sync_bod33();
+    }
+
+    /// Whether the supply voltage is currently below the BOD33 threshold
+    #[inline]
+    pub fn bod33_detected(&self) -> bool {
+        self.supc.status.read().bod33det().bit_is_set()
+    }
+
+    #[inline]
+    fn sync_bod33(&self) {
+        while self.supc.status.read().b33srdy().bit_is_clear() {}
+    }
+
+    /// Switch the main voltage regulator
+    ///
+    /// The buck converter is more efficient, but needs an external inductor
+    /// on `VSW`. This function blocks until the regulator is ready.
+    #[inline]
+    pub fn set_regulator(&mut self, regulator: Regulator) {
+        self.supc.vreg.modify(|_, w| w.sel().variant(regulator));
+        while self.supc.status.read().vregrdy().bit_is_clear() {}
+    }
+
+    /// Selected main voltage regulator
+    #[inline]
+    pub fn regulator(&self) -> Regulator {
+        self.supc.vreg.read().sel().variant()
+    }
+
+    /// Set the voltage of the internal reference
+    #[inline]
+    pub fn set_reference(&mut self, reference: Reference) {
+        self.supc.vref.modify(|_, w| w.sel().variant(reference));
+    }
+
+    /// Configure the internal voltage reference
+    #[inline]
+    pub fn configure_vref(&mut self, config: VrefConfig) {
+        self.supc.vref.modify(|_, w| {
+            w.sel().variant(config.reference);
+            w.vrefoe().bit(config.output);
+            w.ondemand().bit(config.on_demand);
+            w.runstdby().bit(config.run_in_standby)
+        });
+    }
+
+    /// Configure the battery backup power switch
+    ///
+    /// With [`BackupPowerSwitch::BOD33`], the backup domain switches to
+    /// `VBAT` when the BOD33 detects a drop of `VDD` and its action is
+    /// [`Bod33Action::BKUP`]. With `wake`, the device then wakes up from
+    /// backup sleep mode when `VDD` is restored.
+    #[inline]
+    pub fn configure_backup_power_switch(&mut self, switch: BackupPowerSwitch, wake: bool) {
+        self.supc.bbps.write(|w| {
+            w.conf().variant(switch);
+            w.wakeen().bit(wake)
+        });
+    }
+
+    /// Whether the battery backup power switch wakes the device up from backup
+    /// sleep mode
+    #[inline]
+    pub(crate) fn backup_power_switch_wake_enabled(&self) -> bool {
+        let bbps = self.supc.bbps.read();
+        bbps.conf().is_bod33() && bbps.wakeen().bit_is_set()
+    }
+
+    /// Enable a backup output, driven low until it is set
+    #[inline]
+    pub fn enable_backup_output(&mut self, output: BackupOutput) {
+        let mask = output.mask();
+        // SAFETY: The mask only holds valid bits of the register
+        self.supc
+            .bkout
+            .modify(|r, w| unsafe { w.bits(r.bits() | mask) });
+    }
+
+    /// Disable a backup output, giving the pin back to the PORT
+    #[inline]
+    pub fn disable_backup_output(&mut self, output: BackupOutput) {
+        let mask = output.mask() | output.mask() << 24;
+        // SAFETY: The mask only holds valid bits of the register
+        self.supc
+            .bkout
+            .modify(|r, w| unsafe { w.bits(r.bits() & !mask) });
+    }
+
+    /// Drive a backup output high or low
+    #[inline]
+    pub fn set_backup_output(&mut self, output: BackupOutput, high: bool) {
+        let shift = if high { 16 } else { 8 };
+        let mask = output.mask() << shift;
+        // SAFETY: The mask only holds valid bits of the register
+        self.supc
+            .bkout
+            .modify(|r, w| unsafe { w.bits(r.bits() | mask) });
+    }
+
+    /// Toggle a backup output on each RTC output event
+    #[inline]
+    pub fn toggle_backup_output_on_rtc(&mut self, output: BackupOutput, enable: bool) {
+        let mask = output.mask() << 24;
+        // SAFETY: The mask only holds valid bits of the register
+        self.supc.bkout.modify(|r, w| unsafe {
+            if enable {
+                w.bits(r.bits() | mask)
+            } else {
+                w.bits(r.bits() & !mask)
+            }
+        });
+    }
+
+    /// Level of a backup input
+    #[inline]
+    pub fn backup_input(&self, input: BackupInput) -> bool {
+        let bkin = self.supc.bkin.read();
+        match input {
+            BackupInput::In0 => bkin.bkin0().bit_is_set(),
+            BackupInput::In1 => bkin.bkin1().bit_is_set(),
+        }
+    }
+
+    /// Read the status flags
+    #[inline]
+    pub fn status(&self) -> Flags {
+        Flags::from_bits_truncate(self.supc.status.read().bits())
+    }
+
+    /// Read the interrupt flags
+    #[inline]
+    pub fn read_flags(&self) -> Flags {
+        Flags::from_bits_truncate(self.supc.intflag.read().bits())
+    }
+
+    /// Clear interrupt flags
+    #[inline]
+    pub fn clear_flags(&mut self, flags: Flags) {
+        // SAFETY: Flags only holds valid bits of the register
+        self.supc.intflag.write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Enable interrupts for the given flags
+    #[inline]
+    pub fn enable_interrupts(&mut self, flags: Flags) {
+        // SAFETY: Flags only holds valid bits of the register
+        self.supc
+            .intenset
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Disable interrupts for the given flags
+    #[inline]
+    pub fn disable_interrupts(&mut self, flags: Flags) {
+        // SAFETY: Flags only holds valid bits of the register
+        self.supc
+            .intenclr
+            .write(|w| unsafe { w.bits(flags.bits()) });
+    }
+
+    /// Return the underlying SUPC
+    ///
+    /// The configuration is left untouched.
+    #[inline]
+    pub fn free(self) -> SUPC {
+        self.supc
+    }
+}